@@ -0,0 +1,80 @@
+// Filling a 64x64x64 cuboid and remeshing it, block by block and with a batch
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use minecraft::chunk::{BlockID, ChunkColumn};
+use minecraft::chunk_manager::{BlockEditBatch, ChunkManager};
+use std::sync::Arc;
+
+const SIZE: i32 = 64;
+
+// The columns under the cuboid, marked as uploaded so that the edits are remeshed
+fn loaded_world() -> ChunkManager {
+    let chunk_manager = ChunkManager::new();
+
+    for x in 0..SIZE / 16 {
+        for z in 0..SIZE / 16 {
+            let column = ChunkColumn::new();
+            for y in 0..16 {
+                *column.get_chunk(y).is_uploaded_to_gpu.write() = true;
+            }
+            chunk_manager.add_chunk_column((x, z), Arc::new(column));
+        }
+    }
+
+    chunk_manager
+}
+
+fn cuboid() -> impl Iterator<Item = (i32, i32, i32)> {
+    (0..SIZE).flat_map(|y| (0..SIZE).flat_map(move |z| (0..SIZE).map(move |x| (x, y, z))))
+}
+
+// What `ChunkLoading` does with the edits before uploading the chunks again
+fn remesh(chunk_manager: &ChunkManager) {
+    for ((chunk_x, chunk_y, chunk_z), dirty_blocks) in chunk_manager.take_dirty_blocks() {
+        // The neighbours of the cuboid outside of the world
+        if chunk_manager.get_chunk(chunk_x, chunk_y, chunk_z).is_none() {
+            continue;
+        }
+
+        let block_xyz = dirty_blocks.iter().map(|block| (block.1, block.2, block.3));
+        chunk_manager.update_blocks(chunk_x, chunk_y, chunk_z, block_xyz);
+    }
+}
+
+fn fill(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fill 64x64x64");
+    group.sample_size(10);
+
+    group.bench_function("put_block", |b| {
+        b.iter_batched(
+            loaded_world,
+            |chunk_manager| {
+                for (x, y, z) in cuboid() {
+                    chunk_manager.put_block(x, y, z, BlockID::Stone);
+                }
+                remesh(&chunk_manager);
+                chunk_manager
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("apply_batch", |b| {
+        b.iter_batched(
+            loaded_world,
+            |chunk_manager| {
+                let batch: BlockEditBatch = cuboid()
+                    .map(|position| (position, BlockID::Stone))
+                    .collect();
+                chunk_manager.apply_batch(batch);
+                remesh(&chunk_manager);
+                chunk_manager
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, fill);
+criterion_main!(benches);
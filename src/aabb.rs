@@ -1,4 +1,7 @@
+use crate::chunk::BlockID;
+use crate::chunk_manager::ChunkManager;
 use nalgebra_glm::{vec3, Vec3};
+use num_traits::Zero;
 
 #[derive(Debug, Copy, Clone)]
 pub struct AABB {
@@ -24,6 +27,130 @@ impl AABB {
             && self.mins.z < other.maxs.z
             && self.maxs.z > other.mins.z
     }
+
+    pub fn size(&self) -> Vec3 {
+        self.maxs - self.mins
+    }
+
+    // The box grown by `amount` in every direction
+    pub fn inflate(&self, amount: f32) -> AABB {
+        let margin = vec3(amount, amount, amount);
+
+        AABB::new(self.mins - margin, self.maxs + margin)
+    }
+
+    // Distance along the ray to the first intersection with the box, using the slab method
+    // The direction must be normalized, None when the ray misses the box
+    pub fn ray_intersection(&self, origin: &Vec3, direction: &Vec3) -> Option<f32> {
+        let mut t_min = 0.0f32;
+        let mut t_max = f32::INFINITY;
+
+        for axis in 0..3 {
+            if direction[axis].abs() < f32::EPSILON {
+                if origin[axis] < self.mins[axis] || origin[axis] > self.maxs[axis] {
+                    return None;
+                }
+                continue;
+            }
+
+            let t1 = (self.mins[axis] - origin[axis]) / direction[axis];
+            let t2 = (self.maxs[axis] - origin[axis]) / direction[axis];
+
+            t_min = t_min.max(t1.min(t2));
+            t_max = t_max.min(t1.max(t2));
+
+            if t_min > t_max {
+                return None;
+            }
+        }
+
+        Some(t_min)
+    }
+
+    pub fn get_colliding_block_coords(&self, chunk_manager: &ChunkManager) -> Option<Vec3> {
+        let block_min = self.mins.map(|x| x.floor() as i32);
+        let block_max = self.maxs.map(|x| x.floor() as i32);
+
+        // Find the first solid block that the box is colliding with
+        for y in block_min.y..=block_max.y {
+            for z in block_min.z..=block_max.z {
+                for x in block_min.x..=block_max.x {
+                    if let Some(block) = chunk_manager.get_block(x, y, z) {
+                        if !block.is_solid() {
+                            continue;
+                        }
+
+                        let block_coords = vec3(x as f32, y as f32, z as f32);
+
+                        if self.intersects(&get_block_aabb(&block_coords)) {
+                            return Some(block_coords);
+                        }
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    // Whether a block of the given type intersects the box, used for non solid blocks like lava
+    pub fn is_touching_block(&self, chunk_manager: &ChunkManager, block: BlockID) -> bool {
+        let mins = self.mins.map(|x| x.floor() as i32);
+        let maxs = self.maxs.map(|x| x.floor() as i32);
+
+        for y in mins.y..=maxs.y {
+            for z in mins.z..=maxs.z {
+                for x in mins.x..=maxs.x {
+                    if chunk_manager.get_block(x, y, z) == Some(block) {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    // Pushes the box out of the block against the direction of the movement `v`
+    // Returns true when the box landed on top of the block
+    pub fn separate_from_block(&mut self, v: &Vec3, block_coords: &Vec3) -> bool {
+        let mut is_on_ground = false;
+        let block_aabb = get_block_aabb(block_coords);
+        let size = self.size();
+
+        if !v.x.is_zero() {
+            if v.x < 0.0 {
+                self.mins.x = block_aabb.maxs.x;
+                self.maxs.x = block_aabb.maxs.x + size.x;
+            } else {
+                self.mins.x = block_aabb.mins.x - size.x;
+                self.maxs.x = block_aabb.mins.x;
+            }
+        }
+
+        if !v.y.is_zero() {
+            if v.y < 0.0 {
+                self.mins.y = block_aabb.maxs.y;
+                self.maxs.y = block_aabb.maxs.y + size.y;
+                is_on_ground = true;
+            } else {
+                self.mins.y = block_aabb.mins.y - size.y;
+                self.maxs.y = block_aabb.mins.y;
+            }
+        }
+
+        if !v.z.is_zero() {
+            if v.z < 0.0 {
+                self.mins.z = block_aabb.maxs.z;
+                self.maxs.z = block_aabb.maxs.z + size.z;
+            } else {
+                self.mins.z = block_aabb.mins.z - size.z;
+                self.maxs.z = block_aabb.mins.z;
+            }
+        }
+
+        is_on_ground
+    }
 }
 
 pub fn get_block_aabb(mins: &Vec3) -> AABB {
@@ -0,0 +1,549 @@
+use crate::chunk::{BlockID, ChunkColumn};
+use crate::nbt::{self, NbtError, Tag};
+use crate::schematic::translate_block;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+// Region files hold 32x32 columns, the header gives where each column is in 4 KiB sectors
+const REGION_WIDTH: i32 = 32;
+const SECTOR_SIZE: u64 = 4096;
+// The header is followed by the timestamps of the columns, which are not used
+const HEADER_SIZE: usize = 4 * (REGION_WIDTH * REGION_WIDTH) as usize;
+// The sections of a column are only read up to the height of our columns
+const SECTIONS: i32 = 16;
+// From 20w17a (1.16) the values of the block states no longer span two longs
+const PADDED_BLOCK_STATES_DATA_VERSION: i64 = 2527;
+// Before 17w47a (1.13) the blocks are numeric ids, which are not supported
+const PALETTE_DATA_VERSION: i64 = 1451;
+
+// A vanilla world saved in the Anvil format, read from the region files of its `region` directory
+pub struct AnvilWorld {
+    directory: PathBuf,
+}
+
+impl AnvilWorld {
+    // The directory is the world (with a `region` directory in it) or the region directory itself
+    pub fn open(directory: impl AsRef<Path>) -> io::Result<Self> {
+        let directory = directory.as_ref();
+        let region_directory = directory.join("region");
+        let directory = if region_directory.is_dir() {
+            region_directory
+        } else {
+            directory.to_path_buf()
+        };
+
+        if !directory.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not a directory", directory.display()),
+            ));
+        }
+
+        Ok(Self { directory })
+    }
+
+    // Reads the column into an empty column, returns false when it was never generated
+    pub fn load_column(&self, x: i32, z: i32, column: &ChunkColumn) -> Result<bool, NbtError> {
+        let path = self.directory.join(format!(
+            "r.{}.{}.mca",
+            x.div_euclid(REGION_WIDTH),
+            z.div_euclid(REGION_WIDTH)
+        ));
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut header = [0; HEADER_SIZE];
+        file.read_exact(&mut header)?;
+
+        let index = 4 * (x.rem_euclid(REGION_WIDTH) + z.rem_euclid(REGION_WIDTH) * REGION_WIDTH);
+        let location = &header[index as usize..index as usize + 4];
+        let sector = u32::from_be_bytes([0, location[0], location[1], location[2]]) as u64;
+        if sector == 0 {
+            return Ok(false);
+        }
+
+        file.seek(SeekFrom::Start(sector * SECTOR_SIZE))?;
+        let mut prefix = [0; 5];
+        file.read_exact(&mut prefix)?;
+        let length = u32::from_be_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as u64;
+        if length == 0 {
+            return Err(NbtError::InvalidData("empty column"));
+        }
+
+        // The length counts the compression byte
+        let data = file.take(length - 1);
+        let (_, tag) = match prefix[4] {
+            1 => nbt::read(GzDecoder::new(data))?,
+            2 => nbt::read(ZlibDecoder::new(data))?,
+            3 => nbt::read(data)?,
+            // The columns too large for a region file are stored in a .mcc file next to it
+            _ => return Err(NbtError::InvalidData("unsupported column compression")),
+        };
+
+        read_column(&tag, column)?;
+        Ok(true)
+    }
+}
+
+// Copies the blocks of the NBT of a column, the sections above 255 and below 0 are dropped
+fn read_column(tag: &Tag, column: &ChunkColumn) -> Result<(), NbtError> {
+    let data_version = tag.get("DataVersion").and_then(Tag::as_i64).unwrap_or(0);
+    if data_version < PALETTE_DATA_VERSION {
+        return Err(NbtError::InvalidData(
+            "worlds older than 1.13 are not supported",
+        ));
+    }
+
+    // Until 1.18 everything is in a "Level" compound, with other names
+    let (sections, palette_of) = match tag.get("Level") {
+        Some(level) => (level.child("Sections")?, legacy_palette as PaletteFn),
+        None => (tag.child("sections")?, palette as PaletteFn),
+    };
+    let sections = sections
+        .as_list()
+        .ok_or_else(|| NbtError::Missing("sections".to_string()))?;
+
+    let mut unknown_blocks = HashMap::new();
+
+    for section in sections {
+        let y = section.child("Y")?.as_i64().unwrap_or(-1) as i32;
+        if !(0..SECTIONS).contains(&y) {
+            continue;
+        }
+
+        // Sections with only the light have no blocks
+        let (palette, data) = match palette_of(section) {
+            Some(palette) => palette,
+            None => continue,
+        };
+
+        let blocks = palette
+            .iter()
+            .map(|state| {
+                let name = state
+                    .get("Name")
+                    .and_then(Tag::as_str)
+                    .ok_or_else(|| NbtError::Missing("Name".to_string()))?;
+
+                Ok(translate_block(name).unwrap_or_else(|| {
+                    *unknown_blocks.entry(name.to_string()).or_insert(0) += 1;
+                    BlockID::Stone
+                }))
+            })
+            .collect::<Result<Vec<_>, NbtError>>()?;
+
+        // A section of a single block has no data
+        let data = match data {
+            Some(data) if blocks.len() > 1 => data,
+            _ => {
+                if let Some(&block) = blocks.first().filter(|block| !block.is_air()) {
+                    for i in 0..4096 {
+                        column.set_block(i % 16, y as u32 * 16 + i / 256, (i / 16) % 16, block);
+                    }
+                }
+                continue;
+            }
+        };
+
+        let bits = (usize::BITS - (blocks.len() - 1).leading_zeros()).max(4) as usize;
+        let is_padded = data_version >= PADDED_BLOCK_STATES_DATA_VERSION;
+        let values_per_long = 64 / bits;
+        let expected_longs = if is_padded {
+            4096usize.div_ceil(values_per_long)
+        } else {
+            4096 * bits / 64
+        };
+        if data.len() != expected_longs {
+            return Err(NbtError::InvalidData("wrong block states length"));
+        }
+
+        let mask = (1u64 << bits) - 1;
+
+        for i in 0..4096usize {
+            let value = if is_padded {
+                (data[i / values_per_long] as u64 >> (i % values_per_long * bits)) & mask
+            } else {
+                // A value may start in a long and end in the next one
+                let bit = i * bits;
+                let (long, offset) = (bit / 64, bit % 64);
+
+                let mut value = data[long] as u64 >> offset;
+                if offset + bits > 64 {
+                    value |= (data[long + 1] as u64) << (64 - offset);
+                }
+                value & mask
+            };
+
+            let block = *blocks
+                .get(value as usize)
+                .ok_or(NbtError::InvalidData("block outside of the palette"))?;
+            if !block.is_air() {
+                let i = i as u32;
+                column.set_block(i % 16, y as u32 * 16 + i / 256, (i / 16) % 16, block);
+            }
+        }
+    }
+
+    for (name, count) in unknown_blocks {
+        debug!("Unknown block {name} replaced with stone in {count} sections");
+    }
+
+    Ok(())
+}
+
+type PaletteFn = fn(&Tag) -> Option<(&[Tag], Option<&[i64]>)>;
+
+fn legacy_palette(section: &Tag) -> Option<(&[Tag], Option<&[i64]>)> {
+    let palette = section.get("Palette")?.as_list()?;
+    let data = section.get("BlockStates").and_then(Tag::as_long_array);
+
+    Some((palette, data))
+}
+
+fn palette(section: &Tag) -> Option<(&[Tag], Option<&[i64]>)> {
+    let block_states = section.get("block_states")?;
+    let palette = block_states.get("palette")?.as_list()?;
+    let data = block_states.get("data").and_then(Tag::as_long_array);
+
+    Some((palette, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::io::Write;
+
+    const GZIP: u8 = 1;
+    const ZLIB: u8 = 2;
+    const UNCOMPRESSED: u8 = 3;
+
+    // 1.15, 1.16 and 1.18, the last one without the "Level" compound
+    const DATA_VERSION_1_15: i64 = 2230;
+    const DATA_VERSION_1_16: i64 = 2586;
+    const DATA_VERSION_1_18: i64 = 2975;
+
+    // 17 states need 5 bits, which do not divide 64 so that both layouts differ
+    const PALETTE: [&str; 17] = [
+        "minecraft:air",
+        "minecraft:stone",
+        "minecraft:dirt",
+        "minecraft:glass",
+        "minecraft:oak_planks",
+        "minecraft:cobblestone",
+        "minecraft:obsidian",
+        "minecraft:bedrock",
+        "minecraft:oak_log",
+        "minecraft:oak_leaves",
+        "minecraft:granite",
+        "minecraft:grass_block",
+        "minecraft:lava",
+        "minecraft:cave_air",
+        "minecraft:diorite",
+        "minecraft:andesite",
+        "minecraft:oak_log[axis=x]",
+    ];
+
+    // Position in the region, first sector, compression and payload of a column
+    type RegionEntry = ((i32, i32), u64, u8, Vec<u8>);
+
+    struct TempRegions(PathBuf);
+
+    impl TempRegions {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("minecraft-anvil-{name}-{}", std::process::id()));
+            fs::create_dir_all(path.join("region")).unwrap();
+            Self(path)
+        }
+
+        fn write_region(&self, name: &str, columns: &[RegionEntry]) {
+            let mut bytes = vec![0; 2 * SECTOR_SIZE as usize];
+
+            for ((x, z), sector, compression, data) in columns {
+                let index = 4 * (x + z * REGION_WIDTH) as usize;
+                bytes[index..index + 3].copy_from_slice(&(*sector as u32).to_be_bytes()[1..]);
+                bytes[index + 3] = (data.len() + 5).div_ceil(SECTOR_SIZE as usize) as u8;
+
+                let start = (sector * SECTOR_SIZE) as usize;
+                let end = start + (data.len() + 5).next_multiple_of(SECTOR_SIZE as usize);
+                if bytes.len() < end {
+                    bytes.resize(end, 0);
+                }
+                bytes[start..start + 4].copy_from_slice(&(data.len() as u32 + 1).to_be_bytes());
+                bytes[start + 4] = *compression;
+                bytes[start + 5..start + 5 + data.len()].copy_from_slice(data);
+            }
+
+            fs::write(self.0.join("region").join(name), bytes).unwrap();
+        }
+    }
+
+    impl Drop for TempRegions {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn compound(entries: Vec<(&str, Tag)>) -> Tag {
+        Tag::Compound(
+            entries
+                .into_iter()
+                .map(|(name, tag)| (name.to_string(), tag))
+                .collect::<BTreeMap<_, _>>(),
+        )
+    }
+
+    fn palette_tag(names: &[&str]) -> Tag {
+        Tag::List(
+            names
+                .iter()
+                .map(|name| compound(vec![("Name", Tag::String(name.to_string()))]))
+                .collect(),
+        )
+    }
+
+    // Packs the palette indices like the game does for the data version
+    fn pack(values: &[u64], bits: usize, is_padded: bool) -> Vec<i64> {
+        let values_per_long = 64 / bits;
+        let mut longs = if is_padded {
+            vec![0u64; values.len().div_ceil(values_per_long)]
+        } else {
+            vec![0u64; values.len() * bits / 64]
+        };
+
+        for (i, value) in values.iter().enumerate() {
+            if is_padded {
+                longs[i / values_per_long] |= value << (i % values_per_long * bits);
+            } else {
+                let (long, offset) = (i * bits / 64, i * bits % 64);
+                longs[long] |= value << offset;
+                if offset + bits > 64 {
+                    longs[long + 1] |= value >> (64 - offset);
+                }
+            }
+        }
+
+        longs.into_iter().map(|long| long as i64).collect()
+    }
+
+    // A section of the palette above, its blocks are given by `value_at`
+    fn section(data_version: i64, y: i8, value_at: impl Fn(usize) -> u64) -> Tag {
+        let values: Vec<u64> = (0..4096).map(value_at).collect();
+        let data = Tag::LongArray(pack(
+            &values,
+            5,
+            data_version >= PADDED_BLOCK_STATES_DATA_VERSION,
+        ));
+
+        if data_version >= DATA_VERSION_1_18 {
+            compound(vec![
+                ("Y", Tag::Byte(y)),
+                (
+                    "block_states",
+                    compound(vec![("palette", palette_tag(&PALETTE)), ("data", data)]),
+                ),
+            ])
+        } else {
+            compound(vec![
+                ("Y", Tag::Byte(y)),
+                ("Palette", palette_tag(&PALETTE)),
+                ("BlockStates", data),
+            ])
+        }
+    }
+
+    fn column_tag(data_version: i64, sections: Vec<Tag>) -> Tag {
+        if data_version >= DATA_VERSION_1_18 {
+            compound(vec![
+                ("DataVersion", Tag::Int(data_version as i32)),
+                ("sections", Tag::List(sections)),
+            ])
+        } else {
+            compound(vec![
+                ("DataVersion", Tag::Int(data_version as i32)),
+                ("Level", compound(vec![("Sections", Tag::List(sections))])),
+            ])
+        }
+    }
+
+    fn encode(tag: &Tag, compression: u8) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        nbt::write(&mut bytes, "", tag).unwrap();
+
+        match compression {
+            GZIP => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(&bytes).unwrap();
+                encoder.finish().unwrap()
+            }
+            ZLIB => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(&bytes).unwrap();
+                encoder.finish().unwrap()
+            }
+            _ => bytes,
+        }
+    }
+
+    fn block_at(column: &ChunkColumn, i: usize, section_y: u32) -> BlockID {
+        let i = i as u32;
+        column
+            .get_chunk(section_y as i32)
+            .get_block(i % 16, i / 256, (i / 16) % 16)
+    }
+
+    // Every palette entry appears, and the indices that span two longs in the old layout too
+    fn value_at(i: usize) -> u64 {
+        (i * 7 % PALETTE.len()) as u64
+    }
+
+    fn expected_block(i: usize) -> BlockID {
+        translate_block(PALETTE[value_at(i) as usize]).unwrap()
+    }
+
+    fn read_tag(tag: &Tag) -> Result<ChunkColumn, NbtError> {
+        let column = ChunkColumn::new();
+        read_column(tag, &column)?;
+        Ok(column)
+    }
+
+    #[test]
+    fn header_locates_the_columns() {
+        let regions = TempRegions::new("header");
+        let stone = column_tag(
+            DATA_VERSION_1_18,
+            vec![section(DATA_VERSION_1_18, 0, |_| 1)],
+        );
+        let dirt = column_tag(
+            DATA_VERSION_1_18,
+            vec![section(DATA_VERSION_1_18, 0, |_| 2)],
+        );
+        // The column (-1, 33) is the last of its region along x and the second along z
+        regions.write_region(
+            "r.-1.1.mca",
+            &[
+                ((31, 1), 2, UNCOMPRESSED, encode(&stone, UNCOMPRESSED)),
+                ((0, 0), 4, UNCOMPRESSED, encode(&dirt, UNCOMPRESSED)),
+            ],
+        );
+        let world = AnvilWorld::open(&regions.0).unwrap();
+
+        let column = ChunkColumn::new();
+        assert!(world.load_column(-1, 33, &column).unwrap());
+        assert_eq!(block_at(&column, 0, 0), BlockID::Stone);
+
+        let column = ChunkColumn::new();
+        assert!(world.load_column(-32, 32, &column).unwrap());
+        assert_eq!(block_at(&column, 4095, 0), BlockID::Dirt);
+
+        // Never generated, in an existing region and in a missing one
+        assert!(!world.load_column(-2, 33, &ChunkColumn::new()).unwrap());
+        assert!(!world.load_column(0, 0, &ChunkColumn::new()).unwrap());
+    }
+
+    #[test]
+    fn reads_every_compression() {
+        let regions = TempRegions::new("compression");
+        let tag = column_tag(
+            DATA_VERSION_1_18,
+            vec![section(DATA_VERSION_1_18, 0, value_at)],
+        );
+        regions.write_region(
+            "r.0.0.mca",
+            &[
+                ((0, 0), 2, GZIP, encode(&tag, GZIP)),
+                ((1, 0), 4, ZLIB, encode(&tag, ZLIB)),
+                ((2, 0), 6, UNCOMPRESSED, encode(&tag, UNCOMPRESSED)),
+            ],
+        );
+        let world = AnvilWorld::open(regions.0.join("region")).unwrap();
+
+        for x in 0..3 {
+            let column = ChunkColumn::new();
+            assert!(world.load_column(x, 0, &column).unwrap());
+            for i in 0..4096 {
+                assert_eq!(block_at(&column, i, 0), expected_block(i), "column {x}");
+            }
+        }
+    }
+
+    #[test]
+    fn unpacks_the_block_states_spanning_two_longs() {
+        let tag = column_tag(
+            DATA_VERSION_1_15,
+            vec![section(DATA_VERSION_1_15, 2, value_at)],
+        );
+        let column = read_tag(&tag).unwrap();
+
+        for i in 0..4096 {
+            assert_eq!(block_at(&column, i, 2), expected_block(i));
+        }
+    }
+
+    #[test]
+    fn unpacks_the_padded_block_states() {
+        let tag = column_tag(
+            DATA_VERSION_1_16,
+            vec![section(DATA_VERSION_1_16, 2, value_at)],
+        );
+        let column = read_tag(&tag).unwrap();
+
+        for i in 0..4096 {
+            assert_eq!(block_at(&column, i, 2), expected_block(i));
+        }
+
+        // The same data read with the other layout is too short
+        let tag = column_tag(
+            DATA_VERSION_1_15,
+            vec![section(DATA_VERSION_1_16, 2, value_at)],
+        );
+        assert!(read_tag(&tag).is_err());
+    }
+
+    #[test]
+    fn sections_of_a_single_block_have_no_data() {
+        let single = |y: i8, name: &str| {
+            compound(vec![
+                ("Y", Tag::Byte(y)),
+                (
+                    "block_states",
+                    compound(vec![("palette", palette_tag(&[name]))]),
+                ),
+            ])
+        };
+        let tag = column_tag(
+            DATA_VERSION_1_18,
+            vec![
+                // Below the column, dropped
+                single(-4, "minecraft:stone"),
+                single(0, "minecraft:deepslate"),
+                single(1, "minecraft:air"),
+                // Only the light
+                compound(vec![("Y", Tag::Byte(2))]),
+            ],
+        );
+        let column = read_tag(&tag).unwrap();
+
+        // Unknown blocks are replaced with stone
+        assert_eq!(block_at(&column, 0, 0), BlockID::Stone);
+        assert_eq!(block_at(&column, 4095, 0), BlockID::Stone);
+        assert_eq!(block_at(&column, 0, 1), BlockID::Air);
+        assert_eq!(block_at(&column, 0, 2), BlockID::Air);
+    }
+
+    #[test]
+    fn worlds_before_1_13_are_rejected() {
+        let tag = column_tag(1343, vec![section(DATA_VERSION_1_15, 0, value_at)]);
+        assert!(read_tag(&tag).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+extern crate pretty_env_logger;
+#[macro_use]
+extern crate log;
+
+use minecraft::anvil::AnvilWorld;
+use minecraft::chunk_manager::ChunkManager;
+use minecraft::constants::{WORLD_SEED, WORLD_SEED_OVERRIDE};
+use minecraft::ecs::systems::fps_counter::FpsCounter;
+use minecraft::ecs::systems::*;
+use minecraft::game_mode::GameMode;
+use minecraft::health::Health;
+use minecraft::journal::{EditJournal, JOURNAL_PATH};
+use minecraft::metrics::Metrics;
+use minecraft::mob::{Mob, MobPhysicsState};
+use minecraft::network::server::ServerNetwork;
+use minecraft::physics::Interpolator;
+use minecraft::player::{PlayerPhysicsState, PlayerState};
+use minecraft::server::{
+    read_commands_from_stdin, ServerProperties, ServerState, DEFAULT_PROPERTIES_PATH,
+};
+use minecraft::timer::Timer;
+use minecraft::weather::Weather;
+use minecraft::world_time::WorldTime;
+use nalgebra_glm::vec3;
+use specs::{DispatcherBuilder, World, WorldExt};
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Runs the world without a window: chunk generation, mobs and the commands typed in the terminal
+fn main() {
+    let mut log_builder = pretty_env_logger::formatted_builder();
+    log_builder.parse_filters("info").init();
+
+    let properties = match ServerProperties::load_or_create(Path::new(DEFAULT_PROPERTIES_PATH)) {
+        Ok(properties) => properties,
+        Err(err) => {
+            error!("Could not load {DEFAULT_PROPERTIES_PATH}: {err}");
+            process::exit(1);
+        }
+    };
+
+    if let Some(seed) = properties.level_seed {
+        WORLD_SEED_OVERRIDE.set(seed).unwrap();
+    }
+    info!("Starting the server with the seed {}", *WORLD_SEED);
+
+    let network = match ServerNetwork::bind(properties.server_port) {
+        Ok(network) => network,
+        Err(err) => {
+            error!("Could not listen on port {}: {err}", properties.server_port);
+            process::exit(1);
+        }
+    };
+    info!("Listening on port {}", properties.server_port);
+
+    let mut chunk_loading =
+        ServerChunkLoading::new(properties.view_distance, &properties.spawn_point);
+    if let Some(path) = &properties.import_world {
+        match AnvilWorld::open(path) {
+            Ok(world) => {
+                info!("Importing the terrain of {}", path.display());
+                chunk_loading = chunk_loading.with_imported_world(world);
+            }
+            Err(err) => {
+                error!("Could not import {}: {err}", path.display());
+                process::exit(1);
+            }
+        }
+    }
+
+    let journal = match EditJournal::open(JOURNAL_PATH, *WORLD_SEED) {
+        Ok(journal) => journal,
+        Err(err) => {
+            error!("Could not read {JOURNAL_PATH}: {err}");
+            process::exit(1);
+        }
+    };
+
+    let spawn_point = properties.spawn_point;
+    let spawn_point = vec3(
+        spawn_point.x as f32,
+        spawn_point.y as f32,
+        spawn_point.z as f32,
+    );
+
+    let mut world = World::new();
+    world.register::<PlayerState>();
+    world.register::<Interpolator<PlayerPhysicsState>>();
+    world.register::<GameMode>();
+    world.register::<Health>();
+    world.register::<Mob>();
+    world.register::<Interpolator<MobPhysicsState>>();
+
+    let mut dispatcher = DispatcherBuilder::new()
+        .with_thread_local(HandleServerCommands::new(read_commands_from_stdin()))
+        .with_thread_local(AcceptConnections)
+        .with_thread_local(HandleClientPackets::new(
+            spawn_point,
+            properties.max_players,
+        ))
+        .with_thread_local(UpdateMobBehaviour)
+        .with_thread_local(UpdateMobPhysics)
+        .with_thread_local(MobAttack)
+        .with_thread_local(UpdateClientsHealth)
+        .with_thread_local(chunk_loading)
+        // Before the columns are sent, so that they are sent with their edited blocks
+        .with_thread_local(UpdateEditJournal::new())
+        .with_thread_local(SendChunkColumns::new(properties.view_distance))
+        .with_thread_local(BroadcastEntities::new(properties.view_distance))
+        .with_thread_local(BroadcastBlockChanges)
+        .with_thread_local(UpdateWeather::new())
+        .with_thread_local(BroadcastTime::new())
+        .with_thread_local(BroadcastWeather::new())
+        .with_thread_local(DespawnMobs)
+        .with_thread_local(SpawnMobs::new())
+        .with_thread_local(ClearChangedBlocks)
+        .with_thread_local(AdvanceGlobalTime)
+        .with_thread_local(FpsCounter::new())
+        .build();
+
+    world.insert(Timer::default());
+    world.insert(WorldTime::default());
+    world.insert(Weather::new(properties.weather_durations.clone()));
+    world.insert(Metrics::default());
+    world.insert(ServerState::default());
+    world.insert(network);
+    world.insert(journal);
+    world.insert(Arc::new(ChunkManager::new()));
+
+    info!(
+        "Running at {} ticks per second, type /help for the list of commands",
+        properties.ticks_per_second
+    );
+
+    let tick_duration = Duration::from_secs_f32(1.0 / properties.ticks_per_second as f32);
+
+    while world.read_resource::<ServerState>().is_running {
+        let tick_start = Instant::now();
+
+        dispatcher.dispatch(&world);
+        // Applies the entity creations and deletions of the tick
+        world.maintain();
+
+        // Sleep for the rest of the tick, an overloaded server simply runs slower
+        let elapsed = tick_start.elapsed();
+        if elapsed < tick_duration {
+            thread::sleep(tick_duration - elapsed);
+        } else {
+            debug!("Tick took {:.2} ms", elapsed.as_secs_f32() * 1000.0);
+        }
+    }
+
+    if let Err(err) = world.write_resource::<EditJournal>().save() {
+        error!("Could not save {JOURNAL_PATH}: {err}");
+    }
+    info!("Server stopped");
+}
@@ -1,15 +1,18 @@
 use crate::chunk_manager::{CHUNK_SIZE, CHUNK_VOLUME};
-use crate::gl_call;
+use crate::inventory::tool::{ToolKind, ToolMaterial};
+#[cfg(feature = "client")]
 use crate::shapes::write_unit_cube_to_ptr;
+#[cfg(feature = "client")]
 use crate::types::TexturePack;
 use bit_vec::BitVec;
 use parking_lot::RwLock;
 use rand::distributions::Standard;
 use rand::prelude::Distribution;
 use rand::random;
+#[cfg(feature = "client")]
 use std::ptr::null;
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum BlockID {
     Air,
     Dirt,
@@ -22,6 +25,7 @@ pub enum BlockID {
     OakLeaves,
     OakPlanks,
     Glass,
+    Lava,
     Debug,
     Debug2,
 }
@@ -32,6 +36,18 @@ impl BlockID {
         self == &BlockID::Air
     }
 
+    // Whether entities collide with the block and the player can target it
+    #[inline]
+    pub fn is_solid(&self) -> bool {
+        !matches!(self, BlockID::Air | BlockID::Lava)
+    }
+
+    // Entities avoid walking through fluids
+    #[inline]
+    pub fn is_fluid(&self) -> bool {
+        *self == BlockID::Lava
+    }
+
     #[inline]
     pub fn is_transparent(&self) -> bool {
         match self {
@@ -60,6 +76,92 @@ impl BlockID {
             _ => false,
         }
     }
+
+    pub const ALL: [BlockID; 14] = [
+        BlockID::Air,
+        BlockID::Dirt,
+        BlockID::GrassBlock,
+        BlockID::Stone,
+        BlockID::Cobblestone,
+        BlockID::Bedrock,
+        BlockID::Obsidian,
+        BlockID::OakLog,
+        BlockID::OakLeaves,
+        BlockID::OakPlanks,
+        BlockID::Glass,
+        BlockID::Lava,
+        BlockID::Debug,
+        BlockID::Debug2,
+    ];
+
+    // Name used by commands to refer to the block, follows the Minecraft naming
+    pub fn name(&self) -> &'static str {
+        match self {
+            BlockID::Air => "air",
+            BlockID::Dirt => "dirt",
+            BlockID::GrassBlock => "grass_block",
+            BlockID::Stone => "stone",
+            BlockID::Cobblestone => "cobblestone",
+            BlockID::Bedrock => "bedrock",
+            BlockID::Obsidian => "obsidian",
+            BlockID::OakLog => "oak_log",
+            BlockID::OakLeaves => "oak_leaves",
+            BlockID::OakPlanks => "oak_planks",
+            BlockID::Glass => "glass",
+            BlockID::Lava => "lava",
+            BlockID::Debug => "debug",
+            BlockID::Debug2 => "debug2",
+        }
+    }
+
+    // Time factor of the mining of the block, None when the block cannot be broken
+    pub fn hardness(&self) -> Option<f32> {
+        match self {
+            BlockID::Air => Some(0.0),
+            BlockID::Dirt => Some(0.5),
+            BlockID::GrassBlock => Some(0.6),
+            BlockID::Stone => Some(1.5),
+            BlockID::Cobblestone => Some(2.0),
+            BlockID::Bedrock => None,
+            BlockID::Obsidian => Some(50.0),
+            BlockID::OakLog => Some(2.0),
+            BlockID::OakLeaves => Some(0.2),
+            BlockID::OakPlanks => Some(2.0),
+            BlockID::Glass => Some(0.3),
+            BlockID::Lava => None,
+            BlockID::Debug => Some(0.0),
+            BlockID::Debug2 => Some(0.0),
+        }
+    }
+
+    // The tool that mines the block faster
+    pub fn preferred_tool(&self) -> Option<ToolKind> {
+        match self {
+            BlockID::Dirt | BlockID::GrassBlock => Some(ToolKind::Shovel),
+            BlockID::Stone | BlockID::Cobblestone | BlockID::Obsidian => Some(ToolKind::Pickaxe),
+            BlockID::OakLog | BlockID::OakPlanks => Some(ToolKind::Axe),
+            _ => None,
+        }
+    }
+
+    // The minimum material of the preferred tool for the block to drop an item
+    pub fn required_tool_material(&self) -> Option<ToolMaterial> {
+        match self {
+            BlockID::Stone | BlockID::Cobblestone => Some(ToolMaterial::Wooden),
+            BlockID::Obsidian => Some(ToolMaterial::Diamond),
+            _ => None,
+        }
+    }
+
+    // Accepts names with or without the "minecraft:" namespace
+    pub fn from_name(name: &str) -> Option<BlockID> {
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+
+        BlockID::ALL
+            .iter()
+            .find(|block| block.name() == name)
+            .copied()
+    }
 }
 
 impl Distribution<BlockID> for Standard {
@@ -74,6 +176,7 @@ impl Distribution<BlockID> for Standard {
     }
 }
 
+#[cfg(feature = "client")]
 fn create_vao_vbo() -> (u32, u32) {
     let mut vao = 0;
     gl_call!(gl::CreateVertexArrays(1, &mut vao));
@@ -255,13 +358,22 @@ impl ChunkColumn {
     #[inline]
     pub fn set_block(&self, x: u32, y: u32, z: u32, block: BlockID) {
         self.chunks[(y / 16) as usize].set_block(x, y % 16, z, block);
+        self.update_highest_block(x, y, z, block);
+    }
 
+    // Raises the highest block of (x, z) when `block` is placed above it, or lowers it to the next
+    // block below when the highest block is removed
+    pub fn update_highest_block(&self, x: u32, y: u32, z: u32, block: BlockID) {
         let mut highest_blocks = self.highest_blocks.write();
-        let y = y as u8;
         let idx = (z * 16 + x) as usize;
 
-        if y > highest_blocks[idx] {
-            highest_blocks[idx] = y;
+        if !block.is_air() {
+            highest_blocks[idx] = highest_blocks[idx].max(y as u8);
+        } else if y as u8 == highest_blocks[idx] {
+            highest_blocks[idx] = (0..y)
+                .rev()
+                .find(|&y| !self.get_chunk(y as i32 / 16).get_block(x, y % 16, z).is_air())
+                .unwrap_or(0) as u8;
         }
     }
 }
@@ -402,6 +514,37 @@ impl Chunk {
         self.blocks.write()[index] = block;
     }
 
+    // Like `set_block` for many blocks, the locks are only taken once
+    // Returns the blocks that were replaced, in the same order
+    pub fn set_blocks(&self, edits: &[(u32, u32, u32, BlockID)]) -> Vec<BlockID> {
+        let mut blocks = self.blocks.write();
+        let mut number_of_opaque_blocks = self.number_of_opaque_blocks.write();
+        let mut number_of_transparent_blocks = self.number_of_transparent_blocks.write();
+
+        edits
+            .iter()
+            .map(|&(x, y, z, block)| {
+                let index = Chunk::chunk_coords_to_array_index(x, y, z);
+                let target = blocks[index];
+
+                if target.is_transparent_not_air() {
+                    *number_of_transparent_blocks -= 1;
+                } else if target.is_opaque() {
+                    *number_of_opaque_blocks -= 1;
+                }
+                if block.is_transparent_not_air() {
+                    *number_of_transparent_blocks += 1;
+                } else if block.is_opaque() {
+                    *number_of_opaque_blocks += 1;
+                }
+
+                blocks[index] = block;
+                target
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "client")]
     pub fn unload_from_gpu(&self) {
         *self.is_uploaded_to_gpu.write() = false;
 
@@ -417,6 +560,7 @@ impl Chunk {
         }
     }
 
+    #[cfg(feature = "client")]
     pub fn upload_to_gpu(&self, texture_pack: &TexturePack) {
         if *self.vao.read() == 0 && *self.vbo.read() == 0 {
             let (vao, vbo) = create_vao_vbo();
@@ -1,8 +1,14 @@
+#[cfg(feature = "client")]
+use crate::aabb::AABB;
 use crate::ambient_occlusion::compute_ao_of_block;
-use crate::chunk::{BlockID, BlockIterator, Chunk, ChunkColumn};
+use crate::chunk::{BlockID, Chunk, ChunkColumn};
+#[cfg(feature = "client")]
+use crate::frustum::Frustum;
+#[cfg(feature = "client")]
 use crate::shader::ShaderProgram;
-use crate::types::TexturePack;
+#[cfg(feature = "client")]
 use nalgebra::Matrix4;
+#[cfg(feature = "client")]
 use nalgebra_glm::vec3;
 use owning_ref::OwningRef;
 use parking_lot::RwLock;
@@ -12,10 +18,71 @@ use std::sync::Arc;
 pub const CHUNK_SIZE: u32 = 16;
 pub const CHUNK_VOLUME: u32 = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
 
+// Position, block before the edit and block after the edit
+pub type BlockChange = ((i32, i32, i32), BlockID, BlockID);
+// Position of a block in its chunk and block after the edit
+pub type ChunkEdit = (u32, u32, u32, BlockID);
+// Minimum and maximum corners of a cuboid of blocks
+pub type BlockRegion = ((i32, i32, i32), (i32, i32, i32));
+// Blocks to remesh by chunk, with the priority of the update and their position in the chunk
+pub type DirtyBlocks = HashMap<(i32, i32, i32), Vec<(i32, u32, u32, u32)>>;
+
+// Edits of many blocks, possibly in many columns, applied at once by `ChunkManager::apply_batch`
+// They are grouped by chunk so that each chunk is locked and meshed only once
+#[derive(Default)]
+pub struct BlockEditBatch {
+    edits: HashMap<(i32, i32, i32), Vec<ChunkEdit>>,
+    len: usize,
+}
+
+impl BlockEditBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The blocks above and below the world are ignored, the last edit of a block wins
+    pub fn put_block(&mut self, x: i32, y: i32, z: i32, block: BlockID) {
+        let (chunk_x, chunk_y, chunk_z, block_x, block_y, block_z) =
+            ChunkManager::get_chunk_coords(x, y, z);
+        if !(0..16).contains(&chunk_y) {
+            return;
+        }
+
+        self.edits
+            .entry((chunk_x, chunk_y, chunk_z))
+            .or_default()
+            .push((block_x, block_y, block_z, block));
+        self.len += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl FromIterator<((i32, i32, i32), BlockID)> for BlockEditBatch {
+    fn from_iter<I: IntoIterator<Item = ((i32, i32, i32), BlockID)>>(edits: I) -> Self {
+        let mut batch = Self::new();
+        for ((x, y, z), block) in edits {
+            batch.put_block(x, y, z, block);
+        }
+        batch
+    }
+}
+
 #[derive(Default)]
 pub struct ChunkManager {
     pub loaded_chunk_columns: RwLock<HashMap<(i32, i32), Arc<ChunkColumn>>>,
     pub(crate) block_changelist: RwLock<HashSet<(i32, BlockID, i32, i32, i32)>>,
+    // Minimum and maximum corners of the blocks edited by `apply_batch` in each chunk, remeshed
+    // with the changelist
+    pub(crate) dirty_regions: RwLock<Vec<BlockRegion>>,
+    // Blocks modified by `put_block` during the current frame
+    changed_blocks: RwLock<Vec<(i32, i32, i32)>>,
 }
 
 impl ChunkManager {
@@ -23,6 +90,8 @@ impl ChunkManager {
         Self {
             loaded_chunk_columns: RwLock::new(HashMap::new()),
             block_changelist: RwLock::new(HashSet::new()),
+            dirty_regions: RwLock::new(Vec::new()),
+            changed_blocks: RwLock::new(Vec::new()),
         }
     }
 
@@ -134,20 +203,193 @@ impl ChunkManager {
         // if self.set_block(x, y, z, block) {
         //     self.block_changelist.write().insert((1, block, x, y, z));
         // }
-        self.set_block_internal(1, x, y, z, block)
+        if !self.set_block_internal(1, x, y, z, block) {
+            return false;
+        }
+
+        let (chunk_x, _, chunk_z, block_x, _, block_z) = ChunkManager::get_chunk_coords(x, y, z);
+        if let Some(column) = self.get_column(chunk_x, chunk_z) {
+            column.update_highest_block(block_x, y as u32, block_z, block);
+        }
+
+        self.changed_blocks.write().push((x, y, z));
+        true
+    }
+
+    // Like `put_block` for many blocks, each chunk is locked once and the affected chunks and their
+    // neighbours are meshed once, instead of once per block
+    // Returns the blocks that changed, the blocks in columns that are not loaded are skipped
+    pub fn apply_batch(&self, batch: BlockEditBatch) -> Vec<BlockChange> {
+        self.apply_batch_internal(batch, true)
+    }
+
+    // Like `apply_batch` but the blocks are not in `changed_blocks`, like `set_block`
+    // Should be used for blocks that are not new edits, like the ones put back by the journal
+    pub fn set_batch(&self, batch: BlockEditBatch) -> Vec<BlockChange> {
+        self.apply_batch_internal(batch, false)
+    }
+
+    fn apply_batch_internal(&self, batch: BlockEditBatch, is_edit: bool) -> Vec<BlockChange> {
+        let columns = {
+            let loaded_chunk_columns = self.loaded_chunk_columns.read();
+
+            batch
+                .edits
+                .keys()
+                .filter_map(|&(chunk_x, _, chunk_z)| {
+                    let column = loaded_chunk_columns.get(&(chunk_x, chunk_z))?;
+                    Some(((chunk_x, chunk_z), Arc::clone(column)))
+                })
+                .collect::<HashMap<_, _>>()
+        };
+
+        let mut changes = Vec::with_capacity(batch.len);
+        let mut dirty_regions = Vec::new();
+
+        for ((chunk_x, chunk_y, chunk_z), edits) in batch.edits {
+            let column = match columns.get(&(chunk_x, chunk_z)) {
+                Some(column) => column,
+                None => continue,
+            };
+            let chunk = column.get_chunk(chunk_y);
+            let previous_blocks = chunk.set_blocks(&edits);
+
+            let mut region: Option<BlockRegion> = None;
+            for (&(block_x, block_y, block_z, block), previous) in edits.iter().zip(previous_blocks)
+            {
+                if previous == block {
+                    continue;
+                }
+
+                let (x, y, z) = ChunkManager::get_global_coords((
+                    chunk_x, chunk_y, chunk_z, block_x, block_y, block_z,
+                ));
+                changes.push(((x, y, z), previous, block));
+                column.update_highest_block(block_x, y as u32, block_z, block);
+
+                let (min, max) = region.get_or_insert(((x, y, z), (x, y, z)));
+                *min = (min.0.min(x), min.1.min(y), min.2.min(z));
+                *max = (max.0.max(x), max.1.max(y), max.2.max(z));
+            }
+
+            if let Some(region) = region {
+                if *chunk.is_uploaded_to_gpu.read() {
+                    dirty_regions.push(region);
+                }
+            }
+        }
+
+        self.dirty_regions.write().extend(dirty_regions);
+        if is_edit {
+            self.changed_blocks
+                .write()
+                .extend(changes.iter().map(|&(position, _, _)| position));
+        }
+
+        changes
+    }
+
+    // Used to invalidate what depends on the blocks, like the paths of the mobs
+    pub fn changed_blocks(&self) -> Vec<(i32, i32, i32)> {
+        self.changed_blocks.read().clone()
+    }
+
+    pub fn clear_changed_blocks(&self) {
+        self.changed_blocks.write().clear();
     }
 
     pub fn set_block(&self, x: i32, y: i32, z: i32, block: BlockID) -> bool {
         self.set_block_internal(0, x, y, z, block)
     }
 
+    // Trees are placed across the borders of the columns, so a column is final once its neighbours
+    // have their trees too
+    pub fn is_column_final(&self, x: i32, z: i32) -> bool {
+        (-1..=1).all(|dx| {
+            (-1..=1).all(|dz| {
+                self.get_column(x + dx, z + dz)
+                    .is_some_and(|column| *column.has_foliage.read())
+            })
+        })
+    }
+
+    // Height of the highest block of the loaded column, None if the column is not loaded
+    pub fn highest_block(&self, x: i32, z: i32) -> Option<i32> {
+        let column = self.get_column(x.div_euclid(16), z.div_euclid(16))?;
+        let highest_blocks = column.highest_blocks.read();
+
+        Some(highest_blocks[(16 * z.rem_euclid(16) + x.rem_euclid(16)) as usize] as i32)
+    }
+
     pub fn is_solid_block_at(&self, x: i32, y: i32, z: i32) -> bool {
         self.get_block(x, y, z)
-            .filter(|&block| block != BlockID::Air)
+            .filter(|block| block.is_solid())
             .is_some()
     }
 
-    pub fn update_blocks<I>(&self, chunk_x: i32, chunk_y: i32, chunk_z: i32, blocks: I) where I: Iterator<Item=(u32, u32, u32)> {
+    // Takes the blocks changed since the last call, with their neighbours, grouped by chunk
+    // The chunks are remeshed with `update_blocks`
+    pub fn take_dirty_blocks(&self) -> DirtyBlocks {
+        let mut changelist_per_chunk: DirtyBlocks = HashMap::new();
+
+        for &change in self.block_changelist.read().iter() {
+            for x in -1..=1 {
+                for y in -1..=1 {
+                    for z in -1..=1 {
+                        let (chunk_x, chunk_y, chunk_z, block_x, block_y, block_z) =
+                            ChunkManager::get_chunk_coords(
+                                change.2 + x,
+                                change.3 + y,
+                                change.4 + z,
+                            );
+                        changelist_per_chunk
+                            .entry((chunk_x, chunk_y, chunk_z))
+                            .or_default()
+                            .push((change.0, block_x, block_y, block_z));
+                    }
+                }
+            }
+        }
+
+        self.block_changelist.write().clear();
+
+        // Blocks edited by batches, with a margin of one block for the faces and the ambient
+        // occlusion of their neighbours
+        for (min, max) in self.dirty_regions.write().drain(..) {
+            let (min_chunk_x, min_chunk_y, min_chunk_z, _, _, _) =
+                ChunkManager::get_chunk_coords(min.0 - 1, min.1 - 1, min.2 - 1);
+            let (max_chunk_x, max_chunk_y, max_chunk_z, _, _, _) =
+                ChunkManager::get_chunk_coords(max.0 + 1, max.1 + 1, max.2 + 1);
+
+            for chunk_x in min_chunk_x..=max_chunk_x {
+                for chunk_y in min_chunk_y.max(0)..=max_chunk_y.min(15) {
+                    for chunk_z in min_chunk_z..=max_chunk_z {
+                        let local =
+                            |value: i32, chunk: i32| (value - 16 * chunk).clamp(0, 15) as u32;
+                        let dirty_blocks = changelist_per_chunk
+                            .entry((chunk_x, chunk_y, chunk_z))
+                            .or_default();
+
+                        for block_y in local(min.1 - 1, chunk_y)..=local(max.1 + 1, chunk_y) {
+                            for block_z in local(min.2 - 1, chunk_z)..=local(max.2 + 1, chunk_z) {
+                                for block_x in local(min.0 - 1, chunk_x)..=local(max.0 + 1, chunk_x)
+                                {
+                                    dirty_blocks.push((1, block_x, block_y, block_z));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        changelist_per_chunk
+    }
+
+    pub fn update_blocks<I>(&self, chunk_x: i32, chunk_y: i32, chunk_z: i32, blocks: I)
+    where
+        I: Iterator<Item = (u32, u32, u32)>,
+    {
         let this_column = match self.get_column(chunk_x, chunk_z) {
             Some(column) => column,
             None => {
@@ -361,7 +603,17 @@ impl ChunkManager {
         [right, left, top, bottom, front, back]
     }
 
-    pub fn render_loaded_chunks(&self, program: &mut ShaderProgram) {
+    // Renders the chunks that are inside the frustum
+    #[cfg(feature = "client")]
+    // Returns the number of chunks drawn and the number of chunks culled
+    pub fn render_loaded_chunks(
+        &self,
+        program: &mut ShaderProgram,
+        frustum: &Frustum,
+    ) -> (usize, usize) {
+        let mut chunks_drawn = 0;
+        let mut chunks_culled = 0;
+
         for ((x, z), chunk_column) in self.loaded_chunk_columns.read().iter() {
             for (ref y, chunk) in chunk_column.chunks.iter().enumerate() {
                 // Skip rendering the chunk if there is nothing to draw
@@ -371,6 +623,18 @@ impl ChunkManager {
                     continue;
                 }
 
+                let chunk_aabb = {
+                    let mins = vec3(*x as f32, *y as f32, *z as f32).scale(CHUNK_SIZE as f32);
+                    AABB::new(mins, mins.add_scalar(CHUNK_SIZE as f32))
+                };
+
+                if !frustum.intersects_aabb(&chunk_aabb) {
+                    chunks_culled += 1;
+                    continue;
+                }
+
+                chunks_drawn += 1;
+
                 let model_matrix = {
                     let translate_matrix = Matrix4::new_translation(
                         &vec3(*x as f32, *y as f32, *z as f32).scale(16.0),
@@ -400,5 +664,59 @@ impl ChunkManager {
                 ));
             }
         }
+
+        (chunks_drawn, chunks_culled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world() -> ChunkManager {
+        let chunk_manager = ChunkManager::new();
+        chunk_manager.add_chunk_column((0, 0), Arc::new(ChunkColumn::new()));
+        chunk_manager
+    }
+
+    #[test]
+    fn put_block_moves_the_highest_block() {
+        let chunk_manager = world();
+        chunk_manager.put_block(3, 10, 4, BlockID::Stone);
+        chunk_manager.put_block(3, 40, 4, BlockID::Glass);
+        assert_eq!(chunk_manager.highest_block(3, 4), Some(40));
+
+        // A block below the highest one changes nothing
+        chunk_manager.put_block(3, 10, 4, BlockID::Air);
+        assert_eq!(chunk_manager.highest_block(3, 4), Some(40));
+
+        chunk_manager.put_block(3, 5, 4, BlockID::Dirt);
+        chunk_manager.put_block(3, 40, 4, BlockID::Air);
+        assert_eq!(chunk_manager.highest_block(3, 4), Some(5));
+        assert_eq!(chunk_manager.highest_block(4, 4), Some(0));
+    }
+
+    #[test]
+    fn apply_batch_moves_the_highest_block() {
+        let chunk_manager = world();
+        let pillar = |block| (0..50).map(move |y| ((7, y, 7), block));
+
+        chunk_manager.apply_batch(pillar(BlockID::Stone).collect());
+        assert_eq!(chunk_manager.highest_block(7, 7), Some(49));
+
+        // Removed from the top, in several chunks
+        chunk_manager.apply_batch(pillar(BlockID::Air).skip(20).collect());
+        assert_eq!(chunk_manager.highest_block(7, 7), Some(19));
+
+        // Removed and placed above in the same batch
+        let batch = pillar(BlockID::Air)
+            .take(20)
+            .chain([((7, 30, 7), BlockID::Glass)])
+            .collect();
+        chunk_manager.apply_batch(batch);
+        assert_eq!(chunk_manager.highest_block(7, 7), Some(30));
+
+        chunk_manager.set_batch([((7, 30, 7), BlockID::Air)].into_iter().collect());
+        assert_eq!(chunk_manager.highest_block(7, 7), Some(0));
     }
 }
@@ -0,0 +1,191 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+
+// Like in the vanilla game, the clouds are made of cells of 12 blocks
+pub const CLOUD_CELL_SIZE: f32 = 12.0;
+pub const CLOUD_THICKNESS: f32 = 4.0;
+// Above the highest mountains
+pub const CLOUD_HEIGHT: f32 = 230.0;
+// Cells on each side of the map, which repeats itself over the whole sky
+pub const CLOUD_MAP_SIZE: usize = 64;
+pub const CLOUD_TILE_SIZE: f32 = CLOUD_MAP_SIZE as f32 * CLOUD_CELL_SIZE;
+// Blocks moved by the clouds every tick of the world time
+pub const CLOUD_SPEED: f64 = 0.03;
+// Fraction of the sky covered by the clouds
+const CLOUD_COVERAGE: f32 = 0.4;
+// Cells between the points of the noise of every octave, halved from one octave to the next
+const NOISE_OCTAVES: [usize; 3] = [16, 8, 4];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CloudMode {
+    Off,
+    // A single layer of flat clouds
+    Fast,
+    // Boxes of clouds, drawn twice so that only their closest faces are visible
+    Fancy,
+}
+
+impl CloudMode {
+    pub const ALL: [CloudMode; 3] = [CloudMode::Off, CloudMode::Fast, CloudMode::Fancy];
+
+    pub fn next(&self) -> Self {
+        match self {
+            CloudMode::Off => CloudMode::Fast,
+            CloudMode::Fast => CloudMode::Fancy,
+            CloudMode::Fancy => CloudMode::Off,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CloudMode::Off => "off",
+            CloudMode::Fast => "fast",
+            CloudMode::Fancy => "fancy",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|mode| mode.name() == name)
+    }
+}
+
+// Which cells of the sky are cloudy, the map wraps around on both axes
+pub struct CloudMap {
+    cells: Vec<bool>,
+}
+
+impl CloudMap {
+    // The noise repeats itself every `CLOUD_MAP_SIZE` cells, so that the tiles of the map join
+    // without seams
+    pub fn generate(seed: u32) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed as u64);
+        let mut noise = vec![0.0; CLOUD_MAP_SIZE * CLOUD_MAP_SIZE];
+        let mut amplitude = 1.0;
+
+        for spacing in NOISE_OCTAVES {
+            let points = CLOUD_MAP_SIZE / spacing;
+            let values: Vec<f32> = (0..points * points).map(|_| rng.gen()).collect();
+            let value = |x: usize, z: usize| values[(z % points) * points + x % points];
+
+            for z in 0..CLOUD_MAP_SIZE {
+                for x in 0..CLOUD_MAP_SIZE {
+                    let (x0, z0) = (x / spacing, z / spacing);
+                    let tx = smoothstep((x % spacing) as f32 / spacing as f32);
+                    let tz = smoothstep((z % spacing) as f32 / spacing as f32);
+
+                    let top = lerp(value(x0, z0), value(x0 + 1, z0), tx);
+                    let bottom = lerp(value(x0, z0 + 1), value(x0 + 1, z0 + 1), tx);
+                    noise[z * CLOUD_MAP_SIZE + x] += lerp(top, bottom, tz) * amplitude;
+                }
+            }
+
+            amplitude *= 0.5;
+        }
+
+        // The noise is not evenly distributed, the threshold is picked from its values so that
+        // the coverage is always the same
+        let mut sorted = noise.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let threshold = sorted[((1.0 - CLOUD_COVERAGE) * sorted.len() as f32) as usize];
+
+        Self {
+            cells: noise.iter().map(|&value| value >= threshold).collect(),
+        }
+    }
+
+    pub fn is_cloud(&self, x: i32, z: i32) -> bool {
+        let size = CLOUD_MAP_SIZE as i32;
+        self.cells[(z.rem_euclid(size) * size + x.rem_euclid(size)) as usize]
+    }
+
+    // Positions and normals of the triangles of a tile of the map, from the origin to
+    // `CLOUD_TILE_SIZE` on the x and z axes
+    pub fn mesh(&self, mode: CloudMode) -> Vec<f32> {
+        let mut vertices = Vec::new();
+        let size = CLOUD_MAP_SIZE as i32;
+
+        for z in 0..size {
+            for x in 0..size {
+                if !self.is_cloud(x, z) {
+                    continue;
+                }
+
+                let min = [
+                    x as f32 * CLOUD_CELL_SIZE,
+                    CLOUD_HEIGHT,
+                    z as f32 * CLOUD_CELL_SIZE,
+                ];
+                let max = [
+                    min[0] + CLOUD_CELL_SIZE,
+                    CLOUD_HEIGHT + CLOUD_THICKNESS,
+                    min[2] + CLOUD_CELL_SIZE,
+                ];
+
+                if mode == CloudMode::Fast {
+                    add_face(&mut vertices, &min, &max, [0, -1, 0]);
+                    continue;
+                }
+
+                add_face(&mut vertices, &min, &max, [0, 1, 0]);
+                add_face(&mut vertices, &min, &max, [0, -1, 0]);
+
+                // The sides between two cloudy cells are inside the clouds
+                let sides = [
+                    ([-1, 0, 0], x - 1, z),
+                    ([1, 0, 0], x + 1, z),
+                    ([0, 0, -1], x, z - 1),
+                    ([0, 0, 1], x, z + 1),
+                ];
+                for (normal, neighbour_x, neighbour_z) in sides {
+                    if !self.is_cloud(neighbour_x, neighbour_z) {
+                        add_face(&mut vertices, &min, &max, normal);
+                    }
+                }
+            }
+        }
+
+        vertices
+    }
+}
+
+// Adds the 2 triangles of the face of the box on the side of the normal
+fn add_face(vertices: &mut Vec<f32>, min: &[f32; 3], max: &[f32; 3], normal: [i32; 3]) {
+    let axis = normal.iter().position(|&n| n != 0).unwrap();
+    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+    let plane = if normal[axis] > 0 {
+        max[axis]
+    } else {
+        min[axis]
+    };
+
+    let corner = |a: f32, b: f32| {
+        let mut position = [0.0; 3];
+        position[axis] = plane;
+        position[u] = a;
+        position[v] = b;
+        position
+    };
+    let corners = [
+        corner(min[u], min[v]),
+        corner(max[u], min[v]),
+        corner(max[u], max[v]),
+        corner(max[u], max[v]),
+        corner(min[u], max[v]),
+        corner(min[u], min[v]),
+    ];
+
+    for position in corners {
+        vertices.extend(position);
+        vertices.extend(normal.map(|n| n as f32));
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
@@ -0,0 +1,36 @@
+use crate::inventory::tool::Tool;
+use nalgebra_glm::{vec3, Vec3};
+use std::time::Duration;
+
+// Delay between two attacks of the player
+pub const PLAYER_ATTACK_COOLDOWN: Duration = Duration::from_millis(500);
+// Delay between two hits of a hostile mob touching the player
+pub const MOB_ATTACK_COOLDOWN: Duration = Duration::from_secs(1);
+// Time during which a hurt mob is tinted red and cannot be hurt again
+pub const MOB_HURT_DURATION: Duration = Duration::from_millis(500);
+pub const ATTACK_EXHAUSTION: f32 = 0.1;
+
+// Speeds given by a hit, in blocks per second
+pub const KNOCKBACK_HORIZONTAL_SPEED: f32 = 8.0;
+pub const KNOCKBACK_VERTICAL_SPEED: f32 = 6.0;
+// How fast the knockback fades out, per second
+pub const KNOCKBACK_FRICTION: f32 = 6.0;
+
+// Horizontal knockback pushing an entity at `to` away from `from`
+pub fn knockback(from: &Vec3, to: &Vec3) -> Vec3 {
+    let direction = vec3(to.x - from.x, 0.0, to.z - from.z);
+
+    if direction.norm_squared() < 0.0001 {
+        return vec3(0.0, 0.0, 0.0);
+    }
+
+    direction.normalize() * KNOCKBACK_HORIZONTAL_SPEED
+}
+
+// Damage of a hit of the player, in half hearts
+pub fn attack_damage(tool: Option<Tool>) -> u32 {
+    match tool {
+        Some(tool) => tool.attack_damage(),
+        None => 1,
+    }
+}
@@ -0,0 +1,600 @@
+use crate::chunk::BlockID;
+use crate::chunk_manager::BlockEditBatch;
+use crate::commands::{
+    ArgumentType, Arguments, Command, CommandContext, CommandDispatcher, CommandError,
+};
+use crate::constants::WORLD_SEED;
+use crate::game_mode::GameMode;
+use crate::health::{DamageSource, MAX_HEALTH};
+use crate::inventory::item::Item;
+use crate::player::PlayerPhysicsState;
+use crate::schematic::{Mirror, Schematic};
+use crate::screenshot::{default_screenshot_path, SCREENSHOTS_DIRECTORY};
+use crate::weather::WeatherKind;
+use crate::world_time::{named_time, TICKS_PER_DAY};
+use nalgebra_glm::{vec3, Vec3};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Maximum number of blocks that /fill can modify at once
+pub const MAX_FILL_VOLUME: i64 = 32768;
+// Maximum number of blocks that /export can save at once
+pub const MAX_EXPORT_VOLUME: i64 = 256 * 256 * 256;
+// Where /export and /import read and write the schematics
+pub const SCHEMATICS_DIRECTORY: &str = "schematics";
+
+const COORDINATE: ArgumentType = ArgumentType::Coordinate;
+
+pub fn register_builtin_commands(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(Command::new("help", "Lists the available commands", help));
+    dispatcher.register(
+        Command::new("tp", "Teleports the player", tp)
+            .argument("x", COORDINATE)
+            .argument("y", COORDINATE)
+            .argument("z", COORDINATE),
+    );
+    dispatcher.register(
+        Command::new("give", "Gives items to the player", give)
+            .argument("item", ArgumentType::Item)
+            .optional_argument("amount", ArgumentType::Integer { min: 1, max: 2304 }),
+    );
+    dispatcher.register(
+        Command::new("setblock", "Replaces a block", setblock)
+            .argument("x", COORDINATE)
+            .argument("y", COORDINATE)
+            .argument("z", COORDINATE)
+            .argument("block", ArgumentType::Block),
+    );
+    dispatcher.register(
+        Command::new("fill", "Fills a region with a block", fill)
+            .argument("x1", COORDINATE)
+            .argument("y1", COORDINATE)
+            .argument("z1", COORDINATE)
+            .argument("x2", COORDINATE)
+            .argument("y2", COORDINATE)
+            .argument("z2", COORDINATE)
+            .argument("block", ArgumentType::Block),
+    );
+    dispatcher.register(
+        Command::new("export", "Saves a region to a schematic file", export)
+            .argument("x1", COORDINATE)
+            .argument("y1", COORDINATE)
+            .argument("z1", COORDINATE)
+            .argument("x2", COORDINATE)
+            .argument("y2", COORDINATE)
+            .argument("z2", COORDINATE)
+            .argument("file", ArgumentType::Word),
+    );
+    dispatcher.register(
+        Command::new("import", "Pastes a schematic file", import)
+            .argument("x", COORDINATE)
+            .argument("y", COORDINATE)
+            .argument("z", COORDINATE)
+            .argument("file", ArgumentType::Word)
+            .optional_argument("rotation", ArgumentType::Choice(&["0", "90", "180", "270"]))
+            .optional_argument("mirror", ArgumentType::Choice(&["none", "x", "z"])),
+    );
+    dispatcher.register(
+        Command::new("undo", "Undoes the last edit of a player", undo)
+            .optional_argument("player", ArgumentType::Word),
+    );
+    dispatcher.register(
+        Command::new("redo", "Redoes the last edit undone of a player", redo)
+            .optional_argument("player", ArgumentType::Word),
+    );
+    dispatcher.register(
+        Command::new(
+            "time",
+            "Changes, displays, pauses or resumes the time",
+            time,
+        )
+        .argument(
+            "action",
+            ArgumentType::Choice(&["set", "add", "query", "pause", "resume"]),
+        )
+        .optional_argument("value", ArgumentType::Word),
+    );
+    dispatcher.register(
+        Command::new("weather", "Changes the weather", weather)
+            .argument("kind", ArgumentType::Choice(&["clear", "rain", "thunder"]))
+            .optional_argument("duration", ArgumentType::Integer { min: 1, max: 86400 }),
+    );
+    dispatcher.register(Command::new("seed", "Displays the world seed", seed));
+    dispatcher.register(
+        Command::new("screenshot", "Saves an image of the screen", screenshot)
+            .optional_argument("file", ArgumentType::Word),
+    );
+    dispatcher.register(
+        Command::new("gamemode", "Changes the game mode of the player", gamemode).argument(
+            "mode",
+            ArgumentType::Choice(&["survival", "creative", "spectator"]),
+        ),
+    );
+    dispatcher.register(
+        Command::new("fly", "Toggles flying", fly)
+            .optional_argument("state", ArgumentType::Choice(&["on", "off"])),
+    );
+    dispatcher.register(Command::new("kill", "Kills the player", kill));
+    dispatcher.register(
+        Command::new("spawnpoint", "Sets where the player respawns", spawnpoint)
+            .optional_argument("x", COORDINATE)
+            .optional_argument("y", COORDINATE)
+            .optional_argument("z", COORDINATE),
+    );
+}
+
+// Relative coordinates are relative to the player, or to the origin when there is no player
+pub(super) fn origin(context: &CommandContext) -> Vec3 {
+    match &context.player {
+        Some(player) => player.physics_state.get_latest_state().position,
+        None => vec3(0.0, 0.0, 0.0),
+    }
+}
+
+pub(super) fn block_position(
+    arguments: &Arguments,
+    origin: &Vec3,
+    names: [&'static str; 3],
+) -> Result<(i32, i32, i32), CommandError> {
+    Ok((
+        arguments.coordinate(names[0])?.resolve_block(origin.x),
+        arguments.coordinate(names[1])?.resolve_block(origin.y),
+        arguments.coordinate(names[2])?.resolve_block(origin.z),
+    ))
+}
+
+fn help(
+    dispatcher: &CommandDispatcher,
+    _context: &mut CommandContext,
+    _arguments: &Arguments,
+) -> Result<String, CommandError> {
+    Ok(dispatcher
+        .commands()
+        .map(|command| format!("{} - {}", command.usage(), command.description))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn tp(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let origin = origin(context);
+    let position = vec3(
+        arguments.coordinate("x")?.resolve(origin.x),
+        arguments.coordinate("y")?.resolve(origin.y),
+        arguments.coordinate("z")?.resolve(origin.z),
+    );
+
+    let player = context.player()?;
+    player
+        .physics_state
+        .reset_to(PlayerPhysicsState::new_at_position(position));
+
+    Ok(format!(
+        "Teleported to {:.2} {:.2} {:.2}",
+        position.x, position.y, position.z
+    ))
+}
+
+fn give(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let item = arguments.item("item")?;
+    let amount = if arguments.contains("amount") {
+        arguments.integer("amount")? as u32
+    } else {
+        1
+    };
+
+    if item == Item::Block(BlockID::Air) {
+        return Err(CommandError::Failed("Cannot give air".to_string()));
+    }
+
+    let player = context.player()?;
+    let leftover = player.inventory.add_item(item, amount);
+
+    if leftover == amount {
+        return Err(CommandError::Failed("The inventory is full".to_string()));
+    }
+
+    Ok(format!("Gave {} {}", amount - leftover, item.name()))
+}
+
+fn setblock(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let origin = origin(context);
+    let (x, y, z) = block_position(arguments, &origin, ["x", "y", "z"])?;
+    let block = arguments.block("block")?;
+
+    let previous = context.chunk_manager.get_block(x, y, z);
+
+    if context.chunk_manager.put_block(x, y, z, block) {
+        let author = context.author();
+        if let Some(previous) = previous.filter(|&previous| previous != block) {
+            context
+                .journal
+                .record(author, vec![((x, y, z), previous, block)]);
+        }
+
+        Ok(format!("Placed {} at {x} {y} {z}", block.name()))
+    } else {
+        Err(CommandError::Failed(format!(
+            "The block at {x} {y} {z} is not loaded"
+        )))
+    }
+}
+
+fn fill(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let origin = origin(context);
+    let (x1, y1, z1) = block_position(arguments, &origin, ["x1", "y1", "z1"])?;
+    let (x2, y2, z2) = block_position(arguments, &origin, ["x2", "y2", "z2"])?;
+    let block = arguments.block("block")?;
+
+    let (min_x, max_x) = (x1.min(x2), x1.max(x2));
+    let (min_y, max_y) = (y1.min(y2), y1.max(y2));
+    let (min_z, max_z) = (z1.min(z2), z1.max(z2));
+
+    let volume =
+        (max_x - min_x + 1) as i64 * (max_y - min_y + 1) as i64 * (max_z - min_z + 1) as i64;
+    if volume > MAX_FILL_VOLUME {
+        return Err(CommandError::Failed(format!(
+            "Too many blocks in the region ({volume} > {MAX_FILL_VOLUME})"
+        )));
+    }
+
+    let mut batch = BlockEditBatch::new();
+    for y in min_y..=max_y {
+        for z in min_z..=max_z {
+            for x in min_x..=max_x {
+                batch.put_block(x, y, z, block);
+            }
+        }
+    }
+    let author = context.author();
+    let count = context.journal.apply(author, context.chunk_manager, batch);
+
+    Ok(format!("Filled {count} blocks with {}", block.name()))
+}
+
+// Schematics are only read and written in their directory, with the .schem extension
+// A file of `directory`, the extension can be left out
+fn file_path(
+    arguments: &Arguments,
+    directory: &str,
+    extension: &str,
+) -> Result<PathBuf, CommandError> {
+    let file = arguments.word("file")?;
+
+    if file.is_empty() || file.contains(['/', '\\']) || file.starts_with('.') {
+        return Err(CommandError::InvalidArgument {
+            name: "file",
+            value: file.to_string(),
+            expected: "a file name".to_string(),
+        });
+    }
+
+    let path = PathBuf::from(directory).join(file);
+    if path.extension().is_some() {
+        Ok(path)
+    } else {
+        Ok(path.with_extension(extension))
+    }
+}
+
+fn schematic_path(arguments: &Arguments) -> Result<PathBuf, CommandError> {
+    file_path(arguments, SCHEMATICS_DIRECTORY, "schem")
+}
+
+fn export(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let origin = origin(context);
+    let corner1 = block_position(arguments, &origin, ["x1", "y1", "z1"])?;
+    let corner2 = block_position(arguments, &origin, ["x2", "y2", "z2"])?;
+    let path = schematic_path(arguments)?;
+
+    let volume = ((corner1.0 - corner2.0).abs() + 1) as i64
+        * ((corner1.1 - corner2.1).abs() + 1) as i64
+        * ((corner1.2 - corner2.2).abs() + 1) as i64;
+    if volume > MAX_EXPORT_VOLUME {
+        return Err(CommandError::Failed(format!(
+            "Too many blocks in the region ({volume} > {MAX_EXPORT_VOLUME})"
+        )));
+    }
+
+    let schematic = Schematic::from_region(context.chunk_manager, corner1, corner2)
+        .map_err(|err| CommandError::Failed(err.to_string()))?;
+    fs::create_dir_all(SCHEMATICS_DIRECTORY)
+        .map_err(|err| CommandError::Failed(err.to_string()))?;
+    schematic
+        .save(&path)
+        .map_err(|err| CommandError::Failed(format!("Could not save the schematic: {err}")))?;
+
+    Ok(format!(
+        "Exported {}x{}x{} blocks to {}",
+        schematic.width,
+        schematic.height,
+        schematic.length,
+        path.display()
+    ))
+}
+
+// The schematic is rotated first, then mirrored
+fn import(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let origin = origin(context);
+    let position = block_position(arguments, &origin, ["x", "y", "z"])?;
+    let path = schematic_path(arguments)?;
+
+    let mut schematic = Schematic::load(&path)
+        .map_err(|err| CommandError::Failed(format!("Could not load {}: {err}", path.display())))?;
+
+    if arguments.contains("rotation") {
+        let degrees = arguments.word("rotation")?.parse::<u32>().unwrap();
+        schematic = schematic.rotated(degrees / 90);
+    }
+    if arguments.contains("mirror") {
+        let mirror = match arguments.word("mirror")? {
+            "x" => Mirror::X,
+            "z" => Mirror::Z,
+            _ => Mirror::None,
+        };
+        schematic = schematic.mirrored(mirror);
+    }
+
+    let changes = schematic.paste(context.chunk_manager, position);
+    let count = changes.len();
+    let author = context.author();
+    context.journal.record(author, changes);
+
+    Ok(format!("Pasted {count} blocks from {}", path.display()))
+}
+
+// From the terminal of the server, the edits of any player can be undone
+fn history_author(context: &CommandContext, arguments: &Arguments) -> Result<String, CommandError> {
+    if arguments.contains("player") {
+        Ok(arguments.word("player")?.to_string())
+    } else if context.player.is_some() {
+        Ok(context.author().to_string())
+    } else {
+        Err(CommandError::MissingArgument {
+            name: "player",
+            usage: "/undo <player>".to_string(),
+        })
+    }
+}
+
+fn undo(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    if !context.journal.is_enabled() {
+        return Err(CommandError::Failed(
+            "The edits are not recorded in this world".to_string(),
+        ));
+    }
+    let author = history_author(context, arguments)?;
+
+    match context.journal.undo(&author, context.chunk_manager) {
+        Some(count) => Ok(format!("Undid an edit of {count} blocks by {author}")),
+        None => Err(CommandError::Failed(format!("No edit of {author} to undo"))),
+    }
+}
+
+fn redo(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    if !context.journal.is_enabled() {
+        return Err(CommandError::Failed(
+            "The edits are not recorded in this world".to_string(),
+        ));
+    }
+    let author = history_author(context, arguments)?;
+
+    match context.journal.redo(&author, context.chunk_manager) {
+        Some(count) => Ok(format!("Redid an edit of {count} blocks by {author}")),
+        None => Err(CommandError::Failed(format!("No edit of {author} to redo"))),
+    }
+}
+
+// Number of ticks of `/time set` and `/time add`, `/time set` also takes day, noon, night and midnight
+fn time_value(arguments: &Arguments, allow_names: bool) -> Result<u64, CommandError> {
+    let value = arguments
+        .word("value")
+        .map_err(|_| CommandError::MissingArgument {
+            name: "value",
+            usage: "/time set|add <value>".to_string(),
+        })?;
+
+    let named = if allow_names { named_time(value) } else { None };
+    named
+        .or_else(|| value.parse().ok())
+        .ok_or_else(|| CommandError::InvalidArgument {
+            name: "value",
+            value: value.to_string(),
+            expected: if allow_names {
+                "day, noon, night, midnight or a number of ticks".to_string()
+            } else {
+                "a number of ticks".to_string()
+            },
+        })
+}
+
+fn time(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    match arguments.word("action")? {
+        "set" => {
+            let time = time_value(arguments, true)? % TICKS_PER_DAY;
+            context.world_time.set_time_of_day(time);
+            Ok(format!("Set the time to {time}"))
+        }
+        "add" => {
+            let ticks = time_value(arguments, false)?;
+            context.world_time.add_ticks(ticks);
+            Ok(format!("Added {ticks} ticks to the time"))
+        }
+        "query" => Ok(format!(
+            "The time is {} on day {}",
+            context.world_time.time_of_day(),
+            context.world_time.day()
+        )),
+        "pause" => {
+            context.timer.pause();
+            Ok("Paused the time".to_string())
+        }
+        _ => {
+            context.timer.resume();
+            Ok("Resumed the time".to_string())
+        }
+    }
+}
+
+// Without a duration, the weather lasts as long as if it had changed by itself
+fn weather(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let kind = WeatherKind::from_name(arguments.word("kind")?).unwrap();
+    let duration = if arguments.contains("duration") {
+        Some(Duration::from_secs(arguments.integer("duration")? as u64))
+    } else {
+        None
+    };
+
+    context.weather.set(kind, duration);
+    Ok(format!("Set the weather to {}", kind.name()))
+}
+
+fn seed(
+    _dispatcher: &CommandDispatcher,
+    _context: &mut CommandContext,
+    _arguments: &Arguments,
+) -> Result<String, CommandError> {
+    Ok(format!("Seed: {}", *WORLD_SEED))
+}
+
+// Taken at the end of the frame, the feedback is given once it is saved
+fn screenshot(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let path = if arguments.contains("file") {
+        file_path(arguments, SCREENSHOTS_DIRECTORY, "png")?
+    } else {
+        default_screenshot_path()
+    };
+
+    let screenshots = context.screenshots.as_mut().ok_or_else(|| {
+        CommandError::Failed("Screenshots can only be taken by a client".to_string())
+    })?;
+    screenshots.request(path.clone());
+
+    Ok(format!("Taking a screenshot to {}", path.display()))
+}
+
+fn gamemode(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let game_mode = GameMode::from_name(arguments.word("mode")?).unwrap();
+    let player = context.player()?;
+
+    player.game_mode.switch_to(game_mode, player.state);
+
+    Ok(format!("Game mode set to {}", game_mode.name()))
+}
+
+fn fly(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let player = context.player()?;
+
+    if !player.game_mode.can_fly() {
+        return Err(CommandError::Failed(format!(
+            "Flying is not allowed in {} mode",
+            player.game_mode.name()
+        )));
+    }
+
+    player.state.is_flying = if arguments.contains("state") {
+        arguments.word("state")? == "on"
+    } else {
+        !player.state.is_flying
+    };
+
+    if player.state.is_flying {
+        Ok("Flying enabled".to_string())
+    } else {
+        Ok("Flying disabled".to_string())
+    }
+}
+
+fn kill(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    _arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let player = context.player()?;
+
+    if !player.health.damage(MAX_HEALTH, DamageSource::Command) {
+        return Err(CommandError::Failed(
+            "The player is already dead".to_string(),
+        ));
+    }
+
+    Ok("Killed the player".to_string())
+}
+
+// Without coordinates, the spawn point is set to the position of the player
+fn spawnpoint(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let origin = origin(context);
+    let position = if arguments.contains("x") {
+        vec3(
+            arguments.coordinate("x")?.resolve(origin.x),
+            arguments.coordinate("y")?.resolve(origin.y),
+            arguments.coordinate("z")?.resolve(origin.z),
+        )
+    } else {
+        origin
+    };
+
+    let player = context.player()?;
+    player.state.spawn_point = position;
+
+    Ok(format!(
+        "Set the spawn point to {:.2} {:.2} {:.2}",
+        position.x, position.y, position.z
+    ))
+}
@@ -0,0 +1,631 @@
+pub mod builtin;
+pub mod world_edit;
+
+use crate::chunk::BlockID;
+use crate::chunk_manager::ChunkManager;
+use crate::game_mode::GameMode;
+use crate::health::Health;
+use crate::inventory::item::Item;
+use crate::inventory::Inventory;
+use crate::journal::{EditJournal, SERVER_AUTHOR};
+use crate::physics::Interpolator;
+use crate::player::{PlayerPhysicsState, PlayerState};
+use crate::screenshot::Screenshots;
+use crate::timer::Timer;
+use crate::weather::Weather;
+use crate::world_edit::WorldEditSession;
+use crate::world_time::WorldTime;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+#[derive(Debug)]
+pub enum CommandError {
+    UnknownCommand(String),
+    MissingArgument {
+        name: &'static str,
+        usage: String,
+    },
+    InvalidArgument {
+        name: &'static str,
+        value: String,
+        expected: String,
+    },
+    TooManyArguments {
+        usage: String,
+    },
+    RequiresPlayer,
+    Failed(String),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::UnknownCommand(name) => write!(f, "Unknown command: /{name}"),
+            CommandError::MissingArgument { name, usage } => {
+                write!(f, "Missing argument <{name}>, usage: {usage}")
+            }
+            CommandError::InvalidArgument {
+                name,
+                value,
+                expected,
+            } => write!(f, "Invalid <{name}> '{value}', expected {expected}"),
+            CommandError::TooManyArguments { usage } => {
+                write!(f, "Too many arguments, usage: {usage}")
+            }
+            CommandError::RequiresPlayer => write!(f, "This command can only be run by a player"),
+            CommandError::Failed(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+// A coordinate typed by the user, relative coordinates are written with a tilde ("~", "~-3")
+#[derive(Copy, Clone, Debug)]
+pub struct Coordinate {
+    pub value: f32,
+    pub relative: bool,
+}
+
+impl Coordinate {
+    pub fn resolve(&self, origin: f32) -> f32 {
+        if self.relative {
+            origin + self.value
+        } else {
+            self.value
+        }
+    }
+
+    pub fn resolve_block(&self, origin: f32) -> i32 {
+        self.resolve(origin).floor() as i32
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum ArgumentType {
+    Integer { min: i64, max: i64 },
+    Float,
+    Coordinate,
+    Block,
+    Item,
+    Word,
+    Choice(&'static [&'static str]),
+}
+
+impl ArgumentType {
+    fn parse(&self, name: &'static str, token: &str) -> Result<ArgumentValue, CommandError> {
+        let invalid = |expected: String| CommandError::InvalidArgument {
+            name,
+            value: token.to_string(),
+            expected,
+        };
+
+        match *self {
+            ArgumentType::Integer { min, max } => match token.parse::<i64>() {
+                Ok(value) if value >= min && value <= max => Ok(ArgumentValue::Integer(value)),
+                _ => Err(invalid(format!("an integer between {min} and {max}"))),
+            },
+            ArgumentType::Float => token
+                .parse::<f32>()
+                .map(ArgumentValue::Float)
+                .map_err(|_| invalid("a number".to_string())),
+            ArgumentType::Coordinate => {
+                let (relative, value) = match token.strip_prefix('~') {
+                    Some("") => (true, Ok(0.0)),
+                    Some(offset) => (true, offset.parse::<f32>()),
+                    None => (false, token.parse::<f32>()),
+                };
+
+                value
+                    .map(|value| ArgumentValue::Coordinate(Coordinate { value, relative }))
+                    .map_err(|_| invalid("a coordinate (e.g. 12, -3.5, ~ or ~2)".to_string()))
+            }
+            ArgumentType::Block => BlockID::from_name(token)
+                .map(ArgumentValue::Block)
+                .ok_or_else(|| invalid("a block name".to_string())),
+            ArgumentType::Item => Item::from_name(token)
+                .map(ArgumentValue::Item)
+                .ok_or_else(|| invalid("an item name".to_string())),
+            ArgumentType::Word => Ok(ArgumentValue::Word(token.to_string())),
+            ArgumentType::Choice(choices) => {
+                if choices.contains(&token) {
+                    Ok(ArgumentValue::Word(token.to_string()))
+                } else {
+                    Err(invalid(format!("one of {}", choices.join(", "))))
+                }
+            }
+        }
+    }
+
+    // Values suggested by the tab completion
+    fn suggestions(&self) -> Vec<&'static str> {
+        match *self {
+            ArgumentType::Coordinate => vec!["~"],
+            ArgumentType::Block => BlockID::ALL.iter().map(|block| block.name()).collect(),
+            ArgumentType::Item => Item::all().map(|item| item.name()).collect(),
+            ArgumentType::Choice(choices) => choices.to_vec(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ArgumentValue {
+    Integer(i64),
+    Float(f32),
+    Coordinate(Coordinate),
+    Block(BlockID),
+    Item(Item),
+    Word(String),
+}
+
+// The arguments of a command after they have been parsed and type checked by the dispatcher
+#[derive(Default)]
+pub struct Arguments {
+    values: HashMap<&'static str, ArgumentValue>,
+}
+
+impl Arguments {
+    fn get(&self, name: &'static str) -> Result<&ArgumentValue, CommandError> {
+        self.values.get(name).ok_or(CommandError::MissingArgument {
+            name,
+            usage: String::new(),
+        })
+    }
+
+    fn mismatch(name: &'static str, expected: &str) -> CommandError {
+        CommandError::Failed(format!("Argument <{name}> is not {expected}"))
+    }
+
+    pub fn contains(&self, name: &'static str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    pub fn integer(&self, name: &'static str) -> Result<i64, CommandError> {
+        match self.get(name)? {
+            ArgumentValue::Integer(value) => Ok(*value),
+            _ => Err(Self::mismatch(name, "an integer")),
+        }
+    }
+
+    pub fn float(&self, name: &'static str) -> Result<f32, CommandError> {
+        match self.get(name)? {
+            ArgumentValue::Float(value) => Ok(*value),
+            _ => Err(Self::mismatch(name, "a number")),
+        }
+    }
+
+    pub fn coordinate(&self, name: &'static str) -> Result<Coordinate, CommandError> {
+        match self.get(name)? {
+            ArgumentValue::Coordinate(value) => Ok(*value),
+            _ => Err(Self::mismatch(name, "a coordinate")),
+        }
+    }
+
+    pub fn block(&self, name: &'static str) -> Result<BlockID, CommandError> {
+        match self.get(name)? {
+            ArgumentValue::Block(value) => Ok(*value),
+            _ => Err(Self::mismatch(name, "a block")),
+        }
+    }
+
+    pub fn item(&self, name: &'static str) -> Result<Item, CommandError> {
+        match self.get(name)? {
+            ArgumentValue::Item(value) => Ok(*value),
+            _ => Err(Self::mismatch(name, "an item")),
+        }
+    }
+
+    pub fn word(&self, name: &'static str) -> Result<&str, CommandError> {
+        match self.get(name)? {
+            ArgumentValue::Word(value) => Ok(value.as_str()),
+            _ => Err(Self::mismatch(name, "a word")),
+        }
+    }
+}
+
+// The player who issued the command
+pub struct PlayerContext<'a> {
+    pub name: &'a str,
+    pub state: &'a mut PlayerState,
+    pub physics_state: &'a mut Interpolator<PlayerPhysicsState>,
+    pub inventory: &'a mut Inventory,
+    pub game_mode: &'a mut GameMode,
+    pub health: &'a mut Health,
+    pub world_edit: &'a mut WorldEditSession,
+}
+
+// Everything a command is allowed to act on
+// There is no player when the commands are run from a script or from a terminal
+pub struct CommandContext<'a> {
+    pub chunk_manager: &'a ChunkManager,
+    pub timer: &'a mut Timer,
+    pub world_time: &'a mut WorldTime,
+    pub weather: &'a mut Weather,
+    pub journal: &'a mut EditJournal,
+    // Only the client has a screen to take screenshots of
+    pub screenshots: Option<&'a mut Screenshots>,
+    pub player: Option<PlayerContext<'a>>,
+}
+
+impl<'a> CommandContext<'a> {
+    pub fn player(&mut self) -> Result<&mut PlayerContext<'a>, CommandError> {
+        self.player.as_mut().ok_or(CommandError::RequiresPlayer)
+    }
+
+    // Name the edits are recorded under in the journal
+    pub fn author(&self) -> &'a str {
+        self.player
+            .as_ref()
+            .map_or(SERVER_AUTHOR, |player| player.name)
+    }
+}
+
+// The dispatcher is passed to the handlers so that commands like /help can list the other commands
+pub type CommandHandler =
+    fn(&CommandDispatcher, &mut CommandContext, &Arguments) -> Result<String, CommandError>;
+
+pub struct Parameter {
+    pub name: &'static str,
+    pub kind: ArgumentType,
+    pub optional: bool,
+}
+
+pub struct Command {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub parameters: Vec<Parameter>,
+    handler: CommandHandler,
+}
+
+impl Command {
+    pub fn new(name: &'static str, description: &'static str, handler: CommandHandler) -> Self {
+        Self {
+            name,
+            description,
+            parameters: Vec::new(),
+            handler,
+        }
+    }
+
+    pub fn argument(mut self, name: &'static str, kind: ArgumentType) -> Self {
+        assert!(
+            self.parameters.last().is_none_or(|p| !p.optional),
+            "Required arguments cannot follow optional arguments"
+        );
+
+        self.parameters.push(Parameter {
+            name,
+            kind,
+            optional: false,
+        });
+        self
+    }
+
+    pub fn optional_argument(mut self, name: &'static str, kind: ArgumentType) -> Self {
+        self.parameters.push(Parameter {
+            name,
+            kind,
+            optional: true,
+        });
+        self
+    }
+
+    pub fn usage(&self) -> String {
+        let mut usage = format!("/{}", self.name);
+
+        for parameter in self.parameters.iter() {
+            if parameter.optional {
+                usage += &format!(" [{}]", parameter.name);
+            } else {
+                usage += &format!(" <{}>", parameter.name);
+            }
+        }
+
+        usage
+    }
+
+    fn parse_arguments(&self, tokens: &[&str]) -> Result<Arguments, CommandError> {
+        if tokens.len() > self.parameters.len() {
+            return Err(CommandError::TooManyArguments {
+                usage: self.usage(),
+            });
+        }
+
+        let mut arguments = Arguments::default();
+
+        for (i, parameter) in self.parameters.iter().enumerate() {
+            match tokens.get(i) {
+                Some(token) => {
+                    let value = parameter.kind.parse(parameter.name, token)?;
+                    arguments.values.insert(parameter.name, value);
+                }
+                None if parameter.optional => break,
+                None => {
+                    return Err(CommandError::MissingArgument {
+                        name: parameter.name,
+                        usage: self.usage(),
+                    })
+                }
+            }
+        }
+
+        Ok(arguments)
+    }
+}
+
+// Parses and runs commands, does not depend on the window or on OpenGL so that commands can be
+// run from scripts or from a terminal
+#[derive(Default)]
+pub struct CommandDispatcher {
+    commands: BTreeMap<&'static str, Command>,
+}
+
+impl CommandDispatcher {
+    pub fn new() -> Self {
+        Self {
+            commands: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_builtin_commands() -> Self {
+        let mut dispatcher = Self::new();
+        builtin::register_builtin_commands(&mut dispatcher);
+        world_edit::register_world_edit_commands(&mut dispatcher);
+        dispatcher
+    }
+
+    pub fn register(&mut self, command: Command) {
+        if self.commands.insert(command.name, command).is_some() {
+            warn!("A command has been registered twice");
+        }
+    }
+
+    pub fn commands(&self) -> impl Iterator<Item = &Command> {
+        self.commands.values()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Command> {
+        self.commands.get(name)
+    }
+
+    // Runs a single command line, the leading slash is optional
+    // Returns the feedback message of the command
+    pub fn execute(
+        &self,
+        context: &mut CommandContext,
+        line: &str,
+    ) -> Result<String, CommandError> {
+        let line = line.trim();
+        let line = line.strip_prefix('/').unwrap_or(line);
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+
+        let name = match tokens.first() {
+            Some(name) => *name,
+            None => return Err(CommandError::UnknownCommand(String::new())),
+        };
+        let command = self
+            .commands
+            .get(name)
+            .ok_or_else(|| CommandError::UnknownCommand(name.to_string()))?;
+        let arguments = command.parse_arguments(&tokens[1..])?;
+
+        (command.handler)(self, context, &arguments)
+    }
+
+    // Runs every line of a script, blank lines and lines starting with '#' are ignored
+    pub fn execute_script(
+        &self,
+        context: &mut CommandContext,
+        script: &str,
+    ) -> Vec<Result<String, CommandError>> {
+        script
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| self.execute(context, line))
+            .collect()
+    }
+
+    // Returns the possible completions of the whole input line
+    pub fn complete(&self, input: &str) -> Vec<String> {
+        let has_slash = input.starts_with('/');
+        let line = input.strip_prefix('/').unwrap_or(input);
+        let mut tokens = line.split(' ').collect::<Vec<_>>();
+        let last = tokens.pop().unwrap_or("");
+        let prefix = if has_slash { "/" } else { "" };
+
+        // Complete the name of the command
+        if tokens.is_empty() {
+            return self
+                .commands
+                .keys()
+                .filter(|name| name.starts_with(last))
+                .map(|name| format!("{prefix}{name} "))
+                .collect();
+        }
+
+        // Complete the current argument
+        let command = match self.commands.get(tokens[0]) {
+            Some(command) => command,
+            None => return Vec::new(),
+        };
+        let parameter = match command.parameters.get(tokens.len() - 1) {
+            Some(parameter) => parameter,
+            None => return Vec::new(),
+        };
+        let head = tokens.join(" ");
+
+        parameter
+            .kind
+            .suggestions()
+            .into_iter()
+            .filter(|suggestion| suggestion.starts_with(last))
+            .map(|suggestion| format!("{prefix}{head} {suggestion}"))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::ChunkColumn;
+    use crate::weather::WeatherKind;
+    use std::sync::Arc;
+
+    struct Resources {
+        chunk_manager: ChunkManager,
+        timer: Timer,
+        world_time: WorldTime,
+        weather: Weather,
+        journal: EditJournal,
+    }
+
+    impl Resources {
+        fn new() -> Self {
+            let chunk_manager = ChunkManager::new();
+            chunk_manager.add_chunk_column((0, 0), Arc::new(ChunkColumn::new()));
+
+            Self {
+                chunk_manager,
+                timer: Timer::default(),
+                world_time: WorldTime::default(),
+                weather: Weather::default(),
+                journal: EditJournal::disabled(),
+            }
+        }
+
+        fn context(&mut self) -> CommandContext<'_> {
+            CommandContext {
+                chunk_manager: &self.chunk_manager,
+                timer: &mut self.timer,
+                world_time: &mut self.world_time,
+                weather: &mut self.weather,
+                journal: &mut self.journal,
+                screenshots: None,
+                player: None,
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_commands_are_rejected() {
+        let dispatcher = CommandDispatcher::with_builtin_commands();
+        let mut resources = Resources::new();
+
+        assert!(matches!(
+            dispatcher.execute(&mut resources.context(), "/dance"),
+            Err(CommandError::UnknownCommand(name)) if name == "dance"
+        ));
+        assert!(matches!(
+            dispatcher.execute(&mut resources.context(), "   "),
+            Err(CommandError::UnknownCommand(_))
+        ));
+    }
+
+    #[test]
+    fn arguments_are_type_checked() {
+        let dispatcher = CommandDispatcher::with_builtin_commands();
+        let mut resources = Resources::new();
+
+        assert!(matches!(
+            dispatcher.execute(&mut resources.context(), "/weather"),
+            Err(CommandError::MissingArgument { name: "kind", .. })
+        ));
+        assert!(matches!(
+            dispatcher.execute(&mut resources.context(), "/weather snow"),
+            Err(CommandError::InvalidArgument { name: "kind", .. })
+        ));
+        assert!(matches!(
+            dispatcher.execute(&mut resources.context(), "/weather rain 0"),
+            Err(CommandError::InvalidArgument {
+                name: "duration",
+                ..
+            })
+        ));
+        assert!(matches!(
+            dispatcher.execute(&mut resources.context(), "/weather rain 10 20"),
+            Err(CommandError::TooManyArguments { .. })
+        ));
+        assert!(matches!(
+            dispatcher.execute(&mut resources.context(), "/setblock 1 2 3 nothing"),
+            Err(CommandError::InvalidArgument { name: "block", .. })
+        ));
+    }
+
+    #[test]
+    fn commands_act_on_the_context() {
+        let dispatcher = CommandDispatcher::with_builtin_commands();
+        let mut resources = Resources::new();
+
+        dispatcher
+            .execute(&mut resources.context(), "/weather thunder")
+            .unwrap();
+        assert_eq!(resources.weather.kind(), WeatherKind::Thunder);
+
+        // The leading slash is optional
+        dispatcher
+            .execute(&mut resources.context(), "time set 1000")
+            .unwrap();
+        assert_eq!(resources.world_time.time_of_day(), 1000);
+    }
+
+    #[test]
+    fn relative_coordinates_are_relative_to_the_origin_without_a_player() {
+        let dispatcher = CommandDispatcher::with_builtin_commands();
+        let mut resources = Resources::new();
+
+        dispatcher
+            .execute(&mut resources.context(), "/setblock ~1 ~2 ~3 stone")
+            .unwrap();
+        assert_eq!(
+            resources.chunk_manager.get_block(1, 2, 3),
+            Some(BlockID::Stone)
+        );
+    }
+
+    #[test]
+    fn commands_that_need_a_player_fail_without_one() {
+        let dispatcher = CommandDispatcher::with_builtin_commands();
+        let mut resources = Resources::new();
+
+        assert!(matches!(
+            dispatcher.execute(&mut resources.context(), "/kill"),
+            Err(CommandError::RequiresPlayer)
+        ));
+    }
+
+    #[test]
+    fn scripts_skip_blank_lines_and_comments() {
+        let dispatcher = CommandDispatcher::with_builtin_commands();
+        let mut resources = Resources::new();
+
+        let results = dispatcher.execute_script(
+            &mut resources.context(),
+            "# Sets up the world\n\n/weather rain\n  # indented comment\n/time set 6000\n/dance\n",
+        );
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_ok());
+        assert!(results[2].is_err());
+        assert_eq!(resources.weather.kind(), WeatherKind::Rain);
+        assert_eq!(resources.world_time.time_of_day(), 6000);
+    }
+
+    #[test]
+    fn completes_command_names_and_arguments() {
+        let dispatcher = CommandDispatcher::with_builtin_commands();
+
+        assert_eq!(dispatcher.complete("/wea"), vec!["/weather ".to_string()]);
+        assert_eq!(
+            dispatcher.complete("/weather th"),
+            vec!["/weather thunder".to_string()]
+        );
+        assert_eq!(
+            dispatcher.complete("time p"),
+            vec!["time pause".to_string()]
+        );
+        assert!(dispatcher.complete("/dance ").is_empty());
+    }
+}
@@ -0,0 +1,270 @@
+use crate::chunk::BlockID;
+use crate::commands::builtin::{block_position, origin};
+use crate::commands::{
+    ArgumentType, Arguments, Command, CommandContext, CommandDispatcher, CommandError,
+};
+use crate::schematic::Schematic;
+use crate::world_edit::{self, Corner, Selection, WorldEditSession, MAX_EDIT_VOLUME};
+
+const COORDINATE: ArgumentType = ArgumentType::Coordinate;
+
+// Commands acting on the selection of the wand, named like the ones of the WorldEdit plugin
+pub fn register_world_edit_commands(dispatcher: &mut CommandDispatcher) {
+    dispatcher.register(
+        Command::new("pos1", "Selects the first corner of the selection", pos1)
+            .optional_argument("x", COORDINATE)
+            .optional_argument("y", COORDINATE)
+            .optional_argument("z", COORDINATE),
+    );
+    dispatcher.register(
+        Command::new("pos2", "Selects the second corner of the selection", pos2)
+            .optional_argument("x", COORDINATE)
+            .optional_argument("y", COORDINATE)
+            .optional_argument("z", COORDINATE),
+    );
+    dispatcher.register(
+        Command::new("set", "Fills the selection with a block", set)
+            .argument("block", ArgumentType::Block),
+    );
+    dispatcher.register(
+        Command::new(
+            "replace",
+            "Replaces a block by another in the selection",
+            replace,
+        )
+        .argument("from", ArgumentType::Block)
+        .argument("to", ArgumentType::Block),
+    );
+    dispatcher.register(
+        Command::new("walls", "Builds the four sides of the selection", walls)
+            .argument("block", ArgumentType::Block),
+    );
+    dispatcher.register(
+        Command::new(
+            "hollow",
+            "Builds the sides of the selection and empties it",
+            hollow,
+        )
+        .argument("block", ArgumentType::Block),
+    );
+    dispatcher.register(Command::new("copy", "Copies the selection", copy));
+    dispatcher.register(
+        Command::new("paste", "Pastes the copied blocks at the player", paste)
+            .optional_argument("rotation", ArgumentType::Choice(&["0", "90", "180", "270"])),
+    );
+}
+
+// The corners are the block of the player when no coordinates are given
+fn select_corner(
+    context: &mut CommandContext,
+    arguments: &Arguments,
+    is_first: bool,
+) -> Result<String, CommandError> {
+    let origin = origin(context);
+    let position = if arguments.contains("x") {
+        block_position(arguments, &origin, ["x", "y", "z"])?
+    } else {
+        (
+            origin.x.floor() as i32,
+            origin.y.floor() as i32,
+            origin.z.floor() as i32,
+        )
+    };
+
+    let session = &mut *context.player()?.world_edit;
+    let corner = if is_first {
+        session.first_corner = Some(position);
+        "First"
+    } else {
+        session.second_corner = Some(position);
+        "Second"
+    };
+
+    let (x, y, z) = position;
+    Ok(match session.selection_volume() {
+        Some(volume) => format!("{corner} corner set to {x} {y} {z} ({volume} blocks)"),
+        None => format!("{corner} corner set to {x} {y} {z}"),
+    })
+}
+
+fn pos1(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    select_corner(context, arguments, true)
+}
+
+fn pos2(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    select_corner(context, arguments, false)
+}
+
+fn selection(session: &WorldEditSession) -> Result<Selection, CommandError> {
+    let (selection, volume) = match (session.selection(), session.selection_volume()) {
+        (Some(selection), Some(volume)) => (selection, volume),
+        _ => {
+            return Err(CommandError::Failed(
+                "Select two corners with /pos1 and /pos2 or with the wand first".to_string(),
+            ))
+        }
+    };
+
+    if volume > MAX_EDIT_VOLUME {
+        return Err(CommandError::Failed(format!(
+            "Too many blocks in the selection ({volume} > {MAX_EDIT_VOLUME})"
+        )));
+    }
+
+    Ok(selection)
+}
+
+// Applies an edit to the blocks of the selection given by `positions`
+fn edit_selection<I: Iterator<Item = (i32, i32, i32)>>(
+    context: &mut CommandContext,
+    positions: fn(Corner, Corner) -> I,
+    block: impl Fn(BlockID) -> BlockID,
+) -> Result<usize, CommandError> {
+    let chunk_manager = context.chunk_manager;
+    let (min, max) = selection(context.player()?.world_edit)?;
+
+    let batch = positions(min, max)
+        .filter_map(|(x, y, z)| {
+            let previous = chunk_manager.get_block(x, y, z)?;
+            Some(((x, y, z), block(previous)))
+        })
+        .collect();
+
+    let author = context.author();
+    Ok(context.journal.apply(author, chunk_manager, batch))
+}
+
+fn set(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let block = arguments.block("block")?;
+    let count = edit_selection(context, world_edit::cuboid, |_| block)?;
+
+    Ok(format!("Set {count} blocks to {}", block.name()))
+}
+
+fn replace(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let (from, to) = (arguments.block("from")?, arguments.block("to")?);
+    let count = edit_selection(context, world_edit::cuboid, |previous| {
+        if previous == from {
+            to
+        } else {
+            previous
+        }
+    })?;
+
+    Ok(format!(
+        "Replaced {count} blocks of {} with {}",
+        from.name(),
+        to.name()
+    ))
+}
+
+fn walls(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let block = arguments.block("block")?;
+    let count = edit_selection(context, world_edit::walls, |_| block)?;
+
+    Ok(format!("Built walls of {count} blocks of {}", block.name()))
+}
+
+// Like `/fill ... hollow` in the vanilla game, the sides are filled and the inside is emptied
+fn hollow(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let block = arguments.block("block")?;
+    let chunk_manager = context.chunk_manager;
+    let (min, max) = selection(context.player()?.world_edit)?;
+
+    let batch = world_edit::cuboid(min, max)
+        .map(|(x, y, z)| {
+            let is_side =
+                x == min.0 || x == max.0 || y == min.1 || y == max.1 || z == min.2 || z == max.2;
+
+            ((x, y, z), if is_side { block } else { BlockID::Air })
+        })
+        .collect();
+    let author = context.author();
+    let count = context.journal.apply(author, chunk_manager, batch);
+
+    Ok(format!("Changed {count} blocks"))
+}
+
+fn copy(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    _arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let origin = origin(context);
+    let chunk_manager = context.chunk_manager;
+    let session = &mut *context.player()?.world_edit;
+    let (min, max) = selection(session)?;
+
+    // The blocks are pasted at the same place relative to the player
+    let mut clipboard = Schematic::from_region(chunk_manager, min, max)
+        .map_err(|err| CommandError::Failed(err.to_string()))?;
+    clipboard.offset = (
+        min.0 - origin.x.floor() as i32,
+        min.1 - origin.y.floor() as i32,
+        min.2 - origin.z.floor() as i32,
+    );
+
+    let volume = clipboard.volume();
+    session.clipboard = Some(clipboard);
+
+    Ok(format!("Copied {volume} blocks"))
+}
+
+// The copied blocks are rotated around the player
+fn paste(
+    _dispatcher: &CommandDispatcher,
+    context: &mut CommandContext,
+    arguments: &Arguments,
+) -> Result<String, CommandError> {
+    let origin = origin(context);
+    let position = (
+        origin.x.floor() as i32,
+        origin.y.floor() as i32,
+        origin.z.floor() as i32,
+    );
+    let chunk_manager = context.chunk_manager;
+
+    let mut clipboard = match &context.player()?.world_edit.clipboard {
+        Some(clipboard) => clipboard.clone(),
+        None => {
+            return Err(CommandError::Failed(
+                "Nothing has been copied, use /copy first".to_string(),
+            ))
+        }
+    };
+    if arguments.contains("rotation") {
+        let degrees = arguments.word("rotation")?.parse::<u32>().unwrap();
+        clipboard = clipboard.rotated(degrees / 90);
+    }
+
+    let changes = clipboard.paste(chunk_manager, position);
+    let count = changes.len();
+    let author = context.author();
+    context.journal.record(author, changes);
+
+    Ok(format!("Pasted {count} blocks"))
+}
@@ -0,0 +1,160 @@
+use crate::text::{TextColor, WHITE};
+use std::collections::VecDeque;
+use std::time::Instant;
+
+// Number of messages kept in the chat
+const MAX_MESSAGES: usize = 100;
+// Number of commands kept in the history
+const MAX_HISTORY: usize = 50;
+
+pub struct ConsoleMessage {
+    pub text: String,
+    pub color: TextColor,
+    pub time: Instant,
+}
+
+// State of the chat console, shared between the input handling and the rendering systems
+#[derive(Default)]
+pub struct Console {
+    pub is_open: bool,
+    pub input: String,
+    pub messages: VecDeque<ConsoleMessage>,
+    history: Vec<String>,
+    // Position in the history while browsing it with the arrow keys, None when editing a new line
+    history_index: Option<usize>,
+    // Candidates of the tab completion and the index of the one currently shown
+    completions: Vec<String>,
+    completion_index: usize,
+    // The key that opens the console also emits a character that must not end up in the input
+    pub(crate) swallow_next_char: bool,
+    // Chat messages waiting to be sent to the server, None when playing offline
+    pub(crate) outgoing_chat: Option<Vec<String>>,
+}
+
+impl Console {
+    pub fn open(&mut self, input: &str) {
+        self.is_open = true;
+        self.input = input.to_string();
+        self.history_index = None;
+        self.reset_completions();
+        self.swallow_next_char = true;
+    }
+
+    pub fn close(&mut self) {
+        self.is_open = false;
+        self.input.clear();
+        self.history_index = None;
+        self.reset_completions();
+    }
+
+    pub fn push_message(&mut self, text: &str, color: TextColor) {
+        // Multiline messages (e.g. /help) take one entry per line
+        for line in text.lines() {
+            if self.messages.len() == MAX_MESSAGES {
+                self.messages.pop_front();
+            }
+
+            self.messages.push_back(ConsoleMessage {
+                text: line.to_string(),
+                color,
+                time: Instant::now(),
+            });
+        }
+    }
+
+    // Offline the message is only echoed, online the server sends it to the other players
+    pub fn chat(&mut self, message: &str) {
+        match &mut self.outgoing_chat {
+            Some(outgoing_chat) => outgoing_chat.push(message.to_string()),
+            None => self.push_message(&format!("<Player> {message}"), WHITE),
+        }
+    }
+
+    pub fn type_char(&mut self, c: char) {
+        if self.swallow_next_char {
+            self.swallow_next_char = false;
+            return;
+        }
+
+        // The font only has the printable ASCII characters
+        if c.is_ascii() && !c.is_ascii_control() {
+            self.input.push(c);
+        }
+        self.reset_completions();
+    }
+
+    pub fn backspace(&mut self) {
+        self.input.pop();
+        self.reset_completions();
+    }
+
+    // Returns the submitted line and adds it to the history
+    pub fn submit(&mut self) -> String {
+        let line = std::mem::take(&mut self.input);
+
+        if !line.trim().is_empty() && self.history.last() != Some(&line) {
+            if self.history.len() == MAX_HISTORY {
+                self.history.remove(0);
+            }
+
+            self.history.push(line.clone());
+        }
+
+        self.close();
+        line
+    }
+
+    pub fn history_previous(&mut self) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        let index = match self.history_index {
+            Some(index) => index.saturating_sub(1),
+            None => self.history.len() - 1,
+        };
+
+        self.history_index = Some(index);
+        self.input = self.history[index].clone();
+        self.reset_completions();
+    }
+
+    pub fn history_next(&mut self) {
+        match self.history_index {
+            Some(index) if index + 1 < self.history.len() => {
+                self.history_index = Some(index + 1);
+                self.input = self.history[index + 1].clone();
+            }
+            Some(_) => {
+                self.history_index = None;
+                self.input.clear();
+            }
+            None => {}
+        }
+
+        self.reset_completions();
+    }
+
+    // Cycles through the completions, `complete` is only called on the first press of Tab
+    pub fn cycle_completion(&mut self, complete: impl FnOnce(&str) -> Vec<String>) {
+        if self.completions.is_empty() {
+            self.completions = complete(&self.input);
+            self.completion_index = 0;
+        } else {
+            self.completion_index = (self.completion_index + 1) % self.completions.len();
+        }
+
+        if let Some(completion) = self.completions.get(self.completion_index) {
+            self.input = completion.clone();
+        }
+    }
+
+    pub fn completions(&self) -> &[String] {
+        &self.completions
+    }
+
+    fn reset_completions(&mut self) {
+        self.completions.clear();
+        self.completion_index = 0;
+    }
+}
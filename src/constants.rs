@@ -1,37 +1,42 @@
 use rand::{thread_rng, RngCore};
+use std::sync::OnceLock;
 use std::time::Duration;
 
-// Logging
-pub const LOG_LEVEL: log::Level = log::Level::Trace;
+// The window size, the field of view, the mouse sensitivity, the GUI scale, the render distance
+// and the other options of the player are in `Settings`
 
 // Window
 pub const OPENGL_MAJOR_VERSION: u32 = 4;
 pub const OPENGL_MINOR_VERSION: u32 = 6;
 pub const WINDOW_NAME: &str = "Minecraft";
-pub const WINDOW_WIDTH: u32 = 1000;
-pub const WINDOW_HEIGHT: u32 = 600;
 pub const NEAR_PLANE: f32 = 0.1;
 pub const FAR_PLANE: f32 = 1000.0;
-pub const BACKGROUND_COLOR: (f32, f32, f32, f32) = (0.74, 0.84, 1.0, 1.0);
-pub const FOV: f32 = 1.22173; // in radians
 
 // GUI
-pub const GUI_SCALING: f32 = 2.0;
 pub const CROSSHAIR_SIZE: f32 = 40.0;
 pub const BLOCK_OUTLINE_WIDTH: f32 = 3.0;
 
+// Set by the server from its properties, before the seed is read for the first time
+pub static WORLD_SEED_OVERRIDE: OnceLock<u32> = OnceLock::new();
+
 lazy_static! {
     pub static ref WORLD_SEED: u32 = {
-        let seed = thread_rng().next_u32();
+        let seed = WORLD_SEED_OVERRIDE
+            .get()
+            .copied()
+            .unwrap_or_else(|| thread_rng().next_u32());
         println!("Seed: {seed}");
         seed
     };
 }
 
 // Rendering
-pub const RENDER_DISTANCE: i32 = 12;
-pub const ENABLE_FOG: bool = true;
-pub const CHUNK_UPLOADS_PER_FRAME: usize = 2;
+// Colours of the sky at the horizon and at the top, they change with the time of day
+pub const DAY_SKY_COLOR: (f32, f32, f32) = (0.74, 0.84, 1.0);
+pub const DAY_ZENITH_COLOR: (f32, f32, f32) = (0.47, 0.65, 1.0);
+pub const NIGHT_SKY_COLOR: (f32, f32, f32) = (0.04, 0.05, 0.09);
+pub const NIGHT_ZENITH_COLOR: (f32, f32, f32) = (0.0, 0.0, 0.03);
+pub const SUNSET_COLOR: (f32, f32, f32) = (0.96, 0.55, 0.26);
 lazy_static! {
     pub static ref WORLD_GENERATION_THREAD_POOL_SIZE: usize = {
         let cpus = num_cpus::get();
@@ -43,9 +48,9 @@ lazy_static! {
     };
 }
 
-// Input
-pub const MOUSE_SENSITIVITY_X: f32 = 0.5;
-pub const MOUSE_SENSITIVITY_Y: f32 = 0.5;
+// Post-processing, the passes are enabled in `Settings`
+pub const EXPOSURE: f32 = 1.5;
+pub const GAMMA: f32 = 2.2;
 
 // Physics
 pub const PHYSICS_TICKRATE: f32 = 60.0;
@@ -1,4 +1,6 @@
+#[cfg(feature = "client")]
 use std::ffi::CStr;
+#[cfg(feature = "client")]
 use std::os::raw::{c_char, c_void};
 
 #[macro_export]
@@ -47,6 +49,7 @@ macro_rules! gl_call {
     };
 }
 
+#[cfg(feature = "client")]
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "system" fn debug_message_callback(
     source: u32,
@@ -1,12 +1,23 @@
+use crate::game_mode::GameMode;
+use crate::health::Health;
+use crate::hunger::Hunger;
 use crate::inventory::Inventory;
+use crate::mob::{Mob, MobPhysicsState};
+use crate::network::client::RemotePlayer;
+use crate::network::interpolation::InterpolationBuffer;
 use crate::physics::Interpolator;
 use crate::player::{PlayerPhysicsState, PlayerState};
+use crate::world_edit::WorldEditSession;
 use specs::{Component, DenseVecStorage, NullStorage};
 
 impl Component for Interpolator<PlayerPhysicsState> {
     type Storage = DenseVecStorage<Self>;
 }
 
+impl Component for Interpolator<MobPhysicsState> {
+    type Storage = DenseVecStorage<Self>;
+}
+
 impl Component for PlayerState {
     type Storage = DenseVecStorage<Self>;
 }
@@ -21,3 +32,31 @@ impl Component for MainHandItemChanged {
 impl Component for Inventory {
     type Storage = DenseVecStorage<Self>;
 }
+
+impl Component for GameMode {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Component for Health {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Component for Hunger {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Component for Mob {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Component for RemotePlayer {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Component for InterpolationBuffer {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Component for WorldEditSession {
+    type Storage = DenseVecStorage<Self>;
+}
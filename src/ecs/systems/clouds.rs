@@ -0,0 +1,189 @@
+use crate::clouds::{CloudMap, CloudMode, CLOUD_SPEED, CLOUD_TILE_SIZE};
+use crate::constants::WORLD_SEED;
+use crate::input::InputCache;
+use crate::physics::Interpolator;
+use crate::player::{PlayerPhysicsState, PlayerState};
+use crate::settings::Settings;
+use crate::types::Shaders;
+use crate::weather::Weather;
+use crate::world_time::WorldTime;
+use glfw::{Action, Key, WindowEvent};
+use nalgebra::Matrix4;
+use nalgebra_glm::vec3;
+use specs::{Join, Read, ReadStorage, System, Write};
+use std::os::raw::c_void;
+
+// Every vertex has a position and a normal
+fn create_clouds_vao(vertices: &[f32]) -> (u32, i32) {
+    // Setup VAO
+    let mut vao = 0;
+    gl_call!(gl::CreateVertexArrays(1, &mut vao));
+
+    // Position
+    gl_call!(gl::EnableVertexArrayAttrib(vao, 0));
+    gl_call!(gl::VertexArrayAttribFormat(
+        vao,
+        0,
+        3,
+        gl::FLOAT,
+        gl::FALSE,
+        0
+    ));
+    gl_call!(gl::VertexArrayAttribBinding(vao, 0, 0));
+
+    // Normal
+    gl_call!(gl::EnableVertexArrayAttrib(vao, 1));
+    gl_call!(gl::VertexArrayAttribFormat(
+        vao,
+        1,
+        3,
+        gl::FLOAT,
+        gl::FALSE,
+        (3 * std::mem::size_of::<f32>()) as u32
+    ));
+    gl_call!(gl::VertexArrayAttribBinding(vao, 1, 0));
+
+    // Setup VBO
+    let mut vbo = 0;
+    gl_call!(gl::CreateBuffers(1, &mut vbo));
+
+    gl_call!(gl::VertexArrayVertexBuffer(
+        vao,
+        0,
+        vbo,
+        0,
+        (6 * std::mem::size_of::<f32>()) as i32
+    ));
+    gl_call!(gl::NamedBufferData(
+        vbo,
+        std::mem::size_of_val(vertices) as isize,
+        vertices.as_ptr() as *const c_void,
+        gl::STATIC_DRAW
+    ));
+
+    (vao, (vertices.len() / 6) as i32)
+}
+
+// Draws the layer of clouds drifting with the world time, after the opaque geometry and before the
+// particles so that they are blended over the terrain and under the rain
+pub struct RenderClouds {
+    // VAO and number of vertices of a tile of the map, for the fast and the fancy clouds
+    flat: (u32, i32),
+    boxes: (u32, i32),
+}
+
+impl Default for RenderClouds {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RenderClouds {
+    pub fn new() -> Self {
+        let map = CloudMap::generate(*WORLD_SEED);
+
+        Self {
+            flat: create_clouds_vao(&map.mesh(CloudMode::Fast)),
+            boxes: create_clouds_vao(&map.mesh(CloudMode::Fancy)),
+        }
+    }
+}
+
+impl<'a> System<'a> for RenderClouds {
+    type SystemData = (
+        Read<'a, InputCache>,
+        ReadStorage<'a, PlayerState>,
+        ReadStorage<'a, Interpolator<PlayerPhysicsState>>,
+        Read<'a, WorldTime>,
+        Read<'a, Weather>,
+        Write<'a, Settings>,
+        Write<'a, Shaders>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            input_cache,
+            player_state,
+            player_physics_state,
+            world_time,
+            weather,
+            mut settings,
+            mut shaders,
+        ) = data;
+
+        for event in input_cache.events.iter() {
+            if let WindowEvent::Key(Key::F6, _, Action::Press, _) = event {
+                settings.clouds = settings.clouds.next();
+                info!("Clouds: {}", settings.clouds.name());
+            }
+        }
+
+        let (vao, vertex_count) = match settings.clouds {
+            CloudMode::Off => return,
+            CloudMode::Fast => self.flat,
+            CloudMode::Fancy => self.boxes,
+        };
+
+        let drift = (world_time.ticks() as f64 * CLOUD_SPEED % CLOUD_TILE_SIZE as f64) as f32;
+        // The clouds are lit by the sky like the blocks, and greyer in the rain
+        let color = weather.sky_color(vec3(1.0, 1.0, 1.0) * world_time.daylight());
+
+        let clouds_shader = shaders.get_mut("clouds_shader").unwrap();
+        clouds_shader.use_program();
+        clouds_shader.set_uniform3f("color", color.as_slice());
+        clouds_shader.set_uniform1i("enable_fog", settings.fog as i32);
+        clouds_shader.set_uniform3f(
+            "sky_color",
+            weather.sky_color(world_time.sky_color()).as_slice(),
+        );
+        clouds_shader.set_uniform1f("tile_size", CLOUD_TILE_SIZE);
+
+        gl_call!(gl::Disable(gl::CULL_FACE));
+        gl_call!(gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA));
+        gl_call!(gl::BindVertexArray(vao));
+
+        for (player_state, player_physics_state) in (&player_state, &player_physics_state).join() {
+            let camera_pos = player_physics_state.get_interpolated_state().position
+                + vec3(
+                    0.0,
+                    *player_state.camera_height.get_interpolated_state(),
+                    0.0,
+                );
+
+            // The tile under the camera, the tiles around it are the other instances
+            let origin = vec3(
+                drift + ((camera_pos.x - drift) / CLOUD_TILE_SIZE).floor() * CLOUD_TILE_SIZE,
+                0.0,
+                (camera_pos.z / CLOUD_TILE_SIZE).floor() * CLOUD_TILE_SIZE,
+            );
+            let model_matrix = Matrix4::new_translation(&origin);
+
+            unsafe {
+                clouds_shader.set_uniform_matrix4fv("model", model_matrix.as_ptr());
+                clouds_shader.set_uniform_matrix4fv("view", player_state.view_matrix.as_ptr());
+                clouds_shader
+                    .set_uniform_matrix4fv("projection", player_state.projection_matrix.as_ptr());
+            }
+            clouds_shader.set_uniform3f("camera_position", camera_pos.as_slice());
+
+            if settings.clouds == CloudMode::Fancy {
+                // Only the depth at first, so that the faces behind the closest ones are not
+                // blended over them
+                gl_call!(gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::FALSE));
+                gl_call!(gl::DrawArraysInstanced(gl::TRIANGLES, 0, vertex_count, 9));
+                gl_call!(gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE));
+
+                gl_call!(gl::DepthFunc(gl::LEQUAL));
+                gl_call!(gl::DrawArraysInstanced(gl::TRIANGLES, 0, vertex_count, 9));
+                gl_call!(gl::DepthFunc(gl::LESS));
+            } else {
+                // A single layer, seen from above and from below
+                gl_call!(gl::DepthMask(gl::FALSE));
+                gl_call!(gl::DrawArraysInstanced(gl::TRIANGLES, 0, vertex_count, 9));
+                gl_call!(gl::DepthMask(gl::TRUE));
+            }
+        }
+
+        gl_call!(gl::Enable(gl::CULL_FACE));
+    }
+}
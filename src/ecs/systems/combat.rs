@@ -0,0 +1,59 @@
+use crate::combat::{knockback, KNOCKBACK_VERTICAL_SPEED, MOB_ATTACK_COOLDOWN};
+use crate::game_mode::GameMode;
+use crate::health::{DamageSource, Health};
+use crate::mob::{Mob, MobPhysicsState};
+use crate::physics::Interpolator;
+use crate::player::PlayerPhysicsState;
+use specs::{Join, ReadStorage, System, WriteStorage};
+use std::time::Instant;
+
+// Hostile mobs hurt the players they touch
+pub struct MobAttack;
+
+impl<'a> System<'a> for MobAttack {
+    type SystemData = (
+        WriteStorage<'a, Mob>,
+        ReadStorage<'a, Interpolator<MobPhysicsState>>,
+        WriteStorage<'a, Interpolator<PlayerPhysicsState>>,
+        WriteStorage<'a, Health>,
+        ReadStorage<'a, GameMode>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut mobs, mob_physics_state, mut player_physics_state, mut health, game_mode) = data;
+
+        let now = Instant::now();
+
+        for (mob, mob_physics_state) in (&mut mobs, &mob_physics_state).join() {
+            let is_cooling_down = mob
+                .attack_last_executed
+                .is_some_and(|time| now.duration_since(time) < MOB_ATTACK_COOLDOWN);
+            if !mob.kind.is_hostile() || mob.is_dead() || is_cooling_down {
+                continue;
+            }
+
+            let mob_state = mob_physics_state.get_latest_state();
+            let reach = mob_state.aabb.inflate(0.1);
+
+            for (player_physics_state, health, game_mode) in
+                (&mut player_physics_state, &mut health, &game_mode).join()
+            {
+                let player = player_physics_state.get_latest_state_mut();
+
+                if !game_mode.takes_damage()
+                    || health.is_dead()
+                    || health.is_invulnerable()
+                    || !reach.intersects(&player.aabb)
+                {
+                    continue;
+                }
+
+                health.damage(mob.kind.attack_damage(), DamageSource::Mob(mob.kind));
+                mob.attack_last_executed = Some(now);
+
+                player.knockback = knockback(&mob_state.position, &player.position);
+                player.velocity.y = KNOCKBACK_VERTICAL_SPEED;
+            }
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use crate::chunk_manager::ChunkManager;
+use crate::commands::{CommandContext, CommandDispatcher, PlayerContext};
+use crate::console::Console;
+use crate::ecs::components::MainHandItemChanged;
+use crate::game_mode::GameMode;
+use crate::health::Health;
+use crate::input::InputCache;
+use crate::inventory::Inventory;
+use crate::journal::EditJournal;
+use crate::network::client::ClientNetwork;
+use crate::physics::Interpolator;
+use crate::player::{PlayerPhysicsState, PlayerState};
+use crate::screenshot::Screenshots;
+use crate::settings::Settings;
+use crate::text::{TextRenderer, GRAY, RED, TRANSLUCENT_BLACK, WHITE};
+use crate::timer::Timer;
+use crate::types::Shaders;
+use crate::weather::Weather;
+use crate::world_edit::WorldEditSession;
+use crate::world_time::WorldTime;
+use glfw::{Action, Key, WindowEvent};
+use specs::{Entities, Join, Read, System, Write, WriteStorage};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// How long the messages stay visible when the console is closed
+const MESSAGE_LIFETIME: Duration = Duration::from_secs(10);
+// Number of messages visible at once
+const VISIBLE_MESSAGES: usize = 10;
+// Space left at the bottom of the screen for the hotbar
+const HOTBAR_MARGIN: f32 = 60.0;
+
+pub struct HandleConsoleInput {
+    dispatcher: CommandDispatcher,
+}
+
+impl Default for HandleConsoleInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HandleConsoleInput {
+    pub fn new() -> Self {
+        Self {
+            dispatcher: CommandDispatcher::with_builtin_commands(),
+        }
+    }
+}
+
+impl<'a> System<'a> for HandleConsoleInput {
+    type SystemData = (
+        Entities<'a>,
+        Write<'a, Console>,
+        Write<'a, InputCache>,
+        Write<'a, Timer>,
+        Write<'a, WorldTime>,
+        Write<'a, Weather>,
+        Write<'a, EditJournal>,
+        Write<'a, Screenshots>,
+        Read<'a, Arc<ChunkManager>>,
+        Read<'a, ClientNetwork>,
+        WriteStorage<'a, PlayerState>,
+        WriteStorage<'a, Interpolator<PlayerPhysicsState>>,
+        WriteStorage<'a, Inventory>,
+        WriteStorage<'a, GameMode>,
+        WriteStorage<'a, Health>,
+        WriteStorage<'a, WorldEditSession>,
+        WriteStorage<'a, MainHandItemChanged>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut console,
+            mut input_cache,
+            mut timer,
+            mut world_time,
+            mut weather,
+            mut journal,
+            mut screenshots,
+            chunk_manager,
+            network,
+            mut player_state,
+            mut player_physics_state,
+            mut inventory,
+            mut game_mode,
+            mut health,
+            mut world_edit,
+            mut main_hand_item_changed,
+        ) = data;
+
+        let mut submitted_lines = Vec::new();
+
+        for event in input_cache.events.iter() {
+            if !console.is_open {
+                match event {
+                    WindowEvent::Key(Key::T, _, Action::Press, _) => console.open(""),
+                    WindowEvent::Key(Key::Slash, _, Action::Press, _) => console.open("/"),
+                    _ => {}
+                }
+                continue;
+            }
+
+            match event {
+                WindowEvent::Char(c) => console.type_char(*c),
+                WindowEvent::Key(key, _, Action::Press | Action::Repeat, _) => match key {
+                    Key::Escape => console.close(),
+                    Key::Enter | Key::KpEnter => submitted_lines.push(console.submit()),
+                    Key::Backspace => console.backspace(),
+                    Key::Up => console.history_previous(),
+                    Key::Down => console.history_next(),
+                    Key::Tab => {
+                        let dispatcher = &self.dispatcher;
+                        console.cycle_completion(|input| dispatcher.complete(input));
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        // The other systems must not react to what is typed in the console
+        if console.is_open || !submitted_lines.is_empty() {
+            input_cache.events.clear();
+            input_cache.key_states.clear();
+            input_cache.mouse_button_states.clear();
+        }
+
+        for line in submitted_lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            // Lines that are not commands are chat messages
+            if !line.starts_with('/') {
+                console.chat(&line);
+                continue;
+            }
+
+            console.push_message(&line, GRAY);
+
+            for (
+                entity,
+                player_state,
+                player_physics_state,
+                inventory,
+                game_mode,
+                health,
+                world_edit,
+            ) in (
+                &entities,
+                &mut player_state,
+                &mut player_physics_state,
+                &mut inventory,
+                &mut game_mode,
+                &mut health,
+                &mut world_edit,
+            )
+                .join()
+            {
+                let mut context = CommandContext {
+                    chunk_manager: &chunk_manager,
+                    timer: &mut timer,
+                    world_time: &mut world_time,
+                    weather: &mut weather,
+                    journal: &mut journal,
+                    screenshots: Some(&mut screenshots),
+                    player: Some(PlayerContext {
+                        name: &network.name,
+                        state: player_state,
+                        physics_state: player_physics_state,
+                        inventory,
+                        game_mode,
+                        health,
+                        world_edit,
+                    }),
+                };
+
+                match self.dispatcher.execute(&mut context, &line) {
+                    Ok(feedback) => console.push_message(&feedback, WHITE),
+                    Err(e) => console.push_message(&e.to_string(), RED),
+                }
+
+                // The command may have changed the items of the inventory
+                if let Err(e) = main_hand_item_changed.insert(entity, MainHandItemChanged) {
+                    error!("{e}");
+                }
+            }
+        }
+    }
+}
+
+pub struct RenderConsole {
+    text_renderer: TextRenderer,
+}
+
+impl Default for RenderConsole {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RenderConsole {
+    pub fn new() -> Self {
+        Self {
+            text_renderer: TextRenderer::new(),
+        }
+    }
+}
+
+impl<'a> System<'a> for RenderConsole {
+    type SystemData = (Read<'a, Console>, Read<'a, Settings>, Write<'a, Shaders>);
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (console, settings, mut shaders) = data;
+
+        let line_height = TextRenderer::line_height(settings.gui_scale);
+        let left = 4.0;
+        let mut y = settings.window_height as f32 - HOTBAR_MARGIN - line_height;
+
+        if console.is_open {
+            self.text_renderer.queue_rect(
+                0.0,
+                y,
+                settings.window_width as f32,
+                line_height,
+                TRANSLUCENT_BLACK,
+            );
+            self.text_renderer.queue_text(
+                left,
+                y + 1.0,
+                settings.gui_scale,
+                WHITE,
+                &format!("{}_", console.input),
+            );
+
+            // Show the other candidates of the tab completion above the input line
+            let completions = console.completions();
+            if completions.len() > 1 {
+                let text = completions
+                    .iter()
+                    .map(|completion| completion.trim_end().rsplit(' ').next().unwrap_or(""))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                y -= line_height;
+                self.text_renderer.queue_text_with_background(
+                    left,
+                    y,
+                    settings.gui_scale,
+                    GRAY,
+                    &text,
+                );
+            }
+
+            y -= line_height / 2.0;
+        }
+
+        let now = Instant::now();
+        let visible_messages = console
+            .messages
+            .iter()
+            .rev()
+            .filter(|message| {
+                console.is_open || now.duration_since(message.time) < MESSAGE_LIFETIME
+            })
+            .take(VISIBLE_MESSAGES);
+
+        for message in visible_messages {
+            y -= line_height;
+            self.text_renderer.queue_rect(
+                0.0,
+                y,
+                settings.window_width as f32 / 2.0,
+                line_height,
+                TRANSLUCENT_BLACK,
+            );
+            self.text_renderer.queue_text(
+                left,
+                y + 1.0,
+                settings.gui_scale,
+                message.color,
+                &message.text,
+            );
+        }
+
+        let text_shader = shaders.get_mut("text_shader").unwrap();
+        self.text_renderer.flush(text_shader, &settings);
+    }
+}
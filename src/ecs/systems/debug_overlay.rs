@@ -0,0 +1,263 @@
+use crate::chunk_manager::ChunkManager;
+use crate::constants::WORLD_SEED;
+use crate::game_mode::GameMode;
+use crate::input::InputCache;
+use crate::metrics::{Metrics, FRAME_TIME_SAMPLES};
+use crate::physics::Interpolator;
+use crate::player::{PlayerPhysicsState, PlayerState};
+use crate::settings::Settings;
+use crate::text::{TextRenderer, GRAY, GREEN, RED, TRANSLUCENT_BLACK, WHITE, YELLOW};
+use crate::types::Shaders;
+use glfw::{Action, Key, WindowEvent};
+use specs::{Join, Read, ReadStorage, System, Write};
+use std::sync::Arc;
+
+const FRAME_TIME_GRAPH_HEIGHT: f32 = 100.0;
+// Height in pixels of one millisecond in the frame time graph
+const FRAME_TIME_GRAPH_SCALE: f32 = 3.0;
+
+pub struct RenderDebugOverlay {
+    text_renderer: TextRenderer,
+    visible: bool,
+}
+
+impl Default for RenderDebugOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RenderDebugOverlay {
+    pub fn new() -> Self {
+        Self {
+            text_renderer: TextRenderer::new(),
+            visible: false,
+        }
+    }
+
+    fn facing(yaw: f32) -> &'static str {
+        let (x, z) = (yaw.cos(), yaw.sin());
+
+        if x.abs() > z.abs() {
+            if x > 0.0 {
+                "east (+X)"
+            } else {
+                "west (-X)"
+            }
+        } else if z > 0.0 {
+            "south (+Z)"
+        } else {
+            "north (-Z)"
+        }
+    }
+
+    fn queue_frame_time_graph(&mut self, metrics: &Metrics, settings: &Settings) {
+        let bar_width = 1.0;
+        let left = 4.0;
+        let bottom = settings.window_height as f32 - 4.0;
+
+        self.text_renderer.queue_rect(
+            left,
+            bottom - FRAME_TIME_GRAPH_HEIGHT,
+            FRAME_TIME_SAMPLES as f32 * bar_width,
+            FRAME_TIME_GRAPH_HEIGHT,
+            TRANSLUCENT_BLACK,
+        );
+
+        for (i, &frame_time) in metrics.frame_times.iter().enumerate() {
+            let height = (frame_time * FRAME_TIME_GRAPH_SCALE).min(FRAME_TIME_GRAPH_HEIGHT);
+            let color = if frame_time <= 1000.0 / 60.0 {
+                GREEN
+            } else if frame_time <= 1000.0 / 30.0 {
+                YELLOW
+            } else {
+                RED
+            };
+
+            self.text_renderer.queue_rect(
+                left + i as f32 * bar_width,
+                bottom - height,
+                bar_width,
+                height,
+                color,
+            );
+        }
+
+        // 60 FPS and 30 FPS thresholds
+        for threshold in [1000.0 / 60.0, 1000.0 / 30.0] {
+            self.text_renderer.queue_rect(
+                left,
+                bottom - threshold * FRAME_TIME_GRAPH_SCALE,
+                FRAME_TIME_SAMPLES as f32 * bar_width,
+                1.0,
+                WHITE,
+            );
+        }
+    }
+}
+
+impl<'a> System<'a> for RenderDebugOverlay {
+    type SystemData = (
+        Read<'a, InputCache>,
+        Read<'a, Metrics>,
+        Read<'a, Arc<ChunkManager>>,
+        ReadStorage<'a, PlayerState>,
+        ReadStorage<'a, Interpolator<PlayerPhysicsState>>,
+        ReadStorage<'a, GameMode>,
+        Read<'a, Settings>,
+        Write<'a, Shaders>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            input_cache,
+            metrics,
+            chunk_manager,
+            player_state,
+            player_physics_state,
+            game_mode,
+            settings,
+            mut shaders,
+        ) = data;
+
+        for event in input_cache.events.iter() {
+            if let WindowEvent::Key(Key::F3, _, Action::Press, _) = event {
+                self.visible = !self.visible;
+            }
+        }
+
+        if !self.visible {
+            return;
+        }
+
+        let mut left_lines = vec![(
+            WHITE,
+            format!(
+                "{} fps ({:.2} ms avg, {:.2} ms max)",
+                metrics.fps,
+                metrics.average_frame_time(),
+                metrics.max_frame_time()
+            ),
+        )];
+
+        for (player_state, player_physics_state, game_mode) in
+            (&player_state, &player_physics_state, &game_mode).join()
+        {
+            let position = player_physics_state.get_interpolated_state().position;
+            let (block_x, block_y, block_z) = (
+                position.x.floor() as i32,
+                position.y.floor() as i32,
+                position.z.floor() as i32,
+            );
+            let (chunk_x, chunk_y, chunk_z, local_x, local_y, local_z) =
+                ChunkManager::get_chunk_coords(block_x, block_y, block_z);
+
+            left_lines.push((
+                WHITE,
+                format!(
+                    "XYZ: {:.3} / {:.3} / {:.3}",
+                    position.x, position.y, position.z
+                ),
+            ));
+            left_lines.push((WHITE, format!("Block: {block_x} {block_y} {block_z}")));
+            left_lines.push((
+                WHITE,
+                format!(
+                    "Chunk: {chunk_x} {chunk_y} {chunk_z} (in chunk {local_x} {local_y} {local_z})"
+                ),
+            ));
+            left_lines.push((
+                WHITE,
+                format!(
+                    "Facing: {} ({:.1} / {:.1})",
+                    Self::facing(player_state.rotation.y),
+                    player_state.rotation.y.to_degrees(),
+                    player_state.rotation.x.to_degrees()
+                ),
+            ));
+            left_lines.push((WHITE, format!("Game mode: {}", game_mode.name())));
+            left_lines.push((
+                GRAY,
+                format!(
+                    "On ground: {}, flying: {}, sprinting: {}, sneaking: {}",
+                    player_state.is_on_ground,
+                    player_state.is_flying,
+                    player_state.is_sprinting,
+                    player_state.is_sneaking
+                ),
+            ));
+
+            let targeted_block = match player_state.targeted_block {
+                Some(((x, y, z), _)) => match chunk_manager.get_block(x, y, z) {
+                    Some(block) => format!("Targeted block: {x} {y} {z} ({block:?})"),
+                    None => format!("Targeted block: {x} {y} {z}"),
+                },
+                None => "Targeted block: none".to_string(),
+            };
+            left_lines.push((WHITE, targeted_block));
+        }
+
+        left_lines.push((
+            YELLOW,
+            format!("Loaded columns: {}", metrics.loaded_columns),
+        ));
+        left_lines.push((
+            YELLOW,
+            format!(
+                "Chunks: {} drawn, {} culled",
+                metrics.chunks_drawn, metrics.chunks_culled
+            ),
+        ));
+        left_lines.push((YELLOW, format!("Mobs: {}", metrics.mobs)));
+        left_lines.push((
+            YELLOW,
+            format!(
+                "Pending uploads: {}, generation queue: {}{}",
+                metrics.pending_uploads,
+                metrics.generation_queue_depth,
+                if metrics.is_generating {
+                    " (generating)"
+                } else {
+                    ""
+                }
+            ),
+        ));
+
+        let right_lines = [
+            format!("Seed: {}", *WORLD_SEED),
+            match metrics.memory_usage {
+                Some(bytes) => format!("Memory: {} MiB", bytes / (1024 * 1024)),
+                None => "Memory: unknown".to_string(),
+            },
+        ];
+
+        let line_height = TextRenderer::line_height(settings.gui_scale);
+
+        for (i, (color, line)) in left_lines.iter().enumerate() {
+            self.text_renderer.queue_text_with_background(
+                4.0,
+                4.0 + i as f32 * line_height,
+                settings.gui_scale,
+                *color,
+                line,
+            );
+        }
+
+        for (i, line) in right_lines.iter().enumerate() {
+            let width = TextRenderer::text_width(line, settings.gui_scale);
+
+            self.text_renderer.queue_text_with_background(
+                settings.window_width as f32 - width - 4.0,
+                4.0 + i as f32 * line_height,
+                settings.gui_scale,
+                WHITE,
+                line,
+            );
+        }
+
+        self.queue_frame_time_graph(&metrics, &settings);
+
+        let text_shader = shaders.get_mut("text_shader").unwrap();
+        self.text_renderer.flush(text_shader, &settings);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::ecs::components::MainHandItemChanged;
+use crate::game_mode::GameMode;
+use crate::health::Health;
+use crate::hunger::{EatingProgress, Hunger, EATING_DURATION};
+use crate::input::InputCache;
+use crate::inventory::Inventory;
+use specs::{Entities, Join, Read, ReadStorage, System, WriteStorage};
+use std::time::Instant;
+
+pub struct EatFood;
+
+impl<'a> System<'a> for EatFood {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, InputCache>,
+        WriteStorage<'a, Inventory>,
+        WriteStorage<'a, Hunger>,
+        ReadStorage<'a, Health>,
+        ReadStorage<'a, GameMode>,
+        WriteStorage<'a, MainHandItemChanged>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            input_cache,
+            mut inventory,
+            mut hunger,
+            health,
+            game_mode,
+            mut main_hand_item_changed,
+        ) = data;
+
+        for (entity, inventory, hunger, health, game_mode) in
+            (&entities, &mut inventory, &mut hunger, &health, &game_mode).join()
+        {
+            let food = match inventory.get_selected_food() {
+                Some(food)
+                    if game_mode.has_hunger()
+                        && !health.is_dead()
+                        && !hunger.is_full()
+                        && input_cache.is_mouse_button_pressed(glfw::MouseButtonRight) =>
+                {
+                    food
+                }
+                _ => {
+                    hunger.eating = None;
+                    continue;
+                }
+            };
+
+            let slot = inventory.selected_hotbar_slot;
+            let now = Instant::now();
+
+            // Switching to another food restarts the eating
+            let eating = match &mut hunger.eating {
+                Some(eating) if eating.food == food && eating.slot == slot => eating,
+                eating => eating.insert(EatingProgress::new(food, slot)),
+            };
+
+            eating.time += now.duration_since(eating.last_updated);
+            eating.last_updated = now;
+
+            if eating.time >= EATING_DURATION {
+                hunger.eating = None;
+                hunger.eat(food);
+                debug!("Ate {}", food.name());
+
+                if inventory.consume_selected_item() {
+                    if let Err(e) = main_hand_item_changed.insert(entity, MainHandItemChanged) {
+                        error!("{e}");
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use specs::System;
+use crate::metrics::{resident_memory, Metrics};
+use specs::{System, Write};
 use std::time::Instant;
 
 const MAXIMUM_OPTIMAL_FRAMETIME: f32 = 1.0 / 60.0;
@@ -10,9 +11,9 @@ pub struct FpsCounter {
 }
 
 impl<'a> System<'a> for FpsCounter {
-    type SystemData = ();
+    type SystemData = Write<'a, Metrics>;
 
-    fn run(&mut self, _data: Self::SystemData) {
+    fn run(&mut self, mut metrics: Self::SystemData) {
         let current_time = Instant::now();
         self.nb_frames += 1;
 
@@ -20,14 +21,16 @@ impl<'a> System<'a> for FpsCounter {
             let frame_time = current_time.duration_since(self.last_frame).as_secs_f32();
 
             if frame_time > MAXIMUM_OPTIMAL_FRAMETIME {
-                warn!("Suboptimal frame time: {:.2} ms", frame_time * 1000.0);
+                debug!("Suboptimal frame time: {:.2} ms", frame_time * 1000.0);
             }
 
+            metrics.push_frame_time(frame_time * 1000.0);
             self.last_frame = current_time;
         }
 
         if current_time.duration_since(self.last_second).as_secs_f32() >= 1.0 {
-            info!("{} FPS", self.nb_frames);
+            metrics.fps = self.nb_frames as u32;
+            metrics.memory_usage = resident_memory();
 
             self.nb_frames = 0;
             self.last_second = current_time;
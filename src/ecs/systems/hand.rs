@@ -1,12 +1,15 @@
-use crate::constants::{FAR_PLANE, NEAR_PLANE, WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::constants::{FAR_PLANE, NEAR_PLANE};
 use crate::ecs::components::MainHandItemChanged;
 use crate::inventory::Inventory;
 use crate::main_hand::MainHand;
 use crate::physics::Interpolator;
 use crate::player::{PlayerPhysicsState, PlayerState};
+use crate::settings::Settings;
 use crate::timer::Timer;
-use crate::types::{Shaders, TexturePack};
+use crate::types::{ItemTextures, Shaders, TexturePack};
 use crate::util::Forward;
+use crate::weather::Weather;
+use crate::world_time::WorldTime;
 use nalgebra::{Matrix4, Vector3};
 use nalgebra_glm::vec3;
 use specs::{Join, Read, ReadStorage, System, Write, WriteStorage};
@@ -54,7 +57,11 @@ impl<'a> System<'a> for RenderMainHand {
         ReadStorage<'a, PlayerState>,
         ReadStorage<'a, Interpolator<PlayerPhysicsState>>,
         Read<'a, TexturePack>,
+        Read<'a, ItemTextures>,
         Read<'a, Timer>,
+        Read<'a, WorldTime>,
+        Read<'a, Weather>,
+        Read<'a, Settings>,
         Write<'a, Shaders>,
     );
 
@@ -64,7 +71,11 @@ impl<'a> System<'a> for RenderMainHand {
             player_state,
             player_physics_state,
             texture_pack,
+            item_textures,
             global_timer,
+            world_time,
+            weather,
+            settings,
             mut shaders,
         ) = data;
 
@@ -117,7 +128,7 @@ impl<'a> System<'a> for RenderMainHand {
                 )
             };
 
-            main_hand.update_if_dirty(&texture_pack);
+            main_hand.update_if_dirty(&texture_pack, &item_textures);
 
             let player_pos = player_physics_state.get_interpolated_state().position;
             let camera_height = *player_state.camera_height.get_interpolated_state();
@@ -142,17 +153,20 @@ impl<'a> System<'a> for RenderMainHand {
                 let rotate_matrix =
                     nalgebra_glm::rotation(-35.0f32.to_radians(), &up) * rotate_matrix;
 
+                // Sprites are turned toward the camera
+                let rotate_matrix = match main_hand.showing_item {
+                    Some(item) if item.is_sprite() => {
+                        rotate_matrix * nalgebra_glm::rotation(90.0f32.to_radians(), &Vector3::y())
+                    }
+                    _ => rotate_matrix,
+                };
+
                 translate_matrix1 * rotate_matrix * translate_matrix2
             };
 
             let projection_matrix = {
                 let fov = 70.0f32.to_radians();
-                nalgebra_glm::perspective(
-                    WINDOW_WIDTH as f32 / WINDOW_HEIGHT as f32,
-                    fov,
-                    NEAR_PLANE,
-                    FAR_PLANE,
-                )
+                nalgebra_glm::perspective(settings.aspect_ratio(), fov, NEAR_PLANE, FAR_PLANE)
             };
 
             let hand_shader = shaders.get_mut("hand_shader").unwrap();
@@ -163,11 +177,16 @@ impl<'a> System<'a> for RenderMainHand {
                 hand_shader.set_uniform_matrix4fv("projection", projection_matrix.as_ptr());
                 hand_shader.set_uniform1i("array_texture", 0);
             }
+            hand_shader.set_uniform1f("daylight", weather.daylight(world_time.daylight()));
 
             gl_call!(gl::BindVertexArray(main_hand.render.vao));
 
             gl_call!(gl::Disable(gl::DEPTH_TEST));
-            gl_call!(gl::DrawArrays(gl::TRIANGLES, 0, 36));
+            gl_call!(gl::DrawArrays(
+                gl::TRIANGLES,
+                0,
+                main_hand.render.vertex_count
+            ));
             gl_call!(gl::Enable(gl::DEPTH_TEST));
         }
     }
@@ -0,0 +1,169 @@
+use crate::chunk_manager::ChunkManager;
+use crate::console::Console;
+use crate::game_mode::GameMode;
+use crate::health::{environment_damage, Health};
+use crate::input::InputCache;
+use crate::network::client::ClientNetwork;
+use crate::network::packet::ServerboundPacket;
+use crate::physics::Interpolator;
+use crate::player::{PlayerPhysicsState, PlayerState};
+use crate::settings::Settings;
+use crate::text::{TextColor, TextRenderer, GRAY, RED, WHITE};
+use crate::types::Shaders;
+use glfw::{Action, Key, WindowEvent};
+use specs::{Join, Read, ReadStorage, System, Write, WriteStorage};
+use std::sync::Arc;
+
+const DEATH_SCREEN_COLOR: TextColor = [0.5, 0.0, 0.0, 0.45];
+
+pub struct UpdatePlayerHealth;
+
+impl<'a> System<'a> for UpdatePlayerHealth {
+    type SystemData = (
+        Read<'a, InputCache>,
+        Read<'a, Arc<ChunkManager>>,
+        Read<'a, ClientNetwork>,
+        Write<'a, Console>,
+        WriteStorage<'a, PlayerState>,
+        WriteStorage<'a, Interpolator<PlayerPhysicsState>>,
+        WriteStorage<'a, Health>,
+        ReadStorage<'a, GameMode>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            input_cache,
+            chunk_manager,
+            network,
+            mut console,
+            mut player_state,
+            mut player_physics_state,
+            mut health,
+            game_mode,
+        ) = data;
+
+        for (player_state, player_physics_state, health, game_mode) in (
+            &mut player_state,
+            &mut player_physics_state,
+            &mut health,
+            &game_mode,
+        )
+            .join()
+        {
+            if health.is_dead() {
+                let respawn = input_cache.events.iter().any(|event| {
+                    matches!(event, WindowEvent::Key(Key::Enter, _, Action::Press, _))
+                });
+
+                // When connected, the player is revived by `HandleServerPackets` once the server
+                // answers
+                if respawn && network.is_connected() {
+                    network.send(ServerboundPacket::Respawn);
+                } else if respawn {
+                    health.revive();
+                    player_physics_state.reset_to(PlayerPhysicsState::new_at_position(
+                        player_state.spawn_point,
+                    ));
+                    player_state.is_flying = false;
+                    player_state.block_breaking = None;
+                }
+
+                continue;
+            }
+
+            // When connected, the server applies the damage and sends it
+            if game_mode.takes_damage() && !network.is_connected() {
+                let player = player_physics_state.get_latest_state();
+                let eyes_height = *player_state.camera_height.get_latest_state();
+
+                for (amount, source) in environment_damage(player, eyes_height, &chunk_manager) {
+                    health.damage(amount, source);
+                }
+            }
+
+            // The fall damage is applied by the physics, so the deaths are announced here
+            if let Some(cause) = health.death_cause {
+                if !health.death_announced {
+                    health.death_announced = true;
+                    info!("{}", cause.death_message());
+                    console.push_message(cause.death_message(), WHITE);
+                }
+            }
+        }
+    }
+}
+
+pub struct RenderDeathScreen {
+    text_renderer: TextRenderer,
+}
+
+impl Default for RenderDeathScreen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RenderDeathScreen {
+    pub fn new() -> Self {
+        Self {
+            text_renderer: TextRenderer::new(),
+        }
+    }
+
+    fn queue_centered_text(
+        &mut self,
+        settings: &Settings,
+        y: f32,
+        scale: f32,
+        color: TextColor,
+        text: &str,
+    ) {
+        let x = (settings.window_width as f32 - TextRenderer::text_width(text, scale)) / 2.0;
+        self.text_renderer.queue_text(x, y, scale, color, text);
+    }
+}
+
+impl<'a> System<'a> for RenderDeathScreen {
+    type SystemData = (
+        ReadStorage<'a, Health>,
+        Read<'a, Settings>,
+        Write<'a, Shaders>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (health, settings, mut shaders) = data;
+
+        for health in (&health).join() {
+            if let Some(cause) = health.death_cause {
+                let title_scale = settings.gui_scale * 2.0;
+                let y = settings.window_height as f32 / 3.0;
+
+                self.text_renderer.queue_rect(
+                    0.0,
+                    0.0,
+                    settings.window_width as f32,
+                    settings.window_height as f32,
+                    DEATH_SCREEN_COLOR,
+                );
+                self.queue_centered_text(&settings, y, title_scale, WHITE, "You died!");
+                self.queue_centered_text(
+                    &settings,
+                    y + TextRenderer::line_height(title_scale) * 1.5,
+                    settings.gui_scale,
+                    GRAY,
+                    cause.death_message(),
+                );
+                self.queue_centered_text(
+                    &settings,
+                    y + TextRenderer::line_height(title_scale) * 3.0,
+                    settings.gui_scale,
+                    RED,
+                    "Press Enter to respawn",
+                );
+            }
+        }
+
+        let text_shader = shaders.get_mut("text_shader").unwrap();
+        self.text_renderer.flush(text_shader, &settings);
+    }
+}
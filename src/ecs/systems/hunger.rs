@@ -0,0 +1,315 @@
+use crate::game_mode::GameMode;
+use crate::health::Health;
+use crate::hunger::{Hunger, HUNGER_TICKRATE, SPRINTING_EXHAUSTION};
+use crate::physics::Interpolator;
+use crate::player::{PlayerPhysicsState, PlayerState};
+use crate::timer::Timer;
+use nalgebra_glm::vec2;
+use specs::{Join, Read, ReadStorage, System, WriteStorage};
+use std::time::Instant;
+
+pub struct UpdateHunger {
+    // Time that has not been turned into ticks yet
+    accumulator: f32,
+    last_updated: Option<Instant>,
+}
+
+impl Default for UpdateHunger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UpdateHunger {
+    pub fn new() -> Self {
+        Self {
+            accumulator: 0.0,
+            last_updated: None,
+        }
+    }
+}
+
+impl<'a> System<'a> for UpdateHunger {
+    type SystemData = (
+        Read<'a, Timer>,
+        WriteStorage<'a, PlayerState>,
+        ReadStorage<'a, Interpolator<PlayerPhysicsState>>,
+        ReadStorage<'a, GameMode>,
+        WriteStorage<'a, Health>,
+        WriteStorage<'a, Hunger>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            global_timer,
+            mut player_state,
+            player_physics_state,
+            game_mode,
+            mut health,
+            mut hunger,
+        ) = data;
+
+        // The timer does not advance while the time is paused
+        let now = global_timer.time();
+        let dt = self
+            .last_updated
+            .map(|last_updated| now.saturating_duration_since(last_updated).as_secs_f32())
+            .unwrap_or(0.0);
+        self.last_updated = Some(now);
+
+        // Long frames must not make the player starve at once
+        self.accumulator += dt.min(1.0);
+        let ticks = (self.accumulator * HUNGER_TICKRATE) as u32;
+        self.accumulator -= ticks as f32 / HUNGER_TICKRATE;
+
+        for (player_state, player_physics_state, game_mode, health, hunger) in (
+            &mut player_state,
+            &player_physics_state,
+            &game_mode,
+            &mut health,
+            &mut hunger,
+        )
+            .join()
+        {
+            if !game_mode.has_hunger() {
+                continue;
+            }
+
+            // The player respawns with a full hunger bar
+            if health.is_dead() {
+                *hunger = Hunger::new();
+                continue;
+            }
+
+            if player_state.is_sprinting && !player_state.is_flying {
+                let velocity = player_physics_state.get_latest_state().velocity;
+                let distance = vec2(velocity.x, velocity.z).norm() * dt;
+                hunger.add_exhaustion(SPRINTING_EXHAUSTION * distance);
+            }
+
+            if !hunger.can_sprint() {
+                player_state.is_sprinting = false;
+            }
+
+            for _ in 0..ticks {
+                hunger.tick(health);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::health::{DamageSource, MAX_HEALTH};
+    use crate::hunger::{HEALTH_UPDATE_INTERVAL, MAX_FOOD_LEVEL, SPRINTING_MIN_FOOD_LEVEL};
+    use nalgebra_glm::vec3;
+    use specs::{Builder, Entity, RunNow, World, WorldExt};
+    use std::time::Duration;
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.register::<PlayerState>();
+        world.register::<Interpolator<PlayerPhysicsState>>();
+        world.register::<GameMode>();
+        world.register::<Health>();
+        world.register::<Hunger>();
+        world.insert(Timer::default());
+        world
+    }
+
+    fn spawn_player(world: &mut World, game_mode: GameMode) -> Entity {
+        let position = vec3(0.0, 64.0, 0.0);
+        world
+            .create_entity()
+            .with(PlayerState::new(position))
+            .with(Interpolator::new(
+                1.0 / 30.0,
+                PlayerPhysicsState::new_at_position(position),
+            ))
+            .with(game_mode)
+            .with(Health::new())
+            .with(Hunger::new())
+            .build()
+    }
+
+    // Runs the system as if `seconds` had passed since its last run, the timer of the world is
+    // never ticked so the simulated time does not depend on the machine
+    fn simulate(system: &mut UpdateHunger, world: &World, seconds: u32) {
+        for _ in 0..seconds {
+            let now = world.read_resource::<Timer>().time();
+            system.last_updated = Some(now - Duration::from_secs(1));
+            system.run_now(world);
+        }
+    }
+
+    #[test]
+    fn first_run_does_not_tick() {
+        let mut world = world();
+        let player = spawn_player(&mut world, GameMode::Survival);
+        world
+            .write_storage::<Health>()
+            .get_mut(player)
+            .unwrap()
+            .health = MAX_HEALTH - 1;
+
+        let mut system = UpdateHunger::new();
+        system.run_now(&world);
+        assert_eq!(system.accumulator, 0.0);
+        assert_eq!(
+            world
+                .read_storage::<Hunger>()
+                .get(player)
+                .unwrap()
+                .health_update_timer,
+            0
+        );
+    }
+
+    #[test]
+    fn health_regenerates_at_the_tick_rate() {
+        let mut world = world();
+        let player = spawn_player(&mut world, GameMode::Survival);
+        world
+            .write_storage::<Health>()
+            .get_mut(player)
+            .unwrap()
+            .health = MAX_HEALTH - 1;
+
+        let seconds = HEALTH_UPDATE_INTERVAL / HUNGER_TICKRATE as u32;
+        let mut system = UpdateHunger::new();
+        simulate(&mut system, &world, seconds - 1);
+        assert_eq!(
+            world.read_storage::<Health>().get(player).unwrap().health,
+            MAX_HEALTH - 1
+        );
+
+        simulate(&mut system, &world, 1);
+        assert_eq!(
+            world.read_storage::<Health>().get(player).unwrap().health,
+            MAX_HEALTH
+        );
+    }
+
+    #[test]
+    fn long_frames_are_capped() {
+        let mut world = world();
+        let player = spawn_player(&mut world, GameMode::Survival);
+        world
+            .write_storage::<Health>()
+            .get_mut(player)
+            .unwrap()
+            .health = MAX_HEALTH - 1;
+
+        let mut system = UpdateHunger::new();
+        let now = world.read_resource::<Timer>().time();
+        system.last_updated = Some(now - Duration::from_secs(60));
+        system.run_now(&world);
+
+        assert_eq!(
+            world.read_storage::<Health>().get(player).unwrap().health,
+            MAX_HEALTH - 1
+        );
+        assert_eq!(
+            world
+                .read_storage::<Hunger>()
+                .get(player)
+                .unwrap()
+                .health_update_timer,
+            HUNGER_TICKRATE as u32
+        );
+    }
+
+    #[test]
+    fn sprinting_exhausts_per_meter() {
+        let mut world = world();
+        let player = spawn_player(&mut world, GameMode::Survival);
+        world
+            .write_storage::<PlayerState>()
+            .get_mut(player)
+            .unwrap()
+            .is_sprinting = true;
+        world
+            .write_storage::<Interpolator<PlayerPhysicsState>>()
+            .get_mut(player)
+            .unwrap()
+            .get_latest_state_mut()
+            .velocity = vec3(3.0, 0.0, 4.0);
+
+        let mut system = UpdateHunger::new();
+        simulate(&mut system, &world, 1);
+
+        let exhaustion = world
+            .read_storage::<Hunger>()
+            .get(player)
+            .unwrap()
+            .exhaustion;
+        assert!((exhaustion - SPRINTING_EXHAUSTION * 5.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn hungry_player_stops_sprinting() {
+        let mut world = world();
+        let player = spawn_player(&mut world, GameMode::Survival);
+        world
+            .write_storage::<PlayerState>()
+            .get_mut(player)
+            .unwrap()
+            .is_sprinting = true;
+        world
+            .write_storage::<Hunger>()
+            .get_mut(player)
+            .unwrap()
+            .food_level = SPRINTING_MIN_FOOD_LEVEL;
+
+        let mut system = UpdateHunger::new();
+        simulate(&mut system, &world, 1);
+
+        assert!(
+            !world
+                .read_storage::<PlayerState>()
+                .get(player)
+                .unwrap()
+                .is_sprinting
+        );
+    }
+
+    #[test]
+    fn creative_player_has_no_hunger() {
+        let mut world = world();
+        let player = spawn_player(&mut world, GameMode::Creative);
+        world
+            .write_storage::<Hunger>()
+            .get_mut(player)
+            .unwrap()
+            .add_exhaustion(100.0);
+
+        let mut system = UpdateHunger::new();
+        simulate(&mut system, &world, 1);
+
+        let hunger = world.read_storage::<Hunger>();
+        assert_eq!(hunger.get(player).unwrap().food_level, MAX_FOOD_LEVEL);
+    }
+
+    #[test]
+    fn dead_player_respawns_with_a_full_hunger_bar() {
+        let mut world = world();
+        let player = spawn_player(&mut world, GameMode::Survival);
+        world
+            .write_storage::<Health>()
+            .get_mut(player)
+            .unwrap()
+            .damage(MAX_HEALTH, DamageSource::Starvation);
+        world
+            .write_storage::<Hunger>()
+            .get_mut(player)
+            .unwrap()
+            .food_level = 0;
+
+        let mut system = UpdateHunger::new();
+        simulate(&mut system, &world, 1);
+
+        let hunger = world.read_storage::<Hunger>();
+        assert_eq!(hunger.get(player).unwrap().food_level, MAX_FOOD_LEVEL);
+    }
+}
@@ -1,7 +1,10 @@
+use crate::console::Console;
 use crate::input::InputCache;
+use crate::journal::{EditJournal, JOURNAL_PATH};
+use crate::settings::SettingsMenu;
 use crate::timer::Timer;
 use glfw::{Action, Context, Glfw, Key, Window, WindowEvent};
-use specs::{System, Write};
+use specs::{Read, System, Write};
 use std::process::exit;
 use std::sync::mpsc::Receiver;
 
@@ -12,12 +15,21 @@ pub struct ReadWindowEvents {
 }
 
 impl<'a> System<'a> for ReadWindowEvents {
-    type SystemData = (Write<'a, InputCache>, Write<'a, Timer>);
+    type SystemData = (
+        Read<'a, Console>,
+        Read<'a, SettingsMenu>,
+        Write<'a, InputCache>,
+        Write<'a, Timer>,
+        Write<'a, EditJournal>,
+    );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut input_cache, mut global_timer) = data;
+        let (console, settings_menu, mut input_cache, mut global_timer, mut journal) = data;
 
         if self.window.should_close() {
+            if let Err(err) = journal.save() {
+                error!("Could not save {JOURNAL_PATH}: {err}");
+            }
             exit(0);
         }
 
@@ -29,6 +41,11 @@ impl<'a> System<'a> for ReadWindowEvents {
         for (_, event) in glfw::flush_messages(&self.events) {
             input_cache.handle_event(&event);
 
+            // The keys are typed in the console or change the settings instead
+            if console.is_open || settings_menu.is_open {
+                continue;
+            }
+
             match event {
                 WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
                     self.window.set_should_close(true);
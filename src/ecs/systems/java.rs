@@ -0,0 +1,415 @@
+use crate::chunk::{BlockID, ChunkColumn};
+use crate::chunk_manager::{BlockEditBatch, ChunkManager};
+use crate::console::Console;
+use crate::game_mode::GameMode;
+use crate::network::java::blocks::{block_of_state, item_of_block};
+use crate::network::java::chunk_data::read_sections;
+use crate::network::java::client::JavaNetwork;
+use crate::network::java::packet::{PlayClientbound, PlayServerbound};
+use crate::physics::Interpolator;
+use crate::player::{PlayerPhysicsState, PlayerState};
+use crate::text::{RED, WHITE};
+use crate::weather::{Weather, WeatherKind};
+use crate::world_time::WorldTime;
+use nalgebra_glm::vec3;
+use specs::{Join, Read, ReadStorage, System, Write, WriteStorage};
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Vanilla clients send their position every tick
+const MOVEMENT_INTERVAL: Duration = Duration::from_millis(50);
+// Hotbar slot holding the block placed, as a slot of the inventory window and of the hotbar
+const PLACEMENT_INVENTORY_SLOT: i16 = 36;
+const PLACEMENT_HOTBAR_SLOT: i16 = 0;
+
+// Our yaw is 0 towards +x, theirs towards +z, and their pitch is positive when looking down
+fn angles_to_java(rotation_x: f32, rotation_y: f32) -> (f32, f32) {
+    (rotation_y.to_degrees() - 90.0, -rotation_x.to_degrees())
+}
+
+fn angles_from_java(yaw: f32, pitch: f32) -> (f32, f32) {
+    (-pitch.to_radians(), (yaw + 90.0).to_radians())
+}
+
+fn game_mode_from_java(game_mode: u8) -> GameMode {
+    // The hardcore flag is in the bit 0x8, adventure mode is played as survival
+    match game_mode & 0x7 {
+        1 => GameMode::Creative,
+        3 => GameMode::Spectator,
+        _ => GameMode::Survival,
+    }
+}
+
+fn unload_column(chunk_manager: &ChunkManager, x: i32, z: i32) {
+    if let Some(column) = chunk_manager.remove_chunk_column(&(x, z)) {
+        for chunk in column.chunks.iter() {
+            chunk.unload_from_gpu();
+        }
+    }
+}
+
+// Applies what a vanilla server sends, like `HandleServerPackets`
+pub struct HandleJavaPackets;
+
+impl<'a> System<'a> for HandleJavaPackets {
+    type SystemData = (
+        Read<'a, Arc<ChunkManager>>,
+        Write<'a, JavaNetwork>,
+        Write<'a, Console>,
+        WriteStorage<'a, PlayerState>,
+        WriteStorage<'a, Interpolator<PlayerPhysicsState>>,
+        WriteStorage<'a, GameMode>,
+        Write<'a, WorldTime>,
+        Write<'a, Weather>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            chunk_manager,
+            mut network,
+            mut console,
+            mut player_state,
+            mut player_physics_state,
+            mut game_mode,
+            mut world_time,
+            mut weather,
+        ) = data;
+
+        let packets = match &network.connection {
+            Some(connection) => connection.received_packets().collect::<Vec<_>>(),
+            None => return,
+        };
+
+        for packet in packets {
+            let packet = match packet {
+                Ok(packet) => packet,
+                Err(err) => {
+                    console.push_message(&format!("Disconnected: {err}"), RED);
+                    network.connection = None;
+                    console.outgoing_chat = None;
+                    break;
+                }
+            };
+
+            match packet {
+                PlayClientbound::JoinGame {
+                    entity_id,
+                    game_mode: java_game_mode,
+                    dimension,
+                } => {
+                    for (_, game_mode) in (&player_state, &mut game_mode).join() {
+                        *game_mode = game_mode_from_java(java_game_mode);
+                    }
+
+                    network.entity_id = Some(entity_id);
+                    network.has_sky_light = dimension == 0;
+                    console.outgoing_chat = Some(Vec::new());
+                }
+                PlayClientbound::Respawn {
+                    dimension,
+                    game_mode: java_game_mode,
+                } => {
+                    for (_, game_mode) in (&player_state, &mut game_mode).join() {
+                        *game_mode = game_mode_from_java(java_game_mode);
+                    }
+
+                    // The columns of the previous dimension are sent again
+                    let columns = chunk_manager
+                        .loaded_chunk_columns
+                        .read()
+                        .keys()
+                        .copied()
+                        .collect::<Vec<_>>();
+                    for (x, z) in columns {
+                        unload_column(&chunk_manager, x, z);
+                    }
+                    network.has_sky_light = dimension == 0;
+                    network.has_spawned = false;
+                }
+                PlayClientbound::PlayerPositionAndLook {
+                    x,
+                    y,
+                    z,
+                    yaw,
+                    pitch,
+                    flags,
+                    teleport_id,
+                } => {
+                    for (player_state, player_physics_state) in
+                        (&mut player_state, &mut player_physics_state).join()
+                    {
+                        let previous = player_physics_state.get_latest_state().position;
+                        let relative = |bit: u8, value: f64, previous: f32| {
+                            value as f32 + if flags & bit != 0 { previous } else { 0.0 }
+                        };
+                        let position = vec3(
+                            relative(0x01, x, previous.x),
+                            relative(0x02, y, previous.y),
+                            relative(0x04, z, previous.z),
+                        );
+
+                        // The relative angles are only offsets
+                        let (rotation_x, rotation_y) = angles_from_java(yaw, pitch);
+                        if flags & 0x08 != 0 {
+                            player_state.rotation.y += yaw.to_radians();
+                        } else {
+                            player_state.rotation.y = rotation_y;
+                        }
+                        if flags & 0x10 != 0 {
+                            player_state.rotation.x -= pitch.to_radians();
+                        } else {
+                            player_state.rotation.x = rotation_x;
+                        }
+
+                        player_physics_state
+                            .reset_to(PlayerPhysicsState::new_at_position(position));
+
+                        let (yaw, pitch) =
+                            angles_to_java(player_state.rotation.x, player_state.rotation.y);
+                        network.send(PlayServerbound::TeleportConfirm { teleport_id });
+                        network.send(PlayServerbound::PlayerPositionAndLook {
+                            x: position.x as f64,
+                            y: position.y as f64,
+                            z: position.z as f64,
+                            yaw,
+                            pitch,
+                            on_ground: false,
+                        });
+                    }
+
+                    network.has_spawned = true;
+                    network.movement_sent = Some(Instant::now());
+                }
+                PlayClientbound::KeepAlive { id } => {
+                    network.send(PlayServerbound::KeepAlive { id });
+                }
+                PlayClientbound::ChunkData {
+                    x,
+                    z,
+                    full_chunk,
+                    primary_bit_mask,
+                    data,
+                } => {
+                    if full_chunk {
+                        let column = ChunkColumn::new();
+
+                        if let Err(err) =
+                            read_sections(&data, primary_bit_mask, network.has_sky_light, &column)
+                        {
+                            warn!("Invalid column ({x}, {z}): {err}");
+                            continue;
+                        }
+                        // The server sends the columns with their trees
+                        *column.has_foliage.write() = true;
+
+                        unload_column(&chunk_manager, x, z);
+                        chunk_manager.add_chunk_column((x, z), Arc::new(column));
+                    } else {
+                        // Only the sections in the bit mask changed, they are read in a column of
+                        // their own and copied
+                        let sections = ChunkColumn::new();
+
+                        if let Err(err) =
+                            read_sections(&data, primary_bit_mask, network.has_sky_light, &sections)
+                        {
+                            warn!("Invalid column ({x}, {z}): {err}");
+                            continue;
+                        }
+
+                        let mut batch = BlockEditBatch::new();
+                        for section_y in (0..16).filter(|y| primary_bit_mask & (1 << y) != 0) {
+                            let chunk = sections.get_chunk(section_y);
+
+                            for bx in 0..16 {
+                                for by in 0..16 {
+                                    for bz in 0..16 {
+                                        batch.put_block(
+                                            x * 16 + bx,
+                                            section_y * 16 + by,
+                                            z * 16 + bz,
+                                            chunk.get_block(bx as u32, by as u32, bz as u32),
+                                        );
+                                    }
+                                }
+                            }
+                        }
+                        // The heights of the column follow the changes, which are not edits of
+                        // the player
+                        chunk_manager.set_batch(batch);
+                    }
+                }
+                PlayClientbound::UnloadChunk { x, z } => {
+                    unload_column(&chunk_manager, x, z);
+                }
+                PlayClientbound::BlockChange { x, y, z, state } => {
+                    let mut batch = BlockEditBatch::new();
+                    batch.put_block(x, y, z, block_of_state(state));
+                    chunk_manager.set_batch(batch);
+                }
+                PlayClientbound::MultiBlockChange {
+                    chunk_x,
+                    chunk_z,
+                    changes,
+                } => {
+                    let batch = changes
+                        .into_iter()
+                        .map(|(x, y, z, state)| {
+                            (
+                                (chunk_x * 16 + x as i32, y as i32, chunk_z * 16 + z as i32),
+                                block_of_state(state),
+                            )
+                        })
+                        .collect();
+                    chunk_manager.set_batch(batch);
+                }
+                PlayClientbound::Chat { message } => {
+                    console.push_message(&message, WHITE);
+                }
+                PlayClientbound::Disconnect { reason } => {
+                    console.push_message(&format!("Disconnected: {reason}"), RED);
+                    network.connection = None;
+                    console.outgoing_chat = None;
+                    break;
+                }
+                PlayClientbound::TimeUpdate { time_of_day, .. } => {
+                    *world_time = WorldTime::from_ticks(time_of_day.unsigned_abs());
+                }
+                // The reason 1 starts the rain, 2 stops it and 8 sets the strength of the storm
+                PlayClientbound::ChangeGameState { reason, value } => match reason {
+                    1 => weather.set(WeatherKind::Rain, None),
+                    2 => weather.set(WeatherKind::Clear, None),
+                    8 if value > 0.5 => weather.set(WeatherKind::Thunder, None),
+                    8 if weather.kind() == WeatherKind::Thunder => {
+                        weather.set(WeatherKind::Rain, None)
+                    }
+                    _ => {}
+                },
+                PlayClientbound::Other { .. } => {}
+            }
+        }
+    }
+}
+
+// Sends the movements of the player, the blocks it edited and its chat messages to a vanilla
+// server, runs before `ClearChangedBlocks`
+pub struct SendJavaUpdates;
+
+impl SendJavaUpdates {
+    // The vanilla servers have no packet to set a block, the player breaks it or places it against
+    // one of its neighbours, with the block to place in its hand
+    fn send_block_edit(
+        network: &JavaNetwork,
+        chunk_manager: &ChunkManager,
+        (x, y, z): (i32, i32, i32),
+        block: BlockID,
+    ) {
+        if block.is_air() {
+            for status in [0, 2] {
+                network.send(PlayServerbound::PlayerDigging {
+                    status,
+                    x,
+                    y,
+                    z,
+                    face: 1,
+                });
+            }
+            return;
+        }
+
+        let item = match item_of_block(block) {
+            Some(item) => item,
+            None => return,
+        };
+
+        // The face of the neighbour that touches the block
+        let neighbours = [
+            ((0, 1, 0), 0),
+            ((0, -1, 0), 1),
+            ((0, 0, 1), 2),
+            ((0, 0, -1), 3),
+            ((1, 0, 0), 4),
+            ((-1, 0, 0), 5),
+        ];
+        let neighbour = neighbours
+            .iter()
+            .find(|((dx, dy, dz), _)| chunk_manager.is_solid_block_at(x + dx, y + dy, z + dz));
+
+        let ((dx, dy, dz), face) = match neighbour {
+            Some(&neighbour) => neighbour,
+            None => {
+                debug!("No block to place ({x}, {y}, {z}) against");
+                return;
+            }
+        };
+
+        network.send(PlayServerbound::CreativeInventoryAction {
+            slot: PLACEMENT_INVENTORY_SLOT,
+            item: Some((item, 1, 0)),
+        });
+        network.send(PlayServerbound::HeldItemChange {
+            slot: PLACEMENT_HOTBAR_SLOT,
+        });
+        network.send(PlayServerbound::PlayerBlockPlacement {
+            x: x + dx,
+            y: y + dy,
+            z: z + dz,
+            face,
+        });
+    }
+}
+
+impl<'a> System<'a> for SendJavaUpdates {
+    type SystemData = (
+        Read<'a, Arc<ChunkManager>>,
+        Write<'a, JavaNetwork>,
+        Write<'a, Console>,
+        ReadStorage<'a, PlayerState>,
+        ReadStorage<'a, Interpolator<PlayerPhysicsState>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (chunk_manager, mut network, mut console, player_state, player_physics_state) = data;
+
+        // The server ignores the movements until the first teleport is confirmed
+        if !network.is_connected() || !network.has_spawned {
+            return;
+        }
+
+        let movement_due = network
+            .movement_sent
+            .is_none_or(|sent| sent.elapsed() >= MOVEMENT_INTERVAL);
+
+        if movement_due {
+            for (player_state, player_physics_state) in
+                (&player_state, &player_physics_state).join()
+            {
+                let state = player_physics_state.get_latest_state();
+                let (yaw, pitch) = angles_to_java(player_state.rotation.x, player_state.rotation.y);
+
+                network.send(PlayServerbound::PlayerPositionAndLook {
+                    x: state.position.x as f64,
+                    y: state.position.y as f64,
+                    z: state.position.z as f64,
+                    yaw,
+                    pitch,
+                    on_ground: state.is_on_ground,
+                });
+            }
+
+            network.movement_sent = Some(Instant::now());
+        }
+
+        for (x, y, z) in chunk_manager.changed_blocks() {
+            if let Some(block) = chunk_manager.get_block(x, y, z) {
+                Self::send_block_edit(&network, &chunk_manager, (x, y, z), block);
+            }
+        }
+
+        // The server sends the messages back with the name of the player
+        let outgoing_chat = console.outgoing_chat.as_mut().map(mem::take);
+        for message in outgoing_chat.unwrap_or_default() {
+            network.send(PlayServerbound::Chat { message });
+        }
+    }
+}
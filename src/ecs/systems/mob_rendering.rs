@@ -0,0 +1,121 @@
+use crate::frustum::Frustum;
+use crate::mob::model::create_model_part_vao;
+use crate::mob::{Mob, MobPhysicsState};
+use crate::physics::Interpolator;
+use crate::player::PlayerState;
+use crate::settings::Settings;
+use crate::types::Shaders;
+use crate::weather::Weather;
+use crate::world_time::WorldTime;
+use nalgebra::Matrix4;
+use nalgebra_glm::{vec3, Vec3};
+use specs::{Join, Read, ReadStorage, System, Write};
+
+pub struct RenderMobs {
+    vao: u32,
+}
+
+impl Default for RenderMobs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RenderMobs {
+    pub fn new() -> Self {
+        Self {
+            vao: create_model_part_vao(),
+        }
+    }
+}
+
+impl<'a> System<'a> for RenderMobs {
+    type SystemData = (
+        ReadStorage<'a, PlayerState>,
+        ReadStorage<'a, Mob>,
+        ReadStorage<'a, Interpolator<MobPhysicsState>>,
+        Write<'a, Shaders>,
+        Read<'a, WorldTime>,
+        Read<'a, Weather>,
+        Read<'a, Settings>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (player_state, mobs, mob_physics_state, mut shaders, world_time, weather, settings) =
+            data;
+
+        let mob_shader = shaders.get_mut("mob_shader").unwrap();
+        mob_shader.use_program();
+
+        mob_shader.set_uniform1i("enable_fog", settings.fog as i32);
+        mob_shader.set_uniform3f(
+            "sky_color",
+            weather.sky_color(world_time.sky_color()).as_slice(),
+        );
+        mob_shader.set_uniform1f("daylight", weather.daylight(world_time.daylight()));
+        mob_shader.set_uniform1f("render_distance", settings.render_distance as f32);
+
+        gl_call!(gl::BindVertexArray(self.vao));
+
+        for player_state in (&player_state).join() {
+            unsafe {
+                mob_shader.set_uniform_matrix4fv("view", player_state.view_matrix.as_ptr());
+                mob_shader
+                    .set_uniform_matrix4fv("projection", player_state.projection_matrix.as_ptr());
+            }
+
+            let frustum =
+                Frustum::from_matrix(&(player_state.projection_matrix * player_state.view_matrix));
+
+            for (mob, mob_physics_state) in (&mobs, &mob_physics_state).join() {
+                let state = mob_physics_state.get_interpolated_state();
+
+                if !frustum.intersects_aabb(&state.aabb) {
+                    continue;
+                }
+
+                let mob_matrix = Matrix4::new_translation(&state.position)
+                    * Matrix4::from_euler_angles(0.0, state.yaw, 0.0);
+                let leg_angle = (state.walk_distance * 4.0).sin() * 0.6;
+
+                for part in mob.kind.model() {
+                    let center = Vec3::from(part.center);
+                    let size = Vec3::from(part.size);
+
+                    // The legs rotate around the middle of their top face
+                    let rotation = match part.swing {
+                        Some(direction) => {
+                            let pivot = vec3(0.0, size.y / 2.0, 0.0);
+
+                            Matrix4::new_translation(&pivot)
+                                * Matrix4::from_euler_angles(direction * leg_angle, 0.0, 0.0)
+                                * Matrix4::new_translation(&-pivot)
+                        }
+                        None => Matrix4::identity(),
+                    };
+
+                    let model_matrix = mob_matrix
+                        * Matrix4::new_translation(&center)
+                        * rotation
+                        * Matrix4::new_nonuniform_scaling(&size)
+                        * Matrix4::new_translation(&vec3(-0.5, -0.5, -0.5));
+
+                    // Hurt mobs flash red
+                    let [r, g, b] = part.color;
+                    let color = if mob.is_hurt() {
+                        [r, g * 0.4, b * 0.4]
+                    } else {
+                        [r, g, b]
+                    };
+
+                    mob_shader.set_uniform3f("color", &color);
+                    unsafe {
+                        mob_shader.set_uniform_matrix4fv("model", model_matrix.as_ptr());
+                    }
+
+                    gl_call!(gl::DrawArrays(gl::TRIANGLES, 0, 36));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,470 @@
+use crate::chunk::BlockID;
+use crate::chunk_manager::ChunkManager;
+use crate::combat::KNOCKBACK_FRICTION;
+use crate::constants::{GRAVITY, MAX_VERTICAL_VELOCITY, ON_GROUND_FRICTION, PHYSICS_TICKRATE};
+use crate::game_mode::GameMode;
+use crate::health::Health;
+use crate::metrics::Metrics;
+use crate::mob::{
+    Mob, MobBehaviour, MobKind, MobPhysicsState, CHASE_SEARCH_BUDGET, DETECTION_DISTANCE,
+    FLEE_TRIGGER_DISTANCE, MOB_JUMP_HEIGHT, REPATH_INTERVAL, WANDER_SEARCH_BUDGET,
+};
+use crate::pathfinding::{find_path, is_standable, Path};
+use crate::physics::Interpolator;
+use crate::player::{PlayerPhysicsState, PlayerState};
+use crate::timer::Timer;
+use nalgebra_glm::{vec2, vec3, IVec3, Vec3};
+use rand::random;
+use specs::{Entities, Join, Read, ReadStorage, System, Write, WriteStorage};
+use std::f32::consts::PI;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Maximum number of mobs of each category in the world
+pub const PASSIVE_MOB_CAP: usize = 20;
+pub const HOSTILE_MOB_CAP: usize = 15;
+pub const MOB_SPAWN_INTERVAL: Duration = Duration::from_secs(1);
+// Mobs spawn in a ring around the player, out of sight but not too far
+pub const MIN_MOB_SPAWN_DISTANCE: f32 = 24.0;
+pub const MAX_MOB_SPAWN_DISTANCE: f32 = 64.0;
+// Radians per second
+const MOB_TURN_SPEED: f32 = 6.0;
+
+// Whether a passive mob can spawn on top of the block at the given position
+fn is_valid_spawn_position(chunk_manager: &ChunkManager, x: i32, y: i32, z: i32) -> bool {
+    let is_free = |y| {
+        chunk_manager
+            .get_block(x, y, z)
+            .is_some_and(|block| !block.is_solid() && block != BlockID::Lava)
+    };
+
+    chunk_manager.get_block(x, y, z) == Some(BlockID::GrassBlock)
+        && is_free(y + 1)
+        && is_free(y + 2)
+}
+
+// There is no light propagation, so the blocks with something above them get no sky light
+fn is_dark(highest_block: i32, y: i32) -> bool {
+    y + 1 < highest_block
+}
+
+// Feet position of a hostile mob spawning in the column, searching down from a random height
+fn find_dark_spawn_position(chunk_manager: &ChunkManager, x: i32, z: i32) -> Option<i32> {
+    let highest_block = chunk_manager.highest_block(x, z)?;
+    if highest_block < 2 {
+        return None;
+    }
+
+    let start = 1 + random::<i32>().rem_euclid(highest_block);
+
+    (start.saturating_sub(16).max(1)..=start)
+        .rev()
+        .find(|&y| is_dark(highest_block, y) && is_standable(chunk_manager, &vec3(x, y, z)))
+}
+
+// Center of an area where mobs spawn around a player
+fn random_spawn_area(player_position: &Vec3) -> (i32, i32) {
+    let angle = random::<f32>() * 2.0 * PI;
+    let distance = MIN_MOB_SPAWN_DISTANCE
+        + random::<f32>() * (MAX_MOB_SPAWN_DISTANCE - MIN_MOB_SPAWN_DISTANCE);
+
+    (
+        (player_position.x + angle.cos() * distance).floor() as i32,
+        (player_position.z + angle.sin() * distance).floor() as i32,
+    )
+}
+
+pub struct SpawnMobs {
+    last_spawn_attempt: Option<Instant>,
+}
+
+impl Default for SpawnMobs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpawnMobs {
+    pub fn new() -> Self {
+        Self {
+            last_spawn_attempt: None,
+        }
+    }
+}
+
+impl<'a> System<'a> for SpawnMobs {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Timer>,
+        Read<'a, Arc<ChunkManager>>,
+        ReadStorage<'a, Interpolator<PlayerPhysicsState>>,
+        WriteStorage<'a, Mob>,
+        WriteStorage<'a, Interpolator<MobPhysicsState>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            global_timer,
+            chunk_manager,
+            player_physics_state,
+            mut mobs,
+            mut mob_physics_state,
+        ) = data;
+
+        let now = global_timer.time();
+        if let Some(last_spawn_attempt) = self.last_spawn_attempt {
+            if now.saturating_duration_since(last_spawn_attempt) < MOB_SPAWN_INTERVAL {
+                return;
+            }
+        }
+        self.last_spawn_attempt = Some(now);
+
+        let mut hostile_count = (&mobs).join().filter(|mob| mob.kind.is_hostile()).count();
+        let mut passive_count = (&mobs).join().count() - hostile_count;
+        let mut spawned_mobs = Vec::new();
+
+        for player_physics_state in (&player_physics_state).join() {
+            let player_position = player_physics_state.get_latest_state().position;
+
+            // Passive mobs spawn in small herds of the same kind on grass
+            if passive_count < PASSIVE_MOB_CAP {
+                let (center_x, center_z) = random_spawn_area(&player_position);
+                let kind = MobKind::PASSIVE[random::<usize>() % MobKind::PASSIVE.len()];
+                let herd_size = 1 + random::<usize>() % 3;
+                let mut spawned = 0;
+
+                for _ in 0..herd_size * 2 {
+                    if spawned == herd_size || passive_count >= PASSIVE_MOB_CAP {
+                        break;
+                    }
+
+                    let x = center_x + random::<i32>().rem_euclid(5) - 2;
+                    let z = center_z + random::<i32>().rem_euclid(5) - 2;
+
+                    if let Some(y) = chunk_manager
+                        .highest_block(x, z)
+                        .filter(|&y| is_valid_spawn_position(&chunk_manager, x, y, z))
+                    {
+                        spawned_mobs.push((kind, vec3(x, y + 1, z)));
+                        spawned += 1;
+                        passive_count += 1;
+                    }
+                }
+            }
+
+            // Hostile mobs spawn alone in the dark
+            if hostile_count < HOSTILE_MOB_CAP {
+                let (x, z) = random_spawn_area(&player_position);
+                let kind = MobKind::HOSTILE[random::<usize>() % MobKind::HOSTILE.len()];
+
+                if let Some(y) = find_dark_spawn_position(&chunk_manager, x, z) {
+                    spawned_mobs.push((kind, vec3(x, y, z)));
+                    hostile_count += 1;
+                }
+            }
+        }
+
+        for (kind, feet) in spawned_mobs {
+            let position = vec3(feet.x as f32 + 0.5, feet.y as f32, feet.z as f32 + 0.5);
+
+            entities
+                .build_entity()
+                .with(Mob::new(kind), &mut mobs)
+                .with(
+                    Interpolator::new(
+                        1.0 / PHYSICS_TICKRATE,
+                        MobPhysicsState::new_at_position(kind, position),
+                    ),
+                    &mut mob_physics_state,
+                )
+                .build();
+
+            debug!(
+                "Spawned a {} at {:?}",
+                kind.name(),
+                (feet.x, feet.y, feet.z)
+            );
+        }
+    }
+}
+
+pub struct UpdateMobBehaviour;
+
+impl<'a> System<'a> for UpdateMobBehaviour {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Arc<ChunkManager>>,
+        ReadStorage<'a, PlayerState>,
+        ReadStorage<'a, Interpolator<PlayerPhysicsState>>,
+        ReadStorage<'a, Health>,
+        ReadStorage<'a, GameMode>,
+        WriteStorage<'a, Mob>,
+        ReadStorage<'a, Interpolator<MobPhysicsState>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            chunk_manager,
+            player_state,
+            player_physics_state,
+            health,
+            game_mode,
+            mut mobs,
+            mob_physics_state,
+        ) = data;
+
+        let now = Instant::now();
+        let changed_blocks = chunk_manager.changed_blocks();
+
+        for (mob, mob_physics_state) in (&mut mobs, &mob_physics_state).join() {
+            let position = mob_physics_state.get_latest_state().position;
+            let mut closest_player = None;
+
+            for (entity, player_state, player_physics_state, health, game_mode) in (
+                &entities,
+                &player_state,
+                &player_physics_state,
+                &health,
+                &game_mode,
+            )
+                .join()
+            {
+                let player_position = player_physics_state.get_latest_state().position;
+                let distance = (player_position - position).norm();
+
+                if player_state.is_sprinting && distance < FLEE_TRIGGER_DISTANCE {
+                    mob.startle(player_position);
+                }
+
+                // Only the players that can be hurt are chased
+                if game_mode.takes_damage()
+                    && !health.is_dead()
+                    && distance < DETECTION_DISTANCE
+                    && closest_player.is_none_or(|(_, _, closest)| distance < closest)
+                {
+                    closest_player = Some((entity, player_position, distance));
+                }
+            }
+
+            if let (true, Some((entity, player_position, _))) =
+                (mob.kind.is_hostile(), closest_player)
+            {
+                mob.chase(entity, player_position, now);
+            }
+
+            mob.update_behaviour(&position, now);
+
+            // Plan again when the blocks along the path changed
+            let path_invalidated = mob.path.as_ref().is_some_and(|path| {
+                changed_blocks
+                    .iter()
+                    .any(|&(x, y, z)| path.is_affected_by(x, y, z))
+            });
+            if path_invalidated {
+                mob.path = None;
+            }
+
+            match mob.behaviour {
+                MobBehaviour::Wander { target } if mob.path.is_none() => {
+                    let goal_x = target.x.floor() as i32;
+                    let goal_z = target.z.floor() as i32;
+
+                    mob.path = chunk_manager.highest_block(goal_x, goal_z).and_then(|y| {
+                        plan_path(
+                            &chunk_manager,
+                            &position,
+                            &vec3(goal_x, y + 1, goal_z),
+                            WANDER_SEARCH_BUDGET,
+                        )
+                    });
+
+                    // Nowhere to go, the mob rests until picking another target
+                    if mob.path.is_none() {
+                        mob.behaviour = MobBehaviour::Idle;
+                    }
+                }
+                MobBehaviour::Chase {
+                    position: target, ..
+                } => {
+                    let goal = target.map(|x| x.floor() as i32);
+
+                    // The player moves, the path is planned again regularly
+                    let is_outdated = mob.path.as_ref().is_none_or(|path| {
+                        path.is_finished() || path.waypoints.last().is_none_or(|&end| end != goal)
+                    });
+                    let can_plan = mob
+                        .path_last_planned
+                        .is_none_or(|time| now.saturating_duration_since(time) >= REPATH_INTERVAL);
+
+                    if is_outdated && can_plan {
+                        mob.path_last_planned = Some(now);
+                        mob.path = plan_path(&chunk_manager, &position, &goal, CHASE_SEARCH_BUDGET);
+                    }
+                }
+                _ => {}
+            }
+
+            if let Some(path) = &mut mob.path {
+                path.advance(&position);
+            }
+        }
+    }
+}
+
+fn plan_path(
+    chunk_manager: &ChunkManager,
+    position: &Vec3,
+    goal: &IVec3,
+    budget: usize,
+) -> Option<Path> {
+    let start = position.map(|x| x.floor() as i32);
+
+    find_path(chunk_manager, &start, goal, budget)
+}
+
+// Rotates from an angle to another by at most `max_step`, going the shortest way around
+fn turn_towards(from: f32, to: f32, max_step: f32) -> f32 {
+    let difference = (to - from + PI).rem_euclid(2.0 * PI) - PI;
+
+    from + difference.clamp(-max_step, max_step)
+}
+
+pub struct UpdateMobPhysics;
+
+impl<'a> System<'a> for UpdateMobPhysics {
+    type SystemData = (
+        Read<'a, Timer>,
+        Read<'a, Arc<ChunkManager>>,
+        WriteStorage<'a, Mob>,
+        WriteStorage<'a, Interpolator<MobPhysicsState>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (global_timer, chunk_manager, mut mobs, mut mob_physics_state) = data;
+
+        let jump_impulse = (MOB_JUMP_HEIGHT * 2.0 * -GRAVITY).sqrt();
+
+        for (mob, mob_physics_state) in (&mut mobs, &mut mob_physics_state).join() {
+            mob_physics_state.step(
+                global_timer.time(),
+                &mut |state: &MobPhysicsState, _t: f32, dt: f32| {
+                    let mut state = state.clone();
+
+                    state.velocity.y =
+                        (state.velocity.y + GRAVITY * dt).max(-MAX_VERTICAL_VELOCITY);
+
+                    match mob.movement_direction(&state.position) {
+                        Some(direction) => {
+                            let target_yaw = direction.x.atan2(direction.y);
+                            state.yaw = turn_towards(state.yaw, target_yaw, MOB_TURN_SPEED * dt);
+
+                            // Walk where the mob is facing, so it turns before going anywhere
+                            let speed = mob.speed();
+                            state.velocity.x = state.yaw.sin() * speed;
+                            state.velocity.z = state.yaw.cos() * speed;
+                        }
+                        None => {
+                            state.velocity.x -= ON_GROUND_FRICTION * state.velocity.x * dt;
+                            state.velocity.z -= ON_GROUND_FRICTION * state.velocity.z * dt;
+                        }
+                    }
+
+                    state.knockback *= (1.0 - KNOCKBACK_FRICTION * dt).max(0.0);
+                    let movement = state.velocity + state.knockback;
+
+                    // Same collision resolution as the player, one axis at a time
+                    let previous_position = state.position;
+                    let mut is_on_ground = false;
+                    let mut is_blocked = false;
+
+                    let separated_axis = &[
+                        vec3(movement.x, 0.0, 0.0),
+                        vec3(0.0, 0.0, movement.z),
+                        vec3(0.0, movement.y, 0.0),
+                    ];
+
+                    for v in separated_axis {
+                        state.aabb.translate(&(v * dt));
+
+                        if let Some(block_collided) =
+                            state.aabb.get_colliding_block_coords(&chunk_manager)
+                        {
+                            is_on_ground |= state.aabb.separate_from_block(v, &block_collided);
+
+                            if v.y != 0.0 {
+                                state.velocity.y = 0.0;
+                            } else {
+                                state.knockback = vec3(0.0, 0.0, 0.0);
+                                is_blocked = true;
+                            }
+                        }
+                    }
+
+                    // Jump over the obstacles in the way
+                    if is_blocked && mob.is_on_ground && is_on_ground {
+                        state.velocity.y = jump_impulse;
+                    }
+                    mob.is_on_ground = is_on_ground;
+
+                    state.update_position();
+                    state.walk_distance += vec2(
+                        state.position.x - previous_position.x,
+                        state.position.z - previous_position.z,
+                    )
+                    .norm();
+
+                    state
+                },
+            );
+        }
+    }
+}
+
+pub struct DespawnMobs;
+
+impl<'a> System<'a> for DespawnMobs {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Arc<ChunkManager>>,
+        Write<'a, Metrics>,
+        ReadStorage<'a, Mob>,
+        ReadStorage<'a, Interpolator<MobPhysicsState>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, chunk_manager, mut metrics, mobs, mob_physics_state) = data;
+
+        let mut mob_count = 0;
+
+        for (entity, mob, mob_physics_state) in (&entities, &mobs, &mob_physics_state).join() {
+            let position = mob_physics_state.get_latest_state().position;
+            let column_x = (position.x.floor() as i32).div_euclid(16);
+            let column_z = (position.z.floor() as i32).div_euclid(16);
+
+            if mob.is_dead() {
+                debug!("A {} was killed", mob.kind.name());
+
+                if let Err(e) = entities.delete(entity) {
+                    error!("{e}");
+                }
+                continue;
+            }
+
+            // The column under the mob was unloaded by the chunk loading
+            if chunk_manager.get_column(column_x, column_z).is_none() {
+                debug!("Despawned a {}", mob.kind.name());
+
+                if let Err(e) = entities.delete(entity) {
+                    error!("{e}");
+                }
+                continue;
+            }
+
+            mob_count += 1;
+        }
+
+        metrics.mobs = mob_count;
+    }
+}
@@ -1,29 +1,171 @@
+#[cfg(feature = "client")]
 pub mod chunk_loading;
+#[cfg(feature = "client")]
+pub mod clouds;
+pub mod combat;
+#[cfg(feature = "client")]
+pub mod console;
+#[cfg(feature = "client")]
+pub mod debug_overlay;
+#[cfg(feature = "client")]
+pub mod eating;
 pub mod fps_counter;
+#[cfg(feature = "client")]
 pub mod hand;
+#[cfg(feature = "client")]
+pub mod health;
+pub mod hunger;
+#[cfg(feature = "client")]
 pub mod input;
+#[cfg(feature = "client")]
 pub mod inventory;
+#[cfg(feature = "java_protocol")]
+pub mod java;
+#[cfg(feature = "client")]
+pub mod mob_rendering;
+pub mod mobs;
+#[cfg(feature = "client")]
+pub mod multiplayer;
+#[cfg(feature = "client")]
 pub mod physics;
+#[cfg(feature = "client")]
 pub mod player;
+#[cfg(feature = "client")]
+pub mod player_attack;
+#[cfg(feature = "client")]
+pub mod post_processing;
+#[cfg(feature = "client")]
+pub mod precipitation;
+#[cfg(feature = "client")]
 pub mod rendering;
+#[cfg(feature = "client")]
+pub mod screenshot;
+pub mod server;
+pub mod server_network;
+#[cfg(feature = "client")]
+pub mod settings;
+#[cfg(feature = "client")]
+pub mod shadows;
+#[cfg(feature = "client")]
+pub mod sky;
+pub mod weather;
 
+use crate::chunk_manager::ChunkManager;
+use crate::journal::EditJournal;
 use crate::timer::Timer;
-use specs::{System, Write};
+use crate::world_time::WorldTime;
+use specs::{Read, System, Write};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+// How often the journal is saved when it changed
+const JOURNAL_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+#[cfg(feature = "client")]
+pub use clouds::*;
+pub use combat::*;
+#[cfg(feature = "client")]
+pub use console::*;
+#[cfg(feature = "client")]
+pub use debug_overlay::*;
+#[cfg(feature = "client")]
+pub use eating::*;
 pub use fps_counter::*;
+#[cfg(feature = "client")]
 pub use hand::*;
+#[cfg(feature = "client")]
+pub use health::*;
+pub use hunger::*;
+#[cfg(feature = "client")]
 pub use input::*;
+#[cfg(feature = "client")]
 pub use inventory::*;
+#[cfg(feature = "java_protocol")]
+pub use java::*;
+#[cfg(feature = "client")]
+pub use mob_rendering::*;
+pub use mobs::*;
+#[cfg(feature = "client")]
+pub use multiplayer::*;
+#[cfg(feature = "client")]
 pub use physics::*;
+#[cfg(feature = "client")]
 pub use player::*;
+#[cfg(feature = "client")]
+pub use player_attack::*;
+#[cfg(feature = "client")]
+pub use post_processing::*;
+#[cfg(feature = "client")]
+pub use precipitation::*;
+#[cfg(feature = "client")]
 pub use rendering::*;
+#[cfg(feature = "client")]
+pub use screenshot::*;
+pub use server::*;
+pub use server_network::*;
+#[cfg(feature = "client")]
+pub use settings::*;
+#[cfg(feature = "client")]
+pub use shadows::*;
+#[cfg(feature = "client")]
+pub use sky::*;
+pub use weather::*;
 
 pub struct AdvanceGlobalTime;
 
 impl<'a> System<'a> for AdvanceGlobalTime {
-    type SystemData = Write<'a, Timer>;
+    type SystemData = (Write<'a, Timer>, Write<'a, WorldTime>);
 
-    fn run(&mut self, mut global_timer: Self::SystemData) {
+    fn run(&mut self, (mut global_timer, mut world_time): Self::SystemData) {
+        // The timer does not move while it is paused, so neither does the world time
+        let previous = global_timer.time();
         global_timer.tick();
+        world_time.advance(global_timer.time().saturating_duration_since(previous));
+    }
+}
+
+// Runs at the end of the frame, once every system saw the blocks changed during the frame
+pub struct ClearChangedBlocks;
+
+impl<'a> System<'a> for ClearChangedBlocks {
+    type SystemData = Read<'a, Arc<ChunkManager>>;
+
+    fn run(&mut self, chunk_manager: Self::SystemData) {
+        chunk_manager.clear_changed_blocks();
+    }
+}
+
+// Puts the blocks edited by the players back in the columns generated again, and saves the journal
+// from time to time
+pub struct UpdateEditJournal {
+    last_saved: Instant,
+}
+
+impl Default for UpdateEditJournal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UpdateEditJournal {
+    pub fn new() -> Self {
+        Self {
+            last_saved: Instant::now(),
+        }
+    }
+}
+
+impl<'a> System<'a> for UpdateEditJournal {
+    type SystemData = (Read<'a, Arc<ChunkManager>>, Write<'a, EditJournal>);
+
+    fn run(&mut self, (chunk_manager, mut journal): Self::SystemData) {
+        journal.restore_columns(&chunk_manager);
+
+        if journal.is_modified() && self.last_saved.elapsed() >= JOURNAL_SAVE_INTERVAL {
+            if let Err(err) = journal.save() {
+                warn!("Could not save the journal: {err}");
+            }
+            self.last_saved = Instant::now();
+        }
     }
 }
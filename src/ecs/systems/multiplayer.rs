@@ -0,0 +1,354 @@
+use crate::chunk::ChunkColumn;
+use crate::chunk_manager::{BlockEditBatch, ChunkManager};
+use crate::console::Console;
+use crate::constants::PHYSICS_TICKRATE;
+use crate::game_mode::GameMode;
+use crate::health::Health;
+use crate::mob::{Mob, MobPhysicsState};
+use crate::network::chunk_data::decompress_column;
+use crate::network::client::{ClientNetwork, RemotePlayer};
+use crate::network::interpolation::{InterpolationBuffer, INTERPOLATION_DELAY};
+use crate::network::packet::{ClientboundPacket, EntityKind, ServerboundPacket};
+use crate::physics::Interpolator;
+use crate::player::{PlayerPhysicsState, PlayerState};
+use crate::text::{RED, WHITE};
+use crate::weather::Weather;
+use crate::world_time::WorldTime;
+use nalgebra_glm::vec2;
+use specs::{Entities, Join, Read, ReadStorage, System, Write, WriteStorage};
+use std::mem;
+use std::sync::Arc;
+use std::time::Instant;
+
+// Applies what the server sends: the terrain, the block changes and the other entities
+pub struct HandleServerPackets;
+
+impl<'a> System<'a> for HandleServerPackets {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Arc<ChunkManager>>,
+        Write<'a, ClientNetwork>,
+        Write<'a, Console>,
+        WriteStorage<'a, PlayerState>,
+        WriteStorage<'a, Interpolator<PlayerPhysicsState>>,
+        ReadStorage<'a, GameMode>,
+        WriteStorage<'a, Health>,
+        WriteStorage<'a, Mob>,
+        WriteStorage<'a, Interpolator<MobPhysicsState>>,
+        WriteStorage<'a, RemotePlayer>,
+        WriteStorage<'a, InterpolationBuffer>,
+        Write<'a, WorldTime>,
+        Write<'a, Weather>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            chunk_manager,
+            mut network,
+            mut console,
+            mut player_state,
+            mut player_physics_state,
+            game_mode,
+            mut health,
+            mut mobs,
+            mut mob_physics_state,
+            mut remote_players,
+            mut interpolation_buffers,
+            mut world_time,
+            mut weather,
+        ) = data;
+
+        let packets = match &network.connection {
+            Some(connection) => connection.received_packets().collect::<Vec<_>>(),
+            None => return,
+        };
+
+        for packet in packets {
+            let packet = match packet {
+                Ok(packet) => packet,
+                Err(err) => {
+                    console.push_message(&format!("Disconnected: {err}"), RED);
+                    network.connection = None;
+                    console.outgoing_chat = None;
+                    break;
+                }
+            };
+
+            match packet {
+                ClientboundPacket::LoginSuccess {
+                    entity_id,
+                    position,
+                } => {
+                    for (_, player_physics_state) in
+                        (&player_state, &mut player_physics_state).join()
+                    {
+                        player_physics_state
+                            .reset_to(PlayerPhysicsState::new_at_position(position));
+                    }
+
+                    network.entity_id = Some(entity_id);
+                    network.prediction.reset();
+                    console.outgoing_chat = Some(Vec::new());
+                    info!("Logged in as {}", network.name);
+                }
+                ClientboundPacket::Disconnect { reason } => {
+                    console.push_message(&format!("Disconnected: {reason}"), RED);
+                    network.connection = None;
+                    console.outgoing_chat = None;
+                    break;
+                }
+                ClientboundPacket::KeepAlive { id } => {
+                    network.send(ServerboundPacket::KeepAlive { id });
+                }
+                ClientboundPacket::ChunkColumn { x, z, data } => {
+                    let column = ChunkColumn::new();
+
+                    if let Err(err) = decompress_column(&data, &column) {
+                        warn!("Invalid column ({x}, {z}): {err}");
+                        continue;
+                    }
+                    // The server only sends the columns with their trees
+                    *column.has_foliage.write() = true;
+
+                    // A column sent again replaces the previous one
+                    if let Some(previous) = chunk_manager.remove_chunk_column(&(x, z)) {
+                        for chunk in previous.chunks.iter() {
+                            chunk.unload_from_gpu();
+                        }
+                    }
+                    chunk_manager.add_chunk_column((x, z), Arc::new(column));
+                }
+                ClientboundPacket::UnloadChunkColumn { x, z } => {
+                    if let Some(column) = chunk_manager.remove_chunk_column(&(x, z)) {
+                        for chunk in column.chunks.iter() {
+                            chunk.unload_from_gpu();
+                        }
+                    }
+                }
+                ClientboundPacket::BlockChange { x, y, z, block } => {
+                    // Like `apply_batch` for the heights of the column, but the change is not
+                    // sent back to the server as an edit of the player
+                    let mut batch = BlockEditBatch::new();
+                    batch.put_block(x, y, z, block);
+                    chunk_manager.set_batch(batch);
+                }
+                ClientboundPacket::PlayerMovement {
+                    sequence,
+                    position,
+                    velocity,
+                    knockback,
+                    is_on_ground,
+                    jump_cooldown,
+                } => {
+                    let mut authoritative = PlayerPhysicsState::new_at_position(position);
+                    authoritative.velocity = velocity;
+                    authoritative.knockback = knockback;
+                    authoritative.is_on_ground = is_on_ground;
+                    authoritative.jump_cooldown = jump_cooldown;
+
+                    for (_, player_physics_state, game_mode, health) in (
+                        &player_state,
+                        &mut player_physics_state,
+                        &game_mode,
+                        &health,
+                    )
+                        .join()
+                    {
+                        let corrected = network.prediction.reconcile(
+                            sequence,
+                            authoritative.clone(),
+                            |state, input| {
+                                state
+                                    .simulate(
+                                        input,
+                                        *game_mode,
+                                        !health.is_dead(),
+                                        &chunk_manager,
+                                        1.0 / PHYSICS_TICKRATE,
+                                    )
+                                    .0
+                            },
+                        );
+
+                        // The previous state is kept so that the correction is smoothed
+                        if let Some(state) = corrected {
+                            debug!("Movement mispredicted, replaying from input {sequence}");
+                            *player_physics_state.get_latest_state_mut() = state;
+                        }
+                    }
+                }
+                ClientboundPacket::SpawnEntity {
+                    entity_id,
+                    kind,
+                    position,
+                    yaw,
+                } => {
+                    let entity = entities.create();
+
+                    let inserted = match kind {
+                        EntityKind::Mob(kind) => {
+                            let mut state = MobPhysicsState::new_at_position(kind, position);
+                            state.yaw = yaw;
+
+                            mobs.insert(entity, Mob::new(kind))
+                                .and(
+                                    mob_physics_state.insert(entity, Interpolator::new(0.0, state)),
+                                )
+                                .map(|_| ())
+                        }
+                        EntityKind::Player { name } => remote_players
+                            .insert(
+                                entity,
+                                RemotePlayer {
+                                    name,
+                                    position,
+                                    yaw,
+                                },
+                            )
+                            .map(|_| ()),
+                    };
+                    let inserted = inserted.and(
+                        interpolation_buffers
+                            .insert(entity, InterpolationBuffer::new(position, yaw))
+                            .map(|_| ()),
+                    );
+                    if let Err(err) = inserted {
+                        error!("{err}");
+                    }
+
+                    if let Some(previous) = network.remote_entities.insert(entity_id, entity) {
+                        let _ = entities.delete(previous);
+                    }
+                }
+                ClientboundPacket::MoveEntity {
+                    entity_id,
+                    position,
+                    yaw,
+                } => {
+                    let entity = match network.remote_entities.get(&entity_id) {
+                        Some(&entity) => entity,
+                        None => continue,
+                    };
+
+                    if let Some(interpolation_buffer) = interpolation_buffers.get_mut(entity) {
+                        interpolation_buffer.push(Instant::now(), position, yaw);
+                    }
+                }
+                ClientboundPacket::DespawnEntity { entity_id } => {
+                    if let Some(entity) = network.remote_entities.remove(&entity_id) {
+                        if let Err(err) = entities.delete(entity) {
+                            error!("{err}");
+                        }
+                    }
+                }
+                ClientboundPacket::Chat { message } => {
+                    console.push_message(&message, WHITE);
+                }
+                ClientboundPacket::TimeUpdate { ticks } => {
+                    *world_time = WorldTime::from_ticks(ticks);
+                }
+                ClientboundPacket::Weather { kind } => {
+                    weather.set(kind, None);
+                }
+                ClientboundPacket::PlayerDamage { amount, source } => {
+                    for (_, health) in (&player_state, &mut health).join() {
+                        health.damage(amount, source);
+                    }
+                }
+                ClientboundPacket::Respawn { position } => {
+                    for (player_state, player_physics_state, health) in
+                        (&mut player_state, &mut player_physics_state, &mut health).join()
+                    {
+                        health.revive();
+                        player_physics_state
+                            .reset_to(PlayerPhysicsState::new_at_position(position));
+                        player_state.is_flying = false;
+                        player_state.block_breaking = None;
+                    }
+                    // The inputs sent before the respawn were simulated from elsewhere
+                    network.prediction.reset();
+                }
+            }
+        }
+    }
+}
+
+// Moves the remote entities between the positions received from the server
+pub struct InterpolateRemoteEntities;
+
+impl<'a> System<'a> for InterpolateRemoteEntities {
+    type SystemData = (
+        WriteStorage<'a, InterpolationBuffer>,
+        WriteStorage<'a, Interpolator<MobPhysicsState>>,
+        WriteStorage<'a, RemotePlayer>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut interpolation_buffers, mut mob_physics_state, mut remote_players) = data;
+
+        let time = Instant::now() - INTERPOLATION_DELAY;
+
+        for (interpolation_buffer, mob_physics_state) in
+            (&mut interpolation_buffers, &mut mob_physics_state).join()
+        {
+            let (position, yaw) = interpolation_buffer.sample(time);
+
+            let mut state = mob_physics_state.get_latest_state().clone();
+            // Drives the walking animation
+            state.walk_distance +=
+                vec2(position.x - state.position.x, position.z - state.position.z).norm();
+            state.position = position;
+            state.yaw = yaw;
+
+            mob_physics_state.reset_to(state);
+        }
+
+        for (interpolation_buffer, remote_player) in
+            (&mut interpolation_buffers, &mut remote_players).join()
+        {
+            let (position, yaw) = interpolation_buffer.sample(time);
+
+            remote_player.position = position;
+            remote_player.yaw = yaw;
+        }
+    }
+}
+
+// Sends the inputs of the player, the blocks it edited and its chat messages to the server
+// Runs before `ClearChangedBlocks`
+pub struct SendPlayerUpdates;
+
+impl<'a> System<'a> for SendPlayerUpdates {
+    type SystemData = (
+        Read<'a, Arc<ChunkManager>>,
+        Write<'a, ClientNetwork>,
+        Write<'a, Console>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (chunk_manager, mut network, mut console) = data;
+
+        // The server ignores everything until the login succeeded
+        if !network.is_connected() || network.entity_id.is_none() {
+            return;
+        }
+
+        // One packet per physics tick, the server simulates them in order
+        for (sequence, input) in network.prediction.take_unsent() {
+            network.send(ServerboundPacket::PlayerInput { sequence, input });
+        }
+
+        for (x, y, z) in chunk_manager.changed_blocks() {
+            if let Some(block) = chunk_manager.get_block(x, y, z) {
+                network.send(ServerboundPacket::BlockEdit { x, y, z, block });
+            }
+        }
+
+        let outgoing_chat = console.outgoing_chat.as_mut().map(mem::take);
+        for message in outgoing_chat.unwrap_or_default() {
+            console.push_message(&format!("<{}> {message}", network.name), WHITE);
+            network.send(ServerboundPacket::Chat { message });
+        }
+    }
+}
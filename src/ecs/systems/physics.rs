@@ -1,12 +1,13 @@
 use crate::chunk_manager::ChunkManager;
-use crate::constants::{GRAVITY, PLAYER_HALF_WIDTH};
+use crate::game_mode::GameMode;
+use crate::health::{fall_damage, DamageSource, Health};
+use crate::hunger::{Hunger, JUMPING_EXHAUSTION, SPRINT_JUMPING_EXHAUSTION};
 use crate::input::InputCache;
+use crate::network::client::ClientNetwork;
 use crate::physics::Interpolator;
-use crate::player::{PlayerPhysicsState, PlayerState};
+use crate::player::{PlayerInput, PlayerPhysicsState, PlayerState};
 use crate::timer::Timer;
-use nalgebra_glm::vec3;
-use num_traits::Zero;
-use specs::{Join, Read, System, WriteStorage};
+use specs::{Join, Read, ReadStorage, System, Write, WriteStorage};
 use std::sync::Arc;
 
 pub struct UpdatePlayerPhysics;
@@ -16,104 +17,79 @@ impl<'a> System<'a> for UpdatePlayerPhysics {
         Read<'a, Timer>,
         Read<'a, InputCache>,
         Read<'a, Arc<ChunkManager>>,
+        Write<'a, ClientNetwork>,
         WriteStorage<'a, Interpolator<PlayerPhysicsState>>,
         WriteStorage<'a, PlayerState>,
+        ReadStorage<'a, GameMode>,
+        WriteStorage<'a, Health>,
+        WriteStorage<'a, Hunger>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (global_timer, input_cache, chunk_manager, mut player_physics_state, mut player_state) =
-            data;
-
-        for (player_physics_state, player_state) in
-            (&mut player_physics_state, &mut player_state).join()
+        let (
+            global_timer,
+            input_cache,
+            chunk_manager,
+            mut network,
+            mut player_physics_state,
+            mut player_state,
+            game_mode,
+            mut health,
+            mut hunger,
+        ) = data;
+
+        // Once logged in, the movement is predicted until the server acknowledges the inputs
+        let is_predicting = network.entity_id.is_some();
+
+        for (player_physics_state, player_state, game_mode, health, hunger) in (
+            &mut player_physics_state,
+            &mut player_state,
+            &game_mode,
+            &mut health,
+            &mut hunger,
+        )
+            .join()
         {
+            let mut input = PlayerInput::sample(&input_cache, player_state);
+
             player_physics_state.step(
                 global_timer.time(),
                 &mut |player: &PlayerPhysicsState, _t: f32, dt: f32| {
-                    let mut player = player.clone();
-
-                    if !player_state.is_flying {
-                        player.acceleration.y += GRAVITY;
-                    }
-
-                    player.apply_keyboard_movement(player_state, &input_cache);
-                    player.velocity += player.acceleration * dt;
-                    player.apply_friction(dt, &player_state);
-                    player.limit_velocity(&player_state);
-
-                    let will_hit_ground = |player: &PlayerPhysicsState| {
-                        let mut player = player.clone();
-                        let v_y = vec3(0.0, player.velocity.y, 0.0);
-
-                        player.aabb.translate(&(v_y * dt));
-
-                        let colliding_block = player.get_colliding_block_coords(&chunk_manager);
-
-                        if let Some(colliding_block) = colliding_block {
-                            player.separate_from_block(&v_y, &colliding_block)
+                    // The server replays the recorded input, so it must be the one simulated here
+                    // rather than the one changed below when landing
+                    let simulated_input = input;
+                    let (player, events) =
+                        player.simulate(&input, *game_mode, !health.is_dead(), &chunk_manager, dt);
+
+                    if events.jumped && game_mode.has_hunger() {
+                        hunger.add_exhaustion(if input.is_sprinting {
+                            SPRINT_JUMPING_EXHAUSTION
                         } else {
-                            false
-                        }
-                    };
-
-                    // We are using the Separated Axis Theorem
-                    // We decompose the velocity vector into 3 vectors for each dimension
-                    // For each one, we move the entity and do the collision detection/resolution
-                    let mut is_player_on_ground = false;
-
-                    let separated_axis = &[
-                        vec3(player.velocity.x, 0.0, 0.0),
-                        vec3(0.0, 0.0, player.velocity.z),
-                        vec3(0.0, player.velocity.y, 0.0),
-                    ];
-
-                    for v in separated_axis {
-                        let backup = player.clone();
-
-                        player.aabb.translate(&(v * dt));
-                        let block_collided = player.get_colliding_block_coords(&chunk_manager);
-
-                        // Collision resolution
-                        if let Some(block_collided) = block_collided {
-                            is_player_on_ground |= player.separate_from_block(&v, &block_collided);
-                        }
-
-                        // If the player is sneaking and is not on the ground, the player should not be able to move
-                        if input_cache.is_key_pressed(glfw::Key::LeftShift)
-                            && player_state.is_on_ground
-                            && !will_hit_ground(&player)
-                            && player.velocity.y < 0.0
-                        {
-                            player = backup;
-
-                            if !v.x.is_zero() {
-                                player.velocity.x = 0.0;
-                            }
+                            JUMPING_EXHAUSTION
+                        });
+                    }
 
-                            if !v.z.is_zero() {
-                                player.velocity.z = 0.0;
-                            }
+                    // Fall damage when landing, the server applies it once logged in
+                    if let Some(vertical_velocity) = events.landing_velocity {
+                        if game_mode.takes_damage() && !is_predicting {
+                            health.damage(fall_damage(vertical_velocity), DamageSource::Fall);
                         }
                     }
 
-                    player_state.is_on_ground = is_player_on_ground;
-
-                    if player_state.is_on_ground {
+                    if player.is_on_ground {
                         player_state.is_flying = false;
+                        input.is_flying = false;
                     }
 
-                    // Update the position of the player and reset the acceleration
-                    player.position.x = player.aabb.mins.x + PLAYER_HALF_WIDTH;
-                    player.position.y = player.aabb.mins.y;
-                    player.position.z = player.aabb.mins.z + PLAYER_HALF_WIDTH;
-
-                    player.acceleration.x = 0.0;
-                    player.acceleration.y = 0.0;
-                    player.acceleration.z = 0.0;
+                    if is_predicting {
+                        network.prediction.record(simulated_input, &player);
+                    }
 
                     player
                 },
-            )
+            );
+
+            player_state.is_on_ground = player_physics_state.get_latest_state().is_on_ground;
         }
     }
 }
@@ -1,22 +1,35 @@
 use crate::aabb::{get_block_aabb, AABB};
 use crate::chunk::BlockID;
-use crate::chunk_manager::ChunkManager;
+use crate::chunk_manager::{BlockChange, ChunkManager};
+use crate::console::Console;
 use crate::constants::{
-    FAR_PLANE, FLYING_TRIGGER_INTERVAL, FOV, JUMP_IMPULSE, NEAR_PLANE, PLAYER_EYES_HEIGHT,
-    REACH_DISTANCE, SPRINTING_TRIGGER_INTERVAL, WINDOW_HEIGHT, WINDOW_WIDTH,
+    FAR_PLANE, FLYING_TRIGGER_INTERVAL, NEAR_PLANE, PLAYER_EYES_HEIGHT, REACH_DISTANCE,
+    SPRINTING_TRIGGER_INTERVAL,
 };
+use crate::ecs::components::MainHandItemChanged;
+use crate::game_mode::GameMode;
+use crate::health::Health;
+use crate::hunger::{Hunger, BLOCK_BREAKING_EXHAUSTION};
 use crate::input::InputCache;
+use crate::inventory::item::Item;
+use crate::inventory::tool;
 use crate::inventory::Inventory;
+use crate::journal::EditJournal;
+use crate::mob::MobPhysicsState;
+use crate::network::client::ClientNetwork;
 use crate::particle_system::ParticleSystem;
 use crate::physics::Interpolator;
-use crate::player::{PlayerPhysicsState, PlayerState};
+use crate::player::{BlockBreakingProgress, PlayerPhysicsState, PlayerState};
 use crate::raycast;
+use crate::settings::Settings;
+use crate::text::WHITE;
 use crate::timer::Timer;
 use crate::types::{ParticleSystems, TexturePack};
 use crate::util::Forward;
+use crate::world_edit::{WorldEditSession, WAND};
 use nalgebra::Vector3;
 use nalgebra_glm::{vec3, IVec3};
-use specs::{Join, Read, ReadStorage, System, Write, WriteStorage};
+use specs::{Entities, Join, Read, ReadStorage, System, Write, WriteStorage};
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -25,17 +38,26 @@ pub struct HandlePlayerInput;
 impl<'a> System<'a> for HandlePlayerInput {
     type SystemData = (
         Read<'a, InputCache>,
+        Read<'a, Settings>,
         WriteStorage<'a, PlayerState>,
-        WriteStorage<'a, Interpolator<PlayerPhysicsState>>,
+        WriteStorage<'a, GameMode>,
+        ReadStorage<'a, Health>,
+        ReadStorage<'a, Hunger>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (input_cache, mut player_state, mut player_physics_state) = data;
+        let (input_cache, settings, mut player_state, mut game_mode, health, hunger) = data;
 
-        for (player_state, player_physics_state) in
-            (&mut player_state, &mut player_physics_state).join()
+        for (player_state, game_mode, health, hunger) in
+            (&mut player_state, &mut game_mode, &health, &hunger).join()
         {
-            let player_physics_state = player_physics_state.get_latest_state_mut();
+            // Dead players wait for the respawn
+            if health.is_dead() {
+                continue;
+            }
+
+            // Hungry players are too weak to sprint
+            let can_sprint = !game_mode.has_hunger() || hunger.can_sprint();
 
             for event in &input_cache.events {
                 match event {
@@ -43,6 +65,7 @@ impl<'a> System<'a> for HandlePlayerInput {
                         player_state.rotate_camera(
                             input_cache.cursor_rel_pos.x as f32,
                             input_cache.cursor_rel_pos.y as f32,
+                            &settings,
                         );
                     }
 
@@ -50,21 +73,24 @@ impl<'a> System<'a> for HandlePlayerInput {
                         // Player state
                         if player_state.fly_throttle {
                             player_state.fly_throttle = false;
-                        } else if Instant::now().duration_since(player_state.fly_last_toggled)
-                            < *FLYING_TRIGGER_INTERVAL
+                        } else if game_mode.can_fly()
+                            && *game_mode != GameMode::Spectator
+                            && Instant::now().duration_since(player_state.fly_last_toggled)
+                                < *FLYING_TRIGGER_INTERVAL
                         {
                             player_state.is_flying = !player_state.is_flying;
-                            println!("Flying: {}", player_state.is_flying);
+                            debug!("Flying: {}", player_state.is_flying);
                             player_state.fly_throttle = true;
                         }
 
+                        // The jump itself is part of the physics, see `UpdatePlayerPhysics`
                         player_state.fly_last_toggled = Instant::now();
+                    }
 
-                        // Player physics state
-                        if player_state.is_on_ground {
-                            player_physics_state.velocity.y = *JUMP_IMPULSE;
-                            player_state.jump_last_executed = Instant::now();
-                        }
+                    // Cycle through the game modes
+                    glfw::WindowEvent::Key(glfw::Key::F4, _, glfw::Action::Press, _) => {
+                        game_mode.switch_to(game_mode.next(), player_state);
+                        info!("Game mode: {}", game_mode.name());
                     }
 
                     // Cancel sneaking
@@ -81,8 +107,9 @@ impl<'a> System<'a> for HandlePlayerInput {
                     glfw::WindowEvent::Key(glfw::Key::W, _, glfw::Action::Press, _) => {
                         if player_state.sprint_throttle {
                             player_state.sprint_throttle = false;
-                        } else if Instant::now().duration_since(player_state.sprint_last_toggled)
-                            < *SPRINTING_TRIGGER_INTERVAL
+                        } else if can_sprint
+                            && Instant::now().duration_since(player_state.sprint_last_toggled)
+                                < *SPRINTING_TRIGGER_INTERVAL
                         {
                             player_state.is_sprinting = true;
                             player_state.sprint_throttle = true;
@@ -96,7 +123,10 @@ impl<'a> System<'a> for HandlePlayerInput {
             }
 
             // Sneaking
-            if input_cache.is_key_pressed(glfw::Key::LeftShift) && player_state.is_on_ground {
+            if input_cache.is_key_pressed(glfw::Key::LeftShift)
+                && player_state.is_on_ground
+                && game_mode.has_collisions()
+            {
                 player_state.is_sneaking = true;
                 player_state.is_sprinting = false;
             }
@@ -105,6 +135,7 @@ impl<'a> System<'a> for HandlePlayerInput {
             if input_cache.is_key_pressed(glfw::Key::LeftControl)
                 && input_cache.is_key_pressed(glfw::Key::W)
                 && !player_state.is_sneaking
+                && can_sprint
             {
                 player_state.is_sprinting = true;
             }
@@ -116,17 +147,37 @@ pub struct UpdatePlayerState;
 
 impl<'a> System<'a> for UpdatePlayerState {
     type SystemData = (
+        Entities<'a>,
         Read<'a, Timer>,
+        Read<'a, Settings>,
         Write<'a, Arc<ChunkManager>>,
         WriteStorage<'a, PlayerState>,
         ReadStorage<'a, Interpolator<PlayerPhysicsState>>,
+        ReadStorage<'a, GameMode>,
+        ReadStorage<'a, Health>,
+        ReadStorage<'a, Interpolator<MobPhysicsState>>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (global_timer, chunk_manager, mut player_state, player_physics_state) = data;
+        let (
+            entities,
+            global_timer,
+            settings,
+            chunk_manager,
+            mut player_state,
+            player_physics_state,
+            game_mode,
+            health,
+            mob_physics_state,
+        ) = data;
 
-        for (player_state, player_physics_state) in
-            (&mut player_state, &player_physics_state).join()
+        for (player_state, player_physics_state, game_mode, health) in (
+            &mut player_state,
+            &player_physics_state,
+            &game_mode,
+            &health,
+        )
+            .join()
         {
             let t = global_timer.time();
 
@@ -142,42 +193,67 @@ impl<'a> System<'a> for UpdatePlayerState {
                 .interpolate_camera_height(t, target_camera_height);
 
             // Field of view
+            let fov = settings.fov_radians();
             let target_fov = if player_state.is_flying {
                 if player_state.is_sprinting {
-                    FOV + FOV * 0.30
+                    fov + fov * 0.30
                 } else {
-                    FOV + FOV * 0.15
+                    fov + fov * 0.15
                 }
             } else {
                 if player_state.is_sprinting {
-                    FOV + FOV * 0.15
+                    fov + fov * 0.15
                 } else {
-                    FOV
+                    fov
                 }
             };
 
             player_state.fov.interpolate_fov(t, target_fov);
 
-            // Targeted block
-            player_state.targeted_block = {
-                let is_solid_block_at =
-                    |x: i32, y: i32, z: i32| chunk_manager.is_solid_block_at(x, y, z);
-
-                let forward = player_state.rotation.forward();
-                let player = player_physics_state.get_interpolated_state();
+            // Targeted entity and block, spectators and dead players cannot target anything
+            player_state.targeted_entity = None;
+            player_state.targeted_block =
+                if !game_mode.can_interact_with_blocks() || health.is_dead() {
+                    None
+                } else {
+                    let is_solid_block_at =
+                        |x: i32, y: i32, z: i32| chunk_manager.is_solid_block_at(x, y, z);
 
-                raycast::raycast(
-                    &is_solid_block_at,
-                    &(player.position
+                    let forward = player_state.rotation.forward().normalize();
+                    let player = player_physics_state.get_interpolated_state();
+                    let eyes_position = player.position
                         + vec3(
                             0.0,
                             *player_state.camera_height.get_interpolated_state(),
                             0.0,
-                        )),
-                    &forward.normalize(),
-                    REACH_DISTANCE,
-                )
-            };
+                        );
+
+                    // The entities are tested first, the blocks behind the closest one are ignored
+                    let targeted_entity = (&entities, &mob_physics_state)
+                        .join()
+                        .filter_map(|(entity, mob_physics_state)| {
+                            mob_physics_state
+                                .get_interpolated_state()
+                                .aabb
+                                .ray_intersection(&eyes_position, &forward)
+                                .filter(|&distance| distance <= REACH_DISTANCE)
+                                .map(|distance| (entity, distance))
+                        })
+                        .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+                    let targeted_block = raycast::raycast(
+                        &is_solid_block_at,
+                        &eyes_position,
+                        &forward,
+                        targeted_entity.map_or(REACH_DISTANCE, |(_, distance)| distance),
+                    );
+
+                    if targeted_block.is_none() {
+                        player_state.targeted_entity = targeted_entity.map(|(entity, _)| entity);
+                    }
+
+                    targeted_block
+                };
 
             // View and projection matrix
             player_state.view_matrix = {
@@ -199,12 +275,7 @@ impl<'a> System<'a> for UpdatePlayerState {
 
             player_state.projection_matrix = {
                 let fov = *player_state.fov.get_interpolated_state();
-                nalgebra_glm::perspective(
-                    WINDOW_WIDTH as f32 / WINDOW_HEIGHT as f32,
-                    fov,
-                    NEAR_PLANE,
-                    FAR_PLANE,
-                )
+                nalgebra_glm::perspective(settings.aspect_ratio(), fov, NEAR_PLANE, FAR_PLANE)
             };
         }
     }
@@ -214,30 +285,71 @@ pub struct PlaceAndBreakBlocks;
 
 impl<'a> System<'a> for PlaceAndBreakBlocks {
     type SystemData = (
+        Entities<'a>,
         Write<'a, Arc<ChunkManager>>,
         Write<'a, ParticleSystems>,
+        Write<'a, Console>,
+        Write<'a, EditJournal>,
+        Read<'a, ClientNetwork>,
         Read<'a, InputCache>,
         Read<'a, TexturePack>,
         WriteStorage<'a, PlayerState>,
         ReadStorage<'a, Interpolator<PlayerPhysicsState>>,
-        ReadStorage<'a, Inventory>,
+        WriteStorage<'a, Inventory>,
+        ReadStorage<'a, GameMode>,
+        WriteStorage<'a, Hunger>,
+        WriteStorage<'a, MainHandItemChanged>,
+        WriteStorage<'a, WorldEditSession>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         let (
+            entities,
             chunk_manager,
             mut particle_systems,
+            mut console,
+            mut journal,
+            network,
             input_cache,
             texture_pack,
             mut player_state,
             player_physics_state,
-            inventory,
+            mut inventory,
+            game_mode,
+            mut hunger,
+            mut main_hand_item_changed,
+            mut world_edit,
         ) = data;
 
-        for (player_state, player_physics_state, inventory) in
-            (&mut player_state, &player_physics_state, &inventory).join()
+        for (entity, player_state, player_physics_state, inventory, game_mode, hunger) in (
+            &entities,
+            &mut player_state,
+            &player_physics_state,
+            &mut inventory,
+            &game_mode,
+            &mut hunger,
+        )
+            .join()
         {
+            if !game_mode.can_interact_with_blocks() {
+                player_state.block_breaking = None;
+                continue;
+            }
+
             let player_physics_state = player_physics_state.get_latest_state();
+            let breaks_instantly = game_mode.breaks_blocks_instantly();
+
+            // In creative, the wand selects the corners of the WorldEdit selection instead
+            if breaks_instantly && inventory.get_selected_tool() == Some(WAND) {
+                if let (Some(session), Some((position, _))) =
+                    (world_edit.get_mut(entity), player_state.targeted_block)
+                {
+                    select_corners(session, position, &input_cache, &mut console);
+                }
+                continue;
+            }
+            let mut should_break = false;
+            let mut should_place = false;
 
             for event in &input_cache.events {
                 match event {
@@ -245,29 +357,8 @@ impl<'a> System<'a> for PlaceAndBreakBlocks {
                         player_state.block_placing_last_executed = Instant::now();
 
                         match button {
-                            glfw::MouseButton::Button1 => {
-                                if let Some(((x, y, z), _)) = &player_state.targeted_block {
-                                    let mut particle_system =
-                                        particle_systems.get_mut("block_particles").unwrap();
-                                    break_block(
-                                        (*x, *y, *z),
-                                        &chunk_manager,
-                                        &mut particle_system,
-                                        &texture_pack,
-                                    );
-                                }
-                            }
-                            glfw::MouseButton::Button2 => {
-                                if let Some(((x, y, z), normal)) = &player_state.targeted_block {
-                                    place_block(
-                                        (*x, *y, *z),
-                                        &normal,
-                                        &player_physics_state.aabb,
-                                        &inventory,
-                                        &chunk_manager,
-                                    );
-                                }
-                            }
+                            glfw::MouseButton::Button1 => should_break = breaks_instantly,
+                            glfw::MouseButton::Button2 => should_place = true,
                             _ => {}
                         }
                     }
@@ -284,44 +375,166 @@ impl<'a> System<'a> for PlaceAndBreakBlocks {
                     .as_secs_f32()
                     >= 0.25
                 {
-                    if input_cache.is_mouse_button_pressed(glfw::MouseButtonLeft) {
-                        if let Some(((x, y, z), _)) = &player_state.targeted_block {
-                            let mut particle_system =
-                                particle_systems.get_mut("block_particles").unwrap();
-                            break_block(
-                                (*x, *y, *z),
-                                &chunk_manager,
-                                &mut particle_system,
-                                &texture_pack,
-                            );
-                        }
-
+                    if breaks_instantly
+                        && input_cache.is_mouse_button_pressed(glfw::MouseButtonLeft)
+                    {
+                        should_break = true;
                         player_state.block_placing_last_executed = now;
                     } else if input_cache.is_mouse_button_pressed(glfw::MouseButtonRight) {
-                        if let Some(((x, y, z), normal)) = &player_state.targeted_block {
-                            place_block(
-                                (*x, *y, *z),
-                                &normal,
-                                &player_physics_state.aabb,
-                                &inventory,
-                                &chunk_manager,
-                            );
+                        should_place = true;
+                        player_state.block_placing_last_executed = now;
+                    }
+                }
+            }
+
+            // Outside of creative, blocks take time to break depending on their hardness
+            let breaking_block = player_state.targeted_block.filter(|_| {
+                !breaks_instantly && input_cache.is_mouse_button_pressed(glfw::MouseButtonLeft)
+            });
+
+            if let Some(((x, y, z), normal)) = breaking_block {
+                let block = chunk_manager.get_block(x, y, z).unwrap_or(BlockID::Air);
+                let now = Instant::now();
+
+                // The progress is lost when the player looks at another block
+                let progress = match &mut player_state.block_breaking {
+                    Some(progress) if progress.block == (x, y, z) => progress,
+                    block_breaking => block_breaking.insert(BlockBreakingProgress::new((x, y, z))),
+                };
+
+                // Long frames must not skip the whole animation
+                let dt = now
+                    .duration_since(progress.last_updated)
+                    .as_secs_f32()
+                    .min(0.1);
+                progress.last_updated = now;
+
+                if let Some(break_time) = tool::break_time(block, inventory.get_selected_tool()) {
+                    progress.progress += if break_time > 0.0 {
+                        dt / break_time
+                    } else {
+                        1.0
+                    };
+                }
+
+                if now
+                    .duration_since(progress.particles_last_emitted)
+                    .as_secs_f32()
+                    >= 0.2
+                {
+                    progress.particles_last_emitted = now;
+                    particle_systems
+                        .get_mut("block_particles")
+                        .unwrap()
+                        .spawn_block_hitting_particles(
+                            vec3(x as f32, y as f32, z as f32),
+                            &normal,
+                            &texture_pack,
+                            block,
+                        );
+                }
+
+                if progress.progress >= 1.0 {
+                    should_break = true;
+                }
+            } else {
+                player_state.block_breaking = None;
+            }
+
+            let mut has_main_hand_changed = false;
+
+            if should_break {
+                if let Some(((x, y, z), _)) = &player_state.targeted_block {
+                    let particle_system = particle_systems.get_mut("block_particles").unwrap();
+                    let broken_block =
+                        break_block((*x, *y, *z), &chunk_manager, particle_system, &texture_pack);
+
+                    player_state.block_breaking = None;
+
+                    if let Some(block) = broken_block {
+                        journal.record(&network.name, vec![((*x, *y, *z), block, BlockID::Air)]);
+                    }
+
+                    if broken_block.is_some() && game_mode.has_hunger() {
+                        hunger.add_exhaustion(BLOCK_BREAKING_EXHAUSTION);
+                    }
+
+                    // In survival, the broken block goes to the inventory if the tool can harvest it
+                    if let Some(block) = broken_block {
+                        if game_mode.consumes_items()
+                            && tool::can_harvest(block, inventory.get_selected_tool())
+                        {
+                            let was_empty_handed = inventory.get_selected_item().is_none();
+                            inventory.add_item(Item::Block(block), 1);
+                            has_main_hand_changed |=
+                                was_empty_handed && inventory.get_selected_item().is_some();
                         }
+                    }
+                }
+            }
 
-                        player_state.block_placing_last_executed = now;
+            if should_place {
+                if let Some(((x, y, z), normal)) = &player_state.targeted_block {
+                    let placed_block = place_block(
+                        (*x, *y, *z),
+                        normal,
+                        &player_physics_state.aabb,
+                        inventory,
+                        &chunk_manager,
+                    );
+
+                    if let Some(change) = placed_block {
+                        journal.record(&network.name, vec![change]);
+
+                        if game_mode.consumes_items() {
+                            has_main_hand_changed |= inventory.consume_selected_item();
+                        }
                     }
                 }
             }
+
+            if has_main_hand_changed {
+                if let Err(e) = main_hand_item_changed.insert(entity, MainHandItemChanged) {
+                    error!("{e}");
+                }
+            }
         }
     }
 }
 
+fn select_corners(
+    session: &mut WorldEditSession,
+    (x, y, z): (i32, i32, i32),
+    input_cache: &InputCache,
+    console: &mut Console,
+) {
+    for event in &input_cache.events {
+        let corner = match event {
+            glfw::WindowEvent::MouseButton(glfw::MouseButton::Button1, glfw::Action::Press, _) => {
+                session.first_corner = Some((x, y, z));
+                "First"
+            }
+            glfw::WindowEvent::MouseButton(glfw::MouseButton::Button2, glfw::Action::Press, _) => {
+                session.second_corner = Some((x, y, z));
+                "Second"
+            }
+            _ => continue,
+        };
+
+        let message = match session.selection_volume() {
+            Some(volume) => format!("{corner} corner set to {x} {y} {z} ({volume} blocks)"),
+            None => format!("{corner} corner set to {x} {y} {z}"),
+        };
+        console.push_message(&message, WHITE);
+    }
+}
+
 fn break_block(
     (x, y, z): (i32, i32, i32),
     chunk_manager: &ChunkManager,
     particle_system: &mut ParticleSystem,
     uv_map: &TexturePack,
-) {
+) -> Option<BlockID> {
     let block = chunk_manager.get_block(x, y, z).unwrap();
 
     if block != BlockID::Air {
@@ -332,8 +545,12 @@ fn break_block(
             block,
         );
 
-        info!("Destroyed block at ({x} {y} {z})");
+        debug!("Destroyed block at ({x} {y} {z})");
+
+        return Some(block);
     }
+
+    None
 }
 
 fn place_block(
@@ -342,7 +559,7 @@ fn place_block(
     player_aabb: &AABB,
     inventory: &Inventory,
     chunk_manager: &ChunkManager,
-) {
+) -> Option<BlockChange> {
     let adjacent_block = IVec3::new(x, y, z) + normal;
     let adjacent_block_aabb = get_block_aabb(&vec3(
         adjacent_block.x as f32,
@@ -351,13 +568,16 @@ fn place_block(
     ));
 
     if !player_aabb.intersects(&adjacent_block_aabb) {
-        if let Some(block) = inventory.get_selected_item() {
-            chunk_manager.put_block(adjacent_block.x, adjacent_block.y, adjacent_block.z, block);
-        }
+        if let Some(block) = inventory.get_selected_block() {
+            let (x, y, z) = (adjacent_block.x, adjacent_block.y, adjacent_block.z);
+            let previous = chunk_manager.get_block(x, y, z)?;
+            let has_placed_block = chunk_manager.put_block(x, y, z, block);
 
-        info!(
-            "Put block at {} {} {}",
-            adjacent_block.x, adjacent_block.y, adjacent_block.z
-        );
+            debug!("Put block at {x} {y} {z}");
+
+            return has_placed_block.then_some(((x, y, z), previous, block));
+        }
     }
+
+    None
 }
@@ -0,0 +1,90 @@
+use crate::combat::{
+    attack_damage, knockback, ATTACK_EXHAUSTION, KNOCKBACK_VERTICAL_SPEED, PLAYER_ATTACK_COOLDOWN,
+};
+use crate::game_mode::GameMode;
+use crate::hunger::Hunger;
+use crate::input::InputCache;
+use crate::inventory::Inventory;
+use crate::mob::{Mob, MobPhysicsState};
+use crate::physics::Interpolator;
+use crate::player::{PlayerPhysicsState, PlayerState};
+use specs::{Join, Read, ReadStorage, System, WriteStorage};
+use std::time::Instant;
+
+pub struct PlayerAttack;
+
+impl<'a> System<'a> for PlayerAttack {
+    type SystemData = (
+        Read<'a, InputCache>,
+        WriteStorage<'a, PlayerState>,
+        ReadStorage<'a, Interpolator<PlayerPhysicsState>>,
+        ReadStorage<'a, Inventory>,
+        ReadStorage<'a, GameMode>,
+        WriteStorage<'a, Hunger>,
+        WriteStorage<'a, Mob>,
+        WriteStorage<'a, Interpolator<MobPhysicsState>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            input_cache,
+            mut player_state,
+            player_physics_state,
+            inventory,
+            game_mode,
+            mut hunger,
+            mut mobs,
+            mut mob_physics_state,
+        ) = data;
+
+        let now = Instant::now();
+
+        for (player_state, player_physics_state, inventory, game_mode, hunger) in (
+            &mut player_state,
+            &player_physics_state,
+            &inventory,
+            &game_mode,
+            &mut hunger,
+        )
+            .join()
+        {
+            // The targeted entity is only set when the player can attack
+            let target = match player_state.targeted_entity {
+                Some(target) if input_cache.is_mouse_button_pressed(glfw::MouseButtonLeft) => {
+                    target
+                }
+                _ => continue,
+            };
+
+            let is_cooling_down = player_state
+                .attack_last_executed
+                .is_some_and(|time| now.duration_since(time) < PLAYER_ATTACK_COOLDOWN);
+            if is_cooling_down {
+                continue;
+            }
+            player_state.attack_last_executed = Some(now);
+
+            let (mob, mob_physics_state) =
+                match (mobs.get_mut(target), mob_physics_state.get_mut(target)) {
+                    (Some(mob), Some(mob_physics_state)) => (mob, mob_physics_state),
+                    _ => continue,
+                };
+
+            if !mob.damage(attack_damage(inventory.get_selected_tool())) {
+                continue;
+            }
+
+            let player_position = player_physics_state.get_latest_state().position;
+            let mob_state = mob_physics_state.get_latest_state_mut();
+            mob_state.knockback = knockback(&player_position, &mob_state.position);
+            mob_state.velocity.y = KNOCKBACK_VERTICAL_SPEED;
+
+            // Passive mobs run away from the player who hit them
+            mob.startle(player_position);
+
+            if game_mode.has_hunger() {
+                hunger.add_exhaustion(ATTACK_EXHAUSTION);
+            }
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use crate::chunk::BlockID;
+use crate::chunk_manager::ChunkManager;
+use crate::constants::{EXPOSURE, GAMMA};
+use crate::ecs::systems::sky::create_screen_quad_vao;
+use crate::physics::Interpolator;
+use crate::player::{PlayerPhysicsState, PlayerState};
+use crate::post_processing::{PostProcessingPass, DEPTH_TEXTURE_UNIT, SCENE_TEXTURE_UNIT};
+use crate::settings::Settings;
+use crate::types::Shaders;
+use nalgebra_glm::{vec3, Mat4};
+use specs::{Join, Read, ReadStorage, System, Write};
+use std::sync::Arc;
+
+// Colour and opacity of the screen when the camera is inside of a fluid
+fn fluid_tint(block: BlockID) -> Option<[f32; 4]> {
+    match block {
+        BlockID::Lava => Some([1.0, 0.35, 0.05, 0.7]),
+        _ => None,
+    }
+}
+
+fn create_render_texture(format: u32, width: i32, height: i32) -> u32 {
+    let mut texture = 0;
+    gl_call!(gl::CreateTextures(gl::TEXTURE_2D, 1, &mut texture));
+    gl_call!(gl::TextureStorage2D(texture, 1, format, width, height));
+
+    gl_call!(gl::TextureParameteri(
+        texture,
+        gl::TEXTURE_MIN_FILTER,
+        gl::LINEAR as i32
+    ));
+    gl_call!(gl::TextureParameteri(
+        texture,
+        gl::TEXTURE_MAG_FILTER,
+        gl::LINEAR as i32
+    ));
+    gl_call!(gl::TextureParameteri(
+        texture,
+        gl::TEXTURE_WRAP_S,
+        gl::CLAMP_TO_EDGE as i32
+    ));
+    gl_call!(gl::TextureParameteri(
+        texture,
+        gl::TEXTURE_WRAP_T,
+        gl::CLAMP_TO_EDGE as i32
+    ));
+
+    texture
+}
+
+// A framebuffer of the size of the screen, the colours are in floating point so that the
+// tonemapping has more than 8 bits to work with
+struct RenderTarget {
+    framebuffer: u32,
+    color: u32,
+    depth: Option<u32>,
+}
+
+impl RenderTarget {
+    fn new(width: i32, height: i32, with_depth: bool) -> Self {
+        let mut framebuffer = 0;
+        gl_call!(gl::CreateFramebuffers(1, &mut framebuffer));
+
+        let color = create_render_texture(gl::RGBA16F, width, height);
+        gl_call!(gl::NamedFramebufferTexture(
+            framebuffer,
+            gl::COLOR_ATTACHMENT0,
+            color,
+            0
+        ));
+
+        let depth = with_depth.then(|| {
+            let depth = create_render_texture(gl::DEPTH_COMPONENT24, width, height);
+            gl_call!(gl::NamedFramebufferTexture(
+                framebuffer,
+                gl::DEPTH_ATTACHMENT,
+                depth,
+                0
+            ));
+            depth
+        });
+
+        let status = gl_call!(gl::CheckNamedFramebufferStatus(
+            framebuffer,
+            gl::FRAMEBUFFER
+        ));
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            error!("The post-processing framebuffer is incomplete: {status:#x}");
+        }
+
+        Self {
+            framebuffer,
+            color,
+            depth,
+        }
+    }
+}
+
+// Redirects the drawing of the world to the framebuffer of `RenderPostProcessing`, runs after
+// `RenderShadows` which draws to its own framebuffer
+// Without any pass the world is drawn straight to the screen
+pub struct BeginPostProcessing {
+    framebuffer: u32,
+}
+
+impl<'a> System<'a> for BeginPostProcessing {
+    type SystemData = Read<'a, Settings>;
+
+    fn run(&mut self, settings: Self::SystemData) {
+        if !PostProcessingPass::enabled(&settings).is_empty() {
+            gl_call!(gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer));
+        }
+    }
+}
+
+// Applies the enabled passes one after the other to the world drawn off screen, the last one
+// draws to the screen before the GUI
+pub struct RenderPostProcessing {
+    scene: RenderTarget,
+    // The passes read from one and write to the other
+    targets: [RenderTarget; 2],
+    vao: u32,
+}
+
+impl Default for RenderPostProcessing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RenderPostProcessing {
+    pub fn new() -> Self {
+        let mut viewport = [0; 4];
+        gl_call!(gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr()));
+        let (width, height) = (viewport[2], viewport[3]);
+
+        let scene = RenderTarget::new(width, height, true);
+        gl_call!(gl::BindTextureUnit(
+            DEPTH_TEXTURE_UNIT,
+            scene.depth.unwrap()
+        ));
+
+        Self {
+            scene,
+            targets: [
+                RenderTarget::new(width, height, false),
+                RenderTarget::new(width, height, false),
+            ],
+            vao: create_screen_quad_vao(),
+        }
+    }
+
+    pub fn begin(&self) -> BeginPostProcessing {
+        BeginPostProcessing {
+            framebuffer: self.scene.framebuffer,
+        }
+    }
+}
+
+impl<'a> System<'a> for RenderPostProcessing {
+    type SystemData = (
+        ReadStorage<'a, PlayerState>,
+        ReadStorage<'a, Interpolator<PlayerPhysicsState>>,
+        Read<'a, Arc<ChunkManager>>,
+        Read<'a, Settings>,
+        Write<'a, Shaders>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (player_state, player_physics_state, chunk_manager, settings, mut shaders) = data;
+
+        let passes = PostProcessingPass::enabled(&settings);
+        if passes.is_empty() {
+            return;
+        }
+
+        gl_call!(gl::Disable(gl::DEPTH_TEST));
+        gl_call!(gl::Disable(gl::BLEND));
+        gl_call!(gl::BindVertexArray(self.vao));
+
+        for (player_state, player_physics_state) in (&player_state, &player_physics_state).join() {
+            let camera_pos = player_physics_state.get_interpolated_state().position
+                + vec3(
+                    0.0,
+                    *player_state.camera_height.get_interpolated_state(),
+                    0.0,
+                );
+            let tint = chunk_manager
+                .get_block(
+                    camera_pos.x.floor() as i32,
+                    camera_pos.y.floor() as i32,
+                    camera_pos.z.floor() as i32,
+                )
+                .and_then(fluid_tint)
+                .unwrap_or([0.0; 4]);
+            let inverse_projection = player_state
+                .projection_matrix
+                .try_inverse()
+                .unwrap_or_else(Mat4::identity);
+
+            let mut input = self.scene.color;
+            for (i, pass) in passes.iter().enumerate() {
+                let output = if i == passes.len() - 1 {
+                    0
+                } else {
+                    self.targets[i % 2].framebuffer
+                };
+                gl_call!(gl::BindFramebuffer(gl::FRAMEBUFFER, output));
+                gl_call!(gl::BindTextureUnit(SCENE_TEXTURE_UNIT, input));
+
+                let shader = shaders.get_mut(pass.shader_name()).unwrap();
+                shader.use_program();
+                shader.set_uniform1i("scene", SCENE_TEXTURE_UNIT as i32);
+
+                match pass {
+                    PostProcessingPass::AmbientOcclusion => {
+                        shader.set_uniform1i("depth", DEPTH_TEXTURE_UNIT as i32);
+                        unsafe {
+                            shader.set_uniform_matrix4fv(
+                                "projection",
+                                player_state.projection_matrix.as_ptr(),
+                            );
+                            shader.set_uniform_matrix4fv(
+                                "inverse_projection",
+                                inverse_projection.as_ptr(),
+                            );
+                        }
+                    }
+                    PostProcessingPass::UnderwaterTint => {
+                        shader.set_uniform4f("tint", &tint);
+                    }
+                    PostProcessingPass::Tonemapping => {
+                        shader.set_uniform1f("exposure", EXPOSURE);
+                        shader.set_uniform1f("gamma", GAMMA);
+                    }
+                    PostProcessingPass::Fxaa | PostProcessingPass::Vignette => {}
+                }
+
+                gl_call!(gl::DrawArrays(gl::TRIANGLES, 0, 6));
+                input = self.targets[i % 2].color;
+            }
+        }
+
+        // The GUI is drawn over the world without testing its depth, but the depth buffer of the
+        // screen still has to be cleared
+        gl_call!(gl::Clear(gl::DEPTH_BUFFER_BIT));
+        gl_call!(gl::Enable(gl::BLEND));
+        gl_call!(gl::Enable(gl::DEPTH_TEST));
+    }
+}
@@ -0,0 +1,123 @@
+use crate::chunk_manager::ChunkManager;
+use crate::particle_system::ParticleProps;
+use crate::physics::Interpolator;
+use crate::player::{PlayerPhysicsState, PlayerState};
+use crate::timer::Timer;
+use crate::types::{EnvironmentTextures, ParticleSystems};
+use crate::weather::{Weather, SNOW_LINE};
+use nalgebra_glm::vec3;
+use rand::{thread_rng, Rng};
+use specs::{Join, Read, ReadStorage, System, Write};
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Particles of rain or snow spawned per second when it rains the most
+const PRECIPITATION_PER_SECOND: f32 = 1500.0;
+// Horizontal distance from the camera of the particles
+const PRECIPITATION_RADIUS: f32 = 16.0;
+// Height above the camera of the particles
+const PRECIPITATION_HEIGHT: Range<f32> = 4.0..16.0;
+const RAIN_SPEED: f32 = 20.0;
+const SNOW_SPEED: f32 = 2.0;
+
+// Spawns the rain and the snow around the camera, only where the sky is not hidden by blocks
+pub struct SpawnPrecipitation {
+    last_time: Option<Instant>,
+    // Fraction of a particle left from the previous frames
+    remainder: f32,
+}
+
+impl Default for SpawnPrecipitation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpawnPrecipitation {
+    pub fn new() -> Self {
+        Self {
+            last_time: None,
+            remainder: 0.0,
+        }
+    }
+}
+
+impl<'a> System<'a> for SpawnPrecipitation {
+    type SystemData = (
+        ReadStorage<'a, PlayerState>,
+        ReadStorage<'a, Interpolator<PlayerPhysicsState>>,
+        Read<'a, Arc<ChunkManager>>,
+        Read<'a, Timer>,
+        Read<'a, Weather>,
+        Read<'a, EnvironmentTextures>,
+        Write<'a, ParticleSystems>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            player_state,
+            player_physics_state,
+            chunk_manager,
+            global_timer,
+            weather,
+            environment_textures,
+            mut particle_systems,
+        ) = data;
+
+        let time = global_timer.time();
+        let elapsed = self.last_time.map_or(Duration::ZERO, |last_time| {
+            time.saturating_duration_since(last_time)
+        });
+        self.last_time = Some(time);
+
+        self.remainder +=
+            elapsed.as_secs_f32() * PRECIPITATION_PER_SECOND * weather.precipitation();
+        let count = self.remainder as usize;
+        self.remainder -= count as f32;
+
+        let precipitation = particle_systems.get_mut("precipitation").unwrap();
+        let mut rng = thread_rng();
+
+        for (player_state, player_physics_state) in (&player_state, &player_physics_state).join() {
+            let camera_pos = player_physics_state.get_interpolated_state().position
+                + vec3(
+                    0.0,
+                    *player_state.camera_height.get_interpolated_state(),
+                    0.0,
+                );
+
+            for _ in 0..count {
+                let x = camera_pos.x + rng.gen_range(-PRECIPITATION_RADIUS..PRECIPITATION_RADIUS);
+                let z = camera_pos.z + rng.gen_range(-PRECIPITATION_RADIUS..PRECIPITATION_RADIUS);
+                let y = camera_pos.y + rng.gen_range(PRECIPITATION_HEIGHT);
+
+                // Nothing falls on the columns that are not loaded, nor below a roof
+                let highest = match chunk_manager.highest_block(x.floor() as i32, z.floor() as i32)
+                {
+                    Some(highest) if ((highest + 1) as f32) < y => highest,
+                    _ => continue,
+                };
+                let ground = (highest + 1) as f32;
+
+                let (texture, speed, scale) = if highest >= SNOW_LINE {
+                    ("snow", SNOW_SPEED, vec3(0.1, 0.1, 0.1))
+                } else {
+                    ("rain", RAIN_SPEED, vec3(0.05, 0.8, 0.05))
+                };
+
+                precipitation.emit_with_texture(
+                    &ParticleProps {
+                        position: vec3(x, y, z),
+                        velocity: vec3(0.0, -speed, 0.0),
+                        acceleration: vec3(0.0, 0.0, 0.0),
+                        lifetime: Duration::from_secs_f32((y - ground) / speed),
+                        scale,
+                    },
+                    environment_textures[texture],
+                    (0.0, 0.0, 1.0, 1.0),
+                );
+            }
+        }
+    }
+}
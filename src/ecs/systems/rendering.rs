@@ -1,16 +1,26 @@
 use crate::chunk_manager::ChunkManager;
-use crate::constants::{BACKGROUND_COLOR, BLOCK_OUTLINE_WIDTH, RENDER_DISTANCE, ENABLE_FOG};
+use crate::constants::BLOCK_OUTLINE_WIDTH;
+use crate::frustum::Frustum;
+use crate::game_mode::GameMode;
 use crate::gui::{
-    create_block_outline_vao, create_crosshair_vao, create_hotbar_selection_vao, create_hotbar_vao,
-    draw_crosshair,
+    create_block_crack_vao, create_block_outline_vao, create_crosshair_vao,
+    create_hotbar_selection_vao, create_hotbar_vao, draw_crosshair, StatusBar,
 };
+use crate::health::Health;
+use crate::hunger::Hunger;
 use crate::inventory::Inventory;
+use crate::metrics::Metrics;
 use crate::player::PlayerState;
+use crate::settings::Settings;
+use crate::shadows::{Shadows, SHADOW_MAP_TEXTURE_UNIT};
 use crate::timer::Timer;
-use crate::types::{ParticleSystems, Shaders, TexturePack};
+use crate::types::{ItemTextures, ParticleSystems, Shaders, TexturePack};
+use crate::weather::Weather;
+use crate::world_edit::WorldEditSession;
+use crate::world_time::WorldTime;
 use nalgebra::Matrix4;
 use nalgebra_glm::vec3;
-use specs::{Join, Read, ReadStorage, System, Write, WriteStorage};
+use specs::{Join, LendJoin, Read, ReadStorage, System, Write, WriteStorage};
 use std::sync::Arc;
 
 pub struct RenderChunks;
@@ -20,22 +30,56 @@ impl<'a> System<'a> for RenderChunks {
         ReadStorage<'a, PlayerState>,
         Read<'a, Arc<ChunkManager>>,
         Write<'a, Shaders>,
+        Write<'a, Metrics>,
+        Read<'a, WorldTime>,
+        Read<'a, Weather>,
+        Read<'a, Shadows>,
+        Read<'a, Settings>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (player_state, chunk_manager, mut shaders) = data;
+        let (
+            player_state,
+            chunk_manager,
+            mut shaders,
+            mut metrics,
+            world_time,
+            weather,
+            shadows,
+            settings,
+        ) = data;
 
         let mut voxel_shader = shaders.get_mut("voxel_shader").unwrap();
         voxel_shader.use_program();
         voxel_shader.set_uniform1i("array_texture", 0);
 
-        let (r, g, b, a) = BACKGROUND_COLOR;
-        voxel_shader.set_uniform1i("enable_fog", ENABLE_FOG as i32);
-        voxel_shader.set_uniform3f("sky_color", &[r, g, b]);
-        voxel_shader.set_uniform1f("render_distance", RENDER_DISTANCE as f32);
+        voxel_shader.set_uniform1i("enable_fog", settings.fog as i32);
+        voxel_shader.set_uniform3f(
+            "sky_color",
+            weather.sky_color(world_time.sky_color()).as_slice(),
+        );
+        voxel_shader.set_uniform1f("daylight", weather.daylight(world_time.daylight()));
+        voxel_shader.set_uniform1f("render_distance", settings.render_distance as f32);
+        voxel_shader.set_uniform1i("enable_baked_ao", !settings.ssao as i32);
 
-        gl_call!(gl::ClearColor(r, g, b, a));
-        gl_call!(gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT));
+        // Drawn by `RenderShadows`
+        voxel_shader.set_uniform1i("shadow_cascades", shadows.cascades.len() as i32);
+        voxel_shader.set_uniform1i("shadow_map", SHADOW_MAP_TEXTURE_UNIT as i32);
+        voxel_shader.set_uniform3f("light_direction", shadows.light_direction.as_slice());
+        voxel_shader.set_uniform1f("shadow_strength", shadows.strength);
+        voxel_shader.set_uniform1i("pcf_radius", shadows.pcf_radius);
+        for (i, cascade) in shadows.cascades.iter().enumerate() {
+            unsafe {
+                voxel_shader.set_uniform_matrix4fv(
+                    &format!("light_matrices[{i}]"),
+                    cascade.light_matrix.as_ptr(),
+                );
+            }
+            voxel_shader.set_uniform1f(&format!("cascade_splits[{i}]"), cascade.split);
+            voxel_shader.set_uniform1f(&format!("texel_sizes[{i}]"), cascade.texel_size);
+        }
+
+        // The screen, or the framebuffer of `RenderPostProcessing`, is cleared by `RenderSky`
         gl_call!(gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA));
 
         for player_state in (&player_state).join() {
@@ -45,7 +89,13 @@ impl<'a> System<'a> for RenderChunks {
                     .set_uniform_matrix4fv("projection", player_state.projection_matrix.as_ptr());
             }
 
-            chunk_manager.render_loaded_chunks(&mut voxel_shader);
+            let frustum =
+                Frustum::from_matrix(&(player_state.projection_matrix * player_state.view_matrix));
+            let (chunks_drawn, chunks_culled) =
+                chunk_manager.render_loaded_chunks(voxel_shader, &frustum);
+
+            metrics.chunks_drawn = chunks_drawn;
+            metrics.chunks_culled = chunks_culled;
         }
     }
 }
@@ -59,16 +109,27 @@ impl<'a> System<'a> for RenderParticles {
         Write<'a, Arc<ChunkManager>>,
         Write<'a, Shaders>,
         Write<'a, ParticleSystems>,
+        Read<'a, WorldTime>,
+        Read<'a, Weather>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (global_timer, player_state, chunk_manager, mut shaders, mut particle_systems) = data;
+        let (
+            global_timer,
+            player_state,
+            chunk_manager,
+            mut shaders,
+            mut particle_systems,
+            world_time,
+            weather,
+        ) = data;
 
         gl_call!(gl::Disable(gl::CULL_FACE));
 
         let mut particle_shader = shaders.get_mut("particle_shader").unwrap();
         particle_shader.use_program();
         particle_shader.set_uniform1i("array_texture", 0);
+        particle_shader.set_uniform1f("daylight", weather.daylight(world_time.daylight()));
 
         for player_state in (&player_state).join() {
             for particle_system in particle_systems.values_mut() {
@@ -87,50 +148,110 @@ impl<'a> System<'a> for RenderParticles {
 
 pub struct RenderBlockOutline {
     vao: u32,
+    crack_vao: u32,
 }
 
 impl RenderBlockOutline {
     pub fn new() -> Self {
         Self {
             vao: create_block_outline_vao(),
+            crack_vao: create_block_crack_vao(),
+        }
+    }
+
+    // The outline is a unit cube, scaled by the model matrix for bigger boxes
+    fn draw_outline(
+        &self,
+        shaders: &mut Shaders,
+        player_state: &PlayerState,
+        model: &Matrix4<f32>,
+    ) {
+        let outline_shader = shaders.get_mut("outline_shader").unwrap();
+        outline_shader.use_program();
+        unsafe {
+            outline_shader.set_uniform_matrix4fv("model", model.as_ptr());
+            outline_shader.set_uniform_matrix4fv("view", player_state.view_matrix.as_ptr());
+            outline_shader
+                .set_uniform_matrix4fv("projection", player_state.projection_matrix.as_ptr());
         }
+
+        gl_call!(gl::LineWidth(BLOCK_OUTLINE_WIDTH));
+        gl_call!(gl::BindVertexArray(self.vao));
+        gl_call!(gl::DrawArrays(gl::LINES, 0, 24));
     }
 }
 
 impl<'a> System<'a> for RenderBlockOutline {
-    type SystemData = (ReadStorage<'a, PlayerState>, Write<'a, Shaders>);
+    type SystemData = (
+        ReadStorage<'a, PlayerState>,
+        ReadStorage<'a, WorldEditSession>,
+        Write<'a, Shaders>,
+    );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (player_state, mut shaders) = data;
+        let (player_state, world_edit, mut shaders) = data;
+
+        for (player_state, world_edit) in (&player_state, (&world_edit).maybe()).join() {
+            // Box around the WorldEdit selection, slightly bigger so that it is not hidden by the
+            // faces of the blocks
+            if let Some(((x1, y1, z1), (x2, y2, z2))) =
+                world_edit.and_then(WorldEditSession::selection)
+            {
+                let corner = vec3(x1 as f32 - 0.01, y1 as f32 - 0.01, z1 as f32 - 0.01);
+                let size = vec3(
+                    (x2 - x1 + 1) as f32 + 0.02,
+                    (y2 - y1 + 1) as f32 + 0.02,
+                    (z2 - z1 + 1) as f32 + 0.02,
+                );
+                let model_matrix =
+                    Matrix4::new_translation(&corner) * Matrix4::new_nonuniform_scaling(&size);
+
+                self.draw_outline(&mut shaders, player_state, &model_matrix);
+            }
 
-        for player_state in (&player_state).join() {
             if let Some(((x, y, z), _)) = player_state.targeted_block {
                 let (x, y, z) = (x as f32, y as f32, z as f32);
                 let model_matrix = Matrix4::new_translation(&vec3(x, y, z));
 
-                let outline_shader = shaders.get_mut("outline_shader").unwrap();
-                outline_shader.use_program();
-                unsafe {
-                    outline_shader.set_uniform_matrix4fv("model", model_matrix.as_ptr());
-                    outline_shader.set_uniform_matrix4fv("view", player_state.view_matrix.as_ptr());
-                    outline_shader.set_uniform_matrix4fv(
-                        "projection",
-                        player_state.projection_matrix.as_ptr(),
-                    );
-                }
+                self.draw_outline(&mut shaders, player_state, &model_matrix);
+
+                // Crack overlay on the block being broken
+                if let Some(block_breaking) = &player_state.block_breaking {
+                    // Slightly bigger than the block to avoid z-fighting
+                    let model_matrix = model_matrix
+                        * Matrix4::new_translation(&vec3(0.5, 0.5, 0.5))
+                        * Matrix4::new_scaling(1.002)
+                        * Matrix4::new_translation(&vec3(-0.5, -0.5, -0.5));
 
-                gl_call!(gl::LineWidth(BLOCK_OUTLINE_WIDTH));
-                gl_call!(gl::BindVertexArray(self.vao));
-                gl_call!(gl::DrawArrays(gl::LINES, 0, 24));
+                    let crack_shader = shaders.get_mut("crack_shader").unwrap();
+                    crack_shader.use_program();
+                    crack_shader.set_uniform1i("tex", 4);
+                    crack_shader.set_uniform1i("stage", block_breaking.stage() as i32);
+                    unsafe {
+                        crack_shader.set_uniform_matrix4fv("model", model_matrix.as_ptr());
+                        crack_shader
+                            .set_uniform_matrix4fv("view", player_state.view_matrix.as_ptr());
+                        crack_shader.set_uniform_matrix4fv(
+                            "projection",
+                            player_state.projection_matrix.as_ptr(),
+                        );
+                    }
+
+                    gl_call!(gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA));
+                    gl_call!(gl::BindVertexArray(self.crack_vao));
+                    gl_call!(gl::DrawArrays(gl::TRIANGLES, 0, 36));
+                }
             }
         }
     }
 }
 
-pub(crate) struct RenderGUI {
+pub struct RenderGUI {
     crosshair_vao: u32,
     hotbar_vao: u32,
     hotbar_selection_vao: u32,
+    health_bar: StatusBar,
+    hunger_bar: StatusBar,
 }
 
 impl RenderGUI {
@@ -139,6 +260,8 @@ impl RenderGUI {
             crosshair_vao: create_crosshair_vao(),
             hotbar_vao: create_hotbar_vao(),
             hotbar_selection_vao: create_hotbar_selection_vao(),
+            health_bar: StatusBar::new((16.0, 0.0), (52.0, 0.0), (61.0, 0.0)),
+            hunger_bar: StatusBar::new((16.0, 27.0), (52.0, 27.0), (61.0, 27.0)),
         }
     }
 }
@@ -146,25 +269,52 @@ impl RenderGUI {
 impl<'a> System<'a> for RenderGUI {
     type SystemData = (
         Read<'a, TexturePack>,
+        Read<'a, ItemTextures>,
         Write<'a, Shaders>,
         WriteStorage<'a, Inventory>,
+        ReadStorage<'a, Health>,
+        ReadStorage<'a, Hunger>,
+        ReadStorage<'a, GameMode>,
+        Read<'a, Settings>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (texture_pack, mut shaders, mut inventory) = data;
+        let (
+            texture_pack,
+            item_textures,
+            mut shaders,
+            mut inventory,
+            health,
+            hunger,
+            game_mode,
+            settings,
+        ) = data;
 
-        for inventory in (&mut inventory).join() {
-            let mut gui_shader = shaders.get_mut("gui_shader").unwrap();
-            draw_crosshair(self.crosshair_vao, &mut gui_shader);
+        for (inventory, health, hunger, game_mode) in
+            (&mut inventory, &health, &hunger, &game_mode).join()
+        {
+            let gui_shader = shaders.get_mut("gui_shader").unwrap();
+            draw_crosshair(self.crosshair_vao, gui_shader, &settings);
             gl_call!(gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA));
 
             gl_call!(gl::Disable(gl::DEPTH_TEST));
-            inventory.update_dirty_items(&texture_pack);
-            inventory.draw_hotbar(self.hotbar_vao, &mut gui_shader);
-            inventory.draw_hotbar_selection_box(self.hotbar_selection_vao, &mut gui_shader);
+            inventory.update_dirty_items(&texture_pack, &item_textures);
+            inventory.draw_hotbar(self.hotbar_vao, gui_shader, &settings);
+            inventory.draw_hotbar_selection_box(self.hotbar_selection_vao, gui_shader, &settings);
+
+            // Only the players that can be hurt see their health
+            if game_mode.takes_damage() {
+                self.health_bar
+                    .draw(gui_shader, &settings, health.health, false);
+            }
+
+            if game_mode.has_hunger() {
+                self.hunger_bar
+                    .draw(gui_shader, &settings, hunger.food_level, true);
+            }
 
-            let mut item_shader = shaders.get_mut("item_shader").unwrap();
-            inventory.draw_hotbar_items(&mut item_shader);
+            let item_shader = shaders.get_mut("item_shader").unwrap();
+            inventory.draw_hotbar_items(item_shader, &settings);
             gl_call!(gl::Enable(gl::DEPTH_TEST));
         }
     }
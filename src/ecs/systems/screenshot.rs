@@ -0,0 +1,147 @@
+use crate::console::Console;
+use crate::input::InputCache;
+use crate::metrics::Metrics;
+use crate::screenshot::{
+    capture_screen, default_screenshot_path, HeadlessExit, ScreenshotWriter, Screenshots,
+};
+use crate::text::{RED, WHITE};
+use glfw::{Action, Key, WindowEvent};
+use specs::{Read, System, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// Frames in a row without anything left to generate or to upload before the headless frame is
+// captured, the meshing of the last columns can start a frame after the generation is done
+const HEADLESS_SETTLED_FRAMES: u32 = 10;
+const HEADLESS_TIMEOUT: Duration = Duration::from_secs(120);
+
+fn viewport_size() -> (u32, u32) {
+    let mut viewport = [0; 4];
+    gl_call!(gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr()));
+
+    (viewport[2] as u32, viewport[3] as u32)
+}
+
+// Saves the screen when F2 is pressed or when /screenshot is run, after everything else is drawn
+// The messages are shown once the files are written
+#[derive(Default)]
+pub struct TakeScreenshots {
+    writer: ScreenshotWriter,
+}
+
+impl TakeScreenshots {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<'a> System<'a> for TakeScreenshots {
+    type SystemData = (
+        Read<'a, InputCache>,
+        Write<'a, Screenshots>,
+        Write<'a, Console>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (input_cache, mut screenshots, mut console) = data;
+
+        for (path, result) in self.writer.saved() {
+            match result {
+                Ok(()) => console.push_message(&format!("Saved {}", path.display()), WHITE),
+                Err(err) => {
+                    console.push_message(&format!("Could not save {}: {err}", path.display()), RED)
+                }
+            }
+        }
+
+        for event in input_cache.events.iter() {
+            if let WindowEvent::Key(Key::F2, _, Action::Press, _) = event {
+                screenshots.request(default_screenshot_path());
+            }
+        }
+
+        let requests = screenshots.take_requests();
+        if requests.is_empty() {
+            return;
+        }
+
+        let (width, height) = viewport_size();
+        let image = capture_screen(width, height);
+
+        for path in requests {
+            self.writer.save(image.clone(), path);
+        }
+    }
+}
+
+// Renders the world in a hidden window until the terrain around the camera is loaded, then saves
+// the frame and exits through `HeadlessExit`, e.g. for comparing the rendering against reference
+// images
+pub struct RenderHeadless {
+    output: PathBuf,
+    settled_frames: u32,
+    started: Instant,
+    writer: ScreenshotWriter,
+    is_captured: bool,
+}
+
+impl RenderHeadless {
+    pub fn new(output: PathBuf) -> Self {
+        Self {
+            output,
+            settled_frames: 0,
+            started: Instant::now(),
+            writer: ScreenshotWriter::new(),
+            is_captured: false,
+        }
+    }
+}
+
+impl<'a> System<'a> for RenderHeadless {
+    type SystemData = (Read<'a, Metrics>, Write<'a, HeadlessExit>);
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (metrics, mut headless_exit) = data;
+
+        // The frame is drawn again while the file is written
+        if self.is_captured {
+            if let Some((path, result)) = self.writer.saved().pop() {
+                headless_exit.exit_code = Some(match result {
+                    Ok(()) => {
+                        info!("Saved {}", path.display());
+                        0
+                    }
+                    Err(err) => {
+                        error!("Could not save {}: {err}", path.display());
+                        1
+                    }
+                });
+            }
+            return;
+        }
+
+        let is_settled = metrics.loaded_columns > 0
+            && !metrics.is_generating
+            && metrics.generation_queue_depth == 0
+            && metrics.pending_uploads == 0;
+
+        if is_settled {
+            self.settled_frames += 1;
+        } else {
+            self.settled_frames = 0;
+        }
+
+        if self.settled_frames < HEADLESS_SETTLED_FRAMES {
+            if self.started.elapsed() > HEADLESS_TIMEOUT {
+                error!("The terrain was not loaded after {HEADLESS_TIMEOUT:?}");
+                headless_exit.exit_code = Some(1);
+            }
+            return;
+        }
+
+        let (width, height) = viewport_size();
+        self.writer
+            .save(capture_screen(width, height), self.output.clone());
+        self.is_captured = true;
+    }
+}
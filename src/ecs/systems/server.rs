@@ -0,0 +1,246 @@
+use crate::anvil::AnvilWorld;
+use crate::chunk_manager::ChunkManager;
+use crate::commands::{CommandContext, CommandDispatcher};
+use crate::journal::EditJournal;
+use crate::metrics::Metrics;
+use crate::network::server::ServerNetwork;
+use crate::physics::Interpolator;
+use crate::player::PlayerPhysicsState;
+use crate::server::ServerState;
+use crate::timer::Timer;
+use crate::weather::Weather;
+use crate::world_generation::ChunkGenerator;
+use crate::world_time::WorldTime;
+use crossbeam_channel::Receiver;
+use nalgebra_glm::IVec3;
+use specs::{Join, Read, ReadStorage, System, Write};
+use std::sync::Arc;
+
+// Commands handled by the server itself rather than by the command dispatcher
+const SERVER_COMMANDS: [(&str, &str); 4] = [
+    ("/stop", "Stops the server"),
+    ("/tps", "Displays the number of ticks per second"),
+    ("/list", "Lists the connected players"),
+    ("/say <message>", "Sends a message to every player"),
+];
+
+// Generates the chunks around the players and keeps the ones around the spawn point loaded
+pub struct ServerChunkLoading {
+    generator: ChunkGenerator,
+    spawn_chunk: (i32, i32, i32),
+}
+
+impl ServerChunkLoading {
+    pub fn new(view_distance: i32, spawn_point: &IVec3) -> Self {
+        let (chunk_x, chunk_y, chunk_z, _, _, _) =
+            ChunkManager::get_chunk_coords(spawn_point.x, spawn_point.y, spawn_point.z);
+
+        Self {
+            generator: ChunkGenerator::new(view_distance),
+            spawn_chunk: (chunk_x, chunk_y, chunk_z),
+        }
+    }
+
+    pub fn with_imported_world(mut self, world: AnvilWorld) -> Self {
+        self.generator = self.generator.with_imported_world(world);
+        self
+    }
+}
+
+impl<'a> System<'a> for ServerChunkLoading {
+    type SystemData = (
+        ReadStorage<'a, Interpolator<PlayerPhysicsState>>,
+        Read<'a, Arc<ChunkManager>>,
+        Write<'a, Metrics>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (player_physics_state, chunk_manager, mut metrics) = data;
+
+        let mut centers = vec![self.spawn_chunk];
+        for player_physics_state in player_physics_state.join() {
+            let position = player_physics_state.get_latest_state().position;
+            let (chunk_x, chunk_y, chunk_z, _, _, _) = ChunkManager::get_chunk_coords(
+                position.x as i32,
+                position.y as i32,
+                position.z as i32,
+            );
+
+            centers.push((chunk_x, chunk_y, chunk_z));
+        }
+
+        self.generator.update(&chunk_manager, &centers);
+        self.generator.update_metrics(&chunk_manager, &mut metrics);
+    }
+}
+
+// Runs the commands typed in the terminal, there is no player to run them on
+pub struct HandleServerCommands {
+    dispatcher: CommandDispatcher,
+    commands_rx: Receiver<String>,
+}
+
+impl HandleServerCommands {
+    pub fn new(commands_rx: Receiver<String>) -> Self {
+        Self {
+            dispatcher: CommandDispatcher::with_builtin_commands(),
+            commands_rx,
+        }
+    }
+}
+
+impl<'a> System<'a> for HandleServerCommands {
+    type SystemData = (
+        Read<'a, Arc<ChunkManager>>,
+        Write<'a, Timer>,
+        Read<'a, Metrics>,
+        Write<'a, ServerState>,
+        Read<'a, ServerNetwork>,
+        Write<'a, EditJournal>,
+        Write<'a, WorldTime>,
+        Write<'a, Weather>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            chunk_manager,
+            mut timer,
+            metrics,
+            mut server_state,
+            network,
+            mut journal,
+            mut world_time,
+            mut weather,
+        ) = data;
+
+        for line in self.commands_rx.try_iter() {
+            let line = line.trim();
+            let command = line.strip_prefix('/').unwrap_or(line);
+
+            match command {
+                "" => continue,
+                "stop" => {
+                    info!("Stopping the server");
+                    server_state.is_running = false;
+                    continue;
+                }
+                "tps" => {
+                    info!("Ticks per second: {}", metrics.fps);
+                    continue;
+                }
+                "list" => {
+                    let names: Vec<&str> = network
+                        .clients
+                        .iter()
+                        .map(|client| client.name.as_str())
+                        .collect();
+                    info!("{} players online: {}", names.len(), names.join(", "));
+                    continue;
+                }
+                _ => {}
+            }
+
+            if let Some(message) = command.strip_prefix("say ") {
+                network.broadcast_chat(&format!("[Server] {}", message.trim()), None);
+                continue;
+            }
+
+            let mut context = CommandContext {
+                chunk_manager: &chunk_manager,
+                timer: &mut timer,
+                world_time: &mut world_time,
+                weather: &mut weather,
+                journal: &mut journal,
+                screenshots: None,
+                player: None,
+            };
+
+            match self.dispatcher.execute(&mut context, line) {
+                Ok(feedback) => {
+                    for feedback_line in feedback.lines() {
+                        info!("{feedback_line}");
+                    }
+
+                    if command == "help" {
+                        for (usage, description) in SERVER_COMMANDS {
+                            info!("{usage} - {description}");
+                        }
+                    }
+                }
+                Err(e) => warn!("{e}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weather::WeatherKind;
+    use specs::{DispatcherBuilder, World, WorldExt};
+
+    // The server systems need neither a window nor an OpenGL context
+    fn world() -> World {
+        let mut world = World::new();
+        world.register::<Interpolator<PlayerPhysicsState>>();
+        world.insert(Arc::new(ChunkManager::new()));
+        world.insert(Timer::default());
+        world.insert(Metrics::default());
+        world.insert(ServerState::default());
+        world.insert(ServerNetwork::default());
+        world.insert(EditJournal::disabled());
+        world.insert(WorldTime::default());
+        world.insert(Weather::default());
+        world
+    }
+
+    #[test]
+    fn terminal_commands_run_in_the_dispatcher() {
+        let (commands_tx, commands_rx) = crossbeam_channel::unbounded();
+        let mut world = world();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                HandleServerCommands::new(commands_rx),
+                "server_commands",
+                &[],
+            )
+            .build();
+
+        commands_tx.send("/weather rain".to_string()).unwrap();
+        commands_tx.send("time set 13000".to_string()).unwrap();
+        commands_tx.send("/unknown".to_string()).unwrap();
+        dispatcher.dispatch(&world);
+        world.maintain();
+
+        assert_eq!(world.read_resource::<Weather>().kind(), WeatherKind::Rain);
+        assert_eq!(world.read_resource::<WorldTime>().time_of_day(), 13000);
+        assert!(world.read_resource::<ServerState>().is_running);
+
+        commands_tx.send("stop".to_string()).unwrap();
+        dispatcher.dispatch(&world);
+
+        assert!(!world.read_resource::<ServerState>().is_running);
+    }
+
+    #[test]
+    fn commands_are_run_once() {
+        let (commands_tx, commands_rx) = crossbeam_channel::unbounded();
+        let mut world = world();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                HandleServerCommands::new(commands_rx),
+                "server_commands",
+                &[],
+            )
+            .build();
+
+        commands_tx.send("/time add 100".to_string()).unwrap();
+        let before = world.read_resource::<WorldTime>().ticks();
+        for _ in 0..3 {
+            dispatcher.dispatch(&world);
+            world.maintain();
+        }
+
+        assert_eq!(world.read_resource::<WorldTime>().ticks(), before + 100);
+    }
+}
@@ -0,0 +1,789 @@
+use crate::chunk_manager::ChunkManager;
+use crate::constants::{PHYSICS_TICKRATE, PLAYER_EYES_HEIGHT, REACH_DISTANCE};
+use crate::game_mode::GameMode;
+use crate::health::{environment_damage, fall_damage, DamageSource, Health};
+use crate::journal::{EditJournal, SERVER_AUTHOR};
+use crate::mob::{Mob, MobPhysicsState};
+use crate::network::chunk_data::compress_column;
+use crate::network::packet::{ClientboundPacket, EntityKind, ServerboundPacket};
+use crate::network::prediction::is_newer_sequence;
+use crate::network::server::{ClientConnection, ConnectedClient, ServerNetwork, MAX_INPUT_BUDGET};
+use crate::network::{KEEP_ALIVE_INTERVAL, KEEP_ALIVE_TIMEOUT, PROTOCOL_VERSION};
+use crate::physics::Interpolator;
+use crate::player::{PlayerPhysicsState, PlayerState};
+use crate::weather::{Weather, WeatherKind};
+use crate::world_time::WorldTime;
+use nalgebra_glm::{vec2, vec3, Vec3};
+use specs::{Entities, Entity, Join, Read, ReadStorage, System, Write, WriteStorage};
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Connections are closed when they do not log in within this delay
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_NAME_LENGTH: usize = 16;
+const MAX_CHAT_LENGTH: usize = 256;
+const TIME_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+// Columns sent to each client per tick, the nearest first
+const COLUMNS_SENT_PER_TICK: usize = 4;
+// Movements smaller than this are not sent to the clients
+const ENTITY_MOVE_THRESHOLD: f32 = 0.01;
+
+pub struct AcceptConnections;
+
+impl<'a> System<'a> for AcceptConnections {
+    type SystemData = Write<'a, ServerNetwork>;
+
+    fn run(&mut self, mut network: Self::SystemData) {
+        network.accept_connections();
+    }
+}
+
+// Logs the clients in and applies what they send: movements, block edits and chat messages
+pub struct HandleClientPackets {
+    spawn_point: Vec3,
+    max_players: usize,
+}
+
+impl HandleClientPackets {
+    pub fn new(spawn_point: Vec3, max_players: usize) -> Self {
+        Self {
+            spawn_point,
+            max_players,
+        }
+    }
+
+    fn validate_login(
+        &self,
+        network: &ServerNetwork,
+        protocol_version: u32,
+        name: &str,
+    ) -> Result<(), String> {
+        if protocol_version != PROTOCOL_VERSION {
+            return Err(format!(
+                "Incompatible protocol version {protocol_version}, the server uses {PROTOCOL_VERSION}"
+            ));
+        }
+
+        let is_name_valid = !name.is_empty()
+            && name.len() <= MAX_NAME_LENGTH
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !is_name_valid {
+            return Err(format!(
+                "Names are made of 1 to {MAX_NAME_LENGTH} letters, digits and underscores"
+            ));
+        }
+
+        // The edits of the terminal are recorded under this name
+        if name == SERVER_AUTHOR {
+            return Err(format!("{name} is a reserved name"));
+        }
+
+        if network.clients.iter().any(|client| client.name == name) {
+            return Err(format!("{name} is already connected"));
+        }
+
+        if network.clients.len() >= self.max_players {
+            return Err("The server is full".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn create_player(
+        &self,
+        entities: &Entities,
+        player_state: &mut WriteStorage<PlayerState>,
+        player_physics_state: &mut WriteStorage<Interpolator<PlayerPhysicsState>>,
+        game_mode: &mut WriteStorage<GameMode>,
+        health: &mut WriteStorage<Health>,
+    ) -> Entity {
+        let player = entities.create();
+
+        // Inserting in the storages of a living entity cannot fail
+        player_state
+            .insert(player, PlayerState::new(self.spawn_point))
+            .unwrap();
+        player_physics_state
+            .insert(
+                player,
+                Interpolator::new(
+                    1.0 / PHYSICS_TICKRATE,
+                    PlayerPhysicsState::new_at_position(self.spawn_point),
+                ),
+            )
+            .unwrap();
+        // Same game mode as the clients
+        game_mode.insert(player, GameMode::Creative).unwrap();
+        health.insert(player, Health::new()).unwrap();
+
+        player
+    }
+}
+
+impl<'a> System<'a> for HandleClientPackets {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Arc<ChunkManager>>,
+        Write<'a, ServerNetwork>,
+        WriteStorage<'a, PlayerState>,
+        WriteStorage<'a, Interpolator<PlayerPhysicsState>>,
+        WriteStorage<'a, GameMode>,
+        WriteStorage<'a, Health>,
+        Write<'a, EditJournal>,
+        Read<'a, WorldTime>,
+        Read<'a, Weather>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            chunk_manager,
+            mut network,
+            mut player_state,
+            mut player_physics_state,
+            mut game_mode,
+            mut health,
+            mut journal,
+            world_time,
+            weather,
+        ) = data;
+
+        let now = Instant::now();
+
+        // Logins
+        for (connection, accepted) in mem::take(&mut network.pending) {
+            let mut login = None;
+            let mut is_closed = false;
+
+            // The packets following the login are handled once the client is connected
+            if let Some(packet) = connection.received_packets().next() {
+                match packet {
+                    Ok(ServerboundPacket::Login {
+                        protocol_version,
+                        name,
+                    }) => login = Some((protocol_version, name)),
+                    Ok(_) => {
+                        connection.send(ClientboundPacket::Disconnect {
+                            reason: "Expected a login".to_string(),
+                        });
+                        is_closed = true;
+                    }
+                    Err(err) => {
+                        debug!("{} disconnected: {err}", connection.address());
+                        is_closed = true;
+                    }
+                }
+            }
+
+            let (protocol_version, name) = match login {
+                Some(login) => login,
+                None if !is_closed && now.duration_since(accepted) < LOGIN_TIMEOUT => {
+                    network.pending.push((connection, accepted));
+                    continue;
+                }
+                None => continue,
+            };
+
+            if let Err(reason) = self.validate_login(&network, protocol_version, &name) {
+                info!("{} could not log in: {reason}", connection.address());
+                connection.send(ClientboundPacket::Disconnect { reason });
+                continue;
+            }
+
+            let player = self.create_player(
+                &entities,
+                &mut player_state,
+                &mut player_physics_state,
+                &mut game_mode,
+                &mut health,
+            );
+
+            connection.send(ClientboundPacket::LoginSuccess {
+                entity_id: player.id(),
+                position: self.spawn_point,
+            });
+            connection.send(ClientboundPacket::TimeUpdate {
+                ticks: world_time.ticks(),
+            });
+            connection.send(ClientboundPacket::Weather {
+                kind: weather.kind(),
+            });
+            info!("{name} logged in from {}", connection.address());
+
+            network.broadcast_chat(&format!("{name} joined the game"), None);
+            network
+                .clients
+                .push(ConnectedClient::new(connection, name, player));
+        }
+
+        // Connected clients
+        let mut chat_messages = Vec::new();
+        let mut disconnected = Vec::new();
+
+        for (i, client) in network.clients.iter_mut().enumerate() {
+            let mut disconnect_reason = None;
+            let mut has_simulated_inputs = false;
+
+            let elapsed = now
+                .duration_since(client.input_budget_refilled)
+                .as_secs_f32();
+            client.input_budget =
+                (client.input_budget + elapsed * PHYSICS_TICKRATE).min(MAX_INPUT_BUDGET);
+            client.input_budget_refilled = now;
+
+            for packet in client.connection.received_packets() {
+                let packet = match packet {
+                    Ok(packet) => packet,
+                    Err(err) => {
+                        disconnect_reason = Some(err.to_string());
+                        break;
+                    }
+                };
+
+                match packet {
+                    ServerboundPacket::Login { .. } => {
+                        disconnect_reason = Some("Already logged in".to_string());
+                        break;
+                    }
+                    ServerboundPacket::KeepAlive { id } => {
+                        if id == client.keep_alive_id {
+                            client.keep_alive_received = now;
+                        }
+                    }
+                    ServerboundPacket::PlayerInput {
+                        sequence,
+                        mut input,
+                    } => {
+                        // Skipped inputs are corrected by the next acknowledgement
+                        let is_outdated = client
+                            .last_input
+                            .is_some_and(|last| !is_newer_sequence(sequence, last));
+                        if is_outdated || client.input_budget < 1.0 {
+                            continue;
+                        }
+
+                        let (player_state, player_physics_state, game_mode, health) = match (
+                            player_state.get_mut(client.player),
+                            player_physics_state.get_mut(client.player),
+                            game_mode.get(client.player),
+                            health.get_mut(client.player),
+                        ) {
+                            (Some(state), Some(physics_state), Some(game_mode), Some(health)) => {
+                                (state, physics_state, game_mode, health)
+                            }
+                            _ => continue,
+                        };
+
+                        input.is_flying &= game_mode.can_fly();
+
+                        let (state, events) = player_physics_state.get_latest_state().simulate(
+                            &input,
+                            *game_mode,
+                            !health.is_dead(),
+                            &chunk_manager,
+                            1.0 / PHYSICS_TICKRATE,
+                        );
+
+                        // The client does not apply the fall damage itself
+                        if let Some(vertical_velocity) = events.landing_velocity {
+                            let previous_health = health.health;
+                            if game_mode.takes_damage() {
+                                health.damage(fall_damage(vertical_velocity), DamageSource::Fall);
+                            }
+
+                            if health.health < previous_health {
+                                client.connection.send(ClientboundPacket::PlayerDamage {
+                                    amount: previous_health - health.health,
+                                    source: DamageSource::Fall,
+                                });
+                            }
+                        }
+
+                        player_state.rotation = input.rotation();
+                        player_state.is_on_ground = state.is_on_ground;
+                        player_state.is_flying = input.is_flying && !state.is_on_ground;
+                        player_state.is_sprinting = input.is_sprinting;
+                        player_state.is_sneaking = input.is_sneaking;
+                        player_physics_state.reset_to(state);
+
+                        client.input_budget -= 1.0;
+                        client.last_input = Some(sequence);
+                        has_simulated_inputs = true;
+                    }
+                    ServerboundPacket::BlockEdit { x, y, z, block } => {
+                        let eyes = player_physics_state
+                            .get(client.player)
+                            .map(|state| state.get_latest_state().position)
+                            .unwrap_or(self.spawn_point)
+                            + vec3(0.0, PLAYER_EYES_HEIGHT, 0.0);
+                        let center = vec3(x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5);
+
+                        // Out of reach edits are undone on the client
+                        let is_in_reach = (center - eyes).norm() <= REACH_DISTANCE + 1.0;
+                        let previous = chunk_manager.get_block(x, y, z);
+                        if is_in_reach && chunk_manager.put_block(x, y, z, block) {
+                            if let Some(previous) = previous.filter(|&previous| previous != block) {
+                                journal.record(&client.name, vec![((x, y, z), previous, block)]);
+                            }
+                        } else if let Some(block) = chunk_manager.get_block(x, y, z) {
+                            client.connection.send(ClientboundPacket::BlockChange {
+                                x,
+                                y,
+                                z,
+                                block,
+                            });
+                        }
+                    }
+                    ServerboundPacket::Chat { message } => {
+                        let message = message.trim();
+
+                        if !message.is_empty() {
+                            let message: String = message.chars().take(MAX_CHAT_LENGTH).collect();
+                            chat_messages
+                                .push((client.player, format!("<{}> {message}", client.name)));
+                        }
+                    }
+                    ServerboundPacket::Respawn => client.wants_respawn = true,
+                }
+            }
+
+            // Where the inputs led, the client replays the inputs it sent since
+            let acknowledged = client.last_input.filter(|_| has_simulated_inputs);
+            if let (Some(sequence), Some(player_physics_state)) =
+                (acknowledged, player_physics_state.get(client.player))
+            {
+                let state = player_physics_state.get_latest_state();
+
+                client.connection.send(ClientboundPacket::PlayerMovement {
+                    sequence,
+                    position: state.position,
+                    velocity: state.velocity,
+                    knockback: state.knockback,
+                    is_on_ground: state.is_on_ground,
+                    jump_cooldown: state.jump_cooldown,
+                });
+            }
+
+            // Keep alive
+            if disconnect_reason.is_none() {
+                if now.duration_since(client.keep_alive_received) > KEEP_ALIVE_TIMEOUT {
+                    disconnect_reason = Some("Timed out".to_string());
+                } else if now.duration_since(client.keep_alive_sent) >= KEEP_ALIVE_INTERVAL {
+                    client.keep_alive_id += 1;
+                    client.keep_alive_sent = now;
+                    client.connection.send(ClientboundPacket::KeepAlive {
+                        id: client.keep_alive_id,
+                    });
+                }
+            }
+
+            if let Some(reason) = disconnect_reason {
+                disconnected.push((i, reason));
+            }
+        }
+
+        for (author, message) in chat_messages {
+            network.broadcast_chat(&message, Some(author));
+        }
+
+        // In reverse so that the indices stay valid
+        for (i, reason) in disconnected.into_iter().rev() {
+            let client = network.clients.remove(i);
+            let name = client.name.clone();
+
+            info!("{name} lost connection: {reason}");
+            if let Err(err) = entities.delete(client.player) {
+                error!("{err}");
+            }
+            client.disconnect(&reason);
+
+            network.broadcast_chat(&format!("{name} left the game"), None);
+        }
+    }
+}
+
+// Hurts the players standing in the void, in lava or in a wall, the clients only show the damage
+// they are sent
+// Also answers the respawn requests, the dead players come back at the spawn point
+pub struct UpdateClientsHealth;
+
+impl<'a> System<'a> for UpdateClientsHealth {
+    type SystemData = (
+        Read<'a, Arc<ChunkManager>>,
+        Write<'a, ServerNetwork>,
+        WriteStorage<'a, PlayerState>,
+        WriteStorage<'a, Interpolator<PlayerPhysicsState>>,
+        ReadStorage<'a, GameMode>,
+        WriteStorage<'a, Health>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            chunk_manager,
+            mut network,
+            mut player_state,
+            mut player_physics_state,
+            game_mode,
+            mut health,
+        ) = data;
+
+        for client in network.clients.iter_mut() {
+            let (player_state, player_physics_state, game_mode, health) = match (
+                player_state.get_mut(client.player),
+                player_physics_state.get_mut(client.player),
+                game_mode.get(client.player),
+                health.get_mut(client.player),
+            ) {
+                (Some(state), Some(physics_state), Some(game_mode), Some(health)) => {
+                    (state, physics_state, game_mode, health)
+                }
+                _ => continue,
+            };
+
+            // A living player is answered too, so that a client that believes it is dead does
+            // not wait forever
+            if mem::take(&mut client.wants_respawn) {
+                if health.is_dead() {
+                    health.revive();
+                    player_physics_state.reset_to(PlayerPhysicsState::new_at_position(
+                        player_state.spawn_point,
+                    ));
+                    player_state.is_flying = false;
+                    info!("{} respawned", client.name);
+                }
+
+                client.connection.send(ClientboundPacket::Respawn {
+                    position: player_physics_state.get_latest_state().position,
+                });
+            }
+
+            if health.is_dead() || !game_mode.takes_damage() {
+                continue;
+            }
+
+            let player = player_physics_state.get_latest_state();
+            let eyes_height = *player_state.camera_height.get_latest_state();
+
+            for (amount, source) in environment_damage(player, eyes_height, &chunk_manager) {
+                let previous_health = health.health;
+                health.damage(amount, source);
+
+                if health.health < previous_health {
+                    client.connection.send(ClientboundPacket::PlayerDamage {
+                        amount: previous_health - health.health,
+                        source,
+                    });
+                }
+            }
+
+            if let Some(cause) = health.death_cause {
+                info!("{}: {}", client.name, cause.death_message());
+            }
+        }
+    }
+}
+
+// Sends the columns around each player, and tells the clients to forget the distant ones
+pub struct SendChunkColumns {
+    view_distance: i32,
+    // Column offsets within the view distance, the nearest first
+    offsets: Vec<(i32, i32)>,
+}
+
+impl SendChunkColumns {
+    pub fn new(view_distance: i32) -> Self {
+        let mut offsets = Vec::new();
+
+        for x in -view_distance..=view_distance {
+            for z in -view_distance..=view_distance {
+                offsets.push((x, z));
+            }
+        }
+        offsets.sort_by_key(|&(x, z)| x * x + z * z);
+
+        Self {
+            view_distance,
+            offsets,
+        }
+    }
+}
+
+impl<'a> System<'a> for SendChunkColumns {
+    type SystemData = (
+        Read<'a, Arc<ChunkManager>>,
+        Write<'a, ServerNetwork>,
+        ReadStorage<'a, Interpolator<PlayerPhysicsState>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (chunk_manager, mut network, player_physics_state) = data;
+
+        for client in network.clients.iter_mut() {
+            let position = match player_physics_state.get(client.player) {
+                Some(state) => state.get_latest_state().position,
+                None => continue,
+            };
+            let (chunk_x, _, chunk_z, _, _, _) = ChunkManager::get_chunk_coords(
+                position.x as i32,
+                position.y as i32,
+                position.z as i32,
+            );
+
+            // Forget the distant columns, and the ones the server unloaded
+            let view_distance = self.view_distance;
+            client.sent_columns.retain(|&(x, z)| {
+                let is_kept = (x - chunk_x).abs() <= view_distance
+                    && (z - chunk_z).abs() <= view_distance
+                    && chunk_manager.get_column(x, z).is_some();
+
+                if !is_kept {
+                    client
+                        .connection
+                        .send(ClientboundPacket::UnloadChunkColumn { x, z });
+                }
+
+                is_kept
+            });
+
+            let mut columns_sent = 0;
+
+            for &(dx, dz) in self.offsets.iter() {
+                if columns_sent >= COLUMNS_SENT_PER_TICK {
+                    break;
+                }
+
+                let (x, z) = (chunk_x + dx, chunk_z + dz);
+
+                if client.sent_columns.contains(&(x, z)) || !chunk_manager.is_column_final(x, z) {
+                    continue;
+                }
+
+                if let Some(column) = chunk_manager.get_column(x, z) {
+                    client.connection.send(ClientboundPacket::ChunkColumn {
+                        x,
+                        z,
+                        data: compress_column(&column),
+                    });
+                    client.sent_columns.insert((x, z));
+                    columns_sent += 1;
+                }
+            }
+        }
+    }
+}
+
+// Tells the clients about the blocks changed with `ChunkManager::put_block` during the tick
+// Runs before `ClearChangedBlocks`
+pub struct BroadcastBlockChanges;
+
+impl<'a> System<'a> for BroadcastBlockChanges {
+    type SystemData = (Read<'a, Arc<ChunkManager>>, Read<'a, ServerNetwork>);
+
+    fn run(&mut self, (chunk_manager, network): Self::SystemData) {
+        for (x, y, z) in chunk_manager.changed_blocks() {
+            let block = match chunk_manager.get_block(x, y, z) {
+                Some(block) => block,
+                None => continue,
+            };
+            let (chunk_x, _, chunk_z, _, _, _) = ChunkManager::get_chunk_coords(x, y, z);
+
+            for client in network.clients.iter() {
+                if client.sent_columns.contains(&(chunk_x, chunk_z)) {
+                    client
+                        .connection
+                        .send(ClientboundPacket::BlockChange { x, y, z, block });
+                }
+            }
+        }
+    }
+}
+
+// Corrects the time of the clients, which drifts from the one of the server
+pub struct BroadcastTime {
+    last_sent: Instant,
+}
+
+impl Default for BroadcastTime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BroadcastTime {
+    pub fn new() -> Self {
+        Self {
+            last_sent: Instant::now(),
+        }
+    }
+}
+
+impl<'a> System<'a> for BroadcastTime {
+    type SystemData = (Read<'a, WorldTime>, Read<'a, ServerNetwork>);
+
+    fn run(&mut self, (world_time, network): Self::SystemData) {
+        if self.last_sent.elapsed() < TIME_UPDATE_INTERVAL {
+            return;
+        }
+        self.last_sent = Instant::now();
+
+        for client in network.clients.iter() {
+            client.connection.send(ClientboundPacket::TimeUpdate {
+                ticks: world_time.ticks(),
+            });
+        }
+    }
+}
+
+// Sends the weather to the clients when it changes
+pub struct BroadcastWeather {
+    last_kind: WeatherKind,
+}
+
+impl Default for BroadcastWeather {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BroadcastWeather {
+    pub fn new() -> Self {
+        Self {
+            last_kind: WeatherKind::Clear,
+        }
+    }
+}
+
+impl<'a> System<'a> for BroadcastWeather {
+    type SystemData = (Read<'a, Weather>, Read<'a, ServerNetwork>);
+
+    fn run(&mut self, (weather, network): Self::SystemData) {
+        if weather.kind() == self.last_kind {
+            return;
+        }
+        self.last_kind = weather.kind();
+
+        for client in network.clients.iter() {
+            client.connection.send(ClientboundPacket::Weather {
+                kind: weather.kind(),
+            });
+        }
+    }
+}
+
+// Spawns, moves and despawns the mobs and the other players on the clients
+pub struct BroadcastEntities {
+    // Entities further than this from a player are not sent to its client, in blocks
+    view_distance: f32,
+}
+
+impl BroadcastEntities {
+    pub fn new(view_distance: i32) -> Self {
+        Self {
+            view_distance: (view_distance * 16) as f32,
+        }
+    }
+}
+
+impl<'a> System<'a> for BroadcastEntities {
+    type SystemData = (
+        Entities<'a>,
+        Write<'a, ServerNetwork>,
+        ReadStorage<'a, Mob>,
+        ReadStorage<'a, Interpolator<MobPhysicsState>>,
+        ReadStorage<'a, PlayerState>,
+        ReadStorage<'a, Interpolator<PlayerPhysicsState>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut network, mobs, mob_physics_state, player_state, player_physics_state) =
+            data;
+
+        // Every entity that the clients can see
+        let mut world_entities: HashMap<Entity, (EntityKind, Vec3, f32)> = HashMap::new();
+
+        for (entity, mob, mob_physics_state) in (&entities, &mobs, &mob_physics_state).join() {
+            let state = mob_physics_state.get_latest_state();
+            world_entities.insert(
+                entity,
+                (EntityKind::Mob(mob.kind), state.position, state.yaw),
+            );
+        }
+
+        for client in network.clients.iter() {
+            if let (Some(state), Some(physics_state)) = (
+                player_state.get(client.player),
+                player_physics_state.get(client.player),
+            ) {
+                let kind = EntityKind::Player {
+                    name: client.name.clone(),
+                };
+                let position = physics_state.get_latest_state().position;
+
+                world_entities.insert(client.player, (kind, position, state.rotation.y));
+            }
+        }
+
+        for client in network.clients.iter_mut() {
+            let origin = match world_entities.get(&client.player) {
+                Some(&(_, position, _)) => position,
+                None => continue,
+            };
+            let view_distance = self.view_distance;
+            let is_visible = |entity: &Entity, position: &Vec3| {
+                *entity != client.player
+                    && vec2(position.x - origin.x, position.z - origin.z).norm() <= view_distance
+            };
+
+            // Despawn first, the ids of the deleted entities can be reused
+            let connection: &ClientConnection = &client.connection;
+            client.visible_entities.retain(|entity, _| {
+                let is_kept = world_entities
+                    .get(entity)
+                    .is_some_and(|(_, position, _)| is_visible(entity, position));
+
+                if !is_kept {
+                    connection.send(ClientboundPacket::DespawnEntity {
+                        entity_id: entity.id(),
+                    });
+                }
+
+                is_kept
+            });
+
+            for (entity, (kind, position, yaw)) in world_entities.iter() {
+                if !is_visible(entity, position) {
+                    continue;
+                }
+
+                match client.visible_entities.get_mut(entity) {
+                    Some((last_position, last_yaw)) => {
+                        let has_moved = (position - *last_position).norm() > ENTITY_MOVE_THRESHOLD
+                            || (yaw - *last_yaw).abs() > ENTITY_MOVE_THRESHOLD;
+
+                        if has_moved {
+                            client.connection.send(ClientboundPacket::MoveEntity {
+                                entity_id: entity.id(),
+                                position: *position,
+                                yaw: *yaw,
+                            });
+                            *last_position = *position;
+                            *last_yaw = *yaw;
+                        }
+                    }
+                    None => {
+                        client.connection.send(ClientboundPacket::SpawnEntity {
+                            entity_id: entity.id(),
+                            kind: kind.clone(),
+                            position: *position,
+                            yaw: *yaw,
+                        });
+                        client.visible_entities.insert(*entity, (*position, *yaw));
+                    }
+                }
+            }
+        }
+    }
+}
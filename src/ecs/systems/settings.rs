@@ -0,0 +1,252 @@
+use crate::console::Console;
+use crate::input::InputCache;
+use crate::settings::{Settings, SettingsEntry, SettingsMenu};
+use crate::text::{TextRenderer, GRAY, RED, TRANSLUCENT_BLACK, WHITE, YELLOW};
+use crate::types::Shaders;
+use glfw::{Action, Key, WindowEvent};
+use specs::{Read, System, Write};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+// How often the settings file is checked for changes
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn announce_restart(settings: &Settings, new: &Settings, console: &mut Console) {
+    let names = settings.restart_required(new);
+
+    if !names.is_empty() {
+        console.push_message(
+            &format!("Restart the game to apply the {}", names.join(", ")),
+            YELLOW,
+        );
+    }
+}
+
+// Reloads the settings file when it changes and handles the keys of the settings menu, runs
+// before the console so that the keys pressed in the menu are not typed in it
+pub struct UpdateSettings {
+    path: PathBuf,
+    // The settings as they are in the file, the resource keeps the values of the startup for the
+    // ones that need a restart
+    in_file: Settings,
+    last_modified: Option<SystemTime>,
+    last_checked: Instant,
+}
+
+impl UpdateSettings {
+    pub fn new(path: PathBuf, in_file: Settings) -> Self {
+        Self {
+            last_modified: modified_time(&path),
+            path,
+            in_file,
+            last_checked: Instant::now(),
+        }
+    }
+
+    fn reload(&mut self, settings: &mut Settings, console: &mut Console) {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                warn!("Could not read {}: {err}", self.path.display());
+                return;
+            }
+        };
+
+        // The values removed from the file are back to their defaults
+        let (new, errors) = Settings::parse(&contents, &Settings::default());
+        for err in &errors {
+            warn!("{}: {err}", self.path.display());
+            console.push_message(&format!("{}: {err}", self.path.display()), RED);
+        }
+
+        settings.apply(&new);
+        announce_restart(settings, &new, console);
+        self.in_file = new;
+
+        info!("Reloaded {}", self.path.display());
+        console.push_message(&format!("Reloaded {}", self.path.display()), WHITE);
+    }
+
+    fn save(&mut self, draft: &Settings, settings: &Settings, console: &mut Console) {
+        if *draft == self.in_file {
+            return;
+        }
+
+        match draft.save(&self.path) {
+            Ok(()) => {
+                self.in_file = draft.clone();
+                // The file written here does not need to be read again
+                self.last_modified = modified_time(&self.path);
+
+                console.push_message(&format!("Saved {}", self.path.display()), WHITE);
+                announce_restart(settings, draft, console);
+            }
+            Err(err) => {
+                error!("Could not save {}: {err}", self.path.display());
+                console.push_message(
+                    &format!("Could not save {}: {err}", self.path.display()),
+                    RED,
+                );
+            }
+        }
+    }
+}
+
+impl<'a> System<'a> for UpdateSettings {
+    type SystemData = (
+        Write<'a, InputCache>,
+        Write<'a, SettingsMenu>,
+        Write<'a, Settings>,
+        Write<'a, Console>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut input_cache, mut menu, mut settings, mut console) = data;
+
+        if menu.is_open {
+            let mut is_closing = false;
+
+            for event in input_cache.events.iter() {
+                match event {
+                    WindowEvent::Key(Key::Escape | Key::O, _, Action::Press, _) => {
+                        is_closing = true
+                    }
+                    WindowEvent::Key(key, _, Action::Press | Action::Repeat, _) => match key {
+                        Key::Up => menu.select_previous(),
+                        Key::Down => menu.select_next(),
+                        Key::Left => menu.adjust(-1),
+                        Key::Right => menu.adjust(1),
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+
+            // The values that can change while playing are previewed at once
+            settings.apply(&menu.draft);
+
+            if is_closing {
+                menu.close();
+                self.save(&menu.draft, &settings, &mut console);
+            }
+
+            // The other systems must not react to the keys of the menu
+            input_cache.events.clear();
+            input_cache.key_states.clear();
+            input_cache.mouse_button_states.clear();
+            return;
+        }
+
+        let is_opening = !console.is_open
+            && input_cache
+                .events
+                .iter()
+                .any(|event| matches!(event, WindowEvent::Key(Key::O, _, Action::Press, _)));
+        if is_opening {
+            // Starts from the file, with the changes made while playing (e.g. the clouds with F6)
+            let mut draft = self.in_file.clone();
+            draft.apply(&settings);
+            menu.open(draft);
+            return;
+        }
+
+        if self.last_checked.elapsed() < RELOAD_CHECK_INTERVAL {
+            return;
+        }
+        self.last_checked = Instant::now();
+
+        let modified = modified_time(&self.path);
+        if modified.is_some() && modified != self.last_modified {
+            self.last_modified = modified;
+            self.reload(&mut settings, &mut console);
+        }
+    }
+}
+
+pub struct RenderSettingsMenu {
+    text_renderer: TextRenderer,
+}
+
+impl Default for RenderSettingsMenu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RenderSettingsMenu {
+    pub fn new() -> Self {
+        Self {
+            text_renderer: TextRenderer::new(),
+        }
+    }
+}
+
+impl<'a> System<'a> for RenderSettingsMenu {
+    type SystemData = (
+        Read<'a, SettingsMenu>,
+        Read<'a, Settings>,
+        Write<'a, Shaders>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (menu, settings, mut shaders) = data;
+
+        if !menu.is_open {
+            return;
+        }
+
+        let scale = settings.gui_scale;
+        let line_height = TextRenderer::line_height(scale);
+
+        let rows = SettingsEntry::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let mut text = format!("{}: {}", entry.label(), entry.value(&menu.draft));
+                if entry.needs_restart() && entry.value(&menu.draft) != entry.value(&settings) {
+                    text.push_str(" (restart)");
+                }
+
+                let color = if i == menu.selected { YELLOW } else { WHITE };
+                (color, text)
+            })
+            .collect::<Vec<_>>();
+        let hint = "Up/Down: select, Left/Right: change, Esc: save";
+
+        let width = rows
+            .iter()
+            .map(|(_, text)| TextRenderer::text_width(text, scale))
+            .fold(TextRenderer::text_width(hint, scale), f32::max);
+        let left = (settings.window_width as f32 - width) / 2.0;
+        let mut y = (settings.window_height as f32 - (rows.len() + 4) as f32 * line_height) / 2.0;
+
+        self.text_renderer.queue_rect(
+            0.0,
+            0.0,
+            settings.window_width as f32,
+            settings.window_height as f32,
+            TRANSLUCENT_BLACK,
+        );
+        self.text_renderer
+            .queue_text(left, y, scale, WHITE, "Settings");
+        y += line_height * 2.0;
+
+        for (color, text) in rows.iter() {
+            self.text_renderer.queue_text(left, y, scale, *color, text);
+            y += line_height;
+        }
+
+        y += line_height;
+        self.text_renderer.queue_text(left, y, scale, GRAY, hint);
+
+        let text_shader = shaders.get_mut("text_shader").unwrap();
+        self.text_renderer.flush(text_shader, &settings);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::chunk_manager::ChunkManager;
+use crate::constants::NEAR_PLANE;
+use crate::frustum::Frustum;
+use crate::player::PlayerState;
+use crate::settings::Settings;
+use crate::shadows::{compute_cascades, ShadowQuality, Shadows, SHADOW_MAP_TEXTURE_UNIT};
+use crate::types::Shaders;
+use crate::weather::Weather;
+use crate::world_time::WorldTime;
+use specs::{Join, Read, ReadStorage, System, Write};
+use std::sync::Arc;
+
+// Darkness of the blocks in the shadow of the sun, the moon casts fainter shadows
+const SUN_SHADOW_STRENGTH: f32 = 0.45;
+const MOON_SHADOW_STRENGTH: f32 = 0.2;
+
+// One layer of depth per cascade, compared with the depth of the fragments when sampled
+fn create_shadow_map_texture(size: i32, layers: i32) -> u32 {
+    let mut texture = 0;
+    gl_call!(gl::CreateTextures(gl::TEXTURE_2D_ARRAY, 1, &mut texture));
+    gl_call!(gl::TextureStorage3D(
+        texture,
+        1,
+        gl::DEPTH_COMPONENT24,
+        size,
+        size,
+        layers
+    ));
+
+    gl_call!(gl::TextureParameteri(
+        texture,
+        gl::TEXTURE_MIN_FILTER,
+        gl::LINEAR as i32
+    ));
+    gl_call!(gl::TextureParameteri(
+        texture,
+        gl::TEXTURE_MAG_FILTER,
+        gl::LINEAR as i32
+    ));
+    gl_call!(gl::TextureParameteri(
+        texture,
+        gl::TEXTURE_COMPARE_MODE,
+        gl::COMPARE_REF_TO_TEXTURE as i32
+    ));
+    gl_call!(gl::TextureParameteri(
+        texture,
+        gl::TEXTURE_COMPARE_FUNC,
+        gl::LEQUAL as i32
+    ));
+
+    // Nothing is in the shadows outside of the map
+    gl_call!(gl::TextureParameteri(
+        texture,
+        gl::TEXTURE_WRAP_S,
+        gl::CLAMP_TO_BORDER as i32
+    ));
+    gl_call!(gl::TextureParameteri(
+        texture,
+        gl::TEXTURE_WRAP_T,
+        gl::CLAMP_TO_BORDER as i32
+    ));
+    let border_color = [1.0f32, 1.0, 1.0, 1.0];
+    gl_call!(gl::TextureParameterfv(
+        texture,
+        gl::TEXTURE_BORDER_COLOR,
+        border_color.as_ptr()
+    ));
+
+    texture
+}
+
+// Draws the depth of the terrain seen from the sun or the moon in every cascade, before the
+// terrain is drawn with the shadows
+pub struct RenderShadows {
+    quality: ShadowQuality,
+    framebuffer: u32,
+    texture: u32,
+}
+
+impl RenderShadows {
+    // The quality is only read at startup since it decides the size of the shadow map
+    pub fn new(quality: ShadowQuality) -> Self {
+        // Without shadows the map is a single texel, so that the sampler of the voxel shader
+        // always has a texture
+        let texture =
+            create_shadow_map_texture(quality.map_size(), quality.cascade_count().max(1) as i32);
+        gl_call!(gl::BindTextureUnit(SHADOW_MAP_TEXTURE_UNIT, texture));
+
+        let mut framebuffer = 0;
+        gl_call!(gl::CreateFramebuffers(1, &mut framebuffer));
+        gl_call!(gl::NamedFramebufferDrawBuffer(framebuffer, gl::NONE));
+        gl_call!(gl::NamedFramebufferReadBuffer(framebuffer, gl::NONE));
+
+        Self {
+            quality,
+            framebuffer,
+            texture,
+        }
+    }
+}
+
+impl<'a> System<'a> for RenderShadows {
+    type SystemData = (
+        ReadStorage<'a, PlayerState>,
+        Read<'a, Arc<ChunkManager>>,
+        Read<'a, WorldTime>,
+        Read<'a, Weather>,
+        Read<'a, Settings>,
+        Write<'a, Shadows>,
+        Write<'a, Shaders>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (player_state, chunk_manager, world_time, weather, settings, mut shadows, mut shaders) =
+            data;
+
+        // The moon lights the world once the sun is below the horizon, and the shadows fade out
+        // when the light is close to the horizon since they would be endless
+        let sun_direction = world_time.sun_direction();
+        let (light_direction, strength) = if sun_direction.y >= 0.0 {
+            (sun_direction, SUN_SHADOW_STRENGTH)
+        } else {
+            (-sun_direction, MOON_SHADOW_STRENGTH)
+        };
+        let strength =
+            strength * (light_direction.y * 5.0).clamp(0.0, 1.0) * (1.0 - weather.precipitation());
+
+        shadows.cascades.clear();
+        shadows.light_direction = light_direction;
+        shadows.strength = strength;
+        shadows.pcf_radius = self.quality.pcf_radius();
+
+        if self.quality == ShadowQuality::Off || strength == 0.0 {
+            return;
+        }
+
+        let mut viewport = [0; 4];
+        gl_call!(gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr()));
+
+        gl_call!(gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer));
+        gl_call!(gl::Viewport(
+            0,
+            0,
+            self.quality.map_size(),
+            self.quality.map_size()
+        ));
+        // Both sides of the faces cast shadows, and the depth is pushed back a little to avoid
+        // the faces shadowing themselves
+        gl_call!(gl::Disable(gl::CULL_FACE));
+        gl_call!(gl::Enable(gl::POLYGON_OFFSET_FILL));
+        gl_call!(gl::PolygonOffset(2.0, 4.0));
+
+        let shadow_shader = shaders.get_mut("shadow_shader").unwrap();
+        shadow_shader.use_program();
+        shadow_shader.set_uniform1i("array_texture", 0);
+
+        for player_state in (&player_state).join() {
+            shadows.cascades = compute_cascades(
+                self.quality,
+                &player_state.view_matrix,
+                &player_state.projection_matrix,
+                &light_direction,
+                NEAR_PLANE,
+                (settings.render_distance * 16) as f32,
+            );
+
+            for (layer, cascade) in shadows.cascades.iter().enumerate() {
+                gl_call!(gl::NamedFramebufferTextureLayer(
+                    self.framebuffer,
+                    gl::DEPTH_ATTACHMENT,
+                    self.texture,
+                    0,
+                    layer as i32
+                ));
+                gl_call!(gl::Clear(gl::DEPTH_BUFFER_BIT));
+
+                unsafe {
+                    shadow_shader
+                        .set_uniform_matrix4fv("light_matrix", cascade.light_matrix.as_ptr());
+                }
+                chunk_manager.render_loaded_chunks(
+                    shadow_shader,
+                    &Frustum::from_matrix(&cascade.light_matrix),
+                );
+            }
+        }
+
+        gl_call!(gl::Disable(gl::POLYGON_OFFSET_FILL));
+        gl_call!(gl::Enable(gl::CULL_FACE));
+        gl_call!(gl::BindFramebuffer(gl::FRAMEBUFFER, 0));
+        gl_call!(gl::Viewport(
+            viewport[0],
+            viewport[1],
+            viewport[2],
+            viewport[3]
+        ));
+    }
+}
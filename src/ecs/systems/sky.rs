@@ -0,0 +1,170 @@
+use crate::constants::SUNSET_COLOR;
+use crate::player::PlayerState;
+use crate::types::Shaders;
+use crate::weather::Weather;
+use crate::world_time::WorldTime;
+use nalgebra_glm::{Mat4, Vec3};
+use specs::{Join, Read, ReadStorage, System, Write};
+use std::os::raw::c_void;
+
+const SUN_COLOR: [f32; 3] = [1.0, 0.95, 0.7];
+const MOON_COLOR: [f32; 3] = [0.85, 0.88, 0.95];
+// Half the side of the quads, at 100 blocks from the camera
+const SUN_SIZE: f32 = 15.0;
+const MOON_SIZE: f32 = 10.0;
+
+// Quad of 2 triangles from -1 to 1, used for the whole screen and for the sun and the moon
+pub(super) fn create_screen_quad_vao() -> u32 {
+    let vertices: [f32; 12] = [
+        -1.0, -1.0, 1.0, -1.0, 1.0, 1.0, 1.0, 1.0, -1.0, 1.0, -1.0, -1.0,
+    ];
+
+    // Setup VAO
+    let mut vao = 0;
+    gl_call!(gl::CreateVertexArrays(1, &mut vao));
+
+    // Position
+    gl_call!(gl::EnableVertexArrayAttrib(vao, 0));
+    gl_call!(gl::VertexArrayAttribFormat(
+        vao,
+        0,
+        2,
+        gl::FLOAT,
+        gl::FALSE,
+        0
+    ));
+    gl_call!(gl::VertexArrayAttribBinding(vao, 0, 0));
+
+    // Setup VBO
+    let mut vbo = 0;
+    gl_call!(gl::CreateBuffers(1, &mut vbo));
+
+    gl_call!(gl::VertexArrayVertexBuffer(
+        vao,
+        0,
+        vbo,
+        0,
+        (2 * std::mem::size_of::<f32>()) as i32
+    ));
+    gl_call!(gl::NamedBufferData(
+        vbo,
+        (vertices.len() * std::mem::size_of::<f32>()) as isize,
+        vertices.as_ptr() as *const c_void,
+        gl::STATIC_DRAW
+    ));
+
+    vao
+}
+
+// Clears the screen and draws the sky behind everything else: a gradient from the horizon to the
+// top, and the sun and the moon going around the world with the time of day
+pub struct RenderSky {
+    vao: u32,
+}
+
+impl Default for RenderSky {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RenderSky {
+    pub fn new() -> Self {
+        Self {
+            vao: create_screen_quad_vao(),
+        }
+    }
+}
+
+impl<'a> System<'a> for RenderSky {
+    type SystemData = (
+        ReadStorage<'a, PlayerState>,
+        Read<'a, WorldTime>,
+        Read<'a, Weather>,
+        Write<'a, Shaders>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (player_state, world_time, weather, mut shaders) = data;
+
+        let sky_color = weather.sky_color(world_time.sky_color());
+        gl_call!(gl::ClearColor(sky_color.x, sky_color.y, sky_color.z, 1.0));
+        gl_call!(gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT));
+
+        let sun_direction = world_time.sun_direction();
+
+        gl_call!(gl::Disable(gl::DEPTH_TEST));
+        gl_call!(gl::Disable(gl::CULL_FACE));
+        gl_call!(gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA));
+        gl_call!(gl::BindVertexArray(self.vao));
+
+        for player_state in (&player_state).join() {
+            // The sky is infinitely far away, so it does not move with the camera
+            let mut view_matrix = player_state.view_matrix;
+            view_matrix[(0, 3)] = 0.0;
+            view_matrix[(1, 3)] = 0.0;
+            view_matrix[(2, 3)] = 0.0;
+            let inverse_view_projection = (player_state.projection_matrix * view_matrix)
+                .try_inverse()
+                .unwrap_or_else(Mat4::identity);
+
+            let sky_shader = shaders.get_mut("sky_shader").unwrap();
+            sky_shader.use_program();
+            unsafe {
+                sky_shader.set_uniform_matrix4fv(
+                    "inverse_view_projection",
+                    inverse_view_projection.as_ptr(),
+                );
+            }
+            sky_shader.set_uniform3f("horizon_color", sky_color.as_slice());
+            sky_shader.set_uniform3f(
+                "zenith_color",
+                weather.sky_color(world_time.zenith_color()).as_slice(),
+            );
+            sky_shader.set_uniform3f(
+                "sunset_color",
+                &[SUNSET_COLOR.0, SUNSET_COLOR.1, SUNSET_COLOR.2],
+            );
+            sky_shader.set_uniform3f("sun_direction", sun_direction.as_slice());
+            sky_shader.set_uniform1f(
+                "twilight",
+                world_time.twilight() * (1.0 - weather.precipitation()),
+            );
+            gl_call!(gl::DrawArrays(gl::TRIANGLES, 0, 6));
+
+            let celestial_shader = shaders.get_mut("celestial_shader").unwrap();
+            celestial_shader.use_program();
+            unsafe {
+                celestial_shader.set_uniform_matrix4fv("view", view_matrix.as_ptr());
+                celestial_shader
+                    .set_uniform_matrix4fv("projection", player_state.projection_matrix.as_ptr());
+            }
+
+            // The clouds of the rain hide the sun and the moon
+            let clouds_visibility = 1.0 - weather.precipitation();
+            let bodies = [
+                (sun_direction, SUN_SIZE, SUN_COLOR, 0.5),
+                (-sun_direction, MOON_SIZE, MOON_COLOR, 0.2),
+            ];
+            for (direction, size, color, halo) in bodies {
+                celestial_shader.set_uniform3f("direction", direction.as_slice());
+                celestial_shader.set_uniform1f("size", size);
+                celestial_shader.set_uniform3f("color", &color);
+                celestial_shader.set_uniform1f("halo", halo);
+                celestial_shader.set_uniform1f(
+                    "visibility",
+                    horizon_visibility(&direction) * clouds_visibility,
+                );
+                gl_call!(gl::DrawArrays(gl::TRIANGLES, 0, 6));
+            }
+        }
+
+        gl_call!(gl::Enable(gl::CULL_FACE));
+        gl_call!(gl::Enable(gl::DEPTH_TEST));
+    }
+}
+
+// The sun and the moon fade out below the horizon, there may be no terrain to hide them
+fn horizon_visibility(direction: &Vec3) -> f32 {
+    ((direction.y + 0.1) * 10.0).clamp(0.0, 1.0)
+}
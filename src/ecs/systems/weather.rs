@@ -0,0 +1,50 @@
+use crate::timer::Timer;
+use crate::weather::Weather;
+use specs::{Read, System, Write};
+use std::time::{Duration, Instant};
+
+// Moves the weather forward with the timer, so that it does not change while the game is paused
+pub struct UpdateWeather {
+    // The clients of a server follow the weather of the server
+    is_cycle_enabled: bool,
+    last_time: Option<Instant>,
+}
+
+impl Default for UpdateWeather {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UpdateWeather {
+    pub fn new() -> Self {
+        Self {
+            is_cycle_enabled: true,
+            last_time: None,
+        }
+    }
+
+    pub fn remote() -> Self {
+        Self {
+            is_cycle_enabled: false,
+            last_time: None,
+        }
+    }
+}
+
+impl<'a> System<'a> for UpdateWeather {
+    type SystemData = (Read<'a, Timer>, Write<'a, Weather>);
+
+    fn run(&mut self, (global_timer, mut weather): Self::SystemData) {
+        let time = global_timer.time();
+        let elapsed = self.last_time.map_or(Duration::ZERO, |last_time| {
+            time.saturating_duration_since(last_time)
+        });
+        self.last_time = Some(time);
+
+        if self.is_cycle_enabled {
+            weather.advance_cycle(elapsed);
+        }
+        weather.update(elapsed);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::aabb::AABB;
+use nalgebra_glm::{vec4, Mat4, Vec4};
+
+// The 6 planes of the view frustum, extracted from a view-projection matrix
+// NOTE: https://www.gamedevs.org/uploads/fast-extraction-viewing-frustum-planes-from-world-view-projection-matrix.pdf
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    pub fn from_matrix(view_projection: &Mat4) -> Self {
+        let m = view_projection;
+        let row = |i: usize| vec4(m[(i, 0)], m[(i, 1)], m[(i, 2)], m[(i, 3)]);
+
+        Self {
+            planes: [
+                row(3) + row(0), // Left
+                row(3) - row(0), // Right
+                row(3) + row(1), // Bottom
+                row(3) - row(1), // Top
+                row(3) + row(2), // Near
+                row(3) - row(2), // Far
+            ],
+        }
+    }
+
+    pub fn intersects_aabb(&self, aabb: &AABB) -> bool {
+        for plane in self.planes.iter() {
+            // The corner of the box that is the furthest along the normal of the plane
+            let x = if plane.x >= 0.0 {
+                aabb.maxs.x
+            } else {
+                aabb.mins.x
+            };
+            let y = if plane.y >= 0.0 {
+                aabb.maxs.y
+            } else {
+                aabb.mins.y
+            };
+            let z = if plane.z >= 0.0 {
+                aabb.maxs.z
+            } else {
+                aabb.mins.z
+            };
+
+            if plane.x * x + plane.y * y + plane.z * z + plane.w < 0.0 {
+                return false;
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,82 @@
+use crate::player::PlayerState;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Survival,
+    Creative,
+    // Flies through the terrain and cannot interact with the world
+    Spectator,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 3] = [GameMode::Survival, GameMode::Creative, GameMode::Spectator];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Survival => "survival",
+            GameMode::Creative => "creative",
+            GameMode::Spectator => "spectator",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        GameMode::ALL
+            .iter()
+            .find(|mode| mode.name() == name)
+            .copied()
+    }
+
+    // The game mode that comes after this one when cycling with the keyboard
+    pub fn next(&self) -> GameMode {
+        match self {
+            GameMode::Survival => GameMode::Creative,
+            GameMode::Creative => GameMode::Spectator,
+            GameMode::Spectator => GameMode::Survival,
+        }
+    }
+
+    pub fn can_fly(&self) -> bool {
+        matches!(self, GameMode::Creative | GameMode::Spectator)
+    }
+
+    pub fn breaks_blocks_instantly(&self) -> bool {
+        matches!(self, GameMode::Creative)
+    }
+
+    pub fn can_interact_with_blocks(&self) -> bool {
+        !matches!(self, GameMode::Spectator)
+    }
+
+    // Whether placing blocks consumes the items of the inventory, and breaking them gives items
+    pub fn consumes_items(&self) -> bool {
+        matches!(self, GameMode::Survival)
+    }
+
+    pub fn takes_damage(&self) -> bool {
+        matches!(self, GameMode::Survival)
+    }
+
+    // Whether the player gets hungry and needs to eat
+    pub fn has_hunger(&self) -> bool {
+        matches!(self, GameMode::Survival)
+    }
+
+    // Spectators go through the terrain
+    pub fn has_collisions(&self) -> bool {
+        !matches!(self, GameMode::Spectator)
+    }
+
+    // Changes the game mode and updates the player accordingly
+    pub fn switch_to(&mut self, game_mode: GameMode, player_state: &mut PlayerState) {
+        *self = game_mode;
+
+        if game_mode == GameMode::Spectator {
+            player_state.is_flying = true;
+        } else if !game_mode.can_fly() {
+            player_state.is_flying = false;
+        }
+
+        player_state.is_sneaking = false;
+    }
+}
@@ -1,6 +1,7 @@
-use crate::constants::{CROSSHAIR_SIZE, WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::constants::CROSSHAIR_SIZE;
+use crate::settings::Settings;
 use crate::shader::ShaderProgram;
-use crate::shapes::{block_outline, quad};
+use crate::shapes::{block_outline, centered_unit_cube, quad};
 use image::ColorType;
 use nalgebra::Matrix4;
 use nalgebra_glm::vec3;
@@ -106,11 +107,11 @@ pub fn create_crosshair_vao() -> u32 {
     vao
 }
 
-pub fn draw_crosshair(vao: u32, shader: &mut ShaderProgram) {
+pub fn draw_crosshair(vao: u32, shader: &mut ShaderProgram, settings: &Settings) {
     let model_matrix = {
         let translate_matrix = Matrix4::new_translation(&vec3(
-            WINDOW_WIDTH as f32 / 2.0,
-            WINDOW_HEIGHT as f32 / 2.0,
+            settings.window_width as f32 / 2.0,
+            settings.window_height as f32 / 2.0,
             0.0,
         ));
         let scale_matrix =
@@ -120,9 +121,9 @@ pub fn draw_crosshair(vao: u32, shader: &mut ShaderProgram) {
     };
     let projection_matrix = nalgebra_glm::ortho(
         0.0,
-        WINDOW_WIDTH as f32,
+        settings.window_width as f32,
         0.0,
-        WINDOW_HEIGHT as f32,
+        settings.window_height as f32,
         -5.0,
         5.0,
     );
@@ -177,6 +178,58 @@ pub fn create_block_outline_vao() -> u32 {
     vao
 }
 
+// Cube drawn over the block being broken, only the position and UV coords are used
+pub fn create_block_crack_vao() -> u32 {
+    // Setup VAO
+    let mut vao = 0;
+    gl_call!(gl::CreateVertexArrays(1, &mut vao));
+
+    // Position
+    gl_call!(gl::EnableVertexArrayAttrib(vao, 0));
+    gl_call!(gl::VertexArrayAttribFormat(
+        vao,
+        0,
+        3,
+        gl::FLOAT,
+        gl::FALSE,
+        0
+    ));
+    gl_call!(gl::VertexArrayAttribBinding(vao, 0, 0));
+
+    // Texture coords
+    gl_call!(gl::EnableVertexArrayAttrib(vao, 1));
+    gl_call!(gl::VertexArrayAttribFormat(
+        vao,
+        1,
+        2,
+        gl::FLOAT,
+        gl::FALSE,
+        3 * std::mem::size_of::<f32>() as u32
+    ));
+    gl_call!(gl::VertexArrayAttribBinding(vao, 1, 0));
+
+    // Setup VBO
+    let vbo_data = centered_unit_cube(0.0, 0.0, 0.0, (0, 0, 0, 0, 0, 0));
+    let mut vbo = 0;
+    gl_call!(gl::CreateBuffers(1, &mut vbo));
+
+    gl_call!(gl::VertexArrayVertexBuffer(
+        vao,
+        0,
+        vbo,
+        0,
+        (9 * std::mem::size_of::<f32>()) as i32
+    ));
+    gl_call!(gl::NamedBufferData(
+        vbo,
+        (vbo_data.len() * std::mem::size_of::<f32>()) as isize,
+        vbo_data.as_ptr() as *const c_void,
+        gl::STATIC_DRAW
+    ));
+
+    vao
+}
+
 pub fn create_widgets_texture() -> u32 {
     let widgets_image = match image::open("textures/gui/widgets.png") {
         Ok(img) => img,
@@ -326,3 +379,155 @@ pub fn create_hotbar_selection_vao() -> u32 {
 
     vao
 }
+
+// Size in pixels of the hearts and the other status icons in the icons texture
+pub const STATUS_ICON_SIZE: f32 = 9.0;
+// Number of icons of a status bar, each icon shows 2 points
+pub const STATUS_BAR_ICONS: u32 = 10;
+
+// Creates a VAO for the status icon whose top left corner is at (x, y) in the icons texture
+pub fn create_status_icon_vao(x: f32, y: f32) -> u32 {
+    // Setup VAO
+    let mut vao = 0;
+    gl_call!(gl::CreateVertexArrays(1, &mut vao));
+
+    // Position
+    gl_call!(gl::EnableVertexArrayAttrib(vao, 0));
+    gl_call!(gl::VertexArrayAttribFormat(
+        vao,
+        0,
+        3,
+        gl::FLOAT,
+        gl::FALSE,
+        0
+    ));
+    gl_call!(gl::VertexArrayAttribBinding(vao, 0, 0));
+
+    // Texture coords
+    gl_call!(gl::EnableVertexArrayAttrib(vao, 1));
+    gl_call!(gl::VertexArrayAttribFormat(
+        vao,
+        1,
+        2,
+        gl::FLOAT,
+        gl::FALSE,
+        3 * std::mem::size_of::<f32>() as u32
+    ));
+    gl_call!(gl::VertexArrayAttribBinding(vao, 1, 0));
+
+    // Setup VBO
+    let mut vbo = 0;
+    gl_call!(gl::CreateBuffers(1, &mut vbo));
+
+    gl_call!(gl::VertexArrayVertexBuffer(
+        vao,
+        0,
+        vbo,
+        0,
+        (5 * std::mem::size_of::<f32>()) as i32
+    ));
+    gl_call!(gl::NamedBufferData(
+        vbo,
+        (30 * std::mem::size_of::<f32>()) as isize,
+        quad((
+            x / 256.0,
+            y / 256.0,
+            (x + STATUS_ICON_SIZE) / 256.0,
+            (y + STATUS_ICON_SIZE) / 256.0
+        ))
+        .as_ptr() as *const c_void,
+        gl::STATIC_DRAW
+    ));
+
+    vao
+}
+
+// A row of icons above the hotbar showing a value out of 20, like the hearts
+// Every icon is drawn on top of its container and can be full or half full
+pub struct StatusBar {
+    container_vao: u32,
+    full_vao: u32,
+    half_vao: u32,
+}
+
+impl StatusBar {
+    // The positions of the icons in the icons texture
+    pub fn new(container: (f32, f32), full: (f32, f32), half: (f32, f32)) -> Self {
+        Self {
+            container_vao: create_status_icon_vao(container.0, container.1),
+            full_vao: create_status_icon_vao(full.0, full.1),
+            half_vao: create_status_icon_vao(half.0, half.1),
+        }
+    }
+
+    // The bar starts at the left of the hotbar, or at its right and goes leftward when `right_aligned`
+    pub fn draw(
+        &self,
+        shader: &mut ShaderProgram,
+        settings: &Settings,
+        value: u32,
+        right_aligned: bool,
+    ) {
+        let icon_spacing = (STATUS_ICON_SIZE - 1.0) * settings.gui_scale;
+        let hotbar_half_width = 91.0 * settings.gui_scale;
+        let half_icon = STATUS_ICON_SIZE / 2.0 * settings.gui_scale;
+        let y = (22.0 + 2.0) * settings.gui_scale + half_icon;
+
+        let projection_matrix = nalgebra_glm::ortho(
+            0.0,
+            settings.window_width as f32,
+            0.0,
+            settings.window_height as f32,
+            -5.0,
+            5.0,
+        );
+
+        shader.use_program();
+        unsafe {
+            shader.set_uniform_matrix4fv("projection", projection_matrix.as_ptr());
+        }
+        shader.set_uniform1i("tex", 1);
+
+        for i in 0..STATUS_BAR_ICONS {
+            let x = if right_aligned {
+                settings.window_width as f32 / 2.0 + hotbar_half_width
+                    - half_icon
+                    - i as f32 * icon_spacing
+            } else {
+                settings.window_width as f32 / 2.0 - hotbar_half_width
+                    + half_icon
+                    + i as f32 * icon_spacing
+            };
+
+            let model_matrix = {
+                let translate_matrix = Matrix4::new_translation(&vec3(x, y, 0.0));
+                let scale_matrix = Matrix4::new_nonuniform_scaling(&vec3(
+                    STATUS_ICON_SIZE * settings.gui_scale,
+                    STATUS_ICON_SIZE * settings.gui_scale,
+                    1.0,
+                ));
+
+                translate_matrix * scale_matrix
+            };
+            unsafe {
+                shader.set_uniform_matrix4fv("model", model_matrix.as_ptr());
+            }
+
+            gl_call!(gl::BindVertexArray(self.container_vao));
+            gl_call!(gl::DrawArrays(gl::TRIANGLES, 0, 6));
+
+            let icon_vao = if value >= 2 * (i + 1) {
+                Some(self.full_vao)
+            } else if value == 2 * i + 1 {
+                Some(self.half_vao)
+            } else {
+                None
+            };
+
+            if let Some(vao) = icon_vao {
+                gl_call!(gl::BindVertexArray(vao));
+                gl_call!(gl::DrawArrays(gl::TRIANGLES, 0, 6));
+            }
+        }
+    }
+}
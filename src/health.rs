@@ -0,0 +1,152 @@
+use crate::chunk::BlockID;
+use crate::chunk_manager::ChunkManager;
+use crate::constants::GRAVITY;
+use crate::mob::MobKind;
+use crate::player::PlayerPhysicsState;
+use std::time::{Duration, Instant};
+
+// In half hearts, 10 hearts
+pub const MAX_HEALTH: u32 = 20;
+// Players can fall from this height without being hurt
+pub const SAFE_FALL_DISTANCE: f32 = 3.0;
+// After taking damage, the player is invulnerable for a moment
+pub const INVULNERABILITY_DURATION: Duration = Duration::from_millis(500);
+// Below this height, the player takes damage until death
+pub const VOID_LEVEL: f32 = -64.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DamageSource {
+    Fall,
+    Lava,
+    Suffocation,
+    Starvation,
+    Void,
+    Mob(MobKind),
+    Command,
+}
+
+impl DamageSource {
+    pub fn death_message(&self) -> &'static str {
+        match self {
+            DamageSource::Fall => "Player hit the ground too hard",
+            DamageSource::Lava => "Player tried to swim in lava",
+            DamageSource::Suffocation => "Player suffocated in a wall",
+            DamageSource::Starvation => "Player starved to death",
+            DamageSource::Void => "Player fell out of the world",
+            DamageSource::Mob(MobKind::Zombie) => "Player was slain by a zombie",
+            DamageSource::Mob(_) => "Player was slain by a mob",
+            DamageSource::Command => "Player died",
+        }
+    }
+
+    // Commands ignore the invulnerability delay
+    fn bypasses_invulnerability(&self) -> bool {
+        *self == DamageSource::Command
+    }
+}
+
+#[cfg_attr(not(feature = "client"), allow(dead_code))]
+pub struct Health {
+    pub health: u32,
+    // What killed the player, None while the player is alive
+    pub death_cause: Option<DamageSource>,
+    pub(crate) last_damaged: Option<Instant>,
+    pub(crate) death_announced: bool,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Health {
+    pub fn new() -> Self {
+        Self {
+            health: MAX_HEALTH,
+            death_cause: None,
+            last_damaged: None,
+            death_announced: false,
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.death_cause.is_some()
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.last_damaged
+            .map(|time| time.elapsed() < INVULNERABILITY_DURATION)
+            .unwrap_or(false)
+    }
+
+    // Returns true when the damage killed the player
+    pub fn damage(&mut self, amount: u32, source: DamageSource) -> bool {
+        if amount == 0
+            || self.is_dead()
+            || (self.is_invulnerable() && !source.bypasses_invulnerability())
+        {
+            return false;
+        }
+
+        self.health = self.health.saturating_sub(amount);
+        self.last_damaged = Some(Instant::now());
+
+        if self.health == 0 {
+            self.death_cause = Some(source);
+            return true;
+        }
+
+        false
+    }
+
+    pub fn heal(&mut self, amount: u32) {
+        if !self.is_dead() {
+            self.health = (self.health + amount).min(MAX_HEALTH);
+        }
+    }
+
+    pub fn revive(&mut self) {
+        *self = Self::new();
+    }
+}
+
+// One half heart per block fallen past the safe distance
+// The fall distance is derived from the vertical velocity at the moment of the landing
+pub fn fall_damage(vertical_velocity: f32) -> u32 {
+    if vertical_velocity >= 0.0 {
+        return 0;
+    }
+
+    let fall_distance = vertical_velocity * vertical_velocity / (2.0 * -GRAVITY);
+    (fall_distance - SAFE_FALL_DISTANCE).ceil().max(0.0) as u32
+}
+
+// Damage dealt every frame by the surroundings of the player, the same on the client playing
+// offline and on the server
+pub fn environment_damage(
+    player: &PlayerPhysicsState,
+    eyes_height: f32,
+    chunk_manager: &ChunkManager,
+) -> Vec<(u32, DamageSource)> {
+    let mut damage = Vec::new();
+    let block_at_eyes = chunk_manager.get_block(
+        player.position.x.floor() as i32,
+        (player.position.y + eyes_height).floor() as i32,
+        player.position.z.floor() as i32,
+    );
+
+    if player.position.y < VOID_LEVEL {
+        damage.push((4, DamageSource::Void));
+    }
+
+    if player.is_touching_block(chunk_manager, BlockID::Lava) {
+        damage.push((4, DamageSource::Lava));
+    }
+
+    if block_at_eyes.is_some_and(|block| block.is_solid() && block.is_opaque()) {
+        damage.push((1, DamageSource::Suffocation));
+    }
+
+    damage
+}
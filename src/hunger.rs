@@ -0,0 +1,224 @@
+use crate::health::{DamageSource, Health, MAX_HEALTH};
+use crate::inventory::food::Food;
+use std::time::{Duration, Instant};
+
+pub const MAX_FOOD_LEVEL: u32 = 20;
+// The hunger is updated at the tick rate of Minecraft so that the numbers of the wiki apply
+// NOTE: https://minecraft.fandom.com/wiki/Hunger
+pub const HUNGER_TICKRATE: f32 = 20.0;
+// The player cannot sprint at or below this food level
+pub const SPRINTING_MIN_FOOD_LEVEL: u32 = 6;
+// The health regenerates at or above this food level
+pub const REGENERATION_MIN_FOOD_LEVEL: u32 = 18;
+// Ticks between two points of health regenerated, or lost while starving
+pub const HEALTH_UPDATE_INTERVAL: u32 = 80;
+// Every time the exhaustion reaches this value, the saturation or the food level decreases
+pub const MAX_EXHAUSTION: f32 = 4.0;
+// Time spent holding the use button before the food is eaten
+pub const EATING_DURATION: Duration = Duration::from_millis(1600);
+
+// Exhaustion caused by the actions of the player
+pub const SPRINTING_EXHAUSTION: f32 = 0.1; // per meter
+pub const JUMPING_EXHAUSTION: f32 = 0.05;
+pub const SPRINT_JUMPING_EXHAUSTION: f32 = 0.2;
+pub const BLOCK_BREAKING_EXHAUSTION: f32 = 0.005;
+pub const REGENERATION_EXHAUSTION: f32 = 6.0;
+
+#[cfg_attr(not(feature = "client"), allow(dead_code))]
+pub struct EatingProgress {
+    pub food: Food,
+    // The hotbar slot of the food, eating stops when the player selects another slot
+    pub slot: usize,
+    pub time: Duration,
+    pub(crate) last_updated: Instant,
+}
+
+impl EatingProgress {
+    pub fn new(food: Food, slot: usize) -> Self {
+        Self {
+            food,
+            slot,
+            time: Duration::ZERO,
+            last_updated: Instant::now(),
+        }
+    }
+}
+
+pub struct Hunger {
+    pub food_level: u32,
+    // Consumed before the food level, cannot be higher than the food level
+    pub saturation: f32,
+    pub exhaustion: f32,
+    pub eating: Option<EatingProgress>,
+    pub(crate) health_update_timer: u32,
+}
+
+impl Default for Hunger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hunger {
+    pub fn new() -> Self {
+        Self {
+            food_level: MAX_FOOD_LEVEL,
+            saturation: 5.0,
+            exhaustion: 0.0,
+            eating: None,
+            health_update_timer: 0,
+        }
+    }
+
+    pub fn can_sprint(&self) -> bool {
+        self.food_level > SPRINTING_MIN_FOOD_LEVEL
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.food_level >= MAX_FOOD_LEVEL
+    }
+
+    pub fn add_exhaustion(&mut self, exhaustion: f32) {
+        self.exhaustion += exhaustion;
+    }
+
+    pub fn eat(&mut self, food: Food) {
+        self.food_level = (self.food_level + food.food_points()).min(MAX_FOOD_LEVEL);
+        self.saturation = (self.saturation + food.saturation()).min(self.food_level as f32);
+    }
+
+    // Advances the hunger by one tick, the result only depends on the state of the hunger and
+    // of the health, not on the frame rate
+    pub fn tick(&mut self, health: &mut Health) {
+        if self.exhaustion >= MAX_EXHAUSTION {
+            self.exhaustion -= MAX_EXHAUSTION;
+
+            if self.saturation > 0.0 {
+                self.saturation = (self.saturation - 1.0).max(0.0);
+            } else {
+                self.food_level = self.food_level.saturating_sub(1);
+            }
+        }
+
+        let is_regenerating =
+            self.food_level >= REGENERATION_MIN_FOOD_LEVEL && health.health < MAX_HEALTH;
+        let is_starving = self.food_level == 0;
+
+        if health.is_dead() || !(is_regenerating || is_starving) {
+            self.health_update_timer = 0;
+            return;
+        }
+
+        self.health_update_timer += 1;
+
+        if self.health_update_timer >= HEALTH_UPDATE_INTERVAL {
+            self.health_update_timer = 0;
+
+            if is_regenerating {
+                health.heal(1);
+                self.add_exhaustion(REGENERATION_EXHAUSTION);
+            } else {
+                health.damage(1, DamageSource::Starvation);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(hunger: &mut Hunger, health: &mut Health, ticks: u32) {
+        for _ in 0..ticks {
+            hunger.tick(health);
+        }
+    }
+
+    #[test]
+    fn exhaustion_consumes_the_saturation_before_the_food_level() {
+        let mut hunger = Hunger::new();
+        let mut health = Health::new();
+        hunger.saturation = 1.0;
+        hunger.add_exhaustion(2.0 * MAX_EXHAUSTION);
+
+        tick(&mut hunger, &mut health, 1);
+        assert_eq!(hunger.saturation, 0.0);
+        assert_eq!(hunger.food_level, MAX_FOOD_LEVEL);
+
+        tick(&mut hunger, &mut health, 1);
+        assert_eq!(hunger.food_level, MAX_FOOD_LEVEL - 1);
+        assert_eq!(hunger.exhaustion, 0.0);
+    }
+
+    #[test]
+    fn health_regenerates_every_interval_when_fed() {
+        let mut hunger = Hunger::new();
+        let mut health = Health::new();
+        health.health = MAX_HEALTH - 2;
+
+        tick(&mut hunger, &mut health, HEALTH_UPDATE_INTERVAL - 1);
+        assert_eq!(health.health, MAX_HEALTH - 2);
+
+        tick(&mut hunger, &mut health, 1);
+        assert_eq!(health.health, MAX_HEALTH - 1);
+        assert_eq!(hunger.exhaustion, REGENERATION_EXHAUSTION);
+
+        tick(&mut hunger, &mut health, HEALTH_UPDATE_INTERVAL);
+        assert_eq!(health.health, MAX_HEALTH);
+    }
+
+    #[test]
+    fn health_does_not_regenerate_below_the_food_level_threshold() {
+        let mut hunger = Hunger::new();
+        let mut health = Health::new();
+        hunger.food_level = REGENERATION_MIN_FOOD_LEVEL - 1;
+        health.health = MAX_HEALTH - 1;
+
+        tick(&mut hunger, &mut health, 10 * HEALTH_UPDATE_INTERVAL);
+        assert_eq!(health.health, MAX_HEALTH - 1);
+    }
+
+    #[test]
+    fn starving_player_loses_health_every_interval() {
+        let mut hunger = Hunger::new();
+        let mut health = Health::new();
+        hunger.food_level = 0;
+        hunger.saturation = 0.0;
+
+        tick(&mut hunger, &mut health, HEALTH_UPDATE_INTERVAL - 1);
+        assert_eq!(health.health, MAX_HEALTH);
+
+        tick(&mut hunger, &mut health, 1);
+        assert_eq!(health.health, MAX_HEALTH - 1);
+        assert_eq!(health.death_cause, None);
+    }
+
+    #[test]
+    fn interval_restarts_when_the_health_stops_updating() {
+        let mut hunger = Hunger::new();
+        let mut health = Health::new();
+        health.health = MAX_HEALTH - 1;
+        tick(&mut hunger, &mut health, HEALTH_UPDATE_INTERVAL / 2);
+
+        // Healed by something else, the regeneration stops and starts over
+        health.health = MAX_HEALTH;
+        tick(&mut hunger, &mut health, 1);
+        health.health = MAX_HEALTH - 1;
+
+        tick(&mut hunger, &mut health, HEALTH_UPDATE_INTERVAL - 1);
+        assert_eq!(health.health, MAX_HEALTH - 1);
+        tick(&mut hunger, &mut health, 1);
+        assert_eq!(health.health, MAX_HEALTH);
+    }
+
+    #[test]
+    fn dead_player_neither_regenerates_nor_starves() {
+        let mut hunger = Hunger::new();
+        let mut health = Health::new();
+        health.damage(MAX_HEALTH, DamageSource::Starvation);
+
+        tick(&mut hunger, &mut health, 10 * HEALTH_UPDATE_INTERVAL);
+        assert_eq!(health.health, 0);
+        assert_eq!(hunger.health_update_timer, 0);
+    }
+}
@@ -0,0 +1,57 @@
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Food {
+    Apple,
+    Bread,
+    Carrot,
+    CookedPorkchop,
+    CookedBeef,
+    Cookie,
+}
+
+impl Food {
+    pub const ALL: [Food; 6] = [
+        Food::Apple,
+        Food::Bread,
+        Food::Carrot,
+        Food::CookedPorkchop,
+        Food::CookedBeef,
+        Food::Cookie,
+    ];
+
+    // Name used by the commands and the texture of the food, follows the Minecraft naming
+    pub fn name(&self) -> &'static str {
+        match self {
+            Food::Apple => "apple",
+            Food::Bread => "bread",
+            Food::Carrot => "carrot",
+            Food::CookedPorkchop => "cooked_porkchop",
+            Food::CookedBeef => "cooked_beef",
+            Food::Cookie => "cookie",
+        }
+    }
+
+    // Hunger points restored when eaten
+    pub fn food_points(&self) -> u32 {
+        match self {
+            Food::Apple => 4,
+            Food::Bread => 5,
+            Food::Carrot => 3,
+            Food::CookedPorkchop => 8,
+            Food::CookedBeef => 8,
+            Food::Cookie => 2,
+        }
+    }
+
+    // Saturation restored when eaten
+    // NOTE: https://minecraft.fandom.com/wiki/Food#Foods
+    pub fn saturation(&self) -> f32 {
+        match self {
+            Food::Apple => 2.4,
+            Food::Bread => 6.0,
+            Food::Carrot => 3.6,
+            Food::CookedPorkchop => 12.8,
+            Food::CookedBeef => 12.8,
+            Food::Cookie => 0.4,
+        }
+    }
+}
@@ -1,45 +1,172 @@
 use crate::chunk::BlockID;
-use crate::constants::{GUI_SCALING, WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::inventory::food::Food;
+use crate::inventory::tool::Tool;
+use crate::inventory::MAX_STACK_SIZE;
+#[cfg(feature = "client")]
+use crate::settings::Settings;
+#[cfg(feature = "client")]
 use crate::shader::ShaderProgram;
-use crate::shapes::centered_unit_cube;
-use crate::types::TexturePack;
+use crate::shapes::{centered_sprite, centered_unit_cube};
+use crate::types::{ItemTextures, TexturePack};
+#[cfg(feature = "client")]
 use nalgebra::Matrix4;
-use nalgebra_glm::{pi, vec3, Mat4};
+#[cfg(feature = "client")]
+use nalgebra_glm::{pi, vec3};
+#[cfg(feature = "client")]
 use std::ptr::null;
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Item {
+    Block(BlockID),
+    Tool(Tool),
+    Food(Food),
+}
+
+impl Item {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Item::Block(block) => block.name(),
+            Item::Tool(tool) => tool.name(),
+            Item::Food(food) => food.name(),
+        }
+    }
+
+    // Accepts names with or without the "minecraft:" namespace
+    pub fn from_name(name: &str) -> Option<Item> {
+        if let Some(block) = BlockID::from_name(name) {
+            return Some(Item::Block(block));
+        }
+
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+
+        Item::all().find(|item| item.name() == name)
+    }
+
+    // Every item that can be given to the player
+    pub fn all() -> impl Iterator<Item = Item> {
+        BlockID::ALL
+            .iter()
+            .filter(|block| !block.is_air())
+            .map(|block| Item::Block(*block))
+            .chain(Tool::ALL.iter().map(|tool| Item::Tool(*tool)))
+            .chain(Food::ALL.iter().map(|food| Item::Food(*food)))
+    }
+
+    pub fn max_stack_size(&self) -> u32 {
+        match self {
+            Item::Block(_) | Item::Food(_) => MAX_STACK_SIZE,
+            Item::Tool(_) => 1,
+        }
+    }
+
+    pub fn as_block(&self) -> Option<BlockID> {
+        match self {
+            Item::Block(block) => Some(*block),
+            _ => None,
+        }
+    }
+
+    pub fn as_tool(&self) -> Option<Tool> {
+        match self {
+            Item::Tool(tool) => Some(*tool),
+            _ => None,
+        }
+    }
+
+    pub fn as_food(&self) -> Option<Food> {
+        match self {
+            Item::Food(food) => Some(*food),
+            _ => None,
+        }
+    }
+
+    // Blocks are drawn as cubes, the other items as flat sprites
+    pub fn is_sprite(&self) -> bool {
+        !matches!(self, Item::Block(_))
+    }
+
+    // Vertices of the item with the same layout as `centered_unit_cube`
+    pub fn vertices(&self, texture_pack: &TexturePack, item_textures: &ItemTextures) -> Vec<f32> {
+        match self {
+            Item::Block(block) => centered_unit_cube(
+                -0.5,
+                -0.5,
+                -0.5,
+                texture_pack.get(block).unwrap().get_uv_of_every_face(),
+            ),
+            _ => centered_sprite(-0.5, -0.5, -0.5, *item_textures.get(self).unwrap()),
+        }
+    }
+}
+
+impl From<BlockID> for Item {
+    fn from(block: BlockID) -> Self {
+        Item::Block(block)
+    }
+}
+
+impl From<Tool> for Item {
+    fn from(tool: Tool) -> Self {
+        Item::Tool(tool)
+    }
+}
+
+impl From<Food> for Item {
+    fn from(food: Food) -> Self {
+        Item::Food(food)
+    }
+}
+
 #[derive(Copy, Clone)]
+#[cfg_attr(not(feature = "client"), allow(dead_code))]
 pub struct ItemStack {
-    pub item: BlockID,
+    pub item: Item,
     pub amount: u32,
     pub(crate) item_render: ItemRender,
 }
 
 impl ItemStack {
-    pub fn new(block: BlockID, amount: u32) -> Self {
+    pub fn new(item: impl Into<Item>, amount: u32) -> Self {
         Self {
-            item: block,
+            item: item.into(),
             amount,
             item_render: ItemRender::new(),
         }
     }
 
-    pub fn update_if_dirty(&mut self, texture_pack: &TexturePack) {
+    #[cfg(feature = "client")]
+    pub fn update_if_dirty(&mut self, texture_pack: &TexturePack, item_textures: &ItemTextures) {
         self.item_render
-            .update_vbo_if_dirty(self.item, &texture_pack);
+            .update_vbo_if_dirty(self.item, texture_pack, item_textures);
     }
 }
 
 #[derive(Copy, Clone)]
+#[cfg_attr(not(feature = "client"), allow(dead_code))]
 pub struct ItemRender {
     vao: u32,
     vbo: u32,
+    vertex_count: i32,
+    is_sprite: bool,
     // This is dirty when the VBO needs to be updated (at creation and when changing the block)
     pub(crate) dirty: bool,
-    projection_matrix: Mat4,
 }
 
 impl ItemRender {
+    // The GPU resources are only created when the item is first drawn, so that items can be
+    // created without an OpenGL context
     pub fn new() -> Self {
+        Self {
+            vao: 0,
+            vbo: 0,
+            vertex_count: 0,
+            is_sprite: false,
+            dirty: true,
+        }
+    }
+
+    #[cfg(feature = "client")]
+    fn create_vao_vbo() -> (u32, u32) {
         let mut vao = 0;
         gl_call!(gl::CreateVertexArrays(1, &mut vao));
 
@@ -96,37 +223,36 @@ impl ItemRender {
             (9 * std::mem::size_of::<f32>()) as i32
         ));
 
-        let projection_matrix = nalgebra_glm::ortho(
-            0.0,
-            WINDOW_WIDTH as f32,
-            0.0,
-            WINDOW_HEIGHT as f32,
-            -1000.0,
-            1000.0,
-        );
-
-        Self {
-            vao,
-            vbo,
-            dirty: true,
-            projection_matrix,
-        }
+        (vao, vbo)
     }
 
-    pub fn update_vbo_if_dirty(&mut self, item: BlockID, texture_pack: &TexturePack) {
+    #[cfg(feature = "client")]
+    pub fn update_vbo_if_dirty(
+        &mut self,
+        item: Item,
+        texture_pack: &TexturePack,
+        item_textures: &ItemTextures,
+    ) {
         if self.dirty {
-            self.update_vbo(item, &texture_pack);
+            self.update_vbo(item, texture_pack, item_textures);
             self.dirty = false;
         }
     }
 
-    pub fn update_vbo(&mut self, item: BlockID, texture_pack: &TexturePack) {
-        let vbo_data = centered_unit_cube(
-            -0.5,
-            -0.5,
-            -0.5,
-            texture_pack.get(&item).unwrap().get_uv_of_every_face(),
-        );
+    #[cfg(feature = "client")]
+    pub fn update_vbo(
+        &mut self,
+        item: Item,
+        texture_pack: &TexturePack,
+        item_textures: &ItemTextures,
+    ) {
+        if self.vao == 0 {
+            (self.vao, self.vbo) = Self::create_vao_vbo();
+        }
+
+        let vbo_data = item.vertices(texture_pack, item_textures);
+        self.vertex_count = (vbo_data.len() / 9) as i32;
+        self.is_sprite = item.is_sprite();
 
         gl_call!(gl::NamedBufferSubData(
             self.vbo,
@@ -136,29 +262,47 @@ impl ItemRender {
         ));
     }
 
-    pub fn draw(&self, x: f32, y: f32, shader: &mut ShaderProgram) {
+    #[cfg(feature = "client")]
+    pub fn draw(&self, x: f32, y: f32, shader: &mut ShaderProgram, settings: &Settings) {
         let model_matrix = {
             let translate_matrix = Matrix4::new_translation(&vec3(x, y, 1.0));
-            let rotate_matrix = {
-                let rotate_y = Matrix4::from_euler_angles(0.0, pi::<f32>() / 4.0, 0.0); // 45 degrees
-                let rotate_x = Matrix4::from_euler_angles(pi::<f32>() / 6.0, 0.0, 0.0); // 30 degrees
 
-                rotate_y * rotate_x
-            };
-            let scale_matrix =
-                Matrix4::new_nonuniform_scaling(&(GUI_SCALING * vec3(10.0, 10.0, 10.0)));
+            if self.is_sprite {
+                // Sprites face the screen
+                let scale_matrix =
+                    Matrix4::new_nonuniform_scaling(&(settings.gui_scale * vec3(16.0, 16.0, 16.0)));
 
-            translate_matrix * rotate_matrix * scale_matrix
+                translate_matrix * scale_matrix
+            } else {
+                let rotate_matrix = {
+                    let rotate_y = Matrix4::from_euler_angles(0.0, pi::<f32>() / 4.0, 0.0); // 45 degrees
+                    let rotate_x = Matrix4::from_euler_angles(pi::<f32>() / 6.0, 0.0, 0.0); // 30 degrees
+
+                    rotate_y * rotate_x
+                };
+                let scale_matrix =
+                    Matrix4::new_nonuniform_scaling(&(settings.gui_scale * vec3(10.0, 10.0, 10.0)));
+
+                translate_matrix * rotate_matrix * scale_matrix
+            }
         };
+        let projection_matrix = nalgebra_glm::ortho(
+            0.0,
+            settings.window_width as f32,
+            0.0,
+            settings.window_height as f32,
+            -1000.0,
+            1000.0,
+        );
 
         shader.use_program();
         unsafe {
             shader.set_uniform_matrix4fv("model", model_matrix.as_ptr());
-            shader.set_uniform_matrix4fv("projection", self.projection_matrix.as_ptr());
+            shader.set_uniform_matrix4fv("projection", projection_matrix.as_ptr());
         }
         shader.set_uniform1i("tex", 0);
 
         gl_call!(gl::BindVertexArray(self.vao));
-        gl_call!(gl::DrawArrays(gl::TRIANGLES, 0, 36));
+        gl_call!(gl::DrawArrays(gl::TRIANGLES, 0, self.vertex_count));
     }
 }
@@ -1,15 +1,25 @@
+pub mod food;
 pub mod item;
+pub mod tool;
 
 use crate::chunk::BlockID;
-use crate::constants::{GUI_SCALING, WINDOW_HEIGHT, WINDOW_WIDTH};
-use crate::inventory::item::ItemStack;
+use crate::inventory::food::Food;
+use crate::inventory::item::{Item, ItemStack};
+use crate::inventory::tool::Tool;
+#[cfg(feature = "client")]
+use crate::settings::Settings;
+#[cfg(feature = "client")]
 use crate::shader::ShaderProgram;
-use crate::types::TexturePack;
+#[cfg(feature = "client")]
+use crate::types::{ItemTextures, TexturePack};
+#[cfg(feature = "client")]
 use nalgebra::Matrix4;
+#[cfg(feature = "client")]
 use nalgebra_glm::vec3;
 
 const INVENTORY_SIZE: usize = 36;
 const HOTBAR_SIZE: usize = 9;
+pub const MAX_STACK_SIZE: u32 = 64;
 
 pub struct Inventory {
     pub slots: [Option<ItemStack>; INVENTORY_SIZE],
@@ -43,10 +53,71 @@ impl Inventory {
         }
     }
 
-    pub fn get_selected_item(&self) -> Option<BlockID> {
+    pub fn get_selected_item(&self) -> Option<Item> {
         self.slots[self.selected_hotbar_slot].map(|item_stack| item_stack.item)
     }
 
+    pub fn get_selected_block(&self) -> Option<BlockID> {
+        self.get_selected_item().and_then(|item| item.as_block())
+    }
+
+    pub fn get_selected_tool(&self) -> Option<Tool> {
+        self.get_selected_item().and_then(|item| item.as_tool())
+    }
+
+    pub fn get_selected_food(&self) -> Option<Food> {
+        self.get_selected_item().and_then(|item| item.as_food())
+    }
+
+    // Adds `amount` items, filling the existing stacks of the item first, then the empty slots
+    // Returns the amount of items that did not fit in the inventory
+    pub fn add_item(&mut self, item: Item, mut amount: u32) -> u32 {
+        let max_stack_size = item.max_stack_size();
+
+        for slot in self.slots.iter_mut().flatten() {
+            if amount == 0 {
+                break;
+            }
+
+            if slot.item == item && slot.amount < max_stack_size {
+                let added = amount.min(max_stack_size - slot.amount);
+                slot.amount += added;
+                amount -= added;
+            }
+        }
+
+        for slot in self.slots.iter_mut() {
+            if amount == 0 {
+                break;
+            }
+
+            if slot.is_none() {
+                let added = amount.min(max_stack_size);
+                *slot = Some(ItemStack::new(item, added));
+                amount -= added;
+            }
+        }
+
+        amount
+    }
+
+    // Removes one item from the selected stack
+    // Returns true when the stack is now empty, meaning that the main hand changed
+    pub fn consume_selected_item(&mut self) -> bool {
+        let slot = &mut self.slots[self.selected_hotbar_slot];
+
+        if let Some(item_stack) = slot {
+            item_stack.amount = item_stack.amount.saturating_sub(1);
+
+            if item_stack.amount == 0 {
+                *slot = None;
+                return true;
+            }
+        }
+
+        false
+    }
+
     pub fn select_item(&mut self, index: usize) {
         self.selected_hotbar_slot = index;
     }
@@ -67,21 +138,26 @@ impl Inventory {
         }
     }
 
-    pub fn update_dirty_items(&mut self, texture_pack: &TexturePack) {
+    #[cfg(feature = "client")]
+    pub fn update_dirty_items(&mut self, texture_pack: &TexturePack, item_textures: &ItemTextures) {
         for slot in self.slots.iter_mut() {
             if let Some(item_stack) = slot {
-                item_stack.update_if_dirty(&texture_pack);
+                item_stack.update_if_dirty(texture_pack, item_textures);
             }
         }
     }
 
-    pub fn draw_hotbar(&self, vao: u32, shader: &mut ShaderProgram) {
+    #[cfg(feature = "client")]
+    pub fn draw_hotbar(&self, vao: u32, shader: &mut ShaderProgram, settings: &Settings) {
         let model_matrix = {
-            let translate_matrix =
-                Matrix4::new_translation(&vec3(WINDOW_WIDTH as f32 / 2.0, 11.0 * GUI_SCALING, 0.0));
+            let translate_matrix = Matrix4::new_translation(&vec3(
+                settings.window_width as f32 / 2.0,
+                11.0 * settings.gui_scale,
+                0.0,
+            ));
             let scale_matrix = Matrix4::new_nonuniform_scaling(&vec3(
-                182.0 * GUI_SCALING,
-                22.0 * GUI_SCALING,
+                182.0 * settings.gui_scale,
+                22.0 * settings.gui_scale,
                 1.0,
             ));
 
@@ -89,9 +165,9 @@ impl Inventory {
         };
         let projection_matrix = nalgebra_glm::ortho(
             0.0,
-            WINDOW_WIDTH as f32,
+            settings.window_width as f32,
             0.0,
-            WINDOW_HEIGHT as f32,
+            settings.window_height as f32,
             -5.0,
             5.0,
         );
@@ -107,25 +183,38 @@ impl Inventory {
         gl_call!(gl::DrawArrays(gl::TRIANGLES, 0, 6));
     }
 
-    pub fn draw_hotbar_selection_box(&self, vao: u32, shader: &mut ShaderProgram) {
+    #[cfg(feature = "client")]
+    pub fn draw_hotbar_selection_box(
+        &self,
+        vao: u32,
+        shader: &mut ShaderProgram,
+        settings: &Settings,
+    ) {
         let inter_slot_spacing = 20.0;
-        let hotbar_left_margin = WINDOW_WIDTH as f32 / 2.0 - 4.0 * inter_slot_spacing * GUI_SCALING;
+        let hotbar_left_margin =
+            settings.window_width as f32 / 2.0 - 4.0 * inter_slot_spacing * settings.gui_scale;
         let selection_box_x_pos = hotbar_left_margin
-            + inter_slot_spacing * self.selected_hotbar_slot as f32 * GUI_SCALING;
+            + inter_slot_spacing * self.selected_hotbar_slot as f32 * settings.gui_scale;
 
         let model_matrix = {
-            let translate_matrix =
-                Matrix4::new_translation(&vec3(selection_box_x_pos, 11.0 * GUI_SCALING, 0.0));
-            let scale_matrix =
-                Matrix4::new_nonuniform_scaling(&vec3(24.0 * GUI_SCALING, 24.0 * GUI_SCALING, 1.0));
+            let translate_matrix = Matrix4::new_translation(&vec3(
+                selection_box_x_pos,
+                11.0 * settings.gui_scale,
+                0.0,
+            ));
+            let scale_matrix = Matrix4::new_nonuniform_scaling(&vec3(
+                24.0 * settings.gui_scale,
+                24.0 * settings.gui_scale,
+                1.0,
+            ));
 
             translate_matrix * scale_matrix
         };
         let projection_matrix = nalgebra_glm::ortho(
             0.0,
-            WINDOW_WIDTH as f32,
+            settings.window_width as f32,
             0.0,
-            WINDOW_HEIGHT as f32,
+            settings.window_height as f32,
             -5.0,
             5.0,
         );
@@ -141,21 +230,21 @@ impl Inventory {
         gl_call!(gl::DrawArrays(gl::TRIANGLES, 0, 6));
     }
 
-    pub fn draw_hotbar_items(&self, shader: &mut ShaderProgram) {
+    #[cfg(feature = "client")]
+    pub fn draw_hotbar_items(&self, shader: &mut ShaderProgram, settings: &Settings) {
         let inter_slot_spacing = 20.0;
-        let hotbar_left_margin = WINDOW_WIDTH as f32 / 2.0 - 4.0 * inter_slot_spacing * GUI_SCALING;
+        let hotbar_left_margin =
+            settings.window_width as f32 / 2.0 - 4.0 * inter_slot_spacing * settings.gui_scale;
 
-        let mut x = 0;
         let y = 11;
 
-        for slot in self.slots.iter() {
+        for (x, slot) in self.slots.iter().take(HOTBAR_SIZE).enumerate() {
             if let Some(slot) = slot {
-                let item_x_pos = hotbar_left_margin + (x as f32) * inter_slot_spacing * GUI_SCALING;
+                let item_x_pos =
+                    hotbar_left_margin + (x as f32) * inter_slot_spacing * settings.gui_scale;
                 slot.item_render
-                    .draw(item_x_pos, y as f32 * GUI_SCALING, shader);
+                    .draw(item_x_pos, y as f32 * settings.gui_scale, shader, settings);
             }
-
-            x += 1;
         }
     }
 }
@@ -0,0 +1,121 @@
+use crate::chunk::BlockID;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ToolKind {
+    Pickaxe,
+    Axe,
+    Shovel,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum ToolMaterial {
+    Wooden,
+    Stone,
+    Iron,
+    Diamond,
+}
+
+impl ToolMaterial {
+    // Multiplier of the mining speed when the tool is used on a block it is made for
+    pub fn efficiency(&self) -> f32 {
+        match self {
+            ToolMaterial::Wooden => 2.0,
+            ToolMaterial::Stone => 4.0,
+            ToolMaterial::Iron => 6.0,
+            ToolMaterial::Diamond => 8.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Tool {
+    pub kind: ToolKind,
+    pub material: ToolMaterial,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 12] = {
+        use ToolKind::*;
+        use ToolMaterial::*;
+
+        [
+            Tool::new(Pickaxe, Wooden),
+            Tool::new(Pickaxe, Stone),
+            Tool::new(Pickaxe, Iron),
+            Tool::new(Pickaxe, Diamond),
+            Tool::new(Axe, Wooden),
+            Tool::new(Axe, Stone),
+            Tool::new(Axe, Iron),
+            Tool::new(Axe, Diamond),
+            Tool::new(Shovel, Wooden),
+            Tool::new(Shovel, Stone),
+            Tool::new(Shovel, Iron),
+            Tool::new(Shovel, Diamond),
+        ]
+    };
+
+    pub const fn new(kind: ToolKind, material: ToolMaterial) -> Self {
+        Self { kind, material }
+    }
+
+    // Name used by the commands and the texture of the tool, follows the Minecraft naming
+    pub fn name(&self) -> &'static str {
+        use ToolKind::*;
+        use ToolMaterial::*;
+
+        match (self.material, self.kind) {
+            (Wooden, Pickaxe) => "wooden_pickaxe",
+            (Stone, Pickaxe) => "stone_pickaxe",
+            (Iron, Pickaxe) => "iron_pickaxe",
+            (Diamond, Pickaxe) => "diamond_pickaxe",
+            (Wooden, Axe) => "wooden_axe",
+            (Stone, Axe) => "stone_axe",
+            (Iron, Axe) => "iron_axe",
+            (Diamond, Axe) => "diamond_axe",
+            (Wooden, Shovel) => "wooden_shovel",
+            (Stone, Shovel) => "stone_shovel",
+            (Iron, Shovel) => "iron_shovel",
+            (Diamond, Shovel) => "diamond_shovel",
+        }
+    }
+
+    // Damage dealt to mobs, in half hearts
+    pub fn attack_damage(&self) -> u32 {
+        let base = match self.kind {
+            ToolKind::Axe => 3,
+            ToolKind::Pickaxe => 2,
+            ToolKind::Shovel => 1,
+        };
+
+        base + self.material as u32
+    }
+
+    pub fn is_effective_on(&self, block: BlockID) -> bool {
+        block.preferred_tool() == Some(self.kind)
+    }
+}
+
+// Blocks that need a tool only drop an item when they are mined with a good enough tool
+pub fn can_harvest(block: BlockID, tool: Option<Tool>) -> bool {
+    match block.required_tool_material() {
+        None => true,
+        Some(required) => match tool {
+            Some(tool) => tool.is_effective_on(block) && tool.material >= required,
+            None => false,
+        },
+    }
+}
+
+// Time in seconds needed to break `block` with `tool`, None when the block is unbreakable
+// NOTE: https://minecraft.fandom.com/wiki/Breaking#Calculation
+pub fn break_time(block: BlockID, tool: Option<Tool>) -> Option<f32> {
+    let hardness = block.hardness()?;
+
+    let speed = match tool {
+        Some(tool) if tool.is_effective_on(block) => tool.material.efficiency(),
+        _ => 1.0,
+    };
+    let penalty = if can_harvest(block, tool) { 1.5 } else { 5.0 };
+
+    Some(hardness * penalty / speed)
+}
@@ -0,0 +1,582 @@
+use crate::chunk::BlockID;
+use crate::chunk_manager::{BlockChange, BlockEditBatch, ChunkManager};
+use crate::nbt::{self, NbtError, Tag};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// In the working directory like server.properties, the worlds have no save of their own
+pub const JOURNAL_PATH: &str = "journal.dat";
+// Number of edits of each player that can be undone
+pub const MAX_HISTORY: usize = 256;
+// Author of the edits made with the commands typed in the terminal of the server
+pub const SERVER_AUTHOR: &str = "Server";
+const JOURNAL_VERSION: i32 = 1;
+
+// Blocks changed at once by a player: a click, or a command like /fill
+#[derive(Clone, Debug)]
+pub struct JournalEntry {
+    // Milliseconds since the Unix epoch
+    pub time: u64,
+    pub changes: Vec<BlockChange>,
+}
+
+impl JournalEntry {
+    fn new(changes: Vec<BlockChange>) -> Self {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_millis() as u64);
+
+        Self { time, changes }
+    }
+}
+
+#[derive(Debug)]
+pub enum JournalError {
+    Nbt(NbtError),
+    // The edits were made in the world generated from another seed
+    WrongSeed { recorded: u32, current: u32 },
+}
+
+impl fmt::Display for JournalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JournalError::Nbt(err) => write!(f, "{err}"),
+            JournalError::WrongSeed { recorded, current } => write!(
+                f,
+                "The edits were made in the world of the seed {recorded}, not {current}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for JournalError {}
+
+impl From<NbtError> for JournalError {
+    fn from(err: NbtError) -> Self {
+        JournalError::Nbt(err)
+    }
+}
+
+#[derive(Default)]
+struct History {
+    undo: VecDeque<JournalEntry>,
+    redo: Vec<JournalEntry>,
+}
+
+// Current block of the edited positions of a column
+type ColumnBlocks = HashMap<(i32, i32, i32), BlockID>;
+
+// Records the blocks edited by the players, by name since the entities change at each connection
+// The edited blocks are also put back when their column is generated again, after a restart or
+// once the column has been unloaded, so the journal is the save of the edits
+#[derive(Default)]
+pub struct EditJournal {
+    // Nothing is recorded without a file, like when the world is the one of a server
+    path: Option<PathBuf>,
+    // The edits are put back only in the world generated from the same seed
+    seed: u32,
+    histories: HashMap<String, History>,
+    // Current block of every position edited, by column
+    blocks: HashMap<(i32, i32), ColumnBlocks>,
+    // Loaded columns whose edited blocks have been put back
+    restored_columns: HashSet<(i32, i32)>,
+    is_modified: bool,
+}
+
+impl EditJournal {
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    // The journal is empty when the file does not exist yet, it is refused when it was recorded in
+    // the world of another seed
+    pub fn open(path: impl AsRef<Path>, seed: u32) -> Result<Self, JournalError> {
+        let path = path.as_ref();
+        let mut journal = Self {
+            path: Some(path.to_path_buf()),
+            seed,
+            ..Self::default()
+        };
+
+        match nbt::read_file(path) {
+            Ok((_, tag)) => match read_seed(&tag) {
+                Some(recorded) if recorded != seed => {
+                    return Err(JournalError::WrongSeed {
+                        recorded,
+                        current: seed,
+                    })
+                }
+                _ => journal.read_nbt(&tag)?,
+            },
+            Err(NbtError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        Ok(journal)
+    }
+
+    // Seed of the world of the journal file, None when it cannot be read
+    pub fn recorded_seed(path: impl AsRef<Path>) -> Option<u32> {
+        nbt::read_file(path)
+            .ok()
+            .and_then(|(_, tag)| read_seed(&tag))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.path.is_some()
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.is_modified
+    }
+
+    fn set_blocks(&mut self, changes: impl Iterator<Item = ((i32, i32, i32), BlockID)>) {
+        for ((x, y, z), block) in changes {
+            let (chunk_x, _, chunk_z, _, _, _) = ChunkManager::get_chunk_coords(x, y, z);
+            self.blocks
+                .entry((chunk_x, chunk_z))
+                .or_default()
+                .insert((x, y, z), block);
+        }
+        self.is_modified = true;
+    }
+
+    // Records changes made by a player, they become the last edit it can undo
+    pub fn record(&mut self, author: &str, changes: Vec<BlockChange>) {
+        if !self.is_enabled() || changes.is_empty() {
+            return;
+        }
+
+        self.set_blocks(
+            changes
+                .iter()
+                .map(|&(position, _, block)| (position, block)),
+        );
+
+        let history = self.histories.entry(author.to_string()).or_default();
+        if history.undo.len() == MAX_HISTORY {
+            history.undo.pop_front();
+        }
+        history.undo.push_back(JournalEntry::new(changes));
+        history.redo.clear();
+    }
+
+    // Applies the batch and records what changed, returns the number of blocks changed
+    pub fn apply(
+        &mut self,
+        author: &str,
+        chunk_manager: &ChunkManager,
+        batch: BlockEditBatch,
+    ) -> usize {
+        let changes = chunk_manager.apply_batch(batch);
+        let count = changes.len();
+
+        self.record(author, changes);
+        count
+    }
+
+    // Current block of an edited position, the blocks of the columns that are not loaded or not
+    // restored yet are the ones of the journal
+    fn current_block(
+        &self,
+        chunk_manager: &ChunkManager,
+        (x, y, z): (i32, i32, i32),
+    ) -> Option<BlockID> {
+        let (chunk_x, _, chunk_z, _, _, _) = ChunkManager::get_chunk_coords(x, y, z);
+
+        if self.restored_columns.contains(&(chunk_x, chunk_z)) {
+            chunk_manager.get_block(x, y, z)
+        } else {
+            self.blocks
+                .get(&(chunk_x, chunk_z))?
+                .get(&(x, y, z))
+                .copied()
+        }
+    }
+
+    // Replaces the blocks `from` with `to`, the positions changed since by someone else are skipped
+    // Returns the number of changes made
+    fn replace_blocks(
+        &mut self,
+        chunk_manager: &ChunkManager,
+        changes: impl Iterator<Item = ((i32, i32, i32), BlockID, BlockID)>,
+    ) -> usize {
+        // A position can be changed many times by an entry
+        let mut blocks = HashMap::new();
+        let mut count = 0;
+        for (position, from, to) in changes {
+            let current = match blocks.get(&position) {
+                Some(&block) => Some(block),
+                None => self.current_block(chunk_manager, position),
+            };
+
+            if current == Some(from) {
+                blocks.insert(position, to);
+                count += 1;
+            }
+        }
+
+        let mut batch = BlockEditBatch::new();
+        for (&(x, y, z), &block) in blocks.iter() {
+            batch.put_block(x, y, z, block);
+        }
+        chunk_manager.apply_batch(batch);
+        self.set_blocks(blocks.into_iter());
+
+        count
+    }
+
+    // Puts back the blocks of the last edit of the player, returns the number of blocks changed
+    // The blocks in columns that are not loaded are put back when they are loaded
+    pub fn undo(&mut self, author: &str, chunk_manager: &ChunkManager) -> Option<usize> {
+        let entry = self.histories.get_mut(author)?.undo.pop_back()?;
+
+        let count = self.replace_blocks(
+            chunk_manager,
+            entry
+                .changes
+                .iter()
+                .rev()
+                .map(|&(position, previous, block)| (position, block, previous)),
+        );
+
+        self.histories.get_mut(author)?.redo.push(entry);
+        Some(count)
+    }
+
+    pub fn redo(&mut self, author: &str, chunk_manager: &ChunkManager) -> Option<usize> {
+        let entry = self.histories.get_mut(author)?.redo.pop()?;
+
+        let count = self.replace_blocks(chunk_manager, entry.changes.iter().copied());
+
+        self.histories.get_mut(author)?.undo.push_back(entry);
+        Some(count)
+    }
+
+    // Puts the edited blocks back in the columns that have been generated since the last call
+    // They are not new edits, so they are not sent to the players again
+    pub fn restore_columns(&mut self, chunk_manager: &ChunkManager) {
+        self.restored_columns
+            .retain(|&(x, z)| chunk_manager.get_column(x, z).is_some());
+
+        for (&(x, z), blocks) in self.blocks.iter() {
+            if self.restored_columns.contains(&(x, z)) || !chunk_manager.is_column_final(x, z) {
+                continue;
+            }
+
+            let mut batch = BlockEditBatch::new();
+            for (&(x, y, z), &block) in blocks.iter() {
+                batch.put_block(x, y, z, block);
+            }
+            chunk_manager.set_batch(batch);
+
+            self.restored_columns.insert((x, z));
+        }
+    }
+
+    pub fn save(&mut self) -> Result<(), NbtError> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        nbt::write_file(path, "Journal", &self.to_nbt())?;
+        self.is_modified = false;
+
+        Ok(())
+    }
+
+    // The blocks are indices in a palette of names, so that the file does not depend on the order
+    // of `BlockID`
+    fn to_nbt(&self) -> Tag {
+        let palette = BlockID::ALL
+            .iter()
+            .map(|block| Tag::String(block.name().to_string()))
+            .collect();
+        let index = |block: BlockID| BlockID::ALL.iter().position(|&b| b == block).unwrap() as u8;
+
+        let changes_to_nbt = |entry: &JournalEntry| {
+            let mut tags = BTreeMap::new();
+            tags.insert("Time".to_string(), Tag::Long(entry.time as i64));
+            tags.insert(
+                "Positions".to_string(),
+                Tag::IntArray(
+                    entry
+                        .changes
+                        .iter()
+                        .flat_map(|&((x, y, z), _, _)| [x, y, z])
+                        .collect(),
+                ),
+            );
+            tags.insert(
+                "Previous".to_string(),
+                Tag::ByteArray(entry.changes.iter().map(|c| index(c.1)).collect()),
+            );
+            tags.insert(
+                "Blocks".to_string(),
+                Tag::ByteArray(entry.changes.iter().map(|c| index(c.2)).collect()),
+            );
+            Tag::Compound(tags)
+        };
+
+        let mut players = BTreeMap::new();
+        for (author, history) in self.histories.iter() {
+            let mut tags = BTreeMap::new();
+            tags.insert(
+                "Undo".to_string(),
+                Tag::List(history.undo.iter().map(&changes_to_nbt).collect()),
+            );
+            tags.insert(
+                "Redo".to_string(),
+                Tag::List(history.redo.iter().map(&changes_to_nbt).collect()),
+            );
+            players.insert(author.clone(), Tag::Compound(tags));
+        }
+
+        let blocks = self.blocks.values().flat_map(|blocks| blocks.iter());
+        let mut edited_blocks = BTreeMap::new();
+        edited_blocks.insert(
+            "Positions".to_string(),
+            Tag::IntArray(
+                blocks
+                    .clone()
+                    .flat_map(|(&(x, y, z), _)| [x, y, z])
+                    .collect(),
+            ),
+        );
+        edited_blocks.insert(
+            "Blocks".to_string(),
+            Tag::ByteArray(blocks.map(|(_, &block)| index(block)).collect()),
+        );
+
+        let mut journal = BTreeMap::new();
+        journal.insert("Version".to_string(), Tag::Int(JOURNAL_VERSION));
+        journal.insert("Seed".to_string(), Tag::Long(self.seed as i64));
+        journal.insert("Palette".to_string(), Tag::List(palette));
+        journal.insert("Players".to_string(), Tag::Compound(players));
+        journal.insert("EditedBlocks".to_string(), Tag::Compound(edited_blocks));
+
+        Tag::Compound(journal)
+    }
+
+    fn read_nbt(&mut self, tag: &Tag) -> Result<(), NbtError> {
+        // Blocks that no longer exist become air
+        let palette = tag
+            .child("Palette")?
+            .as_list()
+            .ok_or_else(|| NbtError::Missing("Palette".to_string()))?
+            .iter()
+            .map(|name| {
+                name.as_str()
+                    .and_then(BlockID::from_name)
+                    .unwrap_or(BlockID::Air)
+            })
+            .collect::<Vec<_>>();
+
+        let read_blocks = |tag: &Tag, name: &str| -> Result<Vec<BlockID>, NbtError> {
+            tag.child(name)?
+                .as_bytes()
+                .ok_or_else(|| NbtError::Missing(name.to_string()))?
+                .iter()
+                .map(|&index| {
+                    palette
+                        .get(index as usize)
+                        .copied()
+                        .ok_or(NbtError::InvalidData("block outside of the palette"))
+                })
+                .collect()
+        };
+        let read_positions = |tag: &Tag, count: usize| -> Result<Vec<(i32, i32, i32)>, NbtError> {
+            let positions = tag
+                .child("Positions")?
+                .as_int_array()
+                .ok_or_else(|| NbtError::Missing("Positions".to_string()))?;
+            if positions.len() != 3 * count {
+                return Err(NbtError::InvalidData("wrong number of positions"));
+            }
+
+            Ok(positions.chunks(3).map(|p| (p[0], p[1], p[2])).collect())
+        };
+
+        let read_entry = |tag: &Tag| -> Result<JournalEntry, NbtError> {
+            let previous = read_blocks(tag, "Previous")?;
+            let blocks = read_blocks(tag, "Blocks")?;
+            if previous.len() != blocks.len() {
+                return Err(NbtError::InvalidData("wrong number of blocks"));
+            }
+            let positions = read_positions(tag, blocks.len())?;
+
+            Ok(JournalEntry {
+                time: tag.get("Time").and_then(Tag::as_i64).unwrap_or(0) as u64,
+                changes: positions
+                    .into_iter()
+                    .zip(previous)
+                    .zip(blocks)
+                    .map(|((position, previous), block)| (position, previous, block))
+                    .collect(),
+            })
+        };
+        let read_entries = |tag: &Tag, name: &str| -> Result<Vec<JournalEntry>, NbtError> {
+            tag.child(name)?
+                .as_list()
+                .ok_or_else(|| NbtError::Missing(name.to_string()))?
+                .iter()
+                .map(&read_entry)
+                .collect()
+        };
+
+        let players = tag
+            .child("Players")?
+            .as_compound()
+            .ok_or_else(|| NbtError::Missing("Players".to_string()))?;
+        for (author, history) in players.iter() {
+            self.histories.insert(
+                author.clone(),
+                History {
+                    undo: read_entries(history, "Undo")?.into(),
+                    redo: read_entries(history, "Redo")?,
+                },
+            );
+        }
+
+        let edited_blocks = tag.child("EditedBlocks")?;
+        let blocks = read_blocks(edited_blocks, "Blocks")?;
+        let positions = read_positions(edited_blocks, blocks.len())?;
+        self.set_blocks(positions.into_iter().zip(blocks));
+        self.is_modified = false;
+
+        Ok(())
+    }
+}
+
+// The journals written before the seed was recorded have none
+fn read_seed(tag: &Tag) -> Option<u32> {
+    tag.get("Seed")
+        .and_then(Tag::as_i64)
+        .map(|seed| seed as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::ChunkColumn;
+    use std::sync::Arc;
+
+    struct TempJournal(PathBuf);
+
+    impl TempJournal {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!("minecraft-{name}-{}.dat", std::process::id())))
+        }
+    }
+
+    impl Drop for TempJournal {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    // The columns around (0, 0), final so that the journal restores the column (0, 0)
+    fn final_world() -> ChunkManager {
+        let chunk_manager = ChunkManager::new();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                let column = ChunkColumn::new();
+                *column.has_foliage.write() = true;
+                chunk_manager.add_chunk_column((x, z), Arc::new(column));
+            }
+        }
+
+        chunk_manager
+    }
+
+    fn edit(
+        journal: &mut EditJournal,
+        author: &str,
+        chunk_manager: &ChunkManager,
+        block: BlockID,
+    ) -> usize {
+        let batch = [((1, 10, 1), block), ((2, 10, 1), block)]
+            .into_iter()
+            .collect();
+        let count = journal.apply(author, chunk_manager, batch);
+        // Once per frame in the game
+        journal.restore_columns(chunk_manager);
+
+        count
+    }
+
+    #[test]
+    fn journal_of_another_seed_is_refused() {
+        let path = TempJournal::new("journal-seed");
+        let mut journal = EditJournal::open(&path.0, 1).unwrap();
+        let chunk_manager = final_world();
+        edit(&mut journal, "alice", &chunk_manager, BlockID::Glass);
+        journal.save().unwrap();
+
+        assert_eq!(EditJournal::recorded_seed(&path.0), Some(1));
+        assert!(matches!(
+            EditJournal::open(&path.0, 2),
+            Err(JournalError::WrongSeed {
+                recorded: 1,
+                current: 2
+            })
+        ));
+
+        let mut journal = EditJournal::open(&path.0, 1).unwrap();
+        let chunk_manager = final_world();
+        journal.restore_columns(&chunk_manager);
+        assert_eq!(chunk_manager.get_block(2, 10, 1), Some(BlockID::Glass));
+    }
+
+    #[test]
+    fn undo_skips_the_blocks_changed_since() {
+        let path = TempJournal::new("journal-undo");
+        let mut journal = EditJournal::open(&path.0, 1).unwrap();
+        let chunk_manager = final_world();
+
+        assert_eq!(
+            edit(&mut journal, "alice", &chunk_manager, BlockID::Glass),
+            2
+        );
+        chunk_manager.put_block(1, 10, 1, BlockID::Dirt);
+
+        assert_eq!(journal.undo("alice", &chunk_manager), Some(1));
+        assert_eq!(chunk_manager.get_block(1, 10, 1), Some(BlockID::Dirt));
+        assert_eq!(chunk_manager.get_block(2, 10, 1), Some(BlockID::Air));
+
+        assert_eq!(journal.redo("alice", &chunk_manager), Some(1));
+        assert_eq!(chunk_manager.get_block(1, 10, 1), Some(BlockID::Dirt));
+        assert_eq!(chunk_manager.get_block(2, 10, 1), Some(BlockID::Glass));
+
+        // The blocks edited by another player since are kept
+        edit(&mut journal, "bob", &chunk_manager, BlockID::Stone);
+        assert_eq!(journal.undo("alice", &chunk_manager), Some(0));
+        assert_eq!(chunk_manager.get_block(2, 10, 1), Some(BlockID::Stone));
+        assert_eq!(journal.undo("bob", &chunk_manager), Some(2));
+        assert_eq!(chunk_manager.get_block(1, 10, 1), Some(BlockID::Dirt));
+        assert_eq!(chunk_manager.get_block(2, 10, 1), Some(BlockID::Glass));
+    }
+
+    #[test]
+    fn restored_blocks_are_not_new_edits() {
+        let path = TempJournal::new("journal-restore");
+        let mut journal = EditJournal::open(&path.0, 1).unwrap();
+        let chunk_manager = final_world();
+        edit(&mut journal, "alice", &chunk_manager, BlockID::Glass);
+        assert_eq!(chunk_manager.changed_blocks().len(), 2);
+
+        // The column is unloaded and generated again
+        journal.restore_columns(&ChunkManager::new());
+        let chunk_manager = final_world();
+        journal.restore_columns(&chunk_manager);
+        assert_eq!(chunk_manager.get_block(1, 10, 1), Some(BlockID::Glass));
+        assert!(chunk_manager.changed_blocks().is_empty());
+
+        assert_eq!(journal.undo("alice", &chunk_manager), Some(2));
+        assert_eq!(chunk_manager.changed_blocks().len(), 2);
+    }
+}
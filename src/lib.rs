@@ -0,0 +1,71 @@
+#[macro_use]
+extern crate lazy_static;
+
+#[macro_use]
+extern crate log;
+extern crate specs;
+
+#[macro_use]
+pub mod debugging;
+pub mod aabb;
+pub mod ambient_occlusion;
+pub mod anvil;
+pub mod block_texture_faces;
+pub mod chunk;
+pub mod chunk_manager;
+pub mod clouds;
+pub mod combat;
+pub mod commands;
+#[cfg(feature = "client")]
+pub mod console;
+pub mod constants;
+pub mod ecs;
+pub mod frustum;
+pub mod game_mode;
+#[cfg(feature = "client")]
+pub mod gui;
+pub mod health;
+pub mod hunger;
+#[cfg(feature = "client")]
+pub mod input;
+pub mod inventory;
+pub mod journal;
+#[cfg(feature = "client")]
+pub mod main_hand;
+pub mod metrics;
+pub mod mob;
+pub mod nbt;
+pub mod network;
+#[cfg(feature = "client")]
+pub mod particle_system;
+pub mod pathfinding;
+pub mod physics;
+pub mod player;
+#[cfg(feature = "client")]
+pub mod post_processing;
+pub mod raycast;
+#[cfg(feature = "client")]
+pub mod renderer;
+pub mod schematic;
+pub mod screenshot;
+pub mod server;
+pub mod settings;
+#[cfg(feature = "client")]
+pub mod shader;
+pub mod shadows;
+pub mod shapes;
+#[cfg(feature = "client")]
+pub mod text;
+#[cfg(feature = "client")]
+pub mod texture;
+#[cfg(feature = "client")]
+pub mod texture_pack;
+pub mod timer;
+pub mod types;
+pub mod util;
+pub mod weather;
+#[cfg(feature = "client")]
+pub mod window;
+pub mod world_edit;
+pub mod world_generation;
+pub mod world_time;
@@ -1,67 +1,209 @@
-#[macro_use]
-extern crate lazy_static;
-
 extern crate pretty_env_logger;
 #[macro_use]
 extern crate log;
-extern crate specs;
-
 #[macro_use]
-pub mod debugging;
-pub mod aabb;
-pub mod ambient_occlusion;
-pub mod block_texture_faces;
-pub mod chunk;
-pub mod chunk_manager;
-pub mod constants;
-pub mod ecs;
-pub mod gui;
-pub mod input;
-pub mod inventory;
-pub mod main_hand;
-pub mod particle_system;
-pub mod physics;
-pub mod player;
-pub mod raycast;
-pub mod renderer;
-pub mod shader;
-pub mod shapes;
-pub mod texture;
-pub mod texture_pack;
-pub mod timer;
-pub mod types;
-pub mod util;
-pub mod window;
-
-use crate::chunk_manager::ChunkManager;
-use crate::debugging::*;
-use crate::physics::Interpolator;
-use crate::shader::ShaderProgram;
+extern crate minecraft;
+
+use minecraft::anvil::AnvilWorld;
+use minecraft::chunk_manager::ChunkManager;
+use minecraft::debugging::*;
+use minecraft::physics::Interpolator;
+use minecraft::shader::ShaderProgram;
 use std::collections::HashMap;
 // use glfw::ffi::glfwSwapInterval;
-use crate::constants::*;
-use crate::ecs::components::*;
-use crate::ecs::systems::chunk_loading::ChunkLoading;
-use crate::ecs::systems::*;
-use crate::gui::{create_gui_icons_texture, create_widgets_texture};
-use crate::input::InputCache;
-use crate::inventory::Inventory;
-use crate::main_hand::MainHand;
-use crate::particle_system::ParticleSystem;
-use crate::player::{PlayerPhysicsState, PlayerState};
-use crate::texture_pack::generate_array_texture;
-use crate::timer::Timer;
-use crate::types::Shaders;
-use crate::window::create_window;
-use ecs::systems::fps_counter::FpsCounter;
-use nalgebra_glm::vec3;
+use minecraft::console::Console;
+use minecraft::constants::*;
+use minecraft::ecs::components::*;
+use minecraft::ecs::systems::chunk_loading::ChunkLoading;
+use minecraft::ecs::systems::fps_counter::FpsCounter;
+use minecraft::ecs::systems::*;
+use minecraft::game_mode::GameMode;
+use minecraft::gui::{create_gui_icons_texture, create_widgets_texture};
+use minecraft::health::Health;
+use minecraft::hunger::Hunger;
+use minecraft::input::InputCache;
+use minecraft::inventory::Inventory;
+use minecraft::journal::{EditJournal, JOURNAL_PATH};
+use minecraft::main_hand::MainHand;
+use minecraft::metrics::Metrics;
+use minecraft::mob::{Mob, MobPhysicsState};
+use minecraft::network::client::{ClientNetwork, RemotePlayer};
+use minecraft::network::interpolation::InterpolationBuffer;
+#[cfg(feature = "java_protocol")]
+use minecraft::network::java::client::JavaNetwork;
+use minecraft::network::DEFAULT_PORT;
+use minecraft::particle_system::ParticleSystem;
+use minecraft::player::{PlayerPhysicsState, PlayerState};
+use minecraft::post_processing::PostProcessingPass;
+use minecraft::screenshot::{HeadlessExit, Screenshots};
+use minecraft::settings::{Settings, SettingsMenu, DEFAULT_SETTINGS_PATH};
+use minecraft::shadows::Shadows;
+use minecraft::text::create_font_texture;
+use minecraft::texture::create_texture;
+use minecraft::texture_pack::generate_array_texture;
+use minecraft::timer::Timer;
+use minecraft::types::Shaders;
+use minecraft::weather::{Weather, MAX_PRECIPITATION};
+use minecraft::window::create_window;
+use minecraft::world_edit::WorldEditSession;
+use minecraft::world_time::{named_time, WorldTime};
+use nalgebra_glm::{vec3, Vec3};
 use parking_lot::deadlock;
 use specs::{Builder, DispatcherBuilder, World, WorldExt};
+use std::env;
 use std::os::raw::c_void;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
+// Renders a single frame in a hidden window and saves it to `output`
+struct Headless {
+    output: PathBuf,
+    // Position of the eyes
+    camera_position: Vec3,
+    // In degrees, a yaw of 0 faces +x and a pitch of 90 faces up
+    yaw: f32,
+    pitch: f32,
+    // The time and the weather do not change while rendering
+    time: u64,
+}
+
+// Plays offline unless started with `--connect <address>`, the name defaults to Player
+// With `--java` the server is a vanilla Java Edition server instead of `minecraft-server`
+// Offline, `--import <directory>` plays in the terrain of a vanilla world, which is returned
+// `--headless <file>` renders the world seen from `--camera` to a PNG file and exits, the seed
+// of the generated worlds can be set with `--seed`
+fn parse_args(world: &mut World) -> (Option<AnvilWorld>, Option<Headless>) {
+    fn usage() -> ! {
+        error!(
+            "Usage: minecraft [--connect <address>] [--name <name>] [--java] [--import <directory>] \
+             [--seed <seed>] [--headless <file> [--camera <x> <y> <z> <yaw> <pitch>] [--time <time>]]"
+        );
+        process::exit(1);
+    }
+
+    fn parse<T: FromStr>(value: Option<String>) -> T {
+        value
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_else(|| usage())
+    }
+
+    let mut address = None;
+    let mut name = "Player".to_string();
+    let mut is_java = false;
+    let mut import_directory = None;
+    let mut output = None;
+    let mut camera = None;
+    let mut time = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--connect" => address = Some(args.next().unwrap_or_else(|| usage())),
+            "--name" => name = args.next().unwrap_or_else(|| usage()),
+            "--java" => is_java = true,
+            "--import" => import_directory = Some(args.next().unwrap_or_else(|| usage())),
+            "--seed" => {
+                let _ = WORLD_SEED_OVERRIDE.set(parse(args.next()));
+            }
+            "--headless" => output = Some(PathBuf::from(args.next().unwrap_or_else(|| usage()))),
+            "--camera" => {
+                let values: [f32; 5] = [(); 5].map(|_| parse(args.next()));
+                camera = Some(values);
+            }
+            "--time" => {
+                let value = args.next().unwrap_or_else(|| usage());
+                time = Some(named_time(&value).unwrap_or_else(|| parse(Some(value))));
+            }
+            _ => usage(),
+        }
+    }
+
+    let headless = match output {
+        Some(output) => {
+            let [x, y, z, yaw, pitch] = camera.unwrap_or([8.0, 200.0, 8.0, 0.0, -30.0]);
+            Some(Headless {
+                output,
+                camera_position: vec3(x, y, z),
+                yaw,
+                pitch,
+                time: time.unwrap_or_else(|| named_time("noon").unwrap()),
+            })
+        }
+        None if camera.is_some() || time.is_some() => usage(),
+        None => None,
+    };
+
+    // Offline, the name is the author of the edits in the journal
+    world.insert(ClientNetwork {
+        name: name.clone(),
+        ..ClientNetwork::default()
+    });
+    #[cfg(feature = "java_protocol")]
+    world.insert(JavaNetwork::default());
+
+    let address = match (address, import_directory) {
+        (Some(_), Some(_)) => usage(),
+        // The headless frames are rendered from the world on the disk
+        (Some(_), None) if headless.is_some() => usage(),
+        (Some(address), None) => address,
+        (None, Some(directory)) => match AnvilWorld::open(&directory) {
+            Ok(world) => {
+                info!("Importing the terrain of {directory}");
+                return (Some(world), headless);
+            }
+            Err(err) => {
+                error!("Could not import {directory}: {err}");
+                process::exit(1);
+            }
+        },
+        (None, None) => return (None, headless),
+    };
+
+    if is_java {
+        #[cfg(feature = "java_protocol")]
+        match JavaNetwork::connect(&address, &name) {
+            Ok(network) => {
+                info!("Connected to {address}");
+                world.insert(network);
+                return (None, None);
+            }
+            Err(err) => {
+                error!("Could not connect to {address}: {err}");
+                process::exit(1);
+            }
+        }
+
+        #[cfg(not(feature = "java_protocol"))]
+        {
+            error!("Built without the java_protocol feature");
+            process::exit(1);
+        }
+    }
+
+    // The port can be left out
+    let address = if address.contains(':') {
+        address
+    } else {
+        format!("{address}:{DEFAULT_PORT}")
+    };
+
+    match ClientNetwork::connect(address.as_str(), &name) {
+        Ok(network) => {
+            info!("Connected to {address}");
+            world.insert(network);
+            (None, None)
+        }
+        Err(err) => {
+            error!("Could not connect to {address}: {err}");
+            process::exit(1);
+        }
+    }
+}
+
 fn main() {
     thread::spawn(move || loop {
         thread::sleep(Duration::from_secs(10));
@@ -83,19 +225,69 @@ fn main() {
         }
     });
 
+    // Read before the logger is set up since they choose the log level
+    let (settings, settings_errors) = Settings::load_or_create(Path::new(DEFAULT_SETTINGS_PATH));
+
     let mut log_builder = pretty_env_logger::formatted_builder();
-    log_builder.parse_filters(LOG_LEVEL.as_str()).init();
+    log_builder
+        .parse_filters(settings.log_level.as_str())
+        .init();
+
+    for err in settings_errors {
+        warn!("{DEFAULT_SETTINGS_PATH}: {err}");
+    }
 
     let mut world = World::new();
+    let (imported_world, headless) = parse_args(&mut world);
+
+    #[cfg(feature = "java_protocol")]
+    let is_java = world.read_resource::<JavaNetwork>().is_connected();
+    #[cfg(not(feature = "java_protocol"))]
+    let is_java = false;
+    // The server runs the world generation and the mobs of the remote worlds
+    let is_remote = world.read_resource::<ClientNetwork>().is_connected() || is_java;
+
+    // The edits of the remote worlds are recorded by their server, and the headless frames show
+    // the world as it is generated
+    if is_remote || headless.is_some() {
+        world.insert(EditJournal::disabled());
+    } else {
+        // The offline world is the one of the journal, unless another seed is given
+        if let Some(seed) = EditJournal::recorded_seed(JOURNAL_PATH) {
+            let _ = WORLD_SEED_OVERRIDE.set(seed);
+        }
+
+        match EditJournal::open(JOURNAL_PATH, *WORLD_SEED) {
+            Ok(journal) => world.insert(journal),
+            Err(err) => {
+                error!("Could not read {JOURNAL_PATH}: {err}");
+                process::exit(1);
+            }
+        }
+    }
+
     world.register::<PlayerState>();
     world.register::<Interpolator<PlayerPhysicsState>>();
     world.register::<Inventory>();
     world.register::<MainHand>();
     world.register::<MainHandItemChanged>();
+    world.register::<GameMode>();
+    world.register::<Health>();
+    world.register::<Hunger>();
+    world.register::<Mob>();
+    world.register::<Interpolator<MobPhysicsState>>();
+    world.register::<RemotePlayer>();
+    world.register::<InterpolationBuffer>();
+    world.register::<WorldEditSession>();
 
-    let mut dispatcher = DispatcherBuilder::new()
+    let mut dispatcher_builder = DispatcherBuilder::new()
         .with_thread_local({
-            let (glfw, window, events) = create_window(WINDOW_WIDTH, WINDOW_HEIGHT, WINDOW_NAME);
+            let (glfw, window, events) = create_window(
+                settings.window_width,
+                settings.window_height,
+                WINDOW_NAME,
+                headless.is_none(),
+            );
 
             gl_call!(gl::Enable(gl::DEBUG_OUTPUT));
             gl_call!(gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS));
@@ -130,33 +322,136 @@ fn main() {
                 events,
             }
         })
+        .with_thread_local(HandleServerPackets)
+        .with_thread_local(InterpolateRemoteEntities);
+
+    #[cfg(feature = "java_protocol")]
+    if is_java {
+        dispatcher_builder.add_thread_local(HandleJavaPackets);
+    }
+
+    dispatcher_builder = dispatcher_builder
+        .with_thread_local(UpdateSettings::new(
+            PathBuf::from(DEFAULT_SETTINGS_PATH),
+            settings.clone(),
+        ))
+        .with_thread_local(HandleConsoleInput::new())
         .with_thread_local(InventoryHandleInput)
         .with_thread_local(HandlePlayerInput)
-        .with_thread_local(UpdatePlayerState)
+        .with_thread_local(UpdatePlayerState);
+
+    if !is_remote {
+        dispatcher_builder.add_thread_local(PlayerAttack);
+    }
+
+    dispatcher_builder = dispatcher_builder
         .with_thread_local(PlaceAndBreakBlocks)
+        .with_thread_local(EatFood)
         .with_thread_local(UpdatePlayerPhysics)
-        .with_thread_local(UpdateMainHand)
-        .with_thread_local(ChunkLoading::new())
+        .with_thread_local(UpdatePlayerHealth)
+        .with_thread_local(UpdateHunger::new())
+        .with_thread_local(UpdateMainHand);
+
+    if is_remote {
+        dispatcher_builder.add_thread_local(ChunkLoading::remote(settings.render_distance));
+        dispatcher_builder.add_thread_local(UpdateWeather::remote());
+    } else {
+        dispatcher_builder.add_thread_local(UpdateMobBehaviour);
+        dispatcher_builder.add_thread_local(UpdateMobPhysics);
+        dispatcher_builder.add_thread_local(MobAttack);
+        dispatcher_builder.add_thread_local(match imported_world {
+            Some(world) => ChunkLoading::imported(world, settings.render_distance),
+            None => ChunkLoading::new(settings.render_distance),
+        });
+        dispatcher_builder.add_thread_local(DespawnMobs);
+        // The mobs spawn at random
+        if headless.is_none() {
+            dispatcher_builder.add_thread_local(SpawnMobs::new());
+        }
+        dispatcher_builder.add_thread_local(UpdateWeather::new());
+    }
+
+    // Needs the viewport set with the window
+    let post_processing = RenderPostProcessing::new();
+
+    dispatcher_builder = dispatcher_builder
+        .with_thread_local(SpawnPrecipitation::new())
+        .with_thread_local(RenderShadows::new(settings.shadows))
+        .with_thread_local(post_processing.begin())
+        .with_thread_local(RenderSky::new())
         .with_thread_local(RenderChunks)
+        .with_thread_local(RenderMobs::new())
+        .with_thread_local(RenderClouds::new())
         .with_thread_local(RenderParticles)
         .with_thread_local(RenderBlockOutline::new())
         .with_thread_local(RenderMainHand::new())
-        .with_thread_local(RenderGUI::new())
+        .with_thread_local(post_processing);
+
+    // Only the world is drawn in the headless frames
+    match &headless {
+        Some(headless) => {
+            dispatcher_builder.add_thread_local(RenderHeadless::new(headless.output.clone()));
+        }
+        None => {
+            dispatcher_builder = dispatcher_builder
+                .with_thread_local(RenderGUI::new())
+                .with_thread_local(RenderDeathScreen::new())
+                .with_thread_local(RenderConsole::new())
+                .with_thread_local(RenderSettingsMenu::new())
+                .with_thread_local(RenderDebugOverlay::new())
+                .with_thread_local(TakeScreenshots::new());
+        }
+    }
+
+    dispatcher_builder = dispatcher_builder.with_thread_local(SendPlayerUpdates);
+
+    #[cfg(feature = "java_protocol")]
+    if is_java {
+        dispatcher_builder.add_thread_local(SendJavaUpdates);
+    }
+
+    let mut dispatcher = dispatcher_builder
+        .with_thread_local(UpdateEditJournal::new())
+        .with_thread_local(ClearChangedBlocks)
         .with_thread_local(AdvanceGlobalTime)
         .with_thread_local(FpsCounter::new())
         .build();
 
     world.insert(InputCache::default());
-    world.insert(Timer::default());
-    world.insert({
-        let (item_array_texture, texture_pack) = generate_array_texture();
+    match &headless {
+        Some(headless) => {
+            // Nothing moves while the world loads
+            let mut timer = Timer::default();
+            timer.pause();
+            world.insert(timer);
+            world.insert(WorldTime::from_ticks(headless.time));
+        }
+        None => {
+            world.insert(Timer::default());
+            world.insert(WorldTime::default());
+        }
+    }
+    world.insert(Weather::default());
+    world.insert(Shadows::default());
+    world.insert(Metrics::default());
+    world.insert(Console::default());
+    world.insert(Screenshots::default());
+    world.insert(HeadlessExit::default());
+    world.insert(settings);
+    world.insert(SettingsMenu::default());
+    {
+        let (item_array_texture, texture_pack, item_textures, environment_textures) =
+            generate_array_texture();
         gl_call!(gl::BindTextureUnit(0, item_array_texture));
 
-        texture_pack
-    });
+        world.insert(texture_pack);
+        world.insert(item_textures);
+        world.insert(environment_textures);
+    }
     world.insert({
         let mut particle_systems = HashMap::new();
         particle_systems.insert("block_particles", ParticleSystem::new(500));
+        particle_systems.insert("precipitation", ParticleSystem::new(MAX_PRECIPITATION));
 
         particle_systems
     });
@@ -174,6 +469,10 @@ fn main() {
             "outline_shader",
             ShaderProgram::compile("src/shaders/outline.vert", "src/shaders/outline.frag"),
         );
+        shaders_resource.insert(
+            "crack_shader",
+            ShaderProgram::compile("src/shaders/crack.vert", "src/shaders/crack.frag"),
+        );
         shaders_resource.insert(
             "item_shader",
             ShaderProgram::compile("src/shaders/item.vert", "src/shaders/item.frag"),
@@ -182,10 +481,40 @@ fn main() {
             "particle_shader",
             ShaderProgram::compile("src/shaders/particle.vert", "src/shaders/particle.frag"),
         );
+        shaders_resource.insert(
+            "mob_shader",
+            ShaderProgram::compile("src/shaders/mob.vert", "src/shaders/mob.frag"),
+        );
         shaders_resource.insert(
             "hand_shader",
             ShaderProgram::compile("src/shaders/hand.vert", "src/shaders/hand.frag"),
         );
+        shaders_resource.insert(
+            "text_shader",
+            ShaderProgram::compile("src/shaders/text.vert", "src/shaders/text.frag"),
+        );
+        shaders_resource.insert(
+            "sky_shader",
+            ShaderProgram::compile("src/shaders/sky.vert", "src/shaders/sky.frag"),
+        );
+        shaders_resource.insert(
+            "celestial_shader",
+            ShaderProgram::compile("src/shaders/celestial.vert", "src/shaders/celestial.frag"),
+        );
+        shaders_resource.insert(
+            "shadow_shader",
+            ShaderProgram::compile("src/shaders/shadow.vert", "src/shaders/shadow.frag"),
+        );
+        shaders_resource.insert(
+            "clouds_shader",
+            ShaderProgram::compile("src/shaders/clouds.vert", "src/shaders/clouds.frag"),
+        );
+        for pass in PostProcessingPass::ALL {
+            shaders_resource.insert(
+                pass.shader_name(),
+                ShaderProgram::compile("src/shaders/post.vert", pass.fragment_shader()),
+            );
+        }
 
         shaders_resource
     });
@@ -199,22 +528,54 @@ fn main() {
         let gui_widgets_texture = create_widgets_texture();
         gl_call!(gl::ActiveTexture(gl::TEXTURE2));
         gl_call!(gl::BindTexture(gl::TEXTURE_2D, gui_widgets_texture));
+
+        let font_texture = create_font_texture();
+        gl_call!(gl::ActiveTexture(gl::TEXTURE3));
+        gl_call!(gl::BindTexture(gl::TEXTURE_2D, font_texture));
+
+        let destroy_stages_texture = create_texture("textures/blocks/destroy_stages.png");
+        gl_call!(gl::ActiveTexture(gl::TEXTURE4));
+        gl_call!(gl::BindTexture(gl::TEXTURE_2D, destroy_stages_texture));
     }
 
+    let spawn_point = vec3(8.0, 195.0, 8.0);
+    let (player_state, position) = match &headless {
+        Some(headless) => {
+            let mut player_state = PlayerState::new(spawn_point);
+            player_state.rotation =
+                vec3(headless.pitch.to_radians(), headless.yaw.to_radians(), 0.0);
+            player_state.is_flying = true;
+
+            let position = headless.camera_position - vec3(0.0, PLAYER_EYES_HEIGHT, 0.0);
+            (player_state, position)
+        }
+        None => (PlayerState::new(spawn_point), spawn_point),
+    };
     let _player = world
         .create_entity()
-        .with(PlayerState::new())
+        .with(player_state)
         .with(Interpolator::new(
             1.0 / PHYSICS_TICKRATE,
-            PlayerPhysicsState::new_at_position(vec3(8.0, 195.0, 8.0)),
+            PlayerPhysicsState::new_at_position(position),
         ))
         .with(Inventory::new())
         .with(MainHand::new())
         .with(MainHandItemChanged)
+        .with(GameMode::Creative)
+        .with(Health::new())
+        .with(Hunger::new())
+        .with(WorldEditSession::new())
         .build();
 
-    // Loop until the user closes the window
-    loop {
+    // Loop until the user closes the window, or until the headless frame is saved
+    let exit_code = loop {
         dispatcher.dispatch(&world);
-    }
+        // Applies the entity creations and deletions of the frame
+        world.maintain();
+
+        if let Some(exit_code) = world.read_resource::<HeadlessExit>().exit_code {
+            break exit_code;
+        }
+    };
+    process::exit(exit_code);
 }
@@ -1,14 +1,13 @@
-use crate::chunk::BlockID;
-use crate::shapes::centered_unit_cube;
-use crate::types::TexturePack;
+use crate::inventory::item::Item;
+use crate::types::{ItemTextures, TexturePack};
 use specs::{Component, DenseVecStorage};
 
 #[derive(Component)]
 pub struct MainHand {
     pub begin_switch: bool,
-    pub showing_item: Option<BlockID>,
+    pub showing_item: Option<Item>,
     pub render: MainHandRender,
-    pub switching_to: Option<BlockID>,
+    pub switching_to: Option<Item>,
 }
 
 impl MainHand {
@@ -21,19 +20,20 @@ impl MainHand {
         }
     }
 
-    pub fn switch_item_to(&mut self, item: Option<BlockID>) {
+    pub fn switch_item_to(&mut self, item: Option<Item>) {
         self.switching_to = item;
         self.begin_switch = true;
     }
 
-    pub fn set_showing_item(&mut self, item: Option<BlockID>) {
+    pub fn set_showing_item(&mut self, item: Option<Item>) {
         self.showing_item = item;
         self.render.dirty = true;
     }
 
-    pub fn update_if_dirty(&mut self, texture_pack: &TexturePack) {
+    pub fn update_if_dirty(&mut self, texture_pack: &TexturePack, item_textures: &ItemTextures) {
         if let Some(item) = self.showing_item {
-            self.render.update_vbo_if_dirty(item, &texture_pack);
+            self.render
+                .update_vbo_if_dirty(item, texture_pack, item_textures);
         }
     }
 }
@@ -41,6 +41,7 @@ impl MainHand {
 pub struct MainHandRender {
     pub vao: u32,
     pub vbo: u32,
+    pub vertex_count: i32,
     pub dirty: bool,
 }
 
@@ -99,24 +100,31 @@ impl MainHandRender {
         Self {
             vao,
             vbo,
+            vertex_count: 0,
             dirty: true,
         }
     }
 
-    pub fn update_vbo_if_dirty(&mut self, item: BlockID, texture_pack: &TexturePack) {
+    pub fn update_vbo_if_dirty(
+        &mut self,
+        item: Item,
+        texture_pack: &TexturePack,
+        item_textures: &ItemTextures,
+    ) {
         if self.dirty {
-            self.update_vbo(item, &texture_pack);
+            self.update_vbo(item, texture_pack, item_textures);
             self.dirty = false;
         }
     }
 
-    pub fn update_vbo(&mut self, item: BlockID, texture_pack: &TexturePack) {
-        let vbo_data = centered_unit_cube(
-            -0.5,
-            -0.5,
-            -0.5,
-            texture_pack.get(&item).unwrap().get_uv_of_every_face(),
-        );
+    pub fn update_vbo(
+        &mut self,
+        item: Item,
+        texture_pack: &TexturePack,
+        item_textures: &ItemTextures,
+    ) {
+        let vbo_data = item.vertices(texture_pack, item_textures);
+        self.vertex_count = (vbo_data.len() / 9) as i32;
 
         gl_call!(gl::NamedBufferData(
             self.vbo,
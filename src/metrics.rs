@@ -0,0 +1,76 @@
+use std::collections::VecDeque;
+use std::fs::read_to_string;
+
+pub const FRAME_TIME_SAMPLES: usize = 240;
+
+// Central place where systems publish statistics about the game
+// Read by the debug overlay, nothing here is used for the game logic itself
+pub struct Metrics {
+    // Frame times of the last `FRAME_TIME_SAMPLES` frames, in milliseconds
+    pub frame_times: VecDeque<f32>,
+    pub fps: u32,
+
+    pub loaded_columns: usize,
+    pub pending_uploads: usize,
+    pub generation_queue_depth: usize,
+    pub is_generating: bool,
+    pub chunks_drawn: usize,
+    pub chunks_culled: usize,
+    pub mobs: usize,
+
+    // Resident memory of the process in bytes, if the platform lets us know
+    pub memory_usage: Option<u64>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            frame_times: VecDeque::with_capacity(FRAME_TIME_SAMPLES),
+            fps: 0,
+            loaded_columns: 0,
+            pending_uploads: 0,
+            generation_queue_depth: 0,
+            is_generating: false,
+            chunks_drawn: 0,
+            chunks_culled: 0,
+            mobs: 0,
+            memory_usage: None,
+        }
+    }
+
+    pub fn push_frame_time(&mut self, frame_time_ms: f32) {
+        if self.frame_times.len() == FRAME_TIME_SAMPLES {
+            self.frame_times.pop_front();
+        }
+
+        self.frame_times.push_back(frame_time_ms);
+    }
+
+    pub fn average_frame_time(&self) -> f32 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+
+        self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32
+    }
+
+    pub fn max_frame_time(&self) -> f32 {
+        self.frame_times.iter().cloned().fold(0.0, f32::max)
+    }
+}
+
+// Reads the resident set size from procfs (Linux only), the kernel gives it in KiB whatever the
+// page size
+pub fn resident_memory() -> Option<u64> {
+    let status = read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+
+    Some(kib * 1024)
+}
@@ -0,0 +1,338 @@
+pub mod model;
+
+use crate::aabb::AABB;
+use crate::combat::MOB_HURT_DURATION;
+use crate::pathfinding::Path;
+use crate::physics::Interpolatable;
+use nalgebra_glm::{vec2, vec3, Vec2, Vec3};
+use rand::random;
+use specs::Entity;
+use std::f32::consts::PI;
+use std::time::{Duration, Instant};
+
+// A player sprinting closer than this scares the mobs away
+pub const FLEE_TRIGGER_DISTANCE: f32 = 4.0;
+pub const FLEE_DURATION: Duration = Duration::from_secs(4);
+// Wander targets are picked around the mob within this distance
+pub const WANDER_DISTANCE: f32 = 8.0;
+pub const MAX_WANDER_DURATION: Duration = Duration::from_secs(10);
+// Nodes explored when planning the path to a wander target
+pub const WANDER_SEARCH_BUDGET: usize = 300;
+pub const MOB_JUMP_HEIGHT: f32 = 1.2;
+// Hostile mobs notice the players within this distance
+pub const DETECTION_DISTANCE: f32 = 16.0;
+// Close enough to walk straight to the chased player without a path
+pub const CHASE_DIRECT_DISTANCE: f32 = 1.5;
+// Nodes explored when planning the path to a chased player
+pub const CHASE_SEARCH_BUDGET: usize = 600;
+pub const REPATH_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum MobKind {
+    Pig,
+    Sheep,
+    Zombie,
+}
+
+impl MobKind {
+    pub const ALL: [MobKind; 3] = [MobKind::Pig, MobKind::Sheep, MobKind::Zombie];
+    pub const PASSIVE: [MobKind; 2] = [MobKind::Pig, MobKind::Sheep];
+    pub const HOSTILE: [MobKind; 1] = [MobKind::Zombie];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MobKind::Pig => "pig",
+            MobKind::Sheep => "sheep",
+            MobKind::Zombie => "zombie",
+        }
+    }
+
+    // Hostile mobs spawn in the dark and chase the players
+    pub fn is_hostile(&self) -> bool {
+        *self == MobKind::Zombie
+    }
+
+    pub fn width(&self) -> f32 {
+        match self {
+            MobKind::Pig | MobKind::Sheep => 0.9,
+            MobKind::Zombie => 0.6,
+        }
+    }
+
+    pub fn height(&self) -> f32 {
+        match self {
+            MobKind::Pig => 0.9,
+            MobKind::Sheep => 1.3,
+            MobKind::Zombie => 1.95,
+        }
+    }
+
+    // Walking speed in blocks per second, fleeing mobs run twice as fast
+    pub fn speed(&self) -> f32 {
+        match self {
+            MobKind::Pig => 1.5,
+            MobKind::Sheep => 1.3,
+            MobKind::Zombie => 2.3,
+        }
+    }
+
+    // In half hearts
+    pub fn max_health(&self) -> u32 {
+        match self {
+            MobKind::Pig => 10,
+            MobKind::Sheep => 8,
+            MobKind::Zombie => 20,
+        }
+    }
+
+    // Damage dealt to the players touching the mob, in half hearts
+    pub fn attack_damage(&self) -> u32 {
+        match self {
+            MobKind::Zombie => 3,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MobBehaviour {
+    Idle,
+    Wander { target: Vec3 },
+    Flee { from: Vec3 },
+    // The position of the chased player is refreshed every frame
+    Chase { target: Entity, position: Vec3 },
+}
+
+pub struct Mob {
+    pub kind: MobKind,
+    pub behaviour: MobBehaviour,
+    pub is_on_ground: bool,
+    pub health: u32,
+    // Path followed while wandering or chasing
+    pub path: Option<Path>,
+    // When the current behaviour ends
+    pub(crate) behaviour_until: Instant,
+    pub(crate) path_last_planned: Option<Instant>,
+    pub(crate) last_hurt: Option<Instant>,
+    pub(crate) attack_last_executed: Option<Instant>,
+}
+
+impl Mob {
+    pub fn new(kind: MobKind) -> Self {
+        Self {
+            kind,
+            behaviour: MobBehaviour::Idle,
+            is_on_ground: false,
+            health: kind.max_health(),
+            path: None,
+            behaviour_until: Instant::now() + random_idle_duration(),
+            path_last_planned: None,
+            last_hurt: None,
+            attack_last_executed: None,
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.health == 0
+    }
+
+    // Shortly after a hit, the mob is tinted red and cannot be hurt again
+    pub fn is_hurt(&self) -> bool {
+        self.last_hurt
+            .map(|time| time.elapsed() < MOB_HURT_DURATION)
+            .unwrap_or(false)
+    }
+
+    // Returns false when the mob could not be hurt
+    pub fn damage(&mut self, amount: u32) -> bool {
+        if self.is_dead() || self.is_hurt() {
+            return false;
+        }
+
+        self.health = self.health.saturating_sub(amount);
+        self.last_hurt = Some(Instant::now());
+
+        true
+    }
+
+    // Starts chasing a player, the behaviour system keeps the position up to date
+    pub fn chase(&mut self, target: Entity, position: Vec3, now: Instant) {
+        if !matches!(self.behaviour, MobBehaviour::Chase { target: t, .. } if t == target) {
+            self.path = None;
+        }
+
+        self.behaviour = MobBehaviour::Chase { target, position };
+        self.behaviour_until = now + REPATH_INTERVAL;
+    }
+
+    // Makes the mob run away from the given position, hostile mobs are not scared
+    pub fn startle(&mut self, from: Vec3) {
+        if self.kind.is_hostile() {
+            return;
+        }
+
+        self.behaviour = MobBehaviour::Flee { from };
+        self.path = None;
+        self.behaviour_until = Instant::now() + FLEE_DURATION;
+    }
+
+    pub fn is_fleeing(&self) -> bool {
+        matches!(self.behaviour, MobBehaviour::Flee { .. })
+    }
+
+    // Picks the next behaviour once the current one is over
+    pub fn update_behaviour(&mut self, position: &Vec3, now: Instant) {
+        let wander_target_reached = match (&self.behaviour, &self.path) {
+            (MobBehaviour::Wander { .. }, Some(path)) => path.is_finished(),
+            (MobBehaviour::Wander { target }, None) => {
+                vec2(target.x - position.x, target.z - position.z).norm() < 0.5
+            }
+            _ => false,
+        };
+
+        if now < self.behaviour_until && !wander_target_reached {
+            return;
+        }
+
+        // The path to the new wander target is planned by the behaviour system
+        self.path = None;
+
+        if self.behaviour == MobBehaviour::Idle && random::<f32>() < 0.6 {
+            let angle = random::<f32>() * 2.0 * PI;
+            let distance = (0.3 + 0.7 * random::<f32>()) * WANDER_DISTANCE;
+
+            self.behaviour = MobBehaviour::Wander {
+                target: position + vec3(angle.cos(), 0.0, angle.sin()) * distance,
+            };
+            self.behaviour_until = now + MAX_WANDER_DURATION;
+        } else {
+            self.behaviour = MobBehaviour::Idle;
+            self.behaviour_until = now + random_idle_duration();
+        }
+    }
+
+    // Normalized horizontal direction the mob wants to walk to, None when it stands still
+    pub fn movement_direction(&self, position: &Vec3) -> Option<Vec2> {
+        let direction = match self.behaviour {
+            MobBehaviour::Idle => return None,
+            MobBehaviour::Wander { target } => {
+                let target = self
+                    .path
+                    .as_ref()
+                    .and_then(|path| path.current_waypoint())
+                    .unwrap_or(target);
+
+                vec2(target.x - position.x, target.z - position.z)
+            }
+            MobBehaviour::Flee { from } => vec2(position.x - from.x, position.z - from.z),
+            MobBehaviour::Chase {
+                position: target, ..
+            } => {
+                let direct = vec2(target.x - position.x, target.z - position.z);
+
+                match self.path.as_ref().and_then(|path| path.current_waypoint()) {
+                    Some(waypoint) if direct.norm() > CHASE_DIRECT_DISTANCE => {
+                        vec2(waypoint.x - position.x, waypoint.z - position.z)
+                    }
+                    _ => direct,
+                }
+            }
+        };
+
+        if direction.norm_squared() < 0.0001 {
+            // Fleeing from something right on top of the mob, pick any direction
+            return if self.is_fleeing() {
+                Some(vec2(1.0, 0.0))
+            } else {
+                None
+            };
+        }
+
+        Some(direction.normalize())
+    }
+
+    pub fn speed(&self) -> f32 {
+        if self.is_fleeing() {
+            self.kind.speed() * 2.0
+        } else {
+            self.kind.speed()
+        }
+    }
+}
+
+fn random_idle_duration() -> Duration {
+    Duration::from_secs_f32(2.0 + random::<f32>() * 4.0)
+}
+
+#[derive(Clone)]
+pub struct MobPhysicsState {
+    // Center of the bottom of the bounding box
+    pub position: Vec3,
+    pub aabb: AABB,
+    pub velocity: Vec3,
+    // Rotation around the Y axis in radians, 0 when facing +Z
+    pub yaw: f32,
+    // Distance walked since the spawn, drives the leg animation
+    pub walk_distance: f32,
+    // Velocity from the hits taken, added to the walking velocity
+    pub knockback: Vec3,
+}
+
+impl MobPhysicsState {
+    pub fn new_at_position(kind: MobKind, position: Vec3) -> Self {
+        let half_width = kind.width() / 2.0;
+
+        Self {
+            position,
+            aabb: AABB::new(
+                vec3(position.x - half_width, position.y, position.z - half_width),
+                vec3(
+                    position.x + half_width,
+                    position.y + kind.height(),
+                    position.z + half_width,
+                ),
+            ),
+            velocity: vec3(0.0, 0.0, 0.0),
+            yaw: random::<f32>() * 2.0 * PI,
+            walk_distance: 0.0,
+            knockback: vec3(0.0, 0.0, 0.0),
+        }
+    }
+
+    // Updates the position from the bounding box after the collisions were resolved
+    pub fn update_position(&mut self) {
+        let size = self.aabb.size();
+
+        self.position = vec3(
+            self.aabb.mins.x + size.x / 2.0,
+            self.aabb.mins.y,
+            self.aabb.mins.z + size.z / 2.0,
+        );
+    }
+}
+
+impl Interpolatable for MobPhysicsState {
+    fn interpolate(&self, other: &Self, alpha: f32) -> Self {
+        let interpolate_vec3 = |from: &Vec3, to: &Vec3| alpha * from + (1.0 - alpha) * to;
+
+        // Take the shortest way around the circle
+        let mut yaw_difference = self.yaw - other.yaw;
+        if yaw_difference > PI {
+            yaw_difference -= 2.0 * PI;
+        } else if yaw_difference < -PI {
+            yaw_difference += 2.0 * PI;
+        }
+
+        Self {
+            position: interpolate_vec3(&self.position, &other.position),
+            aabb: AABB {
+                mins: interpolate_vec3(&self.aabb.mins, &other.aabb.mins),
+                maxs: interpolate_vec3(&self.aabb.maxs, &other.aabb.maxs),
+            },
+            velocity: interpolate_vec3(&self.velocity, &other.velocity),
+            yaw: other.yaw + alpha * yaw_difference,
+            walk_distance: self.walk_distance.interpolate(&other.walk_distance, alpha),
+            knockback: interpolate_vec3(&self.knockback, &other.knockback),
+        }
+    }
+}
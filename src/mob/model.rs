@@ -0,0 +1,224 @@
+use crate::mob::MobKind;
+#[cfg(feature = "client")]
+use crate::shapes::centered_unit_cube;
+#[cfg(feature = "client")]
+use std::os::raw::c_void;
+
+// Mob models are made of colored boxes, in blocks, facing +Z
+// The origin is the center of the bottom of the bounding box
+pub struct ModelPart {
+    pub center: [f32; 3],
+    pub size: [f32; 3],
+    pub color: [f32; 3],
+    // Legs swing around the top of the box while walking
+    // The sign gives the direction so that diagonal legs move together
+    pub swing: Option<f32>,
+}
+
+const PIG_SKIN: [f32; 3] = [0.94, 0.64, 0.64];
+const PIG_SNOUT: [f32; 3] = [0.85, 0.47, 0.5];
+
+const PIG_MODEL: [ModelPart; 7] = [
+    // Body
+    ModelPart {
+        center: [0.0, 0.625, 0.0],
+        size: [0.625, 0.5, 1.0],
+        color: PIG_SKIN,
+        swing: None,
+    },
+    // Head
+    ModelPart {
+        center: [0.0, 0.75, 0.69],
+        size: [0.5, 0.5, 0.5],
+        color: PIG_SKIN,
+        swing: None,
+    },
+    // Snout
+    ModelPart {
+        center: [0.0, 0.66, 0.97],
+        size: [0.25, 0.19, 0.06],
+        color: PIG_SNOUT,
+        swing: None,
+    },
+    // Legs
+    ModelPart {
+        center: [-0.19, 0.1875, 0.31],
+        size: [0.25, 0.375, 0.25],
+        color: PIG_SKIN,
+        swing: Some(1.0),
+    },
+    ModelPart {
+        center: [0.19, 0.1875, 0.31],
+        size: [0.25, 0.375, 0.25],
+        color: PIG_SKIN,
+        swing: Some(-1.0),
+    },
+    ModelPart {
+        center: [-0.19, 0.1875, -0.31],
+        size: [0.25, 0.375, 0.25],
+        color: PIG_SKIN,
+        swing: Some(-1.0),
+    },
+    ModelPart {
+        center: [0.19, 0.1875, -0.31],
+        size: [0.25, 0.375, 0.25],
+        color: PIG_SKIN,
+        swing: Some(1.0),
+    },
+];
+
+const SHEEP_WOOL: [f32; 3] = [0.92, 0.92, 0.9];
+const SHEEP_SKIN: [f32; 3] = [0.85, 0.73, 0.62];
+
+const SHEEP_MODEL: [ModelPart; 6] = [
+    // Body
+    ModelPart {
+        center: [0.0, 0.84, 0.0],
+        size: [0.75, 0.69, 1.19],
+        color: SHEEP_WOOL,
+        swing: None,
+    },
+    // Head
+    ModelPart {
+        center: [0.0, 1.06, 0.72],
+        size: [0.38, 0.38, 0.5],
+        color: SHEEP_SKIN,
+        swing: None,
+    },
+    // Legs
+    ModelPart {
+        center: [-0.19, 0.3, 0.4],
+        size: [0.22, 0.6, 0.22],
+        color: SHEEP_SKIN,
+        swing: Some(1.0),
+    },
+    ModelPart {
+        center: [0.19, 0.3, 0.4],
+        size: [0.22, 0.6, 0.22],
+        color: SHEEP_SKIN,
+        swing: Some(-1.0),
+    },
+    ModelPart {
+        center: [-0.19, 0.3, -0.4],
+        size: [0.22, 0.6, 0.22],
+        color: SHEEP_SKIN,
+        swing: Some(-1.0),
+    },
+    ModelPart {
+        center: [0.19, 0.3, -0.4],
+        size: [0.22, 0.6, 0.22],
+        color: SHEEP_SKIN,
+        swing: Some(1.0),
+    },
+];
+
+const ZOMBIE_SKIN: [f32; 3] = [0.36, 0.56, 0.32];
+const ZOMBIE_SHIRT: [f32; 3] = [0.0, 0.6, 0.6];
+const ZOMBIE_PANTS: [f32; 3] = [0.27, 0.24, 0.62];
+
+const ZOMBIE_MODEL: [ModelPart; 6] = [
+    // Head
+    ModelPart {
+        center: [0.0, 1.75, 0.0],
+        size: [0.5, 0.5, 0.5],
+        color: ZOMBIE_SKIN,
+        swing: None,
+    },
+    // Body
+    ModelPart {
+        center: [0.0, 1.125, 0.0],
+        size: [0.5, 0.75, 0.25],
+        color: ZOMBIE_SHIRT,
+        swing: None,
+    },
+    // Arms, stretched forward
+    ModelPart {
+        center: [-0.375, 1.375, 0.25],
+        size: [0.25, 0.25, 0.75],
+        color: ZOMBIE_SKIN,
+        swing: None,
+    },
+    ModelPart {
+        center: [0.375, 1.375, 0.25],
+        size: [0.25, 0.25, 0.75],
+        color: ZOMBIE_SKIN,
+        swing: None,
+    },
+    // Legs
+    ModelPart {
+        center: [-0.125, 0.375, 0.0],
+        size: [0.25, 0.75, 0.25],
+        color: ZOMBIE_PANTS,
+        swing: Some(1.0),
+    },
+    ModelPart {
+        center: [0.125, 0.375, 0.0],
+        size: [0.25, 0.75, 0.25],
+        color: ZOMBIE_PANTS,
+        swing: Some(-1.0),
+    },
+];
+
+impl MobKind {
+    pub fn model(&self) -> &'static [ModelPart] {
+        match self {
+            MobKind::Pig => &PIG_MODEL,
+            MobKind::Sheep => &SHEEP_MODEL,
+            MobKind::Zombie => &ZOMBIE_MODEL,
+        }
+    }
+}
+
+// Unit cube going from (0, 0, 0) to (1, 1, 1), with positions and normals
+// Every part of the models is drawn with it
+#[cfg(feature = "client")]
+pub fn create_model_part_vao() -> u32 {
+    // Setup VAO
+    let mut vao = 0;
+    gl_call!(gl::CreateVertexArrays(1, &mut vao));
+
+    // Position
+    gl_call!(gl::EnableVertexArrayAttrib(vao, 0));
+    gl_call!(gl::VertexArrayAttribFormat(
+        vao,
+        0,
+        3,
+        gl::FLOAT,
+        gl::FALSE,
+        0
+    ));
+    gl_call!(gl::VertexArrayAttribBinding(vao, 0, 0));
+
+    // Normal
+    gl_call!(gl::EnableVertexArrayAttrib(vao, 1));
+    gl_call!(gl::VertexArrayAttribFormat(
+        vao,
+        1,
+        3,
+        gl::FLOAT,
+        gl::FALSE,
+        6 * std::mem::size_of::<f32>() as u32
+    ));
+    gl_call!(gl::VertexArrayAttribBinding(vao, 1, 0));
+
+    // Setup VBO
+    let vbo_data = centered_unit_cube(0.0, 0.0, 0.0, (0, 0, 0, 0, 0, 0));
+    let mut vbo = 0;
+    gl_call!(gl::CreateBuffers(1, &mut vbo));
+
+    gl_call!(gl::VertexArrayVertexBuffer(
+        vao,
+        0,
+        vbo,
+        0,
+        (9 * std::mem::size_of::<f32>()) as i32
+    ));
+    gl_call!(gl::NamedBufferData(
+        vbo,
+        (vbo_data.len() * std::mem::size_of::<f32>()) as isize,
+        vbo_data.as_ptr() as *const c_void,
+        gl::STATIC_DRAW
+    ));
+
+    vao
+}
@@ -0,0 +1,438 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+// Deeper structures are refused instead of overflowing the stack
+const MAX_DEPTH: usize = 512;
+// Arrays and lists are read in steps so that a corrupted length cannot allocate gigabytes at once
+const MAX_PREALLOCATION: usize = 4096;
+
+// Named Binary Tag, the format of the saves and schematics of the vanilla game
+// Everything is big endian, the strings are prefixed with their length as a u16
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<u8>),
+    String(String),
+    // All the elements have the same type
+    List(Vec<Tag>),
+    // Sorted by name so that the same compound is always written the same way
+    Compound(BTreeMap<String, Tag>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+#[derive(Debug)]
+pub enum NbtError {
+    Io(io::Error),
+    UnknownTag(u8),
+    InvalidData(&'static str),
+    // A tag that is not in the data or that has another type
+    Missing(String),
+}
+
+impl fmt::Display for NbtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NbtError::Io(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                write!(f, "NBT data ended unexpectedly")
+            }
+            NbtError::Io(err) => write!(f, "{err}"),
+            NbtError::UnknownTag(id) => write!(f, "Unknown NBT tag {id}"),
+            NbtError::InvalidData(what) => write!(f, "Invalid NBT data: {what}"),
+            NbtError::Missing(name) => write!(f, "Missing or invalid NBT tag '{name}'"),
+        }
+    }
+}
+
+impl std::error::Error for NbtError {}
+
+impl From<io::Error> for NbtError {
+    fn from(err: io::Error) -> Self {
+        NbtError::Io(err)
+    }
+}
+
+impl Tag {
+    pub fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => 1,
+            Tag::Short(_) => 2,
+            Tag::Int(_) => 3,
+            Tag::Long(_) => 4,
+            Tag::Float(_) => 5,
+            Tag::Double(_) => 6,
+            Tag::ByteArray(_) => 7,
+            Tag::String(_) => 8,
+            Tag::List(_) => 9,
+            Tag::Compound(_) => 10,
+            Tag::IntArray(_) => 11,
+            Tag::LongArray(_) => 12,
+        }
+    }
+
+    // Child of a compound
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(tags) => tags.get(name),
+            _ => None,
+        }
+    }
+
+    // Child of a compound, an error tells which one is missing
+    pub fn child(&self, name: &str) -> Result<&Tag, NbtError> {
+        self.get(name)
+            .ok_or_else(|| NbtError::Missing(name.to_string()))
+    }
+
+    // The integers are widened, the files written by other tools do not always use the same types
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Tag::Byte(value) => Some(value as i64),
+            Tag::Short(value) => Some(value as i64),
+            Tag::Int(value) => Some(value as i64),
+            Tag::Long(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Tag::ByteArray(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&BTreeMap<String, Tag>> {
+        match self {
+            Tag::Compound(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_int_array(&self) -> Option<&[i32]> {
+        match self {
+            Tag::IntArray(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_long_array(&self) -> Option<&[i64]> {
+        match self {
+            Tag::LongArray(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn write_payload(&self, writer: &mut impl Write) -> io::Result<()> {
+        match self {
+            Tag::Byte(value) => writer.write_all(&value.to_be_bytes()),
+            Tag::Short(value) => writer.write_all(&value.to_be_bytes()),
+            Tag::Int(value) => writer.write_all(&value.to_be_bytes()),
+            Tag::Long(value) => writer.write_all(&value.to_be_bytes()),
+            Tag::Float(value) => writer.write_all(&value.to_be_bytes()),
+            Tag::Double(value) => writer.write_all(&value.to_be_bytes()),
+            Tag::ByteArray(values) => {
+                writer.write_all(&(values.len() as i32).to_be_bytes())?;
+                writer.write_all(values)
+            }
+            Tag::String(value) => write_string(writer, value),
+            Tag::List(tags) => {
+                // Empty lists have the type of the end tag
+                let id = tags.first().map_or(0, Tag::id);
+                if tags.iter().any(|tag| tag.id() != id) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "NBT lists cannot mix types",
+                    ));
+                }
+
+                writer.write_all(&[id])?;
+                writer.write_all(&(tags.len() as i32).to_be_bytes())?;
+                for tag in tags {
+                    tag.write_payload(writer)?;
+                }
+                Ok(())
+            }
+            Tag::Compound(tags) => {
+                for (name, tag) in tags {
+                    write_named(writer, name, tag)?;
+                }
+                writer.write_all(&[0])
+            }
+            Tag::IntArray(values) => {
+                writer.write_all(&(values.len() as i32).to_be_bytes())?;
+                for value in values {
+                    writer.write_all(&value.to_be_bytes())?;
+                }
+                Ok(())
+            }
+            Tag::LongArray(values) => {
+                writer.write_all(&(values.len() as i32).to_be_bytes())?;
+                for value in values {
+                    writer.write_all(&value.to_be_bytes())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn write_string(writer: &mut impl Write, value: &str) -> io::Result<()> {
+    if value.len() > u16::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "NBT string too long",
+        ));
+    }
+
+    writer.write_all(&(value.len() as u16).to_be_bytes())?;
+    writer.write_all(value.as_bytes())
+}
+
+fn write_named(writer: &mut impl Write, name: &str, tag: &Tag) -> io::Result<()> {
+    writer.write_all(&[tag.id()])?;
+    write_string(writer, name)?;
+    tag.write_payload(writer)
+}
+
+struct NbtReader<R: Read> {
+    reader: R,
+    depth: usize,
+}
+
+impl<R: Read> NbtReader<R> {
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], NbtError> {
+        let mut bytes = [0; N];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, NbtError> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    fn read_length(&mut self) -> Result<usize, NbtError> {
+        let length = i32::from_be_bytes(self.read_array()?);
+        if length < 0 {
+            return Err(NbtError::InvalidData("negative length"));
+        }
+
+        Ok(length as usize)
+    }
+
+    fn read_string(&mut self) -> Result<String, NbtError> {
+        let length = u16::from_be_bytes(self.read_array()?) as usize;
+        let mut bytes = vec![0; length];
+        self.reader.read_exact(&mut bytes)?;
+
+        // The game writes "modified UTF-8", which is the same for the usual characters
+        String::from_utf8(bytes).map_err(|_| NbtError::InvalidData("invalid UTF-8 string"))
+    }
+
+    fn read_elements<T>(
+        &mut self,
+        mut read: impl FnMut(&mut Self) -> Result<T, NbtError>,
+    ) -> Result<Vec<T>, NbtError> {
+        let length = self.read_length()?;
+        let mut elements = Vec::with_capacity(length.min(MAX_PREALLOCATION));
+
+        for _ in 0..length {
+            elements.push(read(self)?);
+        }
+
+        Ok(elements)
+    }
+
+    fn read_payload(&mut self, id: u8) -> Result<Tag, NbtError> {
+        Ok(match id {
+            1 => Tag::Byte(i8::from_be_bytes(self.read_array()?)),
+            2 => Tag::Short(i16::from_be_bytes(self.read_array()?)),
+            3 => Tag::Int(i32::from_be_bytes(self.read_array()?)),
+            4 => Tag::Long(i64::from_be_bytes(self.read_array()?)),
+            5 => Tag::Float(f32::from_be_bytes(self.read_array()?)),
+            6 => Tag::Double(f64::from_be_bytes(self.read_array()?)),
+            7 => Tag::ByteArray(self.read_elements(|reader| reader.read_u8())?),
+            8 => Tag::String(self.read_string()?),
+            9 => {
+                let id = self.read_u8()?;
+
+                self.depth += 1;
+                if self.depth > MAX_DEPTH {
+                    return Err(NbtError::InvalidData("too deeply nested"));
+                }
+                let tags = self.read_elements(|reader| reader.read_payload(id))?;
+                self.depth -= 1;
+
+                // A list of end tags can only be empty
+                if id == 0 && !tags.is_empty() {
+                    return Err(NbtError::UnknownTag(0));
+                }
+                Tag::List(tags)
+            }
+            10 => {
+                self.depth += 1;
+                if self.depth > MAX_DEPTH {
+                    return Err(NbtError::InvalidData("too deeply nested"));
+                }
+
+                let mut tags = BTreeMap::new();
+                loop {
+                    let id = self.read_u8()?;
+                    if id == 0 {
+                        break;
+                    }
+
+                    let name = self.read_string()?;
+                    tags.insert(name, self.read_payload(id)?);
+                }
+                self.depth -= 1;
+
+                Tag::Compound(tags)
+            }
+            11 => Tag::IntArray(
+                self.read_elements(|reader| Ok(i32::from_be_bytes(reader.read_array()?)))?,
+            ),
+            12 => Tag::LongArray(
+                self.read_elements(|reader| Ok(i64::from_be_bytes(reader.read_array()?)))?,
+            ),
+            id => return Err(NbtError::UnknownTag(id)),
+        })
+    }
+}
+
+// Reads the root tag, returns its name and the tag
+pub fn read(reader: impl Read) -> Result<(String, Tag), NbtError> {
+    let mut reader = NbtReader { reader, depth: 0 };
+
+    let id = reader.read_u8()?;
+    if id == 0 {
+        return Err(NbtError::InvalidData("empty root tag"));
+    }
+    let name = reader.read_string()?;
+    let tag = reader.read_payload(id)?;
+
+    Ok((name, tag))
+}
+
+pub fn write(mut writer: impl Write, name: &str, tag: &Tag) -> Result<(), NbtError> {
+    write_named(&mut writer, name, tag)?;
+    Ok(())
+}
+
+// The files of the game are compressed with gzip
+pub fn read_file(path: impl AsRef<Path>) -> Result<(String, Tag), NbtError> {
+    let file = File::open(path)?;
+    read(GzDecoder::new(BufReader::new(file)))
+}
+
+pub fn write_file(path: impl AsRef<Path>, name: &str, tag: &Tag) -> Result<(), NbtError> {
+    let file = File::create(path)?;
+    let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+
+    write(&mut encoder, name, tag)?;
+    encoder.finish()?.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Uncompressed, with a tag of every type and the names sorted like the compounds are written
+    const ALL_TAGS: &[u8] = include_bytes!("../tests/fixtures/nbt/all_tags.nbt");
+
+    #[test]
+    fn reads_every_tag() {
+        let (name, tag) = read(ALL_TAGS).unwrap();
+        assert_eq!(name, "Level");
+
+        let child = |name: &str| tag.child(name).unwrap();
+        assert_eq!(child("byte"), &Tag::Byte(-7));
+        assert_eq!(child("short"), &Tag::Short(-12345));
+        assert_eq!(child("int"), &Tag::Int(i32::MAX));
+        assert_eq!(child("long"), &Tag::Long(i64::MIN));
+        assert_eq!(child("float"), &Tag::Float(0.5));
+        assert_eq!(child("double"), &Tag::Double(-1.25));
+        assert_eq!(
+            child("byteArray").as_bytes(),
+            Some(&[0, 1, 2, 254, 255][..])
+        );
+        assert_eq!(child("string").as_str(), Some("Héllo NBT"));
+        assert_eq!(
+            child("list"),
+            &Tag::List(vec![Tag::Int(1), Tag::Int(-2), Tag::Int(3)])
+        );
+        assert_eq!(child("emptyList"), &Tag::List(Vec::new()));
+        assert_eq!(
+            child("compoundList").as_list().unwrap()[1]
+                .child("name")
+                .unwrap(),
+            &Tag::String("second".to_string())
+        );
+        assert_eq!(
+            child("nested")
+                .child("deeper")
+                .unwrap()
+                .child("flag")
+                .unwrap(),
+            &Tag::Byte(1)
+        );
+        assert_eq!(child("intArray").as_int_array(), Some(&[-1, 65536][..]));
+        assert_eq!(child("longArray").as_long_array(), Some(&[1 << 40][..]));
+    }
+
+    #[test]
+    fn writes_the_same_bytes() {
+        let (name, tag) = read(ALL_TAGS).unwrap();
+
+        let mut bytes = Vec::new();
+        write(&mut bytes, &name, &tag).unwrap();
+        assert_eq!(bytes, ALL_TAGS);
+    }
+
+    #[test]
+    fn compressed_file_round_trip() {
+        let (name, tag) = read_file("tests/fixtures/nbt/all_tags.dat").unwrap();
+        assert_eq!((name.clone(), tag.clone()), read(ALL_TAGS).unwrap());
+
+        let path = std::env::temp_dir().join(format!("minecraft-nbt-{}.dat", std::process::id()));
+        write_file(&path, &name, &tag).unwrap();
+        let written = read_file(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(written.unwrap(), (name, tag));
+    }
+
+    #[test]
+    fn truncated_data_is_rejected() {
+        for length in [0, 1, 10, ALL_TAGS.len() - 1] {
+            assert!(read(&ALL_TAGS[..length]).is_err());
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::chunk::{BlockID, ChunkColumn};
+use crate::chunk_manager::{CHUNK_SIZE, CHUNK_VOLUME};
+use crate::network::codec::{block_from_id, block_to_id};
+use crate::network::NetworkError;
+
+const COLUMN_VOLUME: usize = 16 * CHUNK_VOLUME as usize;
+
+// The blocks of a column are run-length encoded, from the bottom to the top, each layer z then x
+// A run is the block followed by the number of blocks as a u16, the columns are mostly made of
+// long runs of air and stone so they shrink from 64 KiB to a few KiB
+pub fn compress_column(column: &ChunkColumn) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut run: Option<(BlockID, u16)> = None;
+
+    let mut push_run = |block: BlockID, length: u16| {
+        data.push(block_to_id(block));
+        data.extend_from_slice(&length.to_be_bytes());
+    };
+
+    for chunk in column.chunks.iter() {
+        for &block in chunk.blocks.read().iter() {
+            run = match run {
+                Some((run_block, length)) if run_block == block && length < u16::MAX => {
+                    Some((run_block, length + 1))
+                }
+                Some((run_block, length)) => {
+                    push_run(run_block, length);
+                    Some((block, 1))
+                }
+                None => Some((block, 1)),
+            };
+        }
+    }
+
+    if let Some((block, length)) = run {
+        push_run(block, length);
+    }
+
+    data
+}
+
+// Fills an empty column with the compressed blocks
+pub fn decompress_column(data: &[u8], column: &ChunkColumn) -> Result<(), NetworkError> {
+    if !data.len().is_multiple_of(3) {
+        return Err(NetworkError::InvalidData("truncated column data"));
+    }
+
+    let mut index = 0;
+
+    for run in data.chunks(3) {
+        let block = block_from_id(run[0]).ok_or(NetworkError::InvalidData("unknown block"))?;
+        let length = u16::from_be_bytes([run[1], run[2]]) as usize;
+
+        if index + length > COLUMN_VOLUME {
+            return Err(NetworkError::InvalidData("too many blocks in column"));
+        }
+
+        // The column starts empty, only the other blocks have to be set
+        if !block.is_air() {
+            for i in index..index + length {
+                let i = i as u32;
+                let layer = CHUNK_SIZE * CHUNK_SIZE;
+
+                column.set_block(i % CHUNK_SIZE, i / layer, (i % layer) / CHUNK_SIZE, block);
+            }
+        }
+
+        index += length;
+    }
+
+    if index != COLUMN_VOLUME {
+        return Err(NetworkError::InvalidData("missing blocks in column"));
+    }
+
+    Ok(())
+}
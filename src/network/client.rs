@@ -0,0 +1,57 @@
+use crate::network::connection::Connection;
+use crate::network::packet::{ClientboundPacket, ServerboundPacket};
+use crate::network::prediction::MovementPrediction;
+use crate::network::PROTOCOL_VERSION;
+use nalgebra_glm::Vec3;
+use specs::Entity;
+use std::collections::HashMap;
+use std::io;
+use std::net::ToSocketAddrs;
+
+pub type ServerConnection = Connection<ClientboundPacket, ServerboundPacket>;
+
+// Another player connected to the same server, at the position shown by its `InterpolationBuffer`
+pub struct RemotePlayer {
+    pub name: String,
+    pub position: Vec3,
+    pub yaw: f32,
+}
+
+// Connection of the client to a server, the client plays offline when there is none
+#[derive(Default)]
+pub struct ClientNetwork {
+    pub connection: Option<ServerConnection>,
+    pub name: String,
+    // Id of the player of this client on the server, known once logged in
+    pub entity_id: Option<u32>,
+    // Local entities standing for the entities of the server, by server id
+    pub remote_entities: HashMap<u32, Entity>,
+    pub prediction: MovementPrediction,
+}
+
+impl ClientNetwork {
+    // Connects and logs in, the server answers with the spawn position
+    pub fn connect(address: impl ToSocketAddrs, name: &str) -> io::Result<Self> {
+        let connection = ServerConnection::connect(address)?;
+        connection.send(ServerboundPacket::Login {
+            protocol_version: PROTOCOL_VERSION,
+            name: name.to_string(),
+        });
+
+        Ok(Self {
+            connection: Some(connection),
+            name: name.to_string(),
+            ..Default::default()
+        })
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    pub fn send(&self, packet: ServerboundPacket) {
+        if let Some(connection) = &self.connection {
+            connection.send(packet);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::chunk::BlockID;
+use crate::network::{NetworkError, MAX_PACKET_SIZE};
+use nalgebra_glm::{vec3, Vec3};
+use std::io::{Read, Write};
+
+// Every value is written in big endian, strings and byte arrays are prefixed with their length
+#[derive(Default)]
+pub struct PacketWriter {
+    buffer: Vec<u8>,
+}
+
+impl PacketWriter {
+    pub fn new() -> Self {
+        Self { buffer: Vec::new() }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.buffer.push(value);
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.write_u8(value as u8);
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_i32(&mut self, value: i32) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_vec3(&mut self, value: &Vec3) {
+        self.write_f32(value.x);
+        self.write_f32(value.y);
+        self.write_f32(value.z);
+    }
+
+    pub fn write_bytes(&mut self, value: &[u8]) {
+        self.write_u32(value.len() as u32);
+        self.buffer.extend_from_slice(value);
+    }
+
+    pub fn write_string(&mut self, value: &str) {
+        self.write_bytes(value.as_bytes());
+    }
+
+    pub fn write_block(&mut self, block: BlockID) {
+        self.write_u8(block_to_id(block));
+    }
+}
+
+pub struct PacketReader<'a> {
+    buffer: &'a [u8],
+}
+
+impl<'a> PacketReader<'a> {
+    pub fn new(buffer: &'a [u8]) -> Self {
+        Self { buffer }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], NetworkError> {
+        if self.buffer.len() < length {
+            return Err(NetworkError::UnexpectedEnd);
+        }
+
+        let (taken, rest) = self.buffer.split_at(length);
+        self.buffer = rest;
+
+        Ok(taken)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], NetworkError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, NetworkError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, NetworkError> {
+        Ok(self.read_u8()? != 0)
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, NetworkError> {
+        Ok(u32::from_be_bytes(self.take_array()?))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, NetworkError> {
+        Ok(u64::from_be_bytes(self.take_array()?))
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32, NetworkError> {
+        Ok(i32::from_be_bytes(self.take_array()?))
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32, NetworkError> {
+        let value = f32::from_be_bytes(self.take_array()?);
+
+        // Positions with infinities or NaNs would poison the physics of the receiver
+        if !value.is_finite() {
+            return Err(NetworkError::InvalidData("non finite number"));
+        }
+
+        Ok(value)
+    }
+
+    pub fn read_vec3(&mut self) -> Result<Vec3, NetworkError> {
+        Ok(vec3(self.read_f32()?, self.read_f32()?, self.read_f32()?))
+    }
+
+    pub fn read_bytes(&mut self) -> Result<&'a [u8], NetworkError> {
+        let length = self.read_u32()? as usize;
+        self.take(length)
+    }
+
+    pub fn read_string(&mut self) -> Result<String, NetworkError> {
+        String::from_utf8(self.read_bytes()?.to_vec())
+            .map_err(|_| NetworkError::InvalidData("invalid UTF-8 string"))
+    }
+
+    pub fn read_block(&mut self) -> Result<BlockID, NetworkError> {
+        block_from_id(self.read_u8()?).ok_or(NetworkError::InvalidData("unknown block"))
+    }
+}
+
+// The blocks are sent as their index in `BlockID::ALL`
+pub fn block_to_id(block: BlockID) -> u8 {
+    BlockID::ALL.iter().position(|&b| b == block).unwrap() as u8
+}
+
+pub fn block_from_id(id: u8) -> Option<BlockID> {
+    BlockID::ALL.get(id as usize).copied()
+}
+
+// A frame is the length of the packet as a u32 followed by the packet itself
+pub fn write_frame(stream: &mut impl Write, packet: &[u8]) -> Result<(), NetworkError> {
+    if packet.len() > MAX_PACKET_SIZE {
+        return Err(NetworkError::PacketTooLarge(packet.len()));
+    }
+
+    stream.write_all(&(packet.len() as u32).to_be_bytes())?;
+    stream.write_all(packet)?;
+    stream.flush()?;
+
+    Ok(())
+}
+
+pub fn read_frame(stream: &mut impl Read) -> Result<Vec<u8>, NetworkError> {
+    let mut length = [0; 4];
+    stream.read_exact(&mut length)?;
+    let length = u32::from_be_bytes(length) as usize;
+
+    // Checked before allocating, the length comes from the other side
+    if length > MAX_PACKET_SIZE {
+        return Err(NetworkError::PacketTooLarge(length));
+    }
+
+    let mut packet = vec![0; length];
+    stream.read_exact(&mut packet)?;
+
+    Ok(packet)
+}
@@ -0,0 +1,76 @@
+use crate::network::codec::{read_frame, write_frame};
+use crate::network::packet::Packet;
+use crate::network::NetworkError;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::io;
+use std::net::{Shutdown, SocketAddr, TcpStream, ToSocketAddrs};
+use std::thread;
+
+// A TCP connection whose packets are read and written by two threads, so that the game never
+// waits for the network
+// `I` is the type of the packets received and `O` the type of the packets sent
+pub struct Connection<I: Packet, O: Packet> {
+    address: SocketAddr,
+    // The last item is the error that closed the connection
+    incoming_rx: Receiver<Result<I, NetworkError>>,
+    outgoing_tx: Sender<O>,
+}
+
+impl<I: Packet, O: Packet> Connection<I, O> {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        let address = stream.peer_addr()?;
+        stream.set_nodelay(true)?;
+
+        let (incoming_tx, incoming_rx) = unbounded();
+        let (outgoing_tx, outgoing_rx) = unbounded::<O>();
+
+        let mut reader = stream.try_clone()?;
+        thread::spawn(move || loop {
+            let packet = read_frame(&mut reader).and_then(|frame| I::from_bytes(&frame));
+            let is_closed = packet.is_err();
+
+            if incoming_tx.send(packet).is_err() || is_closed {
+                return;
+            }
+        });
+
+        let mut writer = stream;
+        thread::spawn(move || {
+            for packet in outgoing_rx.iter() {
+                if let Err(err) = write_frame(&mut writer, &packet.to_bytes()) {
+                    debug!("Stopped writing to {address}: {err}");
+                    break;
+                }
+            }
+
+            // Also stops the reading thread
+            let _ = writer.shutdown(Shutdown::Both);
+        });
+
+        Ok(Self {
+            address,
+            incoming_rx,
+            outgoing_tx,
+        })
+    }
+
+    pub fn connect(address: impl ToSocketAddrs) -> io::Result<Self> {
+        Self::new(TcpStream::connect(address)?)
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    // The packets are sent in order, after the ones sent before
+    pub fn send(&self, packet: O) {
+        // When the writing thread is gone, the reading thread reports the disconnection
+        let _ = self.outgoing_tx.send(packet);
+    }
+
+    // Packets received since the last call, in order
+    // An error means that the connection is closed, nothing comes after it
+    pub fn received_packets(&self) -> impl Iterator<Item = Result<I, NetworkError>> + '_ {
+        self.incoming_rx.try_iter()
+    }
+}
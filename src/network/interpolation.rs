@@ -0,0 +1,60 @@
+use nalgebra_glm::Vec3;
+use std::collections::VecDeque;
+use std::f32::consts::{PI, TAU};
+use std::time::{Duration, Instant};
+
+// The remote entities are shown this far in the past, so that there is almost always a snapshot
+// after the time shown to interpolate towards
+pub const INTERPOLATION_DELAY: Duration = Duration::from_millis(100);
+const MAX_SNAPSHOTS: usize = 32;
+
+// Positions of an entity received from the server, with the time they were received
+pub struct InterpolationBuffer {
+    snapshots: VecDeque<(Instant, Vec3, f32)>,
+}
+
+impl InterpolationBuffer {
+    pub fn new(position: Vec3, yaw: f32) -> Self {
+        let mut snapshots = VecDeque::new();
+        snapshots.push_back((Instant::now(), position, yaw));
+
+        Self { snapshots }
+    }
+
+    pub fn push(&mut self, time: Instant, position: Vec3, yaw: f32) {
+        if self.snapshots.len() == MAX_SNAPSHOTS {
+            self.snapshots.pop_front();
+        }
+
+        self.snapshots.push_back((time, position, yaw));
+    }
+
+    // Position and yaw of the entity at `time`, the latest snapshot when there is none after it
+    pub fn sample(&mut self, time: Instant) -> (Vec3, f32) {
+        // Only the last snapshot before `time` is still needed
+        while self.snapshots.len() >= 2 && self.snapshots[1].0 <= time {
+            self.snapshots.pop_front();
+        }
+
+        let (from_time, from_position, from_yaw) = self.snapshots[0];
+
+        match self.snapshots.get(1) {
+            Some(&(to_time, to_position, to_yaw)) if time > from_time => {
+                let alpha = (time - from_time).as_secs_f32() / (to_time - from_time).as_secs_f32();
+                let alpha = alpha.clamp(0.0, 1.0);
+
+                (
+                    from_position + (to_position - from_position) * alpha,
+                    interpolate_angle(from_yaw, to_yaw, alpha),
+                )
+            }
+            _ => (from_position, from_yaw),
+        }
+    }
+}
+
+// Turns the shortest way, from 350° to 10° goes through 0°
+fn interpolate_angle(from: f32, to: f32, alpha: f32) -> f32 {
+    let difference = (to - from + PI).rem_euclid(TAU) - PI;
+    from + difference * alpha
+}
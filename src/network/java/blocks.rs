@@ -0,0 +1,91 @@
+use crate::chunk::BlockID;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+// Ids of the unknown blocks already reported, a chunk is full of the same ones
+static REPORTED_UNKNOWN_BLOCKS: Mutex<BTreeSet<u32>> = Mutex::new(BTreeSet::new());
+
+// Block states of 1.12.2 are the id of the block in the upper bits and its metadata (orientation,
+// variant, ...) in the 4 lower bits
+pub fn block_of_state(state: u32) -> BlockID {
+    match state >> 4 {
+        0 => BlockID::Air,
+        1 => BlockID::Stone,
+        2 => BlockID::GrassBlock,
+        3 => BlockID::Dirt,
+        4 => BlockID::Cobblestone,
+        5 => BlockID::OakPlanks,
+        7 => BlockID::Bedrock,
+        10 | 11 => BlockID::Lava,
+        17 => BlockID::OakLog,
+        18 => BlockID::OakLeaves,
+        20 => BlockID::Glass,
+        49 => BlockID::Obsidian,
+        // There is no water, and the plants and snow layers would be full cubes
+        8 | 9 | 31 | 32 | 37 | 38 | 39 | 40 | 78 | 106 | 175 => BlockID::Air,
+        // Anything else is at least something the player can stand on
+        id => {
+            if REPORTED_UNKNOWN_BLOCKS.lock().unwrap().insert(id) {
+                warn!("Unknown block {id} (state {state}), replaced with stone");
+            }
+            BlockID::Stone
+        }
+    }
+}
+
+// Item the player holds in creative mode to place the block, the item ids of the blocks are their
+// block ids
+pub fn item_of_block(block: BlockID) -> Option<i16> {
+    match block {
+        BlockID::Air => None,
+        BlockID::Stone => Some(1),
+        BlockID::GrassBlock => Some(2),
+        BlockID::Dirt => Some(3),
+        BlockID::Cobblestone => Some(4),
+        BlockID::OakPlanks => Some(5),
+        BlockID::Bedrock => Some(7),
+        BlockID::Lava => Some(327), // Lava bucket
+        BlockID::OakLog => Some(17),
+        BlockID::OakLeaves => Some(18),
+        BlockID::Glass => Some(20),
+        BlockID::Obsidian => Some(49),
+        BlockID::Debug | BlockID::Debug2 => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_is_ignored() {
+        // Spruce planks and birch logs facing east
+        assert_eq!(block_of_state((5 << 4) | 1), BlockID::OakPlanks);
+        assert_eq!(block_of_state((17 << 4) | 6), BlockID::OakLog);
+        // Flowing lava
+        assert_eq!(block_of_state((10 << 4) | 3), BlockID::Lava);
+    }
+
+    #[test]
+    fn unsupported_blocks() {
+        // Water and tall grass
+        assert_eq!(block_of_state(9 << 4), BlockID::Air);
+        assert_eq!(block_of_state((31 << 4) | 1), BlockID::Air);
+        // Chests and furnaces, reported once
+        assert_eq!(block_of_state(54 << 4), BlockID::Stone);
+        assert_eq!(block_of_state((61 << 4) | 2), BlockID::Stone);
+        assert!(REPORTED_UNKNOWN_BLOCKS.lock().unwrap().contains(&54));
+    }
+
+    #[test]
+    fn placed_blocks_are_the_ones_of_their_item() {
+        for block in BlockID::ALL {
+            let item = match item_of_block(block) {
+                Some(item) if block != BlockID::Lava => item,
+                _ => continue,
+            };
+
+            assert_eq!(block_of_state((item as u32) << 4), block);
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use crate::chunk::ChunkColumn;
+use crate::network::java::blocks::block_of_state;
+use crate::network::java::codec::JavaReader;
+use crate::network::NetworkError;
+
+// Above this number of bits per block the sections have no palette and store the states directly
+const MAX_PALETTE_BITS: u8 = 8;
+const SECTION_VOLUME: usize = 16 * 16 * 16;
+const LIGHT_SIZE: usize = SECTION_VOLUME / 2;
+
+// Reads the sections of a Chunk Data packet into an empty column
+// Only the sections in `primary_bit_mask` are sent, the others are air
+pub fn read_sections(
+    data: &[u8],
+    primary_bit_mask: i32,
+    has_sky_light: bool,
+    column: &ChunkColumn,
+) -> Result<(), NetworkError> {
+    let mut reader = JavaReader::new(data);
+
+    for section_y in 0..16 {
+        if primary_bit_mask & (1 << section_y) == 0 {
+            continue;
+        }
+
+        let bits_per_block = reader.read_u8()?;
+        if bits_per_block == 0 || bits_per_block > 32 {
+            return Err(NetworkError::InvalidData("invalid bits per block"));
+        }
+        // The palette length is sent even for the direct sections, and is then 0
+        let palette_length = reader.read_varint()?;
+        let palette = (0..palette_length.max(0))
+            .map(|_| reader.read_varint().map(|state| state as u32))
+            .collect::<Result<Vec<_>, _>>()?;
+        let bits_per_block = if bits_per_block <= MAX_PALETTE_BITS {
+            bits_per_block.max(4)
+        } else {
+            bits_per_block
+        };
+
+        let longs = reader.read_varint()?;
+        if longs < 0 || longs as usize != SECTION_VOLUME * bits_per_block as usize / 64 {
+            return Err(NetworkError::InvalidData("wrong section data length"));
+        }
+        let longs = (0..longs)
+            .map(|_| reader.read_u64())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mask = (1u64 << bits_per_block) - 1;
+
+        for i in 0..SECTION_VOLUME {
+            // A value may start in a long and end in the next one
+            let bit = i * bits_per_block as usize;
+            let (long, offset) = (bit / 64, bit % 64);
+
+            let mut value = longs[long] >> offset;
+            if offset + bits_per_block as usize > 64 {
+                value |= longs[long + 1] << (64 - offset);
+            }
+            let value = (value & mask) as u32;
+
+            let state = if bits_per_block <= MAX_PALETTE_BITS {
+                *palette
+                    .get(value as usize)
+                    .ok_or(NetworkError::InvalidData("block outside of the palette"))?
+            } else {
+                value
+            };
+
+            let block = block_of_state(state);
+            if !block.is_air() {
+                let i = i as u32;
+                column.set_block(i % 16, section_y * 16 + i / 256, (i / 16) % 16, block);
+            }
+        }
+
+        // The light is computed by the client
+        reader.take(LIGHT_SIZE)?;
+        if has_sky_light {
+            reader.take(LIGHT_SIZE)?;
+        }
+    }
+
+    // The biomes follow in the full chunks, they are not used
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::BlockID;
+    use crate::network::java::codec::read_frame;
+    use crate::network::java::packet::PlayClientbound;
+
+    // Chunk (2, -3) with a single section: bedrock at y = 0, stone from y = 1 to 3 and a chest at
+    // (5, 4, 6), framed with a compression threshold of 256
+    const CHUNK_FRAME: &[u8] =
+        include_bytes!("../../../tests/fixtures/java/chunk_data_compressed_frame.bin");
+
+    #[test]
+    fn compressed_chunk_data() {
+        let packet = read_frame(&mut &CHUNK_FRAME[..], Some(256)).unwrap();
+        assert_eq!(
+            packet,
+            include_bytes!("../../../tests/fixtures/java/chunk_data.bin")
+        );
+
+        let (primary_bit_mask, data) = match PlayClientbound::from_bytes(&packet).unwrap() {
+            PlayClientbound::ChunkData {
+                x: 2,
+                z: -3,
+                full_chunk: true,
+                primary_bit_mask,
+                data,
+            } => (primary_bit_mask, data),
+            packet => panic!("Unexpected packet {packet:?}"),
+        };
+
+        let column = ChunkColumn::new();
+        read_sections(&data, primary_bit_mask, true, &column).unwrap();
+        let block = |x, y: u32, z| column.get_chunk(y as i32 / 16).get_block(x, y % 16, z);
+
+        assert_eq!(block(0, 0, 0), BlockID::Bedrock);
+        assert_eq!(block(15, 3, 15), BlockID::Stone);
+        assert_eq!(block(7, 4, 7), BlockID::Air);
+        // Unknown blocks are replaced with stone
+        assert_eq!(block(5, 4, 6), BlockID::Stone);
+        // Sections that are not sent are air
+        assert_eq!(block(0, 16, 0), BlockID::Air);
+    }
+
+    #[test]
+    fn section_with_the_wrong_data_length_is_rejected() {
+        let packet = include_bytes!("../../../tests/fixtures/java/chunk_data.bin");
+        let data = match PlayClientbound::from_bytes(packet).unwrap() {
+            PlayClientbound::ChunkData { data, .. } => data,
+            packet => panic!("Unexpected packet {packet:?}"),
+        };
+
+        // Cut in the middle of the block states
+        assert!(read_sections(&data[..1000], 1, true, &ChunkColumn::new()).is_err());
+        // The sky light is not sent in the nether
+        assert!(read_sections(&data, 0b11, false, &ChunkColumn::new()).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use crate::network::java::codec::{read_frame, write_frame};
+use crate::network::java::packet::{
+    LoginClientbound, LoginServerbound, PlayClientbound, PlayServerbound,
+};
+use crate::network::java::JAVA_DEFAULT_PORT;
+use crate::network::NetworkError;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::io::BufReader;
+use std::net::{Shutdown, TcpStream};
+use std::thread;
+use std::time::Instant;
+
+// Connection to a vanilla server in the play state, read and written by two threads like
+// `Connection`
+pub struct JavaConnection {
+    incoming_rx: Receiver<Result<PlayClientbound, NetworkError>>,
+    outgoing_tx: Sender<PlayServerbound>,
+}
+
+impl JavaConnection {
+    // Logs in without encryption, which only the servers in offline mode accept
+    // The address is "host" or "host:port"
+    pub fn connect(address: &str, name: &str) -> Result<Self, NetworkError> {
+        let (host, port) = match address.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse()
+                    .map_err(|_| NetworkError::InvalidData("invalid port"))?,
+            ),
+            None => (address, JAVA_DEFAULT_PORT),
+        };
+
+        let mut stream = TcpStream::connect((host, port))?;
+        stream.set_nodelay(true)?;
+
+        let handshake = LoginServerbound::Handshake {
+            address: host.to_string(),
+            port,
+        };
+        let login_start = LoginServerbound::LoginStart {
+            name: name.to_string(),
+        };
+        write_frame(&mut stream, &handshake.to_bytes(), None)?;
+        write_frame(&mut stream, &login_start.to_bytes(), None)?;
+
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut compression_threshold = None;
+
+        loop {
+            let frame = read_frame(&mut reader, compression_threshold)?;
+
+            match LoginClientbound::from_bytes(&frame)? {
+                LoginClientbound::Disconnect { reason } => {
+                    return Err(NetworkError::Disconnected(reason))
+                }
+                LoginClientbound::EncryptionRequest => {
+                    return Err(NetworkError::Disconnected(
+                        "The server is in online mode".to_string(),
+                    ))
+                }
+                LoginClientbound::SetCompression { threshold } => {
+                    compression_threshold = (threshold >= 0).then_some(threshold as usize);
+                }
+                LoginClientbound::LoginSuccess { uuid, name } => {
+                    info!("Logged in as {name} ({uuid})");
+                    break;
+                }
+            }
+        }
+
+        let (incoming_tx, incoming_rx) = unbounded();
+        let (outgoing_tx, outgoing_rx) = unbounded::<PlayServerbound>();
+
+        thread::spawn(move || loop {
+            let packet = read_frame(&mut reader, compression_threshold)
+                .and_then(|frame| PlayClientbound::from_bytes(&frame));
+            let is_closed = packet.is_err();
+
+            if incoming_tx.send(packet).is_err() || is_closed {
+                return;
+            }
+        });
+
+        let mut writer = stream;
+        thread::spawn(move || {
+            for packet in outgoing_rx.iter() {
+                if let Err(err) =
+                    write_frame(&mut writer, &packet.to_bytes(), compression_threshold)
+                {
+                    debug!("Stopped writing to the server: {err}");
+                    break;
+                }
+            }
+
+            let _ = writer.shutdown(Shutdown::Both);
+        });
+
+        Ok(Self {
+            incoming_rx,
+            outgoing_tx,
+        })
+    }
+
+    pub fn send(&self, packet: PlayServerbound) {
+        let _ = self.outgoing_tx.send(packet);
+    }
+
+    // An error means that the connection is closed, nothing comes after it
+    pub fn received_packets(
+        &self,
+    ) -> impl Iterator<Item = Result<PlayClientbound, NetworkError>> + '_ {
+        self.incoming_rx.try_iter()
+    }
+}
+
+// Connection of the client to a vanilla server, used instead of `ClientNetwork`
+#[derive(Default)]
+pub struct JavaNetwork {
+    pub connection: Option<JavaConnection>,
+    pub entity_id: Option<i32>,
+    // Whether the sky light is sent with the chunks, only in the overworld
+    pub has_sky_light: bool,
+    // The server ignores the movements until the client confirmed its position
+    pub has_spawned: bool,
+    pub movement_sent: Option<Instant>,
+}
+
+impl JavaNetwork {
+    pub fn connect(address: &str, name: &str) -> Result<Self, NetworkError> {
+        Ok(Self {
+            connection: Some(JavaConnection::connect(address, name)?),
+            has_sky_light: true,
+            ..Default::default()
+        })
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    pub fn send(&self, packet: PlayServerbound) {
+        if let Some(connection) = &self.connection {
+            connection.send(packet);
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use crate::network::java::JAVA_MAX_PACKET_SIZE;
+use crate::network::NetworkError;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+
+// Values are big endian, except the VarInts which are little endian groups of 7 bits
+// Strings are prefixed with their length as a VarInt
+#[derive(Default)]
+pub struct JavaWriter {
+    buffer: Vec<u8>,
+}
+
+impl JavaWriter {
+    pub fn new() -> Self {
+        Self { buffer: Vec::new() }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.buffer.push(value);
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.write_u8(value as u8);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_i16(&mut self, value: i16) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_i64(&mut self, value: i64) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_f64(&mut self, value: f64) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_varint(&mut self, value: i32) {
+        write_varint(&mut self.buffer, value);
+    }
+
+    pub fn write_string(&mut self, value: &str) {
+        self.write_varint(value.len() as i32);
+        self.buffer.extend_from_slice(value.as_bytes());
+    }
+
+    // 26 bits for x and z, 12 bits for y
+    pub fn write_position(&mut self, x: i32, y: i32, z: i32) {
+        let position =
+            ((x as u64 & 0x3FFFFFF) << 38) | ((y as u64 & 0xFFF) << 26) | (z as u64 & 0x3FFFFFF);
+
+        self.buffer.extend_from_slice(&position.to_be_bytes());
+    }
+}
+
+pub struct JavaReader<'a> {
+    buffer: &'a [u8],
+}
+
+impl<'a> JavaReader<'a> {
+    pub fn new(buffer: &'a [u8]) -> Self {
+        Self { buffer }
+    }
+
+    pub fn remaining(&self) -> &'a [u8] {
+        self.buffer
+    }
+
+    pub fn take(&mut self, length: usize) -> Result<&'a [u8], NetworkError> {
+        if self.buffer.len() < length {
+            return Err(NetworkError::UnexpectedEnd);
+        }
+
+        let (taken, rest) = self.buffer.split_at(length);
+        self.buffer = rest;
+
+        Ok(taken)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], NetworkError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, NetworkError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, NetworkError> {
+        Ok(self.read_u8()? != 0)
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32, NetworkError> {
+        Ok(i32::from_be_bytes(self.take_array()?))
+    }
+
+    pub fn read_i64(&mut self) -> Result<i64, NetworkError> {
+        Ok(i64::from_be_bytes(self.take_array()?))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, NetworkError> {
+        Ok(u64::from_be_bytes(self.take_array()?))
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32, NetworkError> {
+        Ok(f32::from_be_bytes(self.take_array()?))
+    }
+
+    pub fn read_f64(&mut self) -> Result<f64, NetworkError> {
+        Ok(f64::from_be_bytes(self.take_array()?))
+    }
+
+    pub fn read_varint(&mut self) -> Result<i32, NetworkError> {
+        read_varint(|| self.read_u8())
+    }
+
+    pub fn read_string(&mut self) -> Result<String, NetworkError> {
+        let length = self.read_varint()?;
+        if length < 0 {
+            return Err(NetworkError::InvalidData("negative string length"));
+        }
+
+        String::from_utf8(self.take(length as usize)?.to_vec())
+            .map_err(|_| NetworkError::InvalidData("invalid UTF-8 string"))
+    }
+
+    pub fn read_position(&mut self) -> Result<(i32, i32, i32), NetworkError> {
+        let position = self.read_u64()? as i64;
+
+        // The arithmetic shifts extend the signs
+        let x = (position >> 38) as i32;
+        let y = ((position << 26) >> 52) as i32;
+        let z = ((position << 38) >> 38) as i32;
+
+        Ok((x, y, z))
+    }
+}
+
+fn write_varint(buffer: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+
+    loop {
+        if value & !0x7F == 0 {
+            buffer.push(value as u8);
+            return;
+        }
+
+        buffer.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+}
+
+fn read_varint(mut read_u8: impl FnMut() -> Result<u8, NetworkError>) -> Result<i32, NetworkError> {
+    let mut value = 0u32;
+
+    for i in 0..5 {
+        let byte = read_u8()?;
+        value |= ((byte & 0x7F) as u32) << (7 * i);
+
+        if byte & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+
+    Err(NetworkError::InvalidData("VarInt longer than 5 bytes"))
+}
+
+// Frames are prefixed with their length, once the compression is enabled the packets larger than
+// the threshold are compressed with zlib and prefixed with their uncompressed length
+pub fn write_frame(
+    stream: &mut impl Write,
+    packet: &[u8],
+    compression_threshold: Option<usize>,
+) -> Result<(), NetworkError> {
+    let mut frame = Vec::new();
+
+    match compression_threshold {
+        Some(threshold) if packet.len() >= threshold => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(packet)?;
+            let compressed = encoder.finish()?;
+
+            let mut data_length = Vec::new();
+            write_varint(&mut data_length, packet.len() as i32);
+
+            write_varint(&mut frame, (data_length.len() + compressed.len()) as i32);
+            frame.extend_from_slice(&data_length);
+            frame.extend_from_slice(&compressed);
+        }
+        // A data length of 0 means uncompressed
+        Some(_) => {
+            write_varint(&mut frame, packet.len() as i32 + 1);
+            frame.push(0);
+            frame.extend_from_slice(packet);
+        }
+        None => {
+            write_varint(&mut frame, packet.len() as i32);
+            frame.extend_from_slice(packet);
+        }
+    }
+
+    stream.write_all(&frame)?;
+    Ok(())
+}
+
+pub fn read_frame(
+    stream: &mut impl Read,
+    compression_threshold: Option<usize>,
+) -> Result<Vec<u8>, NetworkError> {
+    let length = read_varint(|| {
+        let mut byte = [0u8];
+        stream.read_exact(&mut byte)?;
+        Ok(byte[0])
+    })?;
+
+    if length < 0 || length as usize > JAVA_MAX_PACKET_SIZE {
+        return Err(NetworkError::PacketTooLarge(length as usize));
+    }
+
+    let mut frame = vec![0u8; length as usize];
+    stream.read_exact(&mut frame)?;
+
+    if compression_threshold.is_none() {
+        return Ok(frame);
+    }
+
+    let mut reader = JavaReader::new(&frame);
+    let data_length = reader.read_varint()?;
+
+    if data_length == 0 {
+        return Ok(reader.remaining().to_vec());
+    }
+    if data_length < 0 || data_length as usize > JAVA_MAX_PACKET_SIZE {
+        return Err(NetworkError::PacketTooLarge(data_length as usize));
+    }
+
+    let mut packet = Vec::with_capacity(data_length as usize);
+    ZlibDecoder::new(reader.remaining())
+        .take(data_length as u64)
+        .read_to_end(&mut packet)?;
+
+    if packet.len() != data_length as usize {
+        return Err(NetworkError::InvalidData("wrong uncompressed length"));
+    }
+
+    Ok(packet)
+}
@@ -0,0 +1,14 @@
+// Client side of the protocol of the vanilla Java Edition 1.12.2 servers, in offline mode
+// Only what is needed to play in the terrain of the server is implemented: the login, the chunks,
+// the block changes, the movements of the player, digging and placing blocks, and the chat
+pub mod blocks;
+pub mod chunk_data;
+pub mod client;
+pub mod codec;
+pub mod packet;
+
+// Minecraft 1.12.2
+pub const JAVA_PROTOCOL_VERSION: i32 = 340;
+pub const JAVA_DEFAULT_PORT: u16 = 25565;
+// Largest frame a vanilla server sends, the length is a VarInt of at most 3 bytes
+pub const JAVA_MAX_PACKET_SIZE: usize = 2 * 1024 * 1024;
@@ -0,0 +1,494 @@
+use crate::network::java::codec::{JavaReader, JavaWriter};
+use crate::network::java::JAVA_PROTOCOL_VERSION;
+use crate::network::NetworkError;
+
+// Packets of the handshake and login states, the connection then switches to the play state
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoginServerbound {
+    Handshake { address: String, port: u16 },
+    LoginStart { name: String },
+}
+
+impl LoginServerbound {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = JavaWriter::new();
+
+        match self {
+            LoginServerbound::Handshake { address, port } => {
+                writer.write_varint(0x00);
+                writer.write_varint(JAVA_PROTOCOL_VERSION);
+                writer.write_string(address);
+                writer.write_u16(*port);
+                // Next state: login
+                writer.write_varint(2);
+            }
+            LoginServerbound::LoginStart { name } => {
+                writer.write_varint(0x00);
+                writer.write_string(name);
+            }
+        }
+
+        writer.into_bytes()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoginClientbound {
+    Disconnect { reason: String },
+    // Only sent by the servers in online mode, which are not supported
+    EncryptionRequest,
+    LoginSuccess { uuid: String, name: String },
+    // The packets larger than the threshold are compressed from now on
+    SetCompression { threshold: i32 },
+}
+
+impl LoginClientbound {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, NetworkError> {
+        let mut reader = JavaReader::new(bytes);
+
+        Ok(match reader.read_varint()? {
+            0x00 => LoginClientbound::Disconnect {
+                reason: chat_to_text(&reader.read_string()?),
+            },
+            0x01 => LoginClientbound::EncryptionRequest,
+            0x02 => LoginClientbound::LoginSuccess {
+                uuid: reader.read_string()?,
+                name: reader.read_string()?,
+            },
+            0x03 => LoginClientbound::SetCompression {
+                threshold: reader.read_varint()?,
+            },
+            id => return Err(NetworkError::UnknownPacket(id as u8)),
+        })
+    }
+}
+
+// Sent by the client once logged in
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlayServerbound {
+    TeleportConfirm {
+        teleport_id: i32,
+    },
+    Chat {
+        message: String,
+    },
+    KeepAlive {
+        id: i64,
+    },
+    // Position of the feet, the angles are in degrees
+    PlayerPositionAndLook {
+        x: f64,
+        y: f64,
+        z: f64,
+        yaw: f32,
+        pitch: f32,
+        on_ground: bool,
+    },
+    PlayerDigging {
+        status: i32,
+        x: i32,
+        y: i32,
+        z: i32,
+        face: u8,
+    },
+    HeldItemChange {
+        slot: i16,
+    },
+    // Puts an item in a slot of the inventory, only accepted in creative mode
+    CreativeInventoryAction {
+        slot: i16,
+        item: Option<(i16, u8, i16)>,
+    },
+    // Uses the held item against the `face` of the block
+    PlayerBlockPlacement {
+        x: i32,
+        y: i32,
+        z: i32,
+        face: i32,
+    },
+}
+
+impl PlayServerbound {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = JavaWriter::new();
+
+        match self {
+            PlayServerbound::TeleportConfirm { teleport_id } => {
+                writer.write_varint(0x00);
+                writer.write_varint(*teleport_id);
+            }
+            PlayServerbound::Chat { message } => {
+                writer.write_varint(0x02);
+                writer.write_string(message);
+            }
+            PlayServerbound::KeepAlive { id } => {
+                writer.write_varint(0x0B);
+                writer.write_i64(*id);
+            }
+            PlayServerbound::PlayerPositionAndLook {
+                x,
+                y,
+                z,
+                yaw,
+                pitch,
+                on_ground,
+            } => {
+                writer.write_varint(0x0E);
+                writer.write_f64(*x);
+                writer.write_f64(*y);
+                writer.write_f64(*z);
+                writer.write_f32(*yaw);
+                writer.write_f32(*pitch);
+                writer.write_bool(*on_ground);
+            }
+            PlayServerbound::PlayerDigging {
+                status,
+                x,
+                y,
+                z,
+                face,
+            } => {
+                writer.write_varint(0x14);
+                writer.write_varint(*status);
+                writer.write_position(*x, *y, *z);
+                writer.write_u8(*face);
+            }
+            PlayServerbound::HeldItemChange { slot } => {
+                writer.write_varint(0x1A);
+                writer.write_i16(*slot);
+            }
+            PlayServerbound::CreativeInventoryAction { slot, item } => {
+                writer.write_varint(0x1B);
+                writer.write_i16(*slot);
+
+                match item {
+                    Some((id, count, damage)) => {
+                        writer.write_i16(*id);
+                        writer.write_u8(*count);
+                        writer.write_i16(*damage);
+                        // No NBT
+                        writer.write_u8(0);
+                    }
+                    None => writer.write_i16(-1),
+                }
+            }
+            PlayServerbound::PlayerBlockPlacement { x, y, z, face } => {
+                writer.write_varint(0x1F);
+                writer.write_position(*x, *y, *z);
+                writer.write_varint(*face);
+                // Main hand
+                writer.write_varint(0);
+                // Cursor in the middle of the face
+                writer.write_f32(0.5);
+                writer.write_f32(0.5);
+                writer.write_f32(0.5);
+            }
+        }
+
+        writer.into_bytes()
+    }
+}
+
+// Sent by the server once logged in, the packets that are not handled are only skipped
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlayClientbound {
+    BlockChange {
+        x: i32,
+        y: i32,
+        z: i32,
+        state: u32,
+    },
+    Chat {
+        message: String,
+    },
+    // Changes in the same chunk, the positions are relative to the chunk
+    MultiBlockChange {
+        chunk_x: i32,
+        chunk_z: i32,
+        changes: Vec<(u8, u8, u8, u32)>,
+    },
+    Disconnect {
+        reason: String,
+    },
+    UnloadChunk {
+        x: i32,
+        z: i32,
+    },
+    // Among others, the start and the end of the rain and the strength of the thunderstorms
+    ChangeGameState {
+        reason: u8,
+        value: f32,
+    },
+    KeepAlive {
+        id: i64,
+    },
+    // The sections are read with `read_sections`
+    ChunkData {
+        x: i32,
+        z: i32,
+        full_chunk: bool,
+        primary_bit_mask: i32,
+        data: Vec<u8>,
+    },
+    JoinGame {
+        entity_id: i32,
+        game_mode: u8,
+        dimension: i32,
+    },
+    // Teleports the player, the flags tell which values are relative to the current ones
+    PlayerPositionAndLook {
+        x: f64,
+        y: f64,
+        z: f64,
+        yaw: f32,
+        pitch: f32,
+        flags: u8,
+        teleport_id: i32,
+    },
+    Respawn {
+        dimension: i32,
+        game_mode: u8,
+    },
+    // The time of day is negative when the daylight cycle is stopped
+    TimeUpdate {
+        world_age: i64,
+        time_of_day: i64,
+    },
+    Other {
+        id: i32,
+    },
+}
+
+impl PlayClientbound {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, NetworkError> {
+        let mut reader = JavaReader::new(bytes);
+
+        Ok(match reader.read_varint()? {
+            0x0B => {
+                let (x, y, z) = reader.read_position()?;
+                PlayClientbound::BlockChange {
+                    x,
+                    y,
+                    z,
+                    state: reader.read_varint()? as u32,
+                }
+            }
+            0x0F => PlayClientbound::Chat {
+                message: chat_to_text(&reader.read_string()?),
+            },
+            0x10 => {
+                let chunk_x = reader.read_i32()?;
+                let chunk_z = reader.read_i32()?;
+                let count = reader.read_varint()?;
+                let changes = (0..count.max(0))
+                    .map(|_| {
+                        let horizontal = reader.read_u8()?;
+                        let y = reader.read_u8()?;
+                        let state = reader.read_varint()? as u32;
+                        Ok((horizontal >> 4, y, horizontal & 0x0F, state))
+                    })
+                    .collect::<Result<Vec<_>, NetworkError>>()?;
+
+                PlayClientbound::MultiBlockChange {
+                    chunk_x,
+                    chunk_z,
+                    changes,
+                }
+            }
+            0x1A => PlayClientbound::Disconnect {
+                reason: chat_to_text(&reader.read_string()?),
+            },
+            0x1D => PlayClientbound::UnloadChunk {
+                x: reader.read_i32()?,
+                z: reader.read_i32()?,
+            },
+            0x1E => PlayClientbound::ChangeGameState {
+                reason: reader.read_u8()?,
+                value: reader.read_f32()?,
+            },
+            0x1F => PlayClientbound::KeepAlive {
+                id: reader.read_i64()?,
+            },
+            0x20 => {
+                let x = reader.read_i32()?;
+                let z = reader.read_i32()?;
+                let full_chunk = reader.read_bool()?;
+                let primary_bit_mask = reader.read_varint()?;
+                let length = reader.read_varint()?;
+                if length < 0 {
+                    return Err(NetworkError::InvalidData("negative chunk data length"));
+                }
+
+                PlayClientbound::ChunkData {
+                    x,
+                    z,
+                    full_chunk,
+                    primary_bit_mask,
+                    data: reader.take(length as usize)?.to_vec(),
+                }
+            }
+            0x23 => PlayClientbound::JoinGame {
+                entity_id: reader.read_i32()?,
+                game_mode: reader.read_u8()?,
+                dimension: reader.read_i32()?,
+            },
+            0x2F => PlayClientbound::PlayerPositionAndLook {
+                x: reader.read_f64()?,
+                y: reader.read_f64()?,
+                z: reader.read_f64()?,
+                yaw: reader.read_f32()?,
+                pitch: reader.read_f32()?,
+                flags: reader.read_u8()?,
+                teleport_id: reader.read_varint()?,
+            },
+            0x35 => {
+                let dimension = reader.read_i32()?;
+                let _difficulty = reader.read_u8()?;
+
+                PlayClientbound::Respawn {
+                    dimension,
+                    game_mode: reader.read_u8()?,
+                }
+            }
+            0x47 => PlayClientbound::TimeUpdate {
+                world_age: reader.read_i64()?,
+                time_of_day: reader.read_i64()?,
+            },
+            id => PlayClientbound::Other { id },
+        })
+    }
+}
+
+// The texts are JSON chat components, only their "text" strings are kept
+pub fn chat_to_text(json: &str) -> String {
+    let mut text = String::new();
+    let mut rest = json;
+
+    while let Some(start) = rest.find("\"text\"") {
+        rest = rest[start + "\"text\"".len()..].trim_start();
+        rest = match rest.strip_prefix(':') {
+            Some(rest) => rest.trim_start(),
+            None => continue,
+        };
+        rest = match rest.strip_prefix('"') {
+            Some(rest) => rest,
+            None => continue,
+        };
+
+        let mut chars = rest.char_indices();
+        let mut end = rest.len();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    end = i;
+                    break;
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => text.push('\n'),
+                    Some((_, c)) => text.push(c),
+                    None => {}
+                },
+                c => text.push(c),
+            }
+        }
+        rest = &rest[(end + 1).min(rest.len())..];
+    }
+
+    // Plain strings are valid chat components too
+    if text.is_empty() && !json.trim_start().starts_with('{') {
+        return json.trim_matches('"').to_string();
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Packets laid out as a 1.12.2 server sends them, without the frame
+    macro_rules! fixture {
+        ($name:literal) => {
+            include_bytes!(concat!("../../../tests/fixtures/java/", $name))
+        };
+    }
+
+    #[test]
+    fn login_packets() {
+        assert_eq!(
+            LoginClientbound::from_bytes(fixture!("login_success.bin")).unwrap(),
+            LoginClientbound::LoginSuccess {
+                uuid: "069a79f4-44e9-4726-a5be-fca90e38aaf5".to_string(),
+                name: "Notch".to_string(),
+            }
+        );
+        assert_eq!(
+            LoginClientbound::from_bytes(fixture!("set_compression.bin")).unwrap(),
+            LoginClientbound::SetCompression { threshold: 256 }
+        );
+    }
+
+    #[test]
+    fn play_packets() {
+        let cases = [
+            (
+                &fixture!("join_game.bin")[..],
+                PlayClientbound::JoinGame {
+                    entity_id: 42,
+                    game_mode: 1,
+                    dimension: 0,
+                },
+            ),
+            (
+                fixture!("block_change.bin"),
+                PlayClientbound::BlockChange {
+                    x: -5,
+                    y: 64,
+                    z: 300,
+                    state: 20 << 4,
+                },
+            ),
+            (
+                fixture!("multi_block_change.bin"),
+                PlayClientbound::MultiBlockChange {
+                    chunk_x: 2,
+                    chunk_z: -3,
+                    changes: vec![(3, 70, 7, 1 << 4), (15, 255, 0, 0)],
+                },
+            ),
+            (
+                fixture!("chat.bin"),
+                PlayClientbound::Chat {
+                    message: "<Steve> hello".to_string(),
+                },
+            ),
+            (
+                fixture!("keep_alive.bin"),
+                PlayClientbound::KeepAlive {
+                    id: 0x0123456789ABCDEF,
+                },
+            ),
+            (
+                fixture!("time_update.bin"),
+                PlayClientbound::TimeUpdate {
+                    world_age: 24000,
+                    time_of_day: -6000,
+                },
+            ),
+        ];
+
+        for (bytes, expected) in cases {
+            assert_eq!(PlayClientbound::from_bytes(bytes).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn truncated_packets_are_rejected() {
+        let bytes = fixture!("block_change.bin");
+
+        assert!(matches!(
+            PlayClientbound::from_bytes(&bytes[..bytes.len() - 3]),
+            Err(NetworkError::UnexpectedEnd)
+        ));
+    }
+}
@@ -0,0 +1,59 @@
+pub mod chunk_data;
+pub mod client;
+pub mod codec;
+pub mod connection;
+pub mod interpolation;
+#[cfg(feature = "java_protocol")]
+pub mod java;
+pub mod packet;
+pub mod prediction;
+pub mod server;
+
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+// Must be the same on both sides, the server refuses the clients of other versions
+pub const PROTOCOL_VERSION: u32 = 5;
+pub const DEFAULT_PORT: u16 = 25565;
+// The largest packet is a chunk column, even without any compression
+pub const MAX_PACKET_SIZE: usize = 1024 * 1024;
+
+// The server checks that the clients are still there at this interval
+pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(5);
+pub const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub enum NetworkError {
+    Io(io::Error),
+    PacketTooLarge(usize),
+    UnexpectedEnd,
+    UnknownPacket(u8),
+    InvalidData(&'static str),
+    // The server closed the connection and told why
+    Disconnected(String),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::Io(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                write!(f, "Connection closed")
+            }
+            NetworkError::Io(err) => write!(f, "{err}"),
+            NetworkError::PacketTooLarge(size) => write!(f, "Packet of {size} bytes is too large"),
+            NetworkError::UnexpectedEnd => write!(f, "Packet ended unexpectedly"),
+            NetworkError::UnknownPacket(id) => write!(f, "Unknown packet {id:#04x}"),
+            NetworkError::InvalidData(what) => write!(f, "Invalid packet: {what}"),
+            NetworkError::Disconnected(reason) => write!(f, "Disconnected: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+impl From<io::Error> for NetworkError {
+    fn from(err: io::Error) -> Self {
+        NetworkError::Io(err)
+    }
+}
@@ -0,0 +1,496 @@
+use crate::chunk::BlockID;
+use crate::health::DamageSource;
+use crate::mob::MobKind;
+use crate::network::codec::{PacketReader, PacketWriter};
+use crate::network::NetworkError;
+use crate::player::PlayerInput;
+use crate::weather::WeatherKind;
+use nalgebra_glm::Vec3;
+
+pub trait Packet: Sized + Send + 'static {
+    fn encode(&self, writer: &mut PacketWriter);
+    fn decode(reader: &mut PacketReader) -> Result<Self, NetworkError>;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::new();
+        self.encode(&mut writer);
+        writer.into_bytes()
+    }
+
+    // The whole frame must be consumed, trailing bytes mean that both sides disagree on the format
+    fn from_bytes(bytes: &[u8]) -> Result<Self, NetworkError> {
+        let mut reader = PacketReader::new(bytes);
+        let packet = Self::decode(&mut reader)?;
+
+        if !reader.is_empty() {
+            return Err(NetworkError::InvalidData("trailing bytes after packet"));
+        }
+
+        Ok(packet)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EntityKind {
+    Player { name: String },
+    Mob(MobKind),
+}
+
+impl EntityKind {
+    fn encode(&self, writer: &mut PacketWriter) {
+        match self {
+            EntityKind::Player { name } => {
+                writer.write_u8(0);
+                writer.write_string(name);
+            }
+            EntityKind::Mob(kind) => {
+                writer.write_u8(1);
+                writer.write_u8(MobKind::ALL.iter().position(|k| k == kind).unwrap() as u8);
+            }
+        }
+    }
+
+    fn decode(reader: &mut PacketReader) -> Result<Self, NetworkError> {
+        match reader.read_u8()? {
+            0 => Ok(EntityKind::Player {
+                name: reader.read_string()?,
+            }),
+            1 => MobKind::ALL
+                .get(reader.read_u8()? as usize)
+                .map(|&kind| EntityKind::Mob(kind))
+                .ok_or(NetworkError::InvalidData("unknown mob")),
+            _ => Err(NetworkError::InvalidData("unknown entity kind")),
+        }
+    }
+}
+
+impl DamageSource {
+    fn encode(&self, writer: &mut PacketWriter) {
+        match self {
+            DamageSource::Fall => writer.write_u8(0),
+            DamageSource::Lava => writer.write_u8(1),
+            DamageSource::Suffocation => writer.write_u8(2),
+            DamageSource::Starvation => writer.write_u8(3),
+            DamageSource::Void => writer.write_u8(4),
+            DamageSource::Mob(kind) => {
+                writer.write_u8(5);
+                writer.write_u8(MobKind::ALL.iter().position(|k| k == kind).unwrap() as u8);
+            }
+            DamageSource::Command => writer.write_u8(6),
+        }
+    }
+
+    fn decode(reader: &mut PacketReader) -> Result<Self, NetworkError> {
+        Ok(match reader.read_u8()? {
+            0 => DamageSource::Fall,
+            1 => DamageSource::Lava,
+            2 => DamageSource::Suffocation,
+            3 => DamageSource::Starvation,
+            4 => DamageSource::Void,
+            5 => MobKind::ALL
+                .get(reader.read_u8()? as usize)
+                .map(|&kind| DamageSource::Mob(kind))
+                .ok_or(NetworkError::InvalidData("unknown mob"))?,
+            6 => DamageSource::Command,
+            _ => return Err(NetworkError::InvalidData("unknown damage source")),
+        })
+    }
+}
+
+// The keys and the movement states are packed in the bits of a u16
+const INPUT_FLAGS: u8 = 9;
+
+impl PlayerInput {
+    fn flags(&self) -> [bool; INPUT_FLAGS as usize] {
+        [
+            self.forward,
+            self.backward,
+            self.left,
+            self.right,
+            self.jump,
+            self.descend,
+            self.is_flying,
+            self.is_sprinting,
+            self.is_sneaking,
+        ]
+    }
+
+    fn encode(&self, writer: &mut PacketWriter) {
+        let bits = self
+            .flags()
+            .iter()
+            .enumerate()
+            .fold(0u16, |bits, (i, &flag)| bits | ((flag as u16) << i));
+
+        writer.write_u8((bits & 0xFF) as u8);
+        writer.write_u8((bits >> 8) as u8);
+        writer.write_f32(self.yaw);
+        writer.write_f32(self.pitch);
+    }
+
+    fn decode(reader: &mut PacketReader) -> Result<Self, NetworkError> {
+        let bits = (reader.read_u8()? as u16) | ((reader.read_u8()? as u16) << 8);
+        if bits >> INPUT_FLAGS != 0 {
+            return Err(NetworkError::InvalidData("unknown input flags"));
+        }
+        let flag = |i: u8| bits & (1 << i) != 0;
+
+        Ok(PlayerInput {
+            forward: flag(0),
+            backward: flag(1),
+            left: flag(2),
+            right: flag(3),
+            jump: flag(4),
+            descend: flag(5),
+            is_flying: flag(6),
+            is_sprinting: flag(7),
+            is_sneaking: flag(8),
+            yaw: reader.read_f32()?,
+            pitch: reader.read_f32()?,
+        })
+    }
+}
+
+// Sent by the clients to the server
+#[derive(Clone, Debug, PartialEq)]
+pub enum ServerboundPacket {
+    // First packet of a connection
+    Login {
+        protocol_version: u32,
+        name: String,
+    },
+    KeepAlive {
+        id: u64,
+    },
+    // What the player did during one physics tick, the server simulates the movement again
+    PlayerInput {
+        sequence: u32,
+        input: PlayerInput,
+    },
+    // A block placed or broken by the player
+    BlockEdit {
+        x: i32,
+        y: i32,
+        z: i32,
+        block: BlockID,
+    },
+    Chat {
+        message: String,
+    },
+    // Sent from the death screen, the server decides whether the player is dead
+    Respawn,
+}
+
+impl Packet for ServerboundPacket {
+    fn encode(&self, writer: &mut PacketWriter) {
+        match self {
+            ServerboundPacket::Login {
+                protocol_version,
+                name,
+            } => {
+                writer.write_u8(0x00);
+                writer.write_u32(*protocol_version);
+                writer.write_string(name);
+            }
+            ServerboundPacket::KeepAlive { id } => {
+                writer.write_u8(0x01);
+                writer.write_u64(*id);
+            }
+            ServerboundPacket::PlayerInput { sequence, input } => {
+                writer.write_u8(0x02);
+                writer.write_u32(*sequence);
+                input.encode(writer);
+            }
+            ServerboundPacket::BlockEdit { x, y, z, block } => {
+                writer.write_u8(0x03);
+                writer.write_i32(*x);
+                writer.write_i32(*y);
+                writer.write_i32(*z);
+                writer.write_block(*block);
+            }
+            ServerboundPacket::Chat { message } => {
+                writer.write_u8(0x04);
+                writer.write_string(message);
+            }
+            ServerboundPacket::Respawn => writer.write_u8(0x05),
+        }
+    }
+
+    fn decode(reader: &mut PacketReader) -> Result<Self, NetworkError> {
+        Ok(match reader.read_u8()? {
+            0x00 => ServerboundPacket::Login {
+                protocol_version: reader.read_u32()?,
+                name: reader.read_string()?,
+            },
+            0x01 => ServerboundPacket::KeepAlive {
+                id: reader.read_u64()?,
+            },
+            0x02 => ServerboundPacket::PlayerInput {
+                sequence: reader.read_u32()?,
+                input: PlayerInput::decode(reader)?,
+            },
+            0x03 => ServerboundPacket::BlockEdit {
+                x: reader.read_i32()?,
+                y: reader.read_i32()?,
+                z: reader.read_i32()?,
+                block: reader.read_block()?,
+            },
+            0x04 => ServerboundPacket::Chat {
+                message: reader.read_string()?,
+            },
+            0x05 => ServerboundPacket::Respawn,
+            id => return Err(NetworkError::UnknownPacket(id)),
+        })
+    }
+}
+
+// Sent by the server to the clients
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClientboundPacket {
+    LoginSuccess {
+        entity_id: u32,
+        position: Vec3,
+    },
+    // Last packet of a connection
+    Disconnect {
+        reason: String,
+    },
+    // The client must answer with the same id
+    KeepAlive {
+        id: u64,
+    },
+    // The blocks are compressed, see `compress_column`
+    ChunkColumn {
+        x: i32,
+        z: i32,
+        data: Vec<u8>,
+    },
+    UnloadChunkColumn {
+        x: i32,
+        z: i32,
+    },
+    BlockChange {
+        x: i32,
+        y: i32,
+        z: i32,
+        block: BlockID,
+    },
+    // State of the player of the client after the input `sequence`, see `MovementPrediction`
+    PlayerMovement {
+        sequence: u32,
+        position: Vec3,
+        velocity: Vec3,
+        knockback: Vec3,
+        is_on_ground: bool,
+        jump_cooldown: f32,
+    },
+    SpawnEntity {
+        entity_id: u32,
+        kind: EntityKind,
+        position: Vec3,
+        yaw: f32,
+    },
+    MoveEntity {
+        entity_id: u32,
+        position: Vec3,
+        yaw: f32,
+    },
+    DespawnEntity {
+        entity_id: u32,
+    },
+    Chat {
+        message: String,
+    },
+    // Sent regularly, the clients advance the time themselves in between
+    TimeUpdate {
+        ticks: u64,
+    },
+    // Sent when the weather changes, the clients fade the rain in and out themselves
+    Weather {
+        kind: WeatherKind,
+    },
+    // Damage taken by the player of the client, the server is the one that applies the fall damage
+    PlayerDamage {
+        amount: u32,
+        source: DamageSource,
+    },
+    // Answer to a respawn request, the player is alive at `position`
+    Respawn {
+        position: Vec3,
+    },
+}
+
+impl Packet for ClientboundPacket {
+    fn encode(&self, writer: &mut PacketWriter) {
+        match self {
+            ClientboundPacket::LoginSuccess {
+                entity_id,
+                position,
+            } => {
+                writer.write_u8(0x00);
+                writer.write_u32(*entity_id);
+                writer.write_vec3(position);
+            }
+            ClientboundPacket::Disconnect { reason } => {
+                writer.write_u8(0x01);
+                writer.write_string(reason);
+            }
+            ClientboundPacket::KeepAlive { id } => {
+                writer.write_u8(0x02);
+                writer.write_u64(*id);
+            }
+            ClientboundPacket::ChunkColumn { x, z, data } => {
+                writer.write_u8(0x03);
+                writer.write_i32(*x);
+                writer.write_i32(*z);
+                writer.write_bytes(data);
+            }
+            ClientboundPacket::UnloadChunkColumn { x, z } => {
+                writer.write_u8(0x04);
+                writer.write_i32(*x);
+                writer.write_i32(*z);
+            }
+            ClientboundPacket::BlockChange { x, y, z, block } => {
+                writer.write_u8(0x05);
+                writer.write_i32(*x);
+                writer.write_i32(*y);
+                writer.write_i32(*z);
+                writer.write_block(*block);
+            }
+            ClientboundPacket::PlayerMovement {
+                sequence,
+                position,
+                velocity,
+                knockback,
+                is_on_ground,
+                jump_cooldown,
+            } => {
+                writer.write_u8(0x06);
+                writer.write_u32(*sequence);
+                writer.write_vec3(position);
+                writer.write_vec3(velocity);
+                writer.write_vec3(knockback);
+                writer.write_bool(*is_on_ground);
+                writer.write_f32(*jump_cooldown);
+            }
+            ClientboundPacket::SpawnEntity {
+                entity_id,
+                kind,
+                position,
+                yaw,
+            } => {
+                writer.write_u8(0x07);
+                writer.write_u32(*entity_id);
+                kind.encode(writer);
+                writer.write_vec3(position);
+                writer.write_f32(*yaw);
+            }
+            ClientboundPacket::MoveEntity {
+                entity_id,
+                position,
+                yaw,
+            } => {
+                writer.write_u8(0x08);
+                writer.write_u32(*entity_id);
+                writer.write_vec3(position);
+                writer.write_f32(*yaw);
+            }
+            ClientboundPacket::DespawnEntity { entity_id } => {
+                writer.write_u8(0x09);
+                writer.write_u32(*entity_id);
+            }
+            ClientboundPacket::Chat { message } => {
+                writer.write_u8(0x0A);
+                writer.write_string(message);
+            }
+            ClientboundPacket::TimeUpdate { ticks } => {
+                writer.write_u8(0x0B);
+                writer.write_u64(*ticks);
+            }
+            ClientboundPacket::Weather { kind } => {
+                writer.write_u8(0x0C);
+                writer.write_u8(WeatherKind::ALL.iter().position(|k| k == kind).unwrap() as u8);
+            }
+            ClientboundPacket::PlayerDamage { amount, source } => {
+                writer.write_u8(0x0D);
+                writer.write_u32(*amount);
+                source.encode(writer);
+            }
+            ClientboundPacket::Respawn { position } => {
+                writer.write_u8(0x0E);
+                writer.write_vec3(position);
+            }
+        }
+    }
+
+    fn decode(reader: &mut PacketReader) -> Result<Self, NetworkError> {
+        Ok(match reader.read_u8()? {
+            0x00 => ClientboundPacket::LoginSuccess {
+                entity_id: reader.read_u32()?,
+                position: reader.read_vec3()?,
+            },
+            0x01 => ClientboundPacket::Disconnect {
+                reason: reader.read_string()?,
+            },
+            0x02 => ClientboundPacket::KeepAlive {
+                id: reader.read_u64()?,
+            },
+            0x03 => ClientboundPacket::ChunkColumn {
+                x: reader.read_i32()?,
+                z: reader.read_i32()?,
+                data: reader.read_bytes()?.to_vec(),
+            },
+            0x04 => ClientboundPacket::UnloadChunkColumn {
+                x: reader.read_i32()?,
+                z: reader.read_i32()?,
+            },
+            0x05 => ClientboundPacket::BlockChange {
+                x: reader.read_i32()?,
+                y: reader.read_i32()?,
+                z: reader.read_i32()?,
+                block: reader.read_block()?,
+            },
+            0x06 => ClientboundPacket::PlayerMovement {
+                sequence: reader.read_u32()?,
+                position: reader.read_vec3()?,
+                velocity: reader.read_vec3()?,
+                knockback: reader.read_vec3()?,
+                is_on_ground: reader.read_bool()?,
+                jump_cooldown: reader.read_f32()?,
+            },
+            0x07 => ClientboundPacket::SpawnEntity {
+                entity_id: reader.read_u32()?,
+                kind: EntityKind::decode(reader)?,
+                position: reader.read_vec3()?,
+                yaw: reader.read_f32()?,
+            },
+            0x08 => ClientboundPacket::MoveEntity {
+                entity_id: reader.read_u32()?,
+                position: reader.read_vec3()?,
+                yaw: reader.read_f32()?,
+            },
+            0x09 => ClientboundPacket::DespawnEntity {
+                entity_id: reader.read_u32()?,
+            },
+            0x0A => ClientboundPacket::Chat {
+                message: reader.read_string()?,
+            },
+            0x0B => ClientboundPacket::TimeUpdate {
+                ticks: reader.read_u64()?,
+            },
+            0x0C => ClientboundPacket::Weather {
+                kind: *WeatherKind::ALL
+                    .get(reader.read_u8()? as usize)
+                    .ok_or(NetworkError::InvalidData("unknown weather"))?,
+            },
+            0x0D => ClientboundPacket::PlayerDamage {
+                amount: reader.read_u32()?,
+                source: DamageSource::decode(reader)?,
+            },
+            0x0E => ClientboundPacket::Respawn {
+                position: reader.read_vec3()?,
+            },
+            id => return Err(NetworkError::UnknownPacket(id)),
+        })
+    }
+}
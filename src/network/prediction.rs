@@ -0,0 +1,480 @@
+use crate::player::{PlayerInput, PlayerPhysicsState};
+use std::collections::VecDeque;
+use std::mem;
+
+// Inputs kept while waiting for the server, 5 seconds of movement
+const MAX_PENDING_INPUTS: usize = 300;
+// Differences smaller than this are rounding errors rather than mispredictions
+const PREDICTION_TOLERANCE: f32 = 0.01;
+
+// Whether `sequence` comes after `other`, the sequence numbers wrap around so the one that is less
+// than half the range ahead is the newer
+pub fn is_newer_sequence(sequence: u32, other: u32) -> bool {
+    sequence != other && sequence.wrapping_sub(other) < u32::MAX / 2
+}
+
+// The client moves its player right away and sends the inputs to the server, which simulates them
+// again and answers with where they actually led
+// When the answer differs from what was predicted, the client starts over from it and replays the
+// inputs sent since then
+#[derive(Default)]
+pub struct MovementPrediction {
+    next_sequence: u32,
+    // Inputs not acknowledged by the server yet, with the state predicted after each of them
+    pending: VecDeque<(u32, PlayerInput, PlayerPhysicsState)>,
+    // Inputs waiting to be sent, with their sequence number
+    unsent: Vec<(u32, PlayerInput)>,
+}
+
+impl MovementPrediction {
+    pub fn record(&mut self, input: PlayerInput, predicted: &PlayerPhysicsState) {
+        let sequence = self.next_sequence;
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+
+        // The server stopped answering, the oldest inputs cannot be replayed anymore
+        if self.pending.len() == MAX_PENDING_INPUTS {
+            self.pending.pop_front();
+        }
+
+        self.pending.push_back((sequence, input, predicted.clone()));
+        self.unsent.push((sequence, input));
+    }
+
+    pub fn take_unsent(&mut self) -> Vec<(u32, PlayerInput)> {
+        mem::take(&mut self.unsent)
+    }
+
+    // `authoritative` is the state of the server after the input `sequence`
+    // Returns the corrected state of the player, or None when the prediction was right
+    pub fn reconcile(
+        &mut self,
+        sequence: u32,
+        authoritative: PlayerPhysicsState,
+        mut simulate: impl FnMut(&PlayerPhysicsState, &PlayerInput) -> PlayerPhysicsState,
+    ) -> Option<PlayerPhysicsState> {
+        // Acknowledgements that arrive late, or of inputs dropped from the buffer or sent before a
+        // reset, cannot be compared with a prediction
+        if !self
+            .pending
+            .iter()
+            .any(|&(pending_sequence, _, _)| pending_sequence == sequence)
+        {
+            return None;
+        }
+
+        let mut predicted = None;
+
+        while let Some(&(pending_sequence, _, _)) = self.pending.front() {
+            if is_newer_sequence(pending_sequence, sequence) {
+                break;
+            }
+
+            let (_, _, state) = self.pending.pop_front().unwrap();
+            predicted = Some(state);
+        }
+
+        let is_prediction_right = predicted.is_some_and(|predicted| {
+            (predicted.position - authoritative.position).norm() < PREDICTION_TOLERANCE
+                && (predicted.velocity - authoritative.velocity).norm() < PREDICTION_TOLERANCE
+                && predicted.is_on_ground == authoritative.is_on_ground
+        });
+        if is_prediction_right {
+            return None;
+        }
+
+        let mut state = authoritative;
+        for (_, input, predicted) in self.pending.iter_mut() {
+            state = simulate(&state, input);
+            *predicted = state.clone();
+        }
+
+        Some(state)
+    }
+
+    // The inputs of a previous session mean nothing to the server
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.unsent.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{BlockID, ChunkColumn};
+    use crate::chunk_manager::ChunkManager;
+    use crate::constants::PHYSICS_TICKRATE;
+    use crate::game_mode::GameMode;
+    use nalgebra_glm::vec3;
+    use std::sync::Arc;
+
+    const DT: f32 = 1.0 / PHYSICS_TICKRATE;
+
+    // Two loaded columns along x with a stone floor at y = 0, the player stands at y = 1
+    fn flat_world() -> ChunkManager {
+        let chunk_manager = ChunkManager::new();
+
+        for column in [(0, 0), (1, 0)] {
+            chunk_manager.add_chunk_column(column, Arc::new(ChunkColumn::new()));
+        }
+        for x in 0..32 {
+            for z in 0..16 {
+                chunk_manager.set_block(x, 0, z, BlockID::Stone);
+            }
+        }
+
+        chunk_manager
+    }
+
+    // A wall across the floor at x, that only the server knows about
+    fn add_wall(chunk_manager: &ChunkManager, x: i32) {
+        for y in 1..4 {
+            for z in 0..16 {
+                chunk_manager.set_block(x, y, z, BlockID::Stone);
+            }
+        }
+    }
+
+    fn simulate(
+        state: &PlayerPhysicsState,
+        input: &PlayerInput,
+        game_mode: GameMode,
+        chunk_manager: &ChunkManager,
+    ) -> PlayerPhysicsState {
+        state.simulate(input, game_mode, true, chunk_manager, DT).0
+    }
+
+    fn assert_same_state(state: &PlayerPhysicsState, other: &PlayerPhysicsState) {
+        assert_eq!(state.position, other.position);
+        assert_eq!(state.velocity, other.velocity);
+        assert_eq!(state.is_on_ground, other.is_on_ground);
+        assert_eq!(state.jump_cooldown, other.jump_cooldown);
+    }
+
+    // Walking along +x
+    fn forward() -> PlayerInput {
+        PlayerInput {
+            forward: true,
+            ..Default::default()
+        }
+    }
+
+    // A client and a server exchanging inputs and acknowledgements tick by tick, with a latency
+    // and losses that only depend on the parameters
+    struct Harness {
+        tick: u32,
+        // In ticks, each way
+        latency: u32,
+        // Added to the latency of every other acknowledgement, so that they arrive out of order
+        jitter: u32,
+        // Every nth acknowledgement is lost, 0 for none
+        ack_loss: u32,
+        game_mode: GameMode,
+
+        client: MovementPrediction,
+        client_world: ChunkManager,
+        client_state: PlayerPhysicsState,
+        is_flying: bool,
+        corrections: u32,
+
+        // The server may have blocks the client does not know about
+        server_world: ChunkManager,
+        server_state: PlayerPhysicsState,
+        last_input: Option<u32>,
+        acks_sent: u32,
+
+        // With the tick they arrive at
+        to_server: Vec<(u32, u32, PlayerInput)>,
+        to_client: Vec<(u32, u32, PlayerPhysicsState)>,
+    }
+
+    impl Harness {
+        fn new(latency: u32) -> Self {
+            Self::new_at_height(latency, 1.0)
+        }
+
+        fn new_at_height(latency: u32, y: f32) -> Self {
+            let state = PlayerPhysicsState::new_at_position(vec3(2.0, y, 8.0));
+
+            Self {
+                tick: 0,
+                latency,
+                jitter: 0,
+                ack_loss: 0,
+                game_mode: GameMode::Survival,
+                client: MovementPrediction::default(),
+                client_world: flat_world(),
+                client_state: state.clone(),
+                is_flying: false,
+                corrections: 0,
+                server_world: flat_world(),
+                server_state: state,
+                last_input: None,
+                acks_sent: 0,
+                to_server: Vec::new(),
+                to_client: Vec::new(),
+            }
+        }
+
+        fn step(&mut self, input: PlayerInput) {
+            self.predict(input);
+            self.exchange();
+        }
+
+        fn predict(&mut self, input: PlayerInput) {
+            // Client, like `UpdatePlayerPhysics`
+            let input = PlayerInput {
+                is_flying: self.is_flying,
+                ..input
+            };
+            self.client_state = simulate(
+                &self.client_state,
+                &input,
+                self.game_mode,
+                &self.client_world,
+            );
+            if self.client_state.is_on_ground {
+                self.is_flying = false;
+            }
+            self.client.record(input, &self.client_state);
+            for (sequence, input) in self.client.take_unsent() {
+                self.to_server
+                    .push((self.tick + self.latency, sequence, input));
+            }
+        }
+
+        fn exchange(&mut self) {
+            // Server, the inputs are simulated in the order they arrive and acknowledged once per tick
+            let mut acknowledged = None;
+            for (_, sequence, input) in take_arrived(&mut self.to_server, self.tick) {
+                if self
+                    .last_input
+                    .is_some_and(|last| !is_newer_sequence(sequence, last))
+                {
+                    continue;
+                }
+
+                self.server_state = simulate(
+                    &self.server_state,
+                    &input,
+                    self.game_mode,
+                    &self.server_world,
+                );
+                self.last_input = Some(sequence);
+                acknowledged = Some(sequence);
+            }
+            if let Some(sequence) = acknowledged {
+                self.acks_sent += 1;
+
+                let is_lost = self.ack_loss != 0 && self.acks_sent.is_multiple_of(self.ack_loss);
+                let delay = self.latency
+                    + if self.acks_sent.is_multiple_of(2) {
+                        self.jitter
+                    } else {
+                        0
+                    };
+                if !is_lost {
+                    self.to_client
+                        .push((self.tick + delay, sequence, self.server_state.clone()));
+                }
+            }
+
+            // Client again
+            for (_, sequence, authoritative) in take_arrived(&mut self.to_client, self.tick) {
+                let (game_mode, world) = (self.game_mode, &self.client_world);
+                let corrected = self
+                    .client
+                    .reconcile(sequence, authoritative, |state, input| {
+                        simulate(state, input, game_mode, world)
+                    });
+
+                if let Some(state) = corrected {
+                    self.client_state = state;
+                    self.corrections += 1;
+                }
+            }
+
+            self.tick += 1;
+        }
+
+        fn run(&mut self, ticks: u32, input: PlayerInput) {
+            for _ in 0..ticks {
+                self.step(input);
+            }
+        }
+
+        // Lets the packets on their way arrive without any new input, then the client must be
+        // where the server is
+        fn assert_converged(&mut self) {
+            while !self.to_server.is_empty() || !self.to_client.is_empty() {
+                self.exchange();
+            }
+
+            assert_same_state(&self.client_state, &self.server_state);
+        }
+    }
+
+    // Removes the packets that arrived by `tick`, in the order they arrive
+    fn take_arrived<T>(packets: &mut Vec<(u32, u32, T)>, tick: u32) -> Vec<(u32, u32, T)> {
+        let (mut arrived, pending) = packets
+            .drain(..)
+            .partition::<Vec<_>, _>(|&(arrival, _, _)| arrival <= tick);
+        *packets = pending;
+        arrived.sort_by_key(|&(arrival, _, _)| arrival);
+        arrived
+    }
+
+    #[test]
+    fn sequences_compare_across_the_wraparound() {
+        assert!(is_newer_sequence(1, 0));
+        assert!(!is_newer_sequence(0, 1));
+        assert!(!is_newer_sequence(7, 7));
+        assert!(is_newer_sequence(0, u32::MAX));
+        assert!(is_newer_sequence(5, u32::MAX - 5));
+        assert!(!is_newer_sequence(u32::MAX, 0));
+    }
+
+    #[test]
+    fn replaying_the_inputs_gives_the_predicted_states() {
+        let chunk_manager = flat_world();
+        let start = PlayerPhysicsState::new_at_position(vec3(2.0, 1.0, 8.0));
+        let jump = PlayerInput {
+            jump: true,
+            ..forward()
+        };
+        let sprint = PlayerInput {
+            is_sprinting: true,
+            yaw: 0.3,
+            ..forward()
+        };
+        let inputs = [
+            (30, forward()),
+            (40, jump),
+            (30, sprint),
+            (30, PlayerInput::default()),
+        ];
+
+        let mut prediction = MovementPrediction::default();
+        let mut state = start.clone();
+        for &(ticks, input) in &inputs {
+            for _ in 0..ticks {
+                state = simulate(&state, &input, GameMode::Survival, &chunk_manager);
+                prediction.record(input, &state);
+            }
+        }
+
+        let mut replayed = start;
+        for (_, input, predicted) in &prediction.pending {
+            replayed = simulate(&replayed, input, GameMode::Survival, &chunk_manager);
+            assert_same_state(&replayed, predicted);
+        }
+    }
+
+    #[test]
+    fn right_predictions_are_never_corrected() {
+        let mut harness = Harness::new(5);
+        harness.run(100, forward());
+        harness.run(20, PlayerInput::default());
+
+        assert_eq!(harness.corrections, 0);
+        harness.assert_converged();
+        assert!(harness.server_state.position.x > 6.0);
+        // Only the inputs of the last round trip wait for their acknowledgement
+        assert!(harness.client.pending.len() <= 2 * harness.latency as usize + 1);
+    }
+
+    #[test]
+    fn landing_from_flight_is_predicted() {
+        let mut harness = Harness::new_at_height(3, 6.0);
+        harness.game_mode = GameMode::Creative;
+        harness.is_flying = true;
+        let descend = PlayerInput {
+            descend: true,
+            ..forward()
+        };
+        harness.run(60, descend);
+        harness.run(30, forward());
+        harness.run(20, PlayerInput::default());
+
+        assert!(!harness.is_flying);
+        assert_eq!(harness.corrections, 0);
+        harness.assert_converged();
+    }
+
+    #[test]
+    fn mispredictions_converge_to_the_server_state() {
+        let mut harness = Harness::new(3);
+        add_wall(&harness.server_world, 6);
+        harness.run(100, forward());
+        harness.run(20, PlayerInput::default());
+
+        assert!(harness.corrections > 0);
+        assert!(harness.client_state.position.x < 6.0);
+        harness.assert_converged();
+    }
+
+    #[test]
+    fn reordered_acknowledgements_are_ignored() {
+        let mut harness = Harness::new(2);
+        harness.jitter = 4;
+        harness.run(100, forward());
+        harness.run(20, PlayerInput::default());
+
+        // The late acknowledgements would replay from an older state and skip inputs
+        assert_eq!(harness.corrections, 0);
+        harness.assert_converged();
+    }
+
+    #[test]
+    fn lost_acknowledgements_are_covered_by_the_next_ones() {
+        let mut harness = Harness::new(4);
+        harness.ack_loss = 3;
+        add_wall(&harness.server_world, 8);
+        harness.run(100, forward());
+        harness.run(20, PlayerInput::default());
+
+        assert!(harness.client_state.position.x < 8.0);
+        harness.assert_converged();
+    }
+
+    #[test]
+    fn sequences_wrap_around() {
+        let mut harness = Harness::new(3);
+        harness.client.next_sequence = u32::MAX - 10;
+        harness.run(40, forward());
+        harness.run(20, PlayerInput::default());
+
+        assert!(harness.client.next_sequence < 100);
+        assert_eq!(harness.corrections, 0);
+        harness.assert_converged();
+    }
+
+    #[test]
+    fn acknowledgement_of_an_input_not_buffered_is_ignored() {
+        let chunk_manager = flat_world();
+        let simulate = |state: &PlayerPhysicsState, input: &PlayerInput| {
+            simulate(state, input, GameMode::Survival, &chunk_manager)
+        };
+
+        let mut prediction = MovementPrediction::default();
+        let mut state = PlayerPhysicsState::new_at_position(vec3(2.0, 1.0, 8.0));
+        for _ in 0..MAX_PENDING_INPUTS + 5 {
+            state = simulate(&state, &PlayerInput::default());
+            prediction.record(PlayerInput::default(), &state);
+        }
+
+        let far_away = PlayerPhysicsState::new_at_position(vec3(20.0, 1.0, 8.0));
+
+        // Dropped from the buffer when it was full, and never sent
+        for sequence in [0, 4, MAX_PENDING_INPUTS as u32 + 5] {
+            assert!(prediction
+                .reconcile(sequence, far_away.clone(), simulate)
+                .is_none());
+            assert_eq!(prediction.pending.len(), MAX_PENDING_INPUTS);
+        }
+
+        // The inputs of a previous session
+        prediction.reset();
+        assert!(prediction.reconcile(10, far_away, simulate).is_none());
+    }
+}
@@ -0,0 +1,140 @@
+use crate::constants::PHYSICS_TICKRATE;
+use crate::network::connection::Connection;
+use crate::network::packet::{ClientboundPacket, ServerboundPacket};
+use nalgebra_glm::Vec3;
+use specs::Entity;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::TcpListener;
+use std::time::Instant;
+
+// Inputs that can be received at once, absorbs the network jitter
+pub const MAX_INPUT_BUDGET: f32 = PHYSICS_TICKRATE / 2.0;
+
+pub type ClientConnection = Connection<ServerboundPacket, ClientboundPacket>;
+
+// A client that logged in, it controls a player entity
+pub struct ConnectedClient {
+    pub connection: ClientConnection,
+    pub name: String,
+    pub player: Entity,
+    // Columns sent to the client, it is told about the block changes in them
+    pub sent_columns: HashSet<(i32, i32)>,
+    // Entities the client knows about, with the position and yaw it was last sent
+    pub visible_entities: HashMap<Entity, (Vec3, f32)>,
+    // Sequence number of the last input simulated
+    pub(crate) last_input: Option<u32>,
+    // Number of inputs the client may still send, refilled in real time so that a client sending
+    // its inputs faster does not move faster
+    pub(crate) input_budget: f32,
+    pub(crate) input_budget_refilled: Instant,
+    // Set by a respawn request, answered by `UpdateClientsHealth`
+    pub(crate) wants_respawn: bool,
+    pub(crate) keep_alive_id: u64,
+    pub(crate) keep_alive_sent: Instant,
+    pub(crate) keep_alive_received: Instant,
+}
+
+impl ConnectedClient {
+    pub fn new(connection: ClientConnection, name: String, player: Entity) -> Self {
+        let now = Instant::now();
+
+        Self {
+            connection,
+            name,
+            player,
+            sent_columns: HashSet::new(),
+            visible_entities: HashMap::new(),
+            last_input: None,
+            input_budget: MAX_INPUT_BUDGET,
+            input_budget_refilled: now,
+            wants_respawn: false,
+            keep_alive_id: 0,
+            keep_alive_sent: now,
+            keep_alive_received: now,
+        }
+    }
+
+    // The connection is closed once the pending packets are written
+    pub fn disconnect(self, reason: &str) {
+        self.connection.send(ClientboundPacket::Disconnect {
+            reason: reason.to_string(),
+        });
+    }
+}
+
+#[derive(Default)]
+pub struct ServerNetwork {
+    listener: Option<TcpListener>,
+    // Connections that did not log in yet, with the time they were accepted
+    pub pending: Vec<(ClientConnection, Instant)>,
+    pub clients: Vec<ConnectedClient>,
+}
+
+impl ServerNetwork {
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        // Accepting is polled every tick
+        listener.set_nonblocking(true)?;
+
+        Ok(Self {
+            listener: Some(listener),
+            pending: Vec::new(),
+            clients: Vec::new(),
+        })
+    }
+
+    pub fn accept_connections(&mut self) {
+        let listener = match &self.listener {
+            Some(listener) => listener,
+            None => return,
+        };
+
+        loop {
+            let stream = match listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return,
+                Err(err) => {
+                    warn!("Could not accept a connection: {err}");
+                    return;
+                }
+            };
+
+            // The reading and writing threads block on the stream
+            let connection = stream
+                .set_nonblocking(false)
+                .and_then(|_| ClientConnection::new(stream));
+
+            match connection {
+                Ok(connection) => {
+                    debug!("{} connected", connection.address());
+                    self.pending.push((connection, Instant::now()));
+                }
+                Err(err) => warn!("Could not accept a connection: {err}"),
+            }
+        }
+    }
+
+    pub fn client_of(&self, player: Entity) -> Option<&ConnectedClient> {
+        self.clients.iter().find(|client| client.player == player)
+    }
+
+    pub fn broadcast(&self, packet: &ClientboundPacket) {
+        for client in self.clients.iter() {
+            client.connection.send(packet.clone());
+        }
+    }
+
+    // Sends a chat message to every client, except the one who wrote it
+    pub fn broadcast_chat(&self, message: &str, author: Option<Entity>) {
+        info!("{message}");
+
+        for client in self.clients.iter() {
+            if Some(client.player) != author {
+                client.connection.send(ClientboundPacket::Chat {
+                    message: message.to_string(),
+                });
+            }
+        }
+    }
+}
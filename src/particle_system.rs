@@ -4,10 +4,10 @@ use crate::chunk_manager::ChunkManager;
 use crate::physics::{Interpolatable, Interpolator};
 use crate::shader::ShaderProgram;
 use crate::shapes::quad_array_texture;
-use crate::types::TexturePack;
+use crate::types::{TextureLayer, TexturePack};
 use itertools::Itertools;
 use nalgebra::Matrix4;
-use nalgebra_glm::{vec3, vec4, Mat4, Vec3};
+use nalgebra_glm::{vec3, vec4, IVec3, Mat4, Vec3};
 use num_traits::Zero;
 use rand::random;
 use std::ffi::c_void;
@@ -87,14 +87,23 @@ impl ParticleSystem {
         }
     }
 
+    // The particles of the blocks show a random part of their texture
     pub fn emit(&mut self, particle_props: &ParticleProps, uv_map: &TexturePack, block: BlockID) {
-        let get_texture_coords = |uv: (f32, f32, f32, f32), layer: f32| {
-            (&[
-                uv.0, uv.1, layer, uv.2, uv.1, layer, uv.2, uv.3, layer, uv.2, uv.3, layer, uv.0,
-                uv.3, layer, uv.0, uv.1, layer,
-            ])
-                .to_vec()
-        };
+        let uv_x = random::<f32>();
+        let uv_y = random::<f32>();
+        let uv = (uv_x, uv_y, uv_x + 0.2, uv_y + 0.2);
+        let layer = uv_map.get(&block).unwrap().get_uv_of_every_face().0;
+
+        self.emit_with_texture(particle_props, layer, uv);
+    }
+
+    pub fn emit_with_texture(
+        &mut self,
+        particle_props: &ParticleProps,
+        layer: TextureLayer,
+        uv: (f32, f32, f32, f32),
+    ) {
+        let layer = layer as f32;
 
         self.particles[self.index_available] = Particle {
             active: true,
@@ -106,16 +115,10 @@ impl ParticleSystem {
                     acceleration: particle_props.acceleration,
                 },
             ),
-            tex_coords: {
-                let uv_x = random::<f32>();
-                let uv_y = random::<f32>();
-                let uv = (uv_x, uv_y, uv_x + 0.2, uv_y + 0.2);
-
-                get_texture_coords(
-                    uv,
-                    uv_map.get(&block).unwrap().get_uv_of_every_face().0 as f32,
-                )
-            },
+            tex_coords: vec![
+                uv.0, uv.1, layer, uv.2, uv.1, layer, uv.2, uv.3, layer, uv.2, uv.3, layer, uv.0,
+                uv.3, layer, uv.0, uv.1, layer,
+            ],
             scale: particle_props.scale,
             life_remaining: particle_props.lifetime,
         };
@@ -266,7 +269,7 @@ impl Interpolator<ParticlePhysicsProperties> {
                     containing_block.y,
                     containing_block.z,
                 ) {
-                    if block.is_air() {
+                    if !block.is_solid() {
                         continue;
                     }
 
@@ -398,4 +401,46 @@ impl ParticleSystem {
             }
         }
     }
+
+    // Small particles emitted from the hit face while a block is being mined
+    pub fn spawn_block_hitting_particles(
+        &mut self,
+        pos: Vec3,
+        normal: &IVec3,
+        uv_map: &TexturePack,
+        block: BlockID,
+    ) {
+        let normal = vec3(normal.x as f32, normal.y as f32, normal.z as f32);
+        let face_center = pos + vec3(0.5, 0.5, 0.5) + normal * 0.51;
+
+        for _ in 0..4 {
+            // Random point on the face
+            let offset = vec3(
+                random::<f32>() - 0.5,
+                random::<f32>() - 0.5,
+                random::<f32>() - 0.5,
+            );
+            let offset = offset - normal * offset.dot(&normal);
+
+            self.emit(
+                &ParticleProps {
+                    position: face_center + offset,
+                    velocity: normal * 2.0
+                        + vec3(
+                            2.0 * random::<f32>() - 1.0,
+                            2.0 * random::<f32>(),
+                            2.0 * random::<f32>() - 1.0,
+                        ),
+                    acceleration: vec3(0.0, -30.0, 0.0),
+                    lifetime: Duration::from_millis(100 + random::<u64>() % 400),
+                    scale: {
+                        let size = 0.05 + random::<f32>() / 20.0;
+                        Vec3::new(size, size, size)
+                    },
+                },
+                uv_map,
+                block,
+            );
+        }
+    }
 }
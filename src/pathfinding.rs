@@ -0,0 +1,520 @@
+use crate::chunk_manager::ChunkManager;
+use nalgebra_glm::{vec3, IVec3, Vec3};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+// Highest drop an entity is willing to take, in blocks
+pub const MAX_FALL_DISTANCE: i32 = 3;
+// Number of nodes explored before the search gives up
+pub const DEFAULT_SEARCH_BUDGET: usize = 1000;
+
+// The costs are integers so that they can be ordered in the heap
+const WALK_COST: u32 = 10;
+const STEP_UP_COST: u32 = 15;
+const FALL_COST_PER_BLOCK: u32 = 5;
+
+const DIRECTIONS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+// Half width of the body used when smoothing, the paths never cut corners closer than this
+const SMOOTHING_HALF_WIDTH: f32 = 0.3;
+
+// Unloaded blocks are not passable, so the paths stay in the loaded chunks
+fn is_passable(chunk_manager: &ChunkManager, x: i32, y: i32, z: i32) -> bool {
+    chunk_manager
+        .get_block(x, y, z)
+        .is_some_and(|block| !block.is_solid() && !block.is_fluid())
+}
+
+// Whether an entity can stand with its feet in the block, with solid ground under it and room for its head
+pub fn is_standable(chunk_manager: &ChunkManager, position: &IVec3) -> bool {
+    let (x, y, z) = (position.x, position.y, position.z);
+
+    chunk_manager.is_solid_block_at(x, y - 1, z)
+        && is_passable(chunk_manager, x, y, z)
+        && is_passable(chunk_manager, x, y + 1, z)
+}
+
+// Positions reachable in one move from a standable position, with the cost of the move
+fn neighbours(chunk_manager: &ChunkManager, from: &IVec3) -> Vec<(IVec3, u32)> {
+    let mut neighbours = Vec::with_capacity(4);
+
+    for (dx, dz) in DIRECTIONS {
+        let (x, z) = (from.x + dx, from.z + dz);
+        let walk = vec3(x, from.y, z);
+
+        if is_standable(chunk_manager, &walk) {
+            neighbours.push((walk, WALK_COST));
+            continue;
+        }
+
+        // Jumping needs room above the head before moving forward
+        let step_up = vec3(x, from.y + 1, z);
+        if is_passable(chunk_manager, from.x, from.y + 2, from.z)
+            && is_standable(chunk_manager, &step_up)
+        {
+            neighbours.push((step_up, STEP_UP_COST));
+            continue;
+        }
+
+        // Walk off the edge and fall until landing on something
+        if !is_passable(chunk_manager, x, from.y, z)
+            || !is_passable(chunk_manager, x, from.y + 1, z)
+        {
+            continue;
+        }
+
+        for fall_distance in 1..=MAX_FALL_DISTANCE {
+            let y = from.y - fall_distance;
+
+            if !is_passable(chunk_manager, x, y, z) {
+                break;
+            }
+
+            if chunk_manager.is_solid_block_at(x, y - 1, z) {
+                let cost = WALK_COST + FALL_COST_PER_BLOCK * fall_distance as u32;
+                neighbours.push((vec3(x, y, z), cost));
+                break;
+            }
+        }
+    }
+
+    neighbours
+}
+
+// Admissible estimation of the cost between two positions
+fn heuristic(from: &IVec3, to: &IVec3) -> u32 {
+    let horizontal = (from.x - to.x).abs() + (from.z - to.z).abs();
+    let vertical = (from.y - to.y).abs();
+
+    WALK_COST * horizontal as u32 + FALL_COST_PER_BLOCK * vertical as u32
+}
+
+// Blocks that a move between two adjacent positions of the path goes through or stands on
+fn move_footprint(from: &IVec3, to: &IVec3) -> impl Iterator<Item = IVec3> {
+    let (from, to) = (*from, *to);
+    let top = from.y.max(to.y) + 1;
+
+    // The columns of both positions, from the ground up to the head
+    (from.y - 1..=top + 1)
+        .map(move |y| vec3(from.x, y, from.z))
+        .chain((to.y - 1..=top).map(move |y| vec3(to.x, y, to.z)))
+}
+
+pub struct Path {
+    // Feet positions of the entity along the path, the first one is the start
+    pub waypoints: Vec<IVec3>,
+    // Index of the waypoint the entity is walking to
+    pub next_waypoint: usize,
+    // The search ran out of budget, the path only leads closer to the goal
+    pub is_partial: bool,
+    // Blocks that the path relies on, before the smoothing
+    footprint: HashSet<IVec3>,
+}
+
+impl Path {
+    fn new(positions: Vec<IVec3>, is_partial: bool, chunk_manager: &ChunkManager) -> Self {
+        let footprint = positions
+            .windows(2)
+            .flat_map(|pair| move_footprint(&pair[0], &pair[1]))
+            .collect();
+
+        Self {
+            waypoints: smooth_path(chunk_manager, &positions),
+            next_waypoint: 1,
+            is_partial,
+            footprint,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_waypoint >= self.waypoints.len()
+    }
+
+    // Center of the bottom of the next waypoint
+    pub fn current_waypoint(&self) -> Option<Vec3> {
+        self.waypoints.get(self.next_waypoint).map(|waypoint| {
+            vec3(
+                waypoint.x as f32 + 0.5,
+                waypoint.y as f32,
+                waypoint.z as f32 + 0.5,
+            )
+        })
+    }
+
+    // Moves on to the next waypoint once the entity reached the current one
+    pub fn advance(&mut self, position: &Vec3) {
+        while let Some(waypoint) = self.current_waypoint() {
+            let horizontal_distance = (waypoint.xz() - position.xz()).norm();
+
+            if horizontal_distance > 0.35 || (waypoint.y - position.y).abs() >= 1.0 {
+                break;
+            }
+
+            self.next_waypoint += 1;
+        }
+    }
+
+    // Whether a change of the block invalidates the path
+    pub fn is_affected_by(&self, x: i32, y: i32, z: i32) -> bool {
+        self.footprint.contains(&vec3(x, y, z))
+    }
+}
+
+// A* search between two feet positions
+// When the goal is not reached within `budget` explored nodes, returns a partial path to the closest position found
+pub fn find_path(
+    chunk_manager: &ChunkManager,
+    start: &IVec3,
+    goal: &IVec3,
+    budget: usize,
+) -> Option<Path> {
+    if !is_standable(chunk_manager, start) {
+        return None;
+    }
+
+    // Ties are broken by the heuristic so that the search goes deep first
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<IVec3, IVec3> = HashMap::new();
+    let mut costs: HashMap<IVec3, u32> = HashMap::new();
+    let mut closed = HashSet::new();
+
+    let start_heuristic = heuristic(start, goal);
+    let mut closest = (start_heuristic, *start);
+
+    open.push(Reverse((
+        start_heuristic,
+        start_heuristic,
+        start.x,
+        start.y,
+        start.z,
+    )));
+    costs.insert(*start, 0);
+
+    while let Some(Reverse((_, h, x, y, z))) = open.pop() {
+        let current = vec3(x, y, z);
+
+        if !closed.insert(current) {
+            continue;
+        }
+
+        if current == *goal {
+            return Some(reconstruct_path(chunk_manager, &came_from, current, false));
+        }
+
+        if h < closest.0 {
+            closest = (h, current);
+        }
+
+        if closed.len() >= budget {
+            break;
+        }
+
+        let cost = costs[&current];
+
+        for (neighbour, move_cost) in neighbours(chunk_manager, &current) {
+            let new_cost = cost + move_cost;
+
+            if closed.contains(&neighbour) || costs.get(&neighbour).is_some_and(|&c| c <= new_cost)
+            {
+                continue;
+            }
+
+            costs.insert(neighbour, new_cost);
+            came_from.insert(neighbour, current);
+
+            let h = heuristic(&neighbour, goal);
+            open.push(Reverse((
+                new_cost + h,
+                h,
+                neighbour.x,
+                neighbour.y,
+                neighbour.z,
+            )));
+        }
+    }
+
+    let (_, closest) = closest;
+    if closest == *start {
+        return None;
+    }
+
+    Some(reconstruct_path(chunk_manager, &came_from, closest, true))
+}
+
+fn reconstruct_path(
+    chunk_manager: &ChunkManager,
+    came_from: &HashMap<IVec3, IVec3>,
+    end: IVec3,
+    is_partial: bool,
+) -> Path {
+    let mut positions = vec![end];
+    let mut current = end;
+
+    while let Some(previous) = came_from.get(&current) {
+        positions.push(*previous);
+        current = *previous;
+    }
+    positions.reverse();
+
+    Path::new(positions, is_partial, chunk_manager)
+}
+
+// Whether the body can walk in a straight line between two positions at the same height
+fn is_straight_walkable(chunk_manager: &ChunkManager, from: &IVec3, to: &IVec3) -> bool {
+    if from.y != to.y {
+        return false;
+    }
+
+    let from_center = vec3(from.x as f32 + 0.5, 0.0, from.z as f32 + 0.5);
+    let to_center = vec3(to.x as f32 + 0.5, 0.0, to.z as f32 + 0.5);
+    let length = (to_center - from_center).norm();
+    let samples = (length / 0.25).ceil() as i32;
+
+    for i in 0..=samples {
+        let point = from_center.lerp(&to_center, i as f32 / samples.max(1) as f32);
+
+        for (dx, dz) in [(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)] {
+            let x = (point.x + dx * SMOOTHING_HALF_WIDTH).floor() as i32;
+            let z = (point.z + dz * SMOOTHING_HALF_WIDTH).floor() as i32;
+
+            if !is_standable(chunk_manager, &vec3(x, from.y, z)) {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+// Removes the waypoints that can be skipped by walking in a straight line
+fn smooth_path(chunk_manager: &ChunkManager, positions: &[IVec3]) -> Vec<IVec3> {
+    let mut waypoints = Vec::new();
+    let mut anchor = 0;
+
+    waypoints.extend(positions.first());
+
+    while anchor + 1 < positions.len() {
+        let mut next = anchor + 1;
+
+        while next + 1 < positions.len()
+            && is_straight_walkable(chunk_manager, &positions[anchor], &positions[next + 1])
+        {
+            next += 1;
+        }
+
+        waypoints.push(positions[next]);
+        anchor = next;
+    }
+
+    waypoints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{BlockID, ChunkColumn};
+    use std::sync::Arc;
+
+    // One loaded column with a stone floor at y = 0, the entities walk with their feet at y = 1
+    // The blocks around the column are not loaded, so they act as walls
+    fn flat_world() -> ChunkManager {
+        let chunk_manager = ChunkManager::new();
+        chunk_manager.add_chunk_column((0, 0), Arc::new(ChunkColumn::new()));
+
+        for x in 0..16 {
+            for z in 0..16 {
+                chunk_manager.set_block(x, 0, z, BlockID::Stone);
+            }
+        }
+
+        chunk_manager
+    }
+
+    // Fills the blocks of x from y_min to y_max for every z of the column but the gaps
+    fn wall(chunk_manager: &ChunkManager, x: i32, y_min: i32, y_max: i32, gaps: &[i32]) {
+        for z in (0..16).filter(|z| !gaps.contains(z)) {
+            for y in y_min..=y_max {
+                chunk_manager.set_block(x, y, z, BlockID::Stone);
+            }
+        }
+    }
+
+    #[test]
+    fn walks_in_a_straight_line() {
+        let chunk_manager = flat_world();
+        let path = find_path(
+            &chunk_manager,
+            &vec3(1, 1, 1),
+            &vec3(10, 1, 1),
+            DEFAULT_SEARCH_BUDGET,
+        )
+        .unwrap();
+
+        assert!(!path.is_partial);
+        // The smoothing removes every waypoint between the ends
+        assert_eq!(path.waypoints, vec![vec3(1, 1, 1), vec3(10, 1, 1)]);
+    }
+
+    #[test]
+    fn goes_around_a_wall_through_the_gap() {
+        let chunk_manager = flat_world();
+        wall(&chunk_manager, 5, 1, 2, &[15]);
+
+        let path = find_path(
+            &chunk_manager,
+            &vec3(1, 1, 1),
+            &vec3(10, 1, 1),
+            DEFAULT_SEARCH_BUDGET,
+        )
+        .unwrap();
+
+        assert!(!path.is_partial);
+        assert_eq!(path.waypoints.last(), Some(&vec3(10, 1, 1)));
+        assert!(path.waypoints.iter().any(|waypoint| waypoint.z >= 14));
+    }
+
+    #[test]
+    fn steps_up_one_block() {
+        let chunk_manager = flat_world();
+        wall(&chunk_manager, 5, 1, 1, &[]);
+
+        let path = find_path(
+            &chunk_manager,
+            &vec3(1, 1, 1),
+            &vec3(10, 1, 1),
+            DEFAULT_SEARCH_BUDGET,
+        )
+        .unwrap();
+
+        assert!(!path.is_partial);
+        assert!(path.waypoints.contains(&vec3(5, 2, 1)));
+        assert_eq!(path.waypoints.last(), Some(&vec3(10, 1, 1)));
+    }
+
+    #[test]
+    fn cannot_step_up_two_blocks() {
+        let chunk_manager = flat_world();
+        wall(&chunk_manager, 5, 1, 2, &[]);
+
+        let path = find_path(
+            &chunk_manager,
+            &vec3(1, 1, 1),
+            &vec3(10, 1, 1),
+            DEFAULT_SEARCH_BUDGET,
+        )
+        .unwrap();
+
+        // The closest position to the goal is against the wall
+        assert!(path.is_partial);
+        assert_eq!(path.waypoints.last(), Some(&vec3(4, 1, 1)));
+    }
+
+    #[test]
+    fn cannot_step_up_without_headroom() {
+        let chunk_manager = flat_world();
+        wall(&chunk_manager, 5, 1, 1, &[]);
+        // A ceiling above the head of the entity standing next to the step
+        wall(&chunk_manager, 4, 3, 3, &[]);
+
+        let path = find_path(
+            &chunk_manager,
+            &vec3(1, 1, 1),
+            &vec3(10, 1, 1),
+            DEFAULT_SEARCH_BUDGET,
+        )
+        .unwrap();
+
+        assert!(path.is_partial);
+    }
+
+    // A ledge of the given height from x = 0 to 4, with the ground at y = 0 after it
+    fn ledge(height: i32) -> ChunkManager {
+        let chunk_manager = flat_world();
+        for x in 0..=4 {
+            wall(&chunk_manager, x, 1, height, &[]);
+        }
+
+        chunk_manager
+    }
+
+    #[test]
+    fn falls_down_the_maximum_distance() {
+        let chunk_manager = ledge(MAX_FALL_DISTANCE);
+        let start = vec3(2, MAX_FALL_DISTANCE + 1, 1);
+
+        let path = find_path(
+            &chunk_manager,
+            &start,
+            &vec3(10, 1, 1),
+            DEFAULT_SEARCH_BUDGET,
+        )
+        .unwrap();
+
+        assert!(!path.is_partial);
+        assert_eq!(path.waypoints.last(), Some(&vec3(10, 1, 1)));
+    }
+
+    #[test]
+    fn does_not_fall_further_than_the_maximum_distance() {
+        let chunk_manager = ledge(MAX_FALL_DISTANCE + 1);
+        let start = vec3(2, MAX_FALL_DISTANCE + 2, 1);
+
+        let path = find_path(
+            &chunk_manager,
+            &start,
+            &vec3(10, 1, 1),
+            DEFAULT_SEARCH_BUDGET,
+        )
+        .unwrap();
+
+        assert!(path.is_partial);
+        assert!(path.waypoints.iter().all(|waypoint| waypoint.y == start.y));
+        assert_eq!(path.waypoints.last().map(|waypoint| waypoint.x), Some(4));
+    }
+
+    #[test]
+    fn exhausted_budget_returns_a_partial_path_closer_to_the_goal() {
+        let chunk_manager = flat_world();
+        let (start, goal) = (vec3(1, 1, 1), vec3(14, 1, 14));
+
+        let path = find_path(&chunk_manager, &start, &goal, 5).unwrap();
+
+        assert!(path.is_partial);
+        let end = path.waypoints.last().unwrap();
+        assert!(heuristic(end, &goal) < heuristic(&start, &goal));
+    }
+
+    #[test]
+    fn no_path_from_an_unstandable_position() {
+        let chunk_manager = flat_world();
+
+        assert!(find_path(
+            &chunk_manager,
+            &vec3(1, 3, 1),
+            &vec3(10, 1, 1),
+            DEFAULT_SEARCH_BUDGET
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn footprint_covers_the_blocks_under_and_along_the_path() {
+        let chunk_manager = flat_world();
+        let path = find_path(
+            &chunk_manager,
+            &vec3(1, 1, 1),
+            &vec3(5, 1, 1),
+            DEFAULT_SEARCH_BUDGET,
+        )
+        .unwrap();
+
+        // The smoothing removed the waypoint but the path still relies on the blocks
+        assert!(!path.waypoints.contains(&vec3(3, 1, 1)));
+        assert!(path.is_affected_by(3, 0, 1));
+        assert!(path.is_affected_by(3, 1, 1));
+        assert!(path.is_affected_by(3, 2, 1));
+
+        assert!(!path.is_affected_by(3, 1, 8));
+        assert!(!path.is_affected_by(10, 1, 1));
+    }
+}
@@ -51,6 +51,13 @@ impl<T: Clone + Interpolatable> Interpolator<T> {
         &self.interpolated_state
     }
 
+    // Discards the previous states, used when the state jumps (e.g. teleportation)
+    pub fn reset_to(&mut self, state: T) {
+        self.previous_state = state.clone();
+        self.current_state = state.clone();
+        self.interpolated_state = state;
+    }
+
     pub fn step(&mut self, time: Instant, integrate: &mut dyn FnMut(&T, f32, f32) -> T) {
         let now = time;
         let mut frame_time = now
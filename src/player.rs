@@ -1,19 +1,55 @@
-use crate::aabb::{get_block_aabb, AABB};
+use crate::aabb::AABB;
+use crate::chunk::BlockID;
 use crate::chunk_manager::ChunkManager;
+use crate::combat::KNOCKBACK_FRICTION;
 use crate::constants::{
-    FLYING_SPEED, FLYING_SPRINTING_SPEED, FOV, HORIZONTAL_ACCELERATION, IN_AIR_FRICTION,
-    JUMP_IMPULSE, MAX_VERTICAL_VELOCITY, MOUSE_SENSITIVITY_X, MOUSE_SENSITIVITY_Y,
-    ON_GROUND_FRICTION, PLAYER_EYES_HEIGHT, PLAYER_HALF_WIDTH, PLAYER_HEIGHT, PLAYER_WIDTH,
-    SNEAKING_SPEED, SPRINTING_SPEED, WALKING_SPEED,
+    FLYING_SPEED, FLYING_SPRINTING_SPEED, GRAVITY, HORIZONTAL_ACCELERATION, IN_AIR_FRICTION,
+    JUMP_IMPULSE, MAX_VERTICAL_VELOCITY, ON_GROUND_FRICTION, PLAYER_EYES_HEIGHT, PLAYER_HALF_WIDTH,
+    PLAYER_HEIGHT, SNEAKING_SPEED, SPRINTING_SPEED, WALKING_SPEED,
 };
+use crate::game_mode::GameMode;
+#[cfg(feature = "client")]
 use crate::input::InputCache;
 use crate::physics::{Interpolatable, Interpolator};
+use crate::settings::Settings;
 use crate::util::Forward;
 use nalgebra::{clamp, Vector3};
 use nalgebra_glm::{pi, vec2, vec3, IVec3, Mat4, Vec3};
 use num_traits::Zero;
+use specs::Entity;
 use std::time::Instant;
 
+// Time between two jumps while holding the jump key, in seconds
+const JUMP_COOLDOWN: f32 = 0.475;
+
+// Mining progress of the block the player is breaking
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(not(feature = "client"), allow(dead_code))]
+pub struct BlockBreakingProgress {
+    pub block: (i32, i32, i32),
+    // Between 0 and 1, the block breaks when it reaches 1
+    pub progress: f32,
+    pub(crate) last_updated: Instant,
+    pub(crate) particles_last_emitted: Instant,
+}
+
+impl BlockBreakingProgress {
+    pub fn new(block: (i32, i32, i32)) -> Self {
+        Self {
+            block,
+            progress: 0.0,
+            last_updated: Instant::now(),
+            particles_last_emitted: Instant::now(),
+        }
+    }
+
+    // The crack texture to display, from 0 to 9
+    pub fn stage(&self) -> u32 {
+        ((self.progress * 10.0) as u32).min(9)
+    }
+}
+
+#[cfg_attr(not(feature = "client"), allow(dead_code))]
 pub struct PlayerState {
     pub rotation: Vec3,
     pub camera_height: Interpolator<f32>,
@@ -27,21 +63,28 @@ pub struct PlayerState {
     pub is_flying: bool,
 
     pub targeted_block: Option<((i32, i32, i32), IVec3)>,
+    pub block_breaking: Option<BlockBreakingProgress>,
+    // Mob in the crosshair, closer than any block
+    pub targeted_entity: Option<Entity>,
+
+    // Where the player appears again after dying
+    pub spawn_point: Vec3,
 
-    pub(crate) jump_last_executed: Instant,
     pub(crate) fly_throttle: bool,
     pub(crate) fly_last_toggled: Instant,
     pub(crate) sprint_throttle: bool,
     pub(crate) sprint_last_toggled: Instant,
     pub(crate) block_placing_last_executed: Instant,
+    pub(crate) attack_last_executed: Option<Instant>,
 }
 
 impl PlayerState {
-    pub fn new() -> Self {
+    pub fn new(spawn_point: Vec3) -> Self {
         Self {
             rotation: vec3(0.0, 0.0, 0.0),
             camera_height: Interpolator::new(1.0 / 30.0, PLAYER_EYES_HEIGHT),
-            fov: Interpolator::new(1.0 / 30.0, FOV),
+            // Replaced by the one of the settings on the first frame
+            fov: Interpolator::new(1.0 / 30.0, Settings::default().fov_radians()),
             view_matrix: Mat4::identity(),
             projection_matrix: Mat4::identity(),
 
@@ -51,19 +94,23 @@ impl PlayerState {
             is_flying: false,
 
             targeted_block: None,
+            block_breaking: None,
+            targeted_entity: None,
+
+            spawn_point,
 
-            jump_last_executed: Instant::now(),
             fly_throttle: false,
             fly_last_toggled: Instant::now(),
             sprint_throttle: false,
             sprint_last_toggled: Instant::now(),
             block_placing_last_executed: Instant::now(),
+            attack_last_executed: None,
         }
     }
 
-    pub fn rotate_camera(&mut self, horizontal: f32, vertical: f32) {
-        self.rotation.y += horizontal / 100.0 * MOUSE_SENSITIVITY_X;
-        self.rotation.x += vertical / 100.0 * MOUSE_SENSITIVITY_Y;
+    pub fn rotate_camera(&mut self, horizontal: f32, vertical: f32, settings: &Settings) {
+        self.rotation.y += horizontal / 100.0 * settings.mouse_sensitivity_x;
+        self.rotation.x += vertical / 100.0 * settings.mouse_sensitivity_y;
 
         // Limit vertical movement
         self.rotation.x = clamp(
@@ -74,12 +121,60 @@ impl PlayerState {
     }
 }
 
+// What the player does during one physics tick
+// The movement only depends on the inputs, so the server can simulate it again and the client can
+// replay the inputs the server did not acknowledge yet
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PlayerInput {
+    pub forward: bool,
+    pub backward: bool,
+    pub left: bool,
+    pub right: bool,
+    // Jumps, or flies up while flying
+    pub jump: bool,
+    // Stops at the edges of the blocks, or flies down while flying
+    pub descend: bool,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub is_flying: bool,
+    pub is_sprinting: bool,
+    pub is_sneaking: bool,
+}
+
+impl PlayerInput {
+    #[cfg(feature = "client")]
+    pub fn sample(input_cache: &InputCache, player_state: &PlayerState) -> Self {
+        Self {
+            forward: input_cache.is_key_pressed(glfw::Key::W),
+            backward: input_cache.is_key_pressed(glfw::Key::S),
+            left: input_cache.is_key_pressed(glfw::Key::A),
+            right: input_cache.is_key_pressed(glfw::Key::D),
+            jump: input_cache.is_key_pressed(glfw::Key::Space),
+            descend: input_cache.is_key_pressed(glfw::Key::LeftShift),
+            yaw: player_state.rotation.y,
+            pitch: player_state.rotation.x,
+            is_flying: player_state.is_flying,
+            is_sprinting: player_state.is_sprinting,
+            is_sneaking: player_state.is_sneaking,
+        }
+    }
+
+    pub fn rotation(&self) -> Vec3 {
+        vec3(self.pitch, self.yaw, 0.0)
+    }
+}
+
 #[derive(Clone)]
 pub struct PlayerPhysicsState {
     pub position: Vec3,
     pub aabb: AABB,
     pub velocity: Vec3,
     pub acceleration: Vec3,
+    // Velocity from the hits taken, not limited by the walking speed
+    pub knockback: Vec3,
+    pub is_on_ground: bool,
+    // Time left before the player can jump again, in seconds
+    pub jump_cooldown: f32,
 }
 
 impl PlayerPhysicsState {
@@ -101,6 +196,9 @@ impl PlayerPhysicsState {
             },
             velocity: vec3(0.0, 0.0, 0.0),
             acceleration: vec3(0.0, 0.0, 0.0),
+            knockback: vec3(0.0, 0.0, 0.0),
+            is_on_ground: false,
+            jump_cooldown: 0.0,
         }
     }
 }
@@ -117,62 +215,173 @@ impl Interpolatable for PlayerPhysicsState {
             },
             velocity: interpolate_vec3(&self.velocity, &other.velocity),
             acceleration: interpolate_vec3(&self.acceleration, &other.acceleration),
+            knockback: interpolate_vec3(&self.knockback, &other.knockback),
+            is_on_ground: self.is_on_ground,
+            jump_cooldown: self.jump_cooldown,
         }
     }
 }
 
+// What happened during a physics tick besides the movement, for the damage and the exhaustion
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PhysicsEvents {
+    // Vertical velocity of the landing when the player lands
+    pub landing_velocity: Option<f32>,
+    pub jumped: bool,
+}
+
 impl PlayerPhysicsState {
-    pub fn apply_keyboard_movement(
-        &mut self,
-        player_properties: &mut PlayerState,
-        input_cache: &InputCache,
-    ) {
-        let rotation = &player_properties.rotation;
+    // Moves the player by one physics tick
+    pub fn simulate(
+        &self,
+        input: &PlayerInput,
+        game_mode: GameMode,
+        can_move: bool,
+        chunk_manager: &ChunkManager,
+        dt: f32,
+    ) -> (Self, PhysicsEvents) {
+        let mut player = self.clone();
+        let mut events = PhysicsEvents::default();
+        player.jump_cooldown = (player.jump_cooldown - dt).max(0.0);
+
+        if !input.is_flying {
+            player.acceleration.y += GRAVITY;
+        }
+
+        // Dead players cannot move
+        if can_move {
+            events.jumped = player.apply_keyboard_movement(input);
+        }
+        player.velocity += player.acceleration * dt;
+        player.apply_friction(dt, input);
+        player.limit_velocity(input);
+        player.knockback *= (1.0 - KNOCKBACK_FRICTION * dt).max(0.0);
+        let movement = player.velocity + player.knockback;
+
+        // Spectators fly through the terrain without any collision
+        if !game_mode.has_collisions() {
+            player.aabb.translate(&(movement * dt));
+            player.position.x = player.aabb.mins.x + PLAYER_HALF_WIDTH;
+            player.position.y = player.aabb.mins.y;
+            player.position.z = player.aabb.mins.z + PLAYER_HALF_WIDTH;
+            player.acceleration = vec3(0.0, 0.0, 0.0);
+            player.is_on_ground = false;
+
+            return (player, events);
+        }
+
+        let will_hit_ground = |player: &PlayerPhysicsState| {
+            let mut player = player.clone();
+            let v_y = vec3(0.0, player.velocity.y, 0.0);
+
+            player.aabb.translate(&(v_y * dt));
+
+            let colliding_block = player.get_colliding_block_coords(chunk_manager);
+
+            if let Some(colliding_block) = colliding_block {
+                player.separate_from_block(&v_y, &colliding_block)
+            } else {
+                false
+            }
+        };
+
+        // We are using the Separated Axis Theorem
+        // We decompose the velocity vector into 3 vectors for each dimension
+        // For each one, we move the entity and do the collision detection/resolution
+        let mut is_player_on_ground = false;
+        let vertical_velocity = player.velocity.y;
+
+        let separated_axis = &[
+            vec3(movement.x, 0.0, 0.0),
+            vec3(0.0, 0.0, movement.z),
+            vec3(0.0, movement.y, 0.0),
+        ];
+
+        for v in separated_axis {
+            let backup = player.clone();
+
+            player.aabb.translate(&(v * dt));
+            let block_collided = player.get_colliding_block_coords(chunk_manager);
+
+            // Collision resolution
+            if let Some(block_collided) = block_collided {
+                is_player_on_ground |= player.separate_from_block(v, &block_collided);
+            }
+
+            // If the player is sneaking and is not on the ground, the player should not be able to move
+            if input.descend
+                && self.is_on_ground
+                && !will_hit_ground(&player)
+                && player.velocity.y < 0.0
+            {
+                player = backup;
+
+                if !v.x.is_zero() {
+                    player.velocity.x = 0.0;
+                }
+
+                if !v.z.is_zero() {
+                    player.velocity.z = 0.0;
+                }
+            }
+        }
+
+        if is_player_on_ground && !self.is_on_ground && !input.is_flying {
+            events.landing_velocity = Some(vertical_velocity);
+        }
+        player.is_on_ground = is_player_on_ground;
+
+        // Update the position of the player and reset the acceleration
+        player.position.x = player.aabb.mins.x + PLAYER_HALF_WIDTH;
+        player.position.y = player.aabb.mins.y;
+        player.position.z = player.aabb.mins.z + PLAYER_HALF_WIDTH;
+
+        player.acceleration.x = 0.0;
+        player.acceleration.y = 0.0;
+        player.acceleration.z = 0.0;
+
+        (player, events)
+    }
+
+    // Returns true when the player jumps
+    pub fn apply_keyboard_movement(&mut self, input: &PlayerInput) -> bool {
+        let rotation = &input.rotation();
 
         // Flying
-        if player_properties.is_flying {
-            if input_cache.is_key_pressed(glfw::Key::Space) {
+        if input.is_flying {
+            if input.jump {
                 self.acceleration = vec3(0.0, 100.0, 0.0);
             }
 
-            if input_cache.is_key_pressed(glfw::Key::LeftShift) {
+            if input.descend {
                 self.acceleration = vec3(0.0, -100.0, 0.0);
             }
         }
 
-        if input_cache.is_key_pressed(glfw::Key::Space) {
-            let now = Instant::now();
-
-            if now
-                .duration_since(player_properties.jump_last_executed)
-                .as_secs_f32()
-                >= 0.475
-            {
-                if player_properties.is_on_ground {
-                    self.velocity.y = *JUMP_IMPULSE;
-                    player_properties.jump_last_executed = now;
-                }
-            }
+        let jumped = input.jump && self.is_on_ground && self.jump_cooldown <= 0.0;
+        if jumped {
+            self.velocity.y = *JUMP_IMPULSE;
+            self.jump_cooldown = JUMP_COOLDOWN;
         }
 
         // Walk
         let mut directional_acceleration = vec3(0.0, 0.0, 0.0);
 
-        if input_cache.is_key_pressed(glfw::Key::W) {
+        if input.forward {
             directional_acceleration +=
                 -rotation.forward().cross(&Vector3::y()).cross(&Vector3::y());
         }
 
-        if input_cache.is_key_pressed(glfw::Key::S) {
+        if input.backward {
             directional_acceleration +=
                 rotation.forward().cross(&Vector3::y()).cross(&Vector3::y());
         }
 
-        if input_cache.is_key_pressed(glfw::Key::A) {
+        if input.left {
             directional_acceleration += -rotation.forward().cross(&Vector3::y())
         }
 
-        if input_cache.is_key_pressed(glfw::Key::D) {
+        if input.right {
             directional_acceleration += rotation.forward().cross(&Vector3::y())
         }
 
@@ -182,130 +391,39 @@ impl PlayerPhysicsState {
                 .scale(HORIZONTAL_ACCELERATION);
             self.acceleration += directional_acceleration;
         }
+
+        jumped
     }
 
     pub fn get_colliding_block_coords(&self, chunk_manager: &ChunkManager) -> Option<Vec3> {
-        let player_mins = &self.aabb.mins;
-        let player_maxs = &self.aabb.maxs;
-
-        let block_min = vec3(
-            player_mins.x.floor() as i32,
-            player_mins.y.floor() as i32,
-            player_mins.z.floor() as i32,
-        );
-        let block_max = vec3(
-            player_maxs.x.floor() as i32,
-            player_maxs.y.floor() as i32,
-            player_maxs.z.floor() as i32,
-        );
-
-        let mut block_collided = None;
-
-        // Find the block that the player is colliding with
-        'outer: for y in block_min.y..=block_max.y {
-            for z in block_min.z..=block_max.z {
-                for x in block_min.x..=block_max.x {
-                    if let Some(block) = chunk_manager.get_block(x, y, z) {
-                        if block.is_air() {
-                            continue;
-                        }
-
-                        let block_aabb = get_block_aabb(&vec3(x as f32, y as f32, z as f32));
-
-                        if self.aabb.intersects(&block_aabb) {
-                            block_collided = Some(vec3(x as f32, y as f32, z as f32));
-                            break 'outer;
-                        }
-                    }
-                }
-            }
-        }
+        self.aabb.get_colliding_block_coords(chunk_manager)
+    }
 
-        block_collided
+    // Whether a block of the given type intersects the player, used for non solid blocks like lava
+    pub fn is_touching_block(&self, chunk_manager: &ChunkManager, block: BlockID) -> bool {
+        self.aabb.is_touching_block(chunk_manager, block)
     }
 
     pub fn separate_from_block(&mut self, v: &Vec3, block_coords: &Vec3) -> bool {
-        let mut is_player_on_ground = false;
-        let block_aabb = get_block_aabb(&block_coords);
-
         if !v.x.is_zero() {
-            if v.x < 0.0 {
-                self.aabb = AABB::new(
-                    vec3(block_aabb.maxs.x, self.aabb.mins.y, self.aabb.mins.z),
-                    vec3(
-                        block_aabb.maxs.x + PLAYER_WIDTH,
-                        self.aabb.maxs.y,
-                        self.aabb.maxs.z,
-                    ),
-                )
-            } else {
-                self.aabb = AABB::new(
-                    vec3(
-                        block_aabb.mins.x - PLAYER_WIDTH,
-                        self.aabb.mins.y,
-                        self.aabb.mins.z,
-                    ),
-                    vec3(block_aabb.mins.x, self.aabb.maxs.y, self.aabb.maxs.z),
-                )
-            }
-
             self.velocity.x = 0.0;
+            self.knockback.x = 0.0;
         }
 
         if !v.y.is_zero() {
-            if v.y < 0.0 {
-                self.aabb = AABB::new(
-                    vec3(self.aabb.mins.x, block_aabb.maxs.y, self.aabb.mins.z),
-                    vec3(
-                        self.aabb.maxs.x,
-                        block_aabb.maxs.y + PLAYER_HEIGHT,
-                        self.aabb.maxs.z,
-                    ),
-                );
-                is_player_on_ground = true;
-            } else {
-                self.aabb = AABB::new(
-                    vec3(
-                        self.aabb.mins.x,
-                        block_aabb.mins.y - PLAYER_HEIGHT,
-                        self.aabb.mins.z,
-                    ),
-                    vec3(self.aabb.maxs.x, block_aabb.mins.y, self.aabb.maxs.z),
-                )
-            }
-
             self.velocity.y = 0.0;
         }
 
         if !v.z.is_zero() {
-            if v.z < 0.0 {
-                self.aabb = AABB::new(
-                    vec3(self.aabb.mins.x, self.aabb.mins.y, block_aabb.maxs.z),
-                    vec3(
-                        self.aabb.maxs.x,
-                        self.aabb.maxs.y,
-                        block_aabb.maxs.z + PLAYER_WIDTH,
-                    ),
-                )
-            } else {
-                self.aabb = AABB::new(
-                    vec3(
-                        self.aabb.mins.x,
-                        self.aabb.mins.y,
-                        block_aabb.mins.z - PLAYER_WIDTH,
-                    ),
-                    vec3(self.aabb.maxs.x, self.aabb.maxs.y, block_aabb.mins.z),
-                )
-            }
-
             self.velocity.z = 0.0;
+            self.knockback.z = 0.0;
         }
 
-        is_player_on_ground
+        self.aabb.separate_from_block(v, block_coords)
     }
 
-    pub fn apply_friction(&mut self, dt: f32, player_state: &PlayerState) {
-        let friction = if player_state.is_on_ground {
+    pub fn apply_friction(&mut self, dt: f32, input: &PlayerInput) {
+        let friction = if self.is_on_ground {
             ON_GROUND_FRICTION
         } else {
             IN_AIR_FRICTION
@@ -321,32 +439,31 @@ impl PlayerPhysicsState {
             self.velocity.z -= friction * self.velocity.z * dt;
         }
 
-        if player_state.is_flying {
-            if self.acceleration.y.is_zero()
-                || self.acceleration.y.signum() != self.velocity.y.signum()
-            {
-                self.velocity.y -= ON_GROUND_FRICTION * self.velocity.y * dt;
-            }
+        if input.is_flying
+            && (self.acceleration.y.is_zero()
+                || self.acceleration.y.signum() != self.velocity.y.signum())
+        {
+            self.velocity.y -= ON_GROUND_FRICTION * self.velocity.y * dt;
         }
     }
 
-    pub fn limit_velocity(&mut self, player_properties: &PlayerState) {
+    pub fn limit_velocity(&mut self, input: &PlayerInput) {
         // Limit the horizontal speed
         let mut horizontal = vec2(self.velocity.x, self.velocity.z);
         let speed = horizontal.magnitude();
 
-        let max_speed = if player_properties.is_flying {
+        let max_speed = if input.is_flying {
             self.velocity.y = clamp(self.velocity.y, -8.0, 8.0);
 
-            if player_properties.is_sprinting {
+            if input.is_sprinting {
                 FLYING_SPRINTING_SPEED
             } else {
                 FLYING_SPEED
             }
         } else {
-            if player_properties.is_sneaking {
+            if input.is_sneaking {
                 SNEAKING_SPEED
-            } else if player_properties.is_sprinting {
+            } else if input.is_sprinting {
                 SPRINTING_SPEED
             } else {
                 WALKING_SPEED
@@ -0,0 +1,67 @@
+use crate::settings::Settings;
+
+// Texture units of the input of the passes, after the ones of the textures and the shadow map
+pub const SCENE_TEXTURE_UNIT: u32 = 6;
+pub const DEPTH_TEXTURE_UNIT: u32 = 7;
+
+// Full-screen passes applied to the world before the GUI is drawn
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PostProcessingPass {
+    // Screen-space ambient occlusion, replaces the one baked in the meshes of the chunks
+    AmbientOcclusion,
+    // Colours the screen when the camera is in a fluid
+    UnderwaterTint,
+    Tonemapping,
+    Fxaa,
+    Vignette,
+}
+
+impl PostProcessingPass {
+    // In the order they are applied
+    pub const ALL: [PostProcessingPass; 5] = [
+        PostProcessingPass::AmbientOcclusion,
+        PostProcessingPass::UnderwaterTint,
+        PostProcessingPass::Tonemapping,
+        PostProcessingPass::Fxaa,
+        PostProcessingPass::Vignette,
+    ];
+
+    pub fn is_enabled(&self, settings: &Settings) -> bool {
+        match self {
+            PostProcessingPass::AmbientOcclusion => settings.ssao,
+            PostProcessingPass::UnderwaterTint => settings.underwater_tint,
+            PostProcessingPass::Tonemapping => settings.tonemapping,
+            PostProcessingPass::Fxaa => settings.fxaa,
+            PostProcessingPass::Vignette => settings.vignette,
+        }
+    }
+
+    // The passes enabled in the settings, in the order they are applied
+    pub fn enabled(settings: &Settings) -> Vec<PostProcessingPass> {
+        Self::ALL
+            .into_iter()
+            .filter(|pass| pass.is_enabled(settings))
+            .collect()
+    }
+
+    pub fn shader_name(&self) -> &'static str {
+        match self {
+            PostProcessingPass::AmbientOcclusion => "ssao_shader",
+            PostProcessingPass::UnderwaterTint => "underwater_shader",
+            PostProcessingPass::Tonemapping => "tonemapping_shader",
+            PostProcessingPass::Fxaa => "fxaa_shader",
+            PostProcessingPass::Vignette => "vignette_shader",
+        }
+    }
+
+    // All of them share the vertex shader `post.vert`
+    pub fn fragment_shader(&self) -> &'static str {
+        match self {
+            PostProcessingPass::AmbientOcclusion => "src/shaders/ssao.frag",
+            PostProcessingPass::UnderwaterTint => "src/shaders/underwater.frag",
+            PostProcessingPass::Tonemapping => "src/shaders/tonemapping.frag",
+            PostProcessingPass::Fxaa => "src/shaders/fxaa.frag",
+            PostProcessingPass::Vignette => "src/shaders/vignette.frag",
+        }
+    }
+}
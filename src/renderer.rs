@@ -1,4 +1,3 @@
-use crate::gl_call;
 use crate::shader::ShaderProgram;
 use itertools::Itertools;
 use std::cmp::Ordering;
@@ -0,0 +1,475 @@
+use crate::chunk::BlockID;
+use crate::chunk_manager::{BlockChange, BlockEditBatch, ChunkManager};
+use crate::nbt::{self, NbtError, Tag};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+// Sponge schematic format, version 2 is written and versions 1 to 3 are read
+// https://github.com/SpongePowered/Schematic-Specification
+const SCHEMATIC_VERSION: i32 = 2;
+// Minecraft 1.16.5, the block names used are the same in every version since 1.13
+const DATA_VERSION: i32 = 2586;
+// The sizes are stored as unsigned shorts
+pub const MAX_SCHEMATIC_SIZE: i64 = u16::MAX as i64;
+
+// Names of the vanilla blocks that have no block of their own here
+// The names of our blocks are the same as the vanilla ones and are looked up with `from_name`
+const TRANSLATIONS: &[(&str, BlockID)] = &[
+    ("grass", BlockID::Air),
+    ("tall_grass", BlockID::Air),
+    ("fern", BlockID::Air),
+    ("dandelion", BlockID::Air),
+    ("poppy", BlockID::Air),
+    ("torch", BlockID::Air),
+    ("snow", BlockID::Air),
+    ("water", BlockID::Air),
+    ("cave_air", BlockID::Air),
+    ("void_air", BlockID::Air),
+    ("structure_void", BlockID::Air),
+    ("granite", BlockID::Stone),
+    ("diorite", BlockID::Stone),
+    ("andesite", BlockID::Stone),
+    ("stone_bricks", BlockID::Stone),
+    ("smooth_stone", BlockID::Stone),
+    ("gravel", BlockID::Dirt),
+    ("coarse_dirt", BlockID::Dirt),
+    ("podzol", BlockID::GrassBlock),
+    ("mossy_cobblestone", BlockID::Cobblestone),
+    ("crying_obsidian", BlockID::Obsidian),
+    ("oak_wood", BlockID::OakLog),
+    ("stripped_oak_log", BlockID::OakLog),
+    ("spruce_log", BlockID::OakLog),
+    ("birch_log", BlockID::OakLog),
+    ("jungle_log", BlockID::OakLog),
+    ("acacia_log", BlockID::OakLog),
+    ("dark_oak_log", BlockID::OakLog),
+    ("spruce_leaves", BlockID::OakLeaves),
+    ("birch_leaves", BlockID::OakLeaves),
+    ("jungle_leaves", BlockID::OakLeaves),
+    ("acacia_leaves", BlockID::OakLeaves),
+    ("dark_oak_leaves", BlockID::OakLeaves),
+    ("spruce_planks", BlockID::OakPlanks),
+    ("birch_planks", BlockID::OakPlanks),
+    ("jungle_planks", BlockID::OakPlanks),
+    ("acacia_planks", BlockID::OakPlanks),
+    ("dark_oak_planks", BlockID::OakPlanks),
+    ("glass_pane", BlockID::Glass),
+    ("white_stained_glass", BlockID::Glass),
+];
+
+// Block of a block state like "minecraft:oak_log[axis=y]", the properties are ignored
+pub fn translate_block(state: &str) -> Option<BlockID> {
+    let name = state.split('[').next().unwrap_or(state);
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+
+    BlockID::from_name(name).or_else(|| {
+        TRANSLATIONS
+            .iter()
+            .find(|(vanilla_name, _)| *vanilla_name == name)
+            .map(|&(_, block)| block)
+    })
+}
+
+// A region that does not fit in a schematic, with its width, height and length
+#[derive(Debug, PartialEq)]
+pub struct RegionTooLarge(pub i64, pub i64, pub i64);
+
+impl fmt::Display for RegionTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Region of {}x{}x{} blocks is too large, a schematic is at most {MAX_SCHEMATIC_SIZE} \
+             blocks on each side",
+            self.0, self.1, self.2
+        )
+    }
+}
+
+impl std::error::Error for RegionTooLarge {}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Mirror {
+    None,
+    // Flips the x coordinates
+    X,
+    // Flips the z coordinates
+    Z,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schematic {
+    pub width: u16,
+    pub height: u16,
+    pub length: u16,
+    // Indexed by x + z * width + y * width * length, like in the files
+    pub blocks: Vec<BlockID>,
+    // Position of the structure relative to the position it is pasted at
+    pub offset: (i32, i32, i32),
+}
+
+impl Schematic {
+    fn index(&self, x: u16, y: u16, z: u16) -> usize {
+        x as usize
+            + z as usize * self.width as usize
+            + y as usize * self.width as usize * self.length as usize
+    }
+
+    pub fn volume(&self) -> usize {
+        self.width as usize * self.height as usize * self.length as usize
+    }
+
+    pub fn get_block(&self, x: u16, y: u16, z: u16) -> BlockID {
+        self.blocks[self.index(x, y, z)]
+    }
+
+    // Copies the blocks of a cuboid, the blocks that are not loaded are air
+    pub fn from_region(
+        chunk_manager: &ChunkManager,
+        (x1, y1, z1): (i32, i32, i32),
+        (x2, y2, z2): (i32, i32, i32),
+    ) -> Result<Self, RegionTooLarge> {
+        let (min_x, max_x) = (x1.min(x2), x1.max(x2));
+        let (min_y, max_y) = (y1.min(y2), y1.max(y2));
+        let (min_z, max_z) = (z1.min(z2), z1.max(z2));
+
+        let size = |min: i32, max: i32| max as i64 - min as i64 + 1;
+        let (width, height, length) = (size(min_x, max_x), size(min_y, max_y), size(min_z, max_z));
+        if width.max(height).max(length) > MAX_SCHEMATIC_SIZE {
+            return Err(RegionTooLarge(width, height, length));
+        }
+
+        let mut blocks = Vec::new();
+        for y in min_y..=max_y {
+            for z in min_z..=max_z {
+                for x in min_x..=max_x {
+                    blocks.push(chunk_manager.get_block(x, y, z).unwrap_or(BlockID::Air));
+                }
+            }
+        }
+
+        Ok(Self {
+            width: width as u16,
+            height: height as u16,
+            length: length as u16,
+            blocks,
+            offset: (0, 0, 0),
+        })
+    }
+
+    // Puts the blocks with their minimum corner at (x, y, z) plus the offset, air included
+    // Returns the blocks changed, the blocks in columns that are not loaded are skipped
+    pub fn paste(
+        &self,
+        chunk_manager: &ChunkManager,
+        (x, y, z): (i32, i32, i32),
+    ) -> Vec<BlockChange> {
+        let (x, y, z) = (x + self.offset.0, y + self.offset.1, z + self.offset.2);
+        let mut batch = BlockEditBatch::new();
+
+        for dy in 0..self.height {
+            for dz in 0..self.length {
+                for dx in 0..self.width {
+                    let block = self.get_block(dx, dy, dz);
+                    batch.put_block(x + dx as i32, y + dy as i32, z + dz as i32, block);
+                }
+            }
+        }
+
+        chunk_manager.apply_batch(batch)
+    }
+
+    // Rotates clockwise around the vertical axis, seen from above, by a number of quarter turns
+    pub fn rotated(&self, quarter_turns: u32) -> Self {
+        let mut schematic = self.clone();
+
+        for _ in 0..quarter_turns % 4 {
+            let previous = schematic;
+            // A turn maps +x to +z and +z to -x
+            schematic = Self {
+                width: previous.length,
+                height: previous.height,
+                length: previous.width,
+                blocks: vec![BlockID::Air; previous.volume()],
+                offset: (
+                    -previous.offset.2 - (previous.length as i32 - 1),
+                    previous.offset.1,
+                    previous.offset.0,
+                ),
+            };
+
+            for y in 0..previous.height {
+                for z in 0..previous.length {
+                    for x in 0..previous.width {
+                        let index = schematic.index(previous.length - 1 - z, y, x);
+                        schematic.blocks[index] = previous.get_block(x, y, z);
+                    }
+                }
+            }
+        }
+
+        schematic
+    }
+
+    pub fn mirrored(&self, mirror: Mirror) -> Self {
+        let mut schematic = self.clone();
+
+        for y in 0..self.height {
+            for z in 0..self.length {
+                for x in 0..self.width {
+                    let (mirrored_x, mirrored_z) = match mirror {
+                        Mirror::None => (x, z),
+                        Mirror::X => (self.width - 1 - x, z),
+                        Mirror::Z => (x, self.length - 1 - z),
+                    };
+
+                    let index = schematic.index(mirrored_x, y, mirrored_z);
+                    schematic.blocks[index] = self.get_block(x, y, z);
+                }
+            }
+        }
+
+        match mirror {
+            Mirror::None => {}
+            Mirror::X => schematic.offset.0 = -self.offset.0 - (self.width as i32 - 1),
+            Mirror::Z => schematic.offset.2 = -self.offset.2 - (self.length as i32 - 1),
+        }
+
+        schematic
+    }
+
+    pub fn to_nbt(&self) -> Tag {
+        let mut palette = BTreeMap::new();
+        let mut palette_indices = HashMap::new();
+        let mut block_data = Vec::new();
+
+        for block in self.blocks.iter() {
+            let index = *palette_indices.entry(*block).or_insert_with(|| {
+                let index = palette.len() as i32;
+                palette.insert(format!("minecraft:{}", block.name()), Tag::Int(index));
+                index
+            });
+
+            // The indices are VarInts
+            let mut index = index as u32;
+            loop {
+                if index & !0x7F == 0 {
+                    block_data.push(index as u8);
+                    break;
+                }
+                block_data.push((index & 0x7F) as u8 | 0x80);
+                index >>= 7;
+            }
+        }
+
+        let mut schematic = BTreeMap::new();
+        let mut insert = |name: &str, tag: Tag| schematic.insert(name.to_string(), tag);
+        insert("Version", Tag::Int(SCHEMATIC_VERSION));
+        insert("DataVersion", Tag::Int(DATA_VERSION));
+        // The sizes are unsigned even though they are written as shorts
+        insert("Width", Tag::Short(self.width as i16));
+        insert("Height", Tag::Short(self.height as i16));
+        insert("Length", Tag::Short(self.length as i16));
+        insert(
+            "Offset",
+            Tag::IntArray(vec![self.offset.0, self.offset.1, self.offset.2]),
+        );
+        insert("PaletteMax", Tag::Int(palette.len() as i32));
+        insert("Palette", Tag::Compound(palette));
+        insert("BlockData", Tag::ByteArray(block_data));
+        insert("BlockEntities", Tag::List(Vec::new()));
+
+        Tag::Compound(schematic)
+    }
+
+    pub fn from_nbt(tag: &Tag) -> Result<Self, NbtError> {
+        // Version 3 wraps everything in another compound, and moves the blocks in "Blocks"
+        let tag = match tag.get("Schematic") {
+            Some(tag) => tag,
+            None => tag,
+        };
+        let version = tag.child("Version")?.as_i64().unwrap_or(1);
+        let blocks_tag = if version >= 3 {
+            tag.child("Blocks")?
+        } else {
+            tag
+        };
+        let data_name = if version >= 3 { "Data" } else { "BlockData" };
+
+        let size = |name: &str| {
+            tag.child(name)?
+                .as_i64()
+                .map(|size| size as u16)
+                .ok_or_else(|| NbtError::Missing(name.to_string()))
+        };
+        let (width, height, length) = (size("Width")?, size("Height")?, size("Length")?);
+
+        let offset = match tag.get("Offset").and_then(Tag::as_int_array) {
+            Some(&[x, y, z]) => (x, y, z),
+            _ => (0, 0, 0),
+        };
+
+        let mut palette = HashMap::new();
+        let palette_tag = blocks_tag
+            .child("Palette")?
+            .as_compound()
+            .ok_or_else(|| NbtError::Missing("Palette".to_string()))?;
+        for (state, index) in palette_tag.iter() {
+            let index = index
+                .as_i64()
+                .ok_or(NbtError::InvalidData("palette index is not an integer"))?;
+            // Unknown blocks become stone so that the shape of the structure is kept
+            let block = translate_block(state).unwrap_or_else(|| {
+                warn!("Unknown block {state}, replaced with stone");
+                BlockID::Stone
+            });
+            palette.insert(index as u32, block);
+        }
+
+        let data = blocks_tag
+            .child(data_name)?
+            .as_bytes()
+            .ok_or_else(|| NbtError::Missing(data_name.to_string()))?;
+
+        let volume = width as usize * height as usize * length as usize;
+        let mut blocks = Vec::with_capacity(volume);
+        let mut bytes = data.iter();
+
+        while blocks.len() < volume {
+            let mut index = 0u32;
+            for i in 0..=5 {
+                if i == 5 {
+                    return Err(NbtError::InvalidData("VarInt longer than 5 bytes"));
+                }
+
+                let byte = *bytes
+                    .next()
+                    .ok_or(NbtError::InvalidData("not enough blocks"))?;
+                index |= ((byte & 0x7F) as u32) << (7 * i);
+
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+
+            let block = palette
+                .get(&index)
+                .ok_or(NbtError::InvalidData("block outside of the palette"))?;
+            blocks.push(*block);
+        }
+
+        Ok(Self {
+            width,
+            height,
+            length,
+            blocks,
+            offset,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, NbtError> {
+        let (_, tag) = nbt::read_file(path)?;
+        Self::from_nbt(&tag)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), NbtError> {
+        nbt::write_file(path, "Schematic", &self.to_nbt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::ChunkColumn;
+    use std::sync::Arc;
+
+    // A 3x2x4 house: a stone floor, oak logs at the corners, a chest in the middle and glass
+    fn check_house(schematic: &Schematic) {
+        assert_eq!(
+            (schematic.width, schematic.height, schematic.length),
+            (3, 2, 4)
+        );
+        assert_eq!(schematic.offset, (-1, 0, -2));
+        assert_eq!(schematic.get_block(2, 0, 3), BlockID::Stone);
+        assert_eq!(schematic.get_block(0, 1, 3), BlockID::OakLog);
+        // Unknown blocks are replaced with stone
+        assert_eq!(schematic.get_block(1, 1, 1), BlockID::Stone);
+        // Its palette index takes two bytes
+        assert_eq!(schematic.get_block(1, 1, 2), BlockID::Glass);
+        assert_eq!(schematic.get_block(1, 1, 0), BlockID::Air);
+    }
+
+    #[test]
+    fn loads_the_versions_2_and_3() {
+        check_house(&Schematic::load("tests/fixtures/schematic/house_v2.schem").unwrap());
+        check_house(&Schematic::load("tests/fixtures/schematic/house_v3.schem").unwrap());
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let schematic = Schematic::load("tests/fixtures/schematic/house_v2.schem").unwrap();
+
+        let path =
+            std::env::temp_dir().join(format!("minecraft-schematic-{}.schem", std::process::id()));
+        schematic.save(&path).unwrap();
+        let saved = Schematic::load(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(saved.unwrap(), schematic);
+    }
+
+    #[test]
+    fn region_round_trip() {
+        let chunk_manager = ChunkManager::new();
+        chunk_manager.add_chunk_column((0, 0), Arc::new(ChunkColumn::new()));
+        chunk_manager.set_block(1, 10, 2, BlockID::OakPlanks);
+        chunk_manager.set_block(3, 11, 2, BlockID::Obsidian);
+
+        // The corners are in any order, the blocks of the columns not loaded are air
+        let schematic = Schematic::from_region(&chunk_manager, (3, 11, 2), (-1, 10, 2)).unwrap();
+        assert_eq!(
+            (schematic.width, schematic.height, schematic.length),
+            (5, 2, 1)
+        );
+        assert_eq!(schematic.get_block(0, 0, 0), BlockID::Air);
+        assert_eq!(schematic.get_block(2, 0, 0), BlockID::OakPlanks);
+        assert_eq!(schematic.get_block(4, 1, 0), BlockID::Obsidian);
+
+        assert_eq!(Schematic::from_nbt(&schematic.to_nbt()).unwrap(), schematic);
+
+        let pasted = ChunkManager::new();
+        pasted.add_chunk_column((0, 0), Arc::new(ChunkColumn::new()));
+        schematic.paste(&pasted, (5, 20, 5));
+        assert_eq!(pasted.get_block(7, 20, 5), Some(BlockID::OakPlanks));
+        assert_eq!(pasted.get_block(9, 21, 5), Some(BlockID::Obsidian));
+    }
+
+    #[test]
+    fn transformations_round_trip() {
+        let schematic = Schematic::load("tests/fixtures/schematic/house_v2.schem").unwrap();
+
+        assert_eq!(schematic.rotated(4), schematic);
+        assert_eq!(schematic.rotated(1).rotated(3), schematic);
+        assert_eq!(schematic.mirrored(Mirror::X).mirrored(Mirror::X), schematic);
+        assert_eq!(schematic.mirrored(Mirror::Z).mirrored(Mirror::Z), schematic);
+        assert_eq!(
+            Schematic::from_nbt(&schematic.rotated(1).to_nbt()).unwrap(),
+            schematic.rotated(1)
+        );
+    }
+
+    #[test]
+    fn regions_larger_than_a_schematic_are_refused() {
+        let chunk_manager = ChunkManager::new();
+        let max = MAX_SCHEMATIC_SIZE as i32;
+
+        assert_eq!(
+            Schematic::from_region(&chunk_manager, (0, 0, 0), (max, 0, 0)),
+            Err(RegionTooLarge(MAX_SCHEMATIC_SIZE + 1, 1, 1))
+        );
+        assert_eq!(
+            Schematic::from_region(&chunk_manager, (0, i32::MIN, 0), (0, i32::MAX, 0)),
+            Err(RegionTooLarge(1, 1 << 32, 1))
+        );
+    }
+}
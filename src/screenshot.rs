@@ -0,0 +1,150 @@
+use crossbeam_channel::{unbounded, Receiver, Sender};
+#[cfg(feature = "client")]
+use image::imageops;
+use image::{ImageResult, RgbImage};
+use std::fs;
+use std::mem;
+#[cfg(feature = "client")]
+use std::os::raw::c_void;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Where F2 and /screenshot save the screenshots
+pub const SCREENSHOTS_DIRECTORY: &str = "screenshots";
+
+// Screenshots asked for during the frame, taken by `TakeScreenshots` once everything is drawn
+#[derive(Default)]
+pub struct Screenshots {
+    requested: Vec<PathBuf>,
+}
+
+impl Screenshots {
+    pub fn request(&mut self, path: PathBuf) {
+        self.requested.push(path);
+    }
+
+    pub fn take_requests(&mut self) -> Vec<PathBuf> {
+        mem::take(&mut self.requested)
+    }
+}
+
+// Set by `RenderHeadless` once the headless frame is saved, or could not be, the client then exits
+// with this code at the end of the frame
+#[derive(Default)]
+pub struct HeadlessExit {
+    pub exit_code: Option<i32>,
+}
+
+// Compresses and writes the screenshots on its own thread, so that the frames are not held up by
+// the PNG encoding
+pub struct ScreenshotWriter {
+    images_tx: Sender<(RgbImage, PathBuf)>,
+    saved_rx: Receiver<(PathBuf, ImageResult<()>)>,
+}
+
+impl Default for ScreenshotWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScreenshotWriter {
+    pub fn new() -> Self {
+        let (images_tx, images_rx) = unbounded::<(RgbImage, PathBuf)>();
+        let (saved_tx, saved_rx) = unbounded();
+
+        // Stops once the writer is dropped
+        thread::spawn(move || {
+            for (image, path) in images_rx.iter() {
+                let result = save_screenshot(&image, &path);
+                if saved_tx.send((path, result)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            images_tx,
+            saved_rx,
+        }
+    }
+
+    pub fn save(&self, image: RgbImage, path: PathBuf) {
+        self.images_tx.send((image, path)).unwrap();
+    }
+
+    // The screenshots written since the last call, or that could not be
+    pub fn saved(&self) -> Vec<(PathBuf, ImageResult<()>)> {
+        self.saved_rx.try_iter().collect()
+    }
+}
+
+// Named after the time they are taken, so that they sort in order
+pub fn default_screenshot_path() -> PathBuf {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis());
+
+    PathBuf::from(SCREENSHOTS_DIRECTORY).join(format!("screenshot-{millis}.png"))
+}
+
+// Reads back what has been drawn to the window, before the buffers are swapped
+#[cfg(feature = "client")]
+pub fn capture_screen(width: u32, height: u32) -> RgbImage {
+    let mut pixels = vec![0u8; (width * height * 3) as usize];
+
+    gl_call!(gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0));
+    // The rows are not padded to 4 bytes
+    gl_call!(gl::PixelStorei(gl::PACK_ALIGNMENT, 1));
+    gl_call!(gl::ReadPixels(
+        0,
+        0,
+        width as i32,
+        height as i32,
+        gl::RGB,
+        gl::UNSIGNED_BYTE,
+        pixels.as_mut_ptr() as *mut c_void
+    ));
+
+    let image = RgbImage::from_raw(width, height, pixels).unwrap();
+    // The first row of OpenGL is at the bottom of the screen
+    imageops::flip_vertical(&image)
+}
+
+pub fn save_screenshot(image: &RgbImage, path: &Path) -> ImageResult<()> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+
+    image.save(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+    use std::time::Duration;
+
+    #[test]
+    fn screenshots_are_written_on_another_thread() {
+        let directory =
+            std::env::temp_dir().join(format!("minecraft-screenshots-{}", std::process::id()));
+        let path = directory.join("frame.png");
+        let image = RgbImage::from_fn(64, 32, |x, y| Rgb([x as u8, y as u8, 200]));
+
+        let writer = ScreenshotWriter::new();
+        writer.save(image.clone(), path.clone());
+        let (saved, result) = writer
+            .saved_rx
+            .recv_timeout(Duration::from_secs(10))
+            .unwrap();
+        let written = image::open(&path).map(|written| written.to_rgb8());
+        let _ = fs::remove_dir_all(&directory);
+
+        assert_eq!(saved, path);
+        assert!(result.is_ok());
+        assert_eq!(written.unwrap(), image);
+        assert!(writer.saved().is_empty());
+    }
+}
@@ -0,0 +1,282 @@
+use crate::network::DEFAULT_PORT;
+use crate::weather::WeatherDurations;
+use crossbeam_channel::{unbounded, Receiver};
+use nalgebra_glm::{vec3, IVec3};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+pub const DEFAULT_PROPERTIES_PATH: &str = "server.properties";
+
+#[derive(Debug)]
+pub enum PropertiesError {
+    Io(io::Error),
+    // A line that is neither blank, a comment nor a `key=value` pair
+    InvalidLine {
+        line: usize,
+    },
+    InvalidValue {
+        line: usize,
+        key: String,
+        value: String,
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for PropertiesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropertiesError::Io(err) => write!(f, "{err}"),
+            PropertiesError::InvalidLine { line } => {
+                write!(f, "Line {line}: expected a key=value pair")
+            }
+            PropertiesError::InvalidValue {
+                line,
+                key,
+                value,
+                expected,
+            } => write!(
+                f,
+                "Line {line}: invalid {key} '{value}', expected {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PropertiesError {}
+
+impl From<io::Error> for PropertiesError {
+    fn from(err: io::Error) -> Self {
+        PropertiesError::Io(err)
+    }
+}
+
+// Configuration of the dedicated server, read from a file of `key=value` lines
+pub struct ServerProperties {
+    // In chunks, the columns are generated around the spawn point and the players
+    pub view_distance: i32,
+    pub ticks_per_second: u32,
+    pub spawn_point: IVec3,
+    // A random seed is picked when there is none
+    pub level_seed: Option<u32>,
+    pub server_port: u16,
+    pub max_players: usize,
+    // Directory of a vanilla world whose terrain is used instead of the generated one
+    pub import_world: Option<PathBuf>,
+    pub weather_durations: WeatherDurations,
+}
+
+impl Default for ServerProperties {
+    fn default() -> Self {
+        Self {
+            view_distance: 8,
+            ticks_per_second: 20,
+            spawn_point: vec3(8, 195, 8),
+            level_seed: None,
+            server_port: DEFAULT_PORT,
+            max_players: 8,
+            import_world: None,
+            weather_durations: WeatherDurations::default(),
+        }
+    }
+}
+
+impl ServerProperties {
+    // Writes the default properties when the file does not exist yet
+    pub fn load_or_create(path: &Path) -> Result<Self, PropertiesError> {
+        if !path.exists() {
+            let properties = Self::default();
+            fs::write(path, properties.to_file_contents())?;
+            info!("Created {} with the default properties", path.display());
+
+            return Ok(properties);
+        }
+
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    // Blank lines and lines starting with '#' are ignored, unknown keys are reported and skipped
+    pub fn parse(contents: &str) -> Result<Self, PropertiesError> {
+        let mut properties = Self::default();
+
+        for (i, line) in contents.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(PropertiesError::InvalidLine { line: line_number })?;
+            let (key, value) = (key.trim(), value.trim());
+
+            let invalid = |expected: &'static str| PropertiesError::InvalidValue {
+                line: line_number,
+                key: key.to_string(),
+                value: value.to_string(),
+                expected,
+            };
+
+            match key {
+                "view-distance" => {
+                    properties.view_distance = value
+                        .parse()
+                        .ok()
+                        .filter(|distance| (2..=32).contains(distance))
+                        .ok_or_else(|| invalid("an integer between 2 and 32"))?;
+                }
+                "ticks-per-second" => {
+                    properties.ticks_per_second = value
+                        .parse()
+                        .ok()
+                        .filter(|tps| (1..=1000).contains(tps))
+                        .ok_or_else(|| invalid("an integer between 1 and 1000"))?;
+                }
+                "spawn-x" => {
+                    properties.spawn_point.x = value.parse().map_err(|_| invalid("an integer"))?
+                }
+                "spawn-y" => {
+                    properties.spawn_point.y = value
+                        .parse()
+                        .ok()
+                        .filter(|y| (0..256).contains(y))
+                        .ok_or_else(|| invalid("an integer between 0 and 255"))?;
+                }
+                "spawn-z" => {
+                    properties.spawn_point.z = value.parse().map_err(|_| invalid("an integer"))?
+                }
+                "level-seed" if value.is_empty() => properties.level_seed = None,
+                "level-seed" => {
+                    properties.level_seed =
+                        Some(value.parse().map_err(|_| invalid("a positive integer"))?)
+                }
+                "server-port" => {
+                    properties.server_port = value
+                        .parse()
+                        .ok()
+                        .filter(|&port| port > 0)
+                        .ok_or_else(|| invalid("a port between 1 and 65535"))?;
+                }
+                "max-players" => {
+                    properties.max_players = value
+                        .parse()
+                        .ok()
+                        .filter(|&players| players > 0)
+                        .ok_or_else(|| invalid("a positive integer"))?;
+                }
+                "import-world" if value.is_empty() => properties.import_world = None,
+                "import-world" => properties.import_world = Some(PathBuf::from(value)),
+                "clear-weather-duration" => {
+                    properties.weather_durations.clear =
+                        parse_duration_range(value).ok_or_else(|| invalid(DURATION_RANGE))?;
+                }
+                "rain-duration" => {
+                    properties.weather_durations.rain =
+                        parse_duration_range(value).ok_or_else(|| invalid(DURATION_RANGE))?;
+                }
+                "thunder-duration" => {
+                    properties.weather_durations.thunder =
+                        parse_duration_range(value).ok_or_else(|| invalid(DURATION_RANGE))?;
+                }
+                _ => warn!("Line {line_number}: unknown property '{key}'"),
+            }
+        }
+
+        Ok(properties)
+    }
+
+    pub fn to_file_contents(&self) -> String {
+        let level_seed = self
+            .level_seed
+            .map(|seed| seed.to_string())
+            .unwrap_or_default();
+        let import_world = self
+            .import_world
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+
+        format!(
+            "# Server properties\n\
+             view-distance={}\n\
+             ticks-per-second={}\n\
+             spawn-x={}\n\
+             spawn-y={}\n\
+             spawn-z={}\n\
+             level-seed={}\n\
+             server-port={}\n\
+             max-players={}\n\
+             import-world={}\n\
+             clear-weather-duration={}\n\
+             rain-duration={}\n\
+             thunder-duration={}\n",
+            self.view_distance,
+            self.ticks_per_second,
+            self.spawn_point.x,
+            self.spawn_point.y,
+            self.spawn_point.z,
+            level_seed,
+            self.server_port,
+            self.max_players,
+            import_world,
+            format_duration_range(&self.weather_durations.clear),
+            format_duration_range(&self.weather_durations.rain),
+            format_duration_range(&self.weather_durations.thunder),
+        )
+    }
+}
+
+const DURATION_RANGE: &str = "a number of seconds or a range of seconds like 600-1200";
+
+// The weather durations are either a number of seconds or a `min-max` range
+fn parse_duration_range(value: &str) -> Option<RangeInclusive<u32>> {
+    let (min, max) = value.split_once('-').unwrap_or((value, value));
+    let (min, max) = (min.trim().parse().ok()?, max.trim().parse().ok()?);
+
+    (0 < min && min <= max).then_some(min..=max)
+}
+
+fn format_duration_range(range: &RangeInclusive<u32>) -> String {
+    format!("{}-{}", range.start(), range.end())
+}
+
+// Shared between the server systems and the tick loop
+pub struct ServerState {
+    // Cleared by the /stop command, the server exits at the end of the tick
+    pub is_running: bool,
+}
+
+impl Default for ServerState {
+    fn default() -> Self {
+        Self { is_running: true }
+    }
+}
+
+// Reads the commands typed in the terminal on a separate thread, so that the ticks never wait for them
+pub fn read_commands_from_stdin() -> Receiver<String> {
+    let (commands_tx, commands_rx) = unbounded();
+
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    error!("Could not read the standard input: {err}");
+                    return;
+                }
+            };
+
+            if commands_tx.send(line).is_err() {
+                return;
+            }
+        }
+    });
+
+    commands_rx
+}
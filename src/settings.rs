@@ -0,0 +1,610 @@
+use crate::clouds::CloudMode;
+use crate::shadows::ShadowQuality;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use toml::{Table, Value};
+
+// In the working directory like the journal
+pub const DEFAULT_SETTINGS_PATH: &str = "settings.toml";
+
+// Bounds of the values that can be changed in the menu, the file is validated against them too
+const RENDER_DISTANCE_RANGE: RangeInclusive<i32> = 2..=32;
+const FOV_RANGE: RangeInclusive<f32> = 30.0..=110.0;
+const CHUNK_UPLOADS_RANGE: RangeInclusive<usize> = 1..=64;
+const GUI_SCALE_RANGE: RangeInclusive<f32> = 1.0..=4.0;
+const MOUSE_SENSITIVITY_RANGE: RangeInclusive<f32> = 0.01..=5.0;
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(io::Error),
+    // The file is not valid TOML, none of its values are read
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    InvalidValue {
+        key: String,
+        value: String,
+        expected: &'static str,
+    },
+    UnknownKey(String),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Io(err) => write!(f, "{err}"),
+            SettingsError::Syntax { line, message } => write!(f, "Line {line}: {message}"),
+            SettingsError::InvalidValue {
+                key,
+                value,
+                expected,
+            } => write!(f, "Invalid {key} {value}, expected {expected}"),
+            SettingsError::UnknownKey(key) => write!(f, "Unknown setting {key}"),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+impl From<io::Error> for SettingsError {
+    fn from(err: io::Error) -> Self {
+        SettingsError::Io(err)
+    }
+}
+
+// Settings of the client, read from a TOML file at startup and again whenever the file changes
+// The window size, the render distance, the shadows and the log level are only read at startup
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub window_width: u32,
+    pub window_height: u32,
+    // In chunks, around the player
+    pub render_distance: i32,
+    // In degrees
+    pub fov: f32,
+    pub fog: bool,
+    pub chunk_uploads_per_frame: usize,
+    pub gui_scale: f32,
+    // F6 cycles through them
+    pub clouds: CloudMode,
+    pub shadows: ShadowQuality,
+    // The passes of the post-processing, see `PostProcessingPass`
+    pub ssao: bool,
+    pub underwater_tint: bool,
+    pub tonemapping: bool,
+    pub fxaa: bool,
+    pub vignette: bool,
+    pub mouse_sensitivity_x: f32,
+    pub mouse_sensitivity_y: f32,
+    pub log_level: log::Level,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            window_width: 1000,
+            window_height: 600,
+            render_distance: 12,
+            fov: 70.0,
+            fog: true,
+            chunk_uploads_per_frame: 2,
+            gui_scale: 2.0,
+            clouds: CloudMode::Fancy,
+            shadows: ShadowQuality::Medium,
+            ssao: false,
+            underwater_tint: true,
+            tonemapping: true,
+            fxaa: true,
+            vignette: true,
+            mouse_sensitivity_x: 0.5,
+            mouse_sensitivity_y: 0.5,
+            log_level: log::Level::Info,
+        }
+    }
+}
+
+impl Settings {
+    // Writes the default settings when the file does not exist yet
+    // The problems are returned along with the settings rather than logged, since the logger is
+    // configured from the settings
+    pub fn load_or_create(path: &Path) -> (Self, Vec<SettingsError>) {
+        let defaults = Self::default();
+
+        if !path.exists() {
+            let errors = match defaults.save(path) {
+                Ok(()) => Vec::new(),
+                Err(err) => vec![SettingsError::Io(err)],
+            };
+            return (defaults, errors);
+        }
+
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents, &defaults),
+            Err(err) => (defaults, vec![SettingsError::Io(err)]),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_file_contents())
+    }
+
+    // The values that are missing or invalid are taken from `fallback`
+    pub fn parse(contents: &str, fallback: &Settings) -> (Self, Vec<SettingsError>) {
+        let mut settings = fallback.clone();
+        let mut errors = Vec::new();
+
+        let tables = match contents.parse::<Table>() {
+            Ok(tables) => tables,
+            Err(err) => {
+                let line = err
+                    .span()
+                    .map_or(1, |span| contents[..span.start].matches('\n').count() + 1);
+                errors.push(SettingsError::Syntax {
+                    line,
+                    message: err.message().to_string(),
+                });
+                return (settings, errors);
+            }
+        };
+
+        for (table_name, table) in tables.iter() {
+            let values = match table.as_table() {
+                Some(values) => values,
+                None => {
+                    errors.push(SettingsError::UnknownKey(table_name.clone()));
+                    continue;
+                }
+            };
+
+            for (name, value) in values.iter() {
+                let key = format!("{table_name}.{name}");
+
+                let result = match key.as_str() {
+                    "window.width" => {
+                        in_range(value, 320..=7680, "an integer between 320 and 7680")
+                            .map(|width| settings.window_width = width)
+                    }
+                    "window.height" => {
+                        in_range(value, 240..=4320, "an integer between 240 and 4320")
+                            .map(|height| settings.window_height = height)
+                    }
+                    "video.render_distance" => {
+                        in_range(value, RENDER_DISTANCE_RANGE, "an integer between 2 and 32")
+                            .map(|distance| settings.render_distance = distance)
+                    }
+                    "video.fov" => in_range(value, FOV_RANGE, "a number between 30 and 110")
+                        .map(|fov| settings.fov = fov),
+                    "video.fog" => deserialize(value)
+                        .ok_or("true or false")
+                        .map(|fog| settings.fog = fog),
+                    "video.chunk_uploads_per_frame" => {
+                        in_range(value, CHUNK_UPLOADS_RANGE, "an integer between 1 and 64")
+                            .map(|uploads| settings.chunk_uploads_per_frame = uploads)
+                    }
+                    "video.gui_scale" => {
+                        in_range(value, GUI_SCALE_RANGE, "a number between 1 and 4")
+                            .map(|scale| settings.gui_scale = scale)
+                    }
+                    "video.clouds" => deserialize::<CloudMode>(value)
+                        .ok_or("\"off\", \"fast\" or \"fancy\"")
+                        .map(|clouds| settings.clouds = clouds),
+                    "video.shadows" => deserialize::<ShadowQuality>(value)
+                        .ok_or("\"off\", \"low\", \"medium\" or \"high\"")
+                        .map(|shadows| settings.shadows = shadows),
+                    "post_processing.ssao" => deserialize(value)
+                        .ok_or("true or false")
+                        .map(|ssao| settings.ssao = ssao),
+                    "post_processing.underwater_tint" => deserialize(value)
+                        .ok_or("true or false")
+                        .map(|tint| settings.underwater_tint = tint),
+                    "post_processing.tonemapping" => deserialize(value)
+                        .ok_or("true or false")
+                        .map(|tonemapping| settings.tonemapping = tonemapping),
+                    "post_processing.fxaa" => deserialize(value)
+                        .ok_or("true or false")
+                        .map(|fxaa| settings.fxaa = fxaa),
+                    "post_processing.vignette" => deserialize(value)
+                        .ok_or("true or false")
+                        .map(|vignette| settings.vignette = vignette),
+                    "controls.mouse_sensitivity_x" => in_range(
+                        value,
+                        MOUSE_SENSITIVITY_RANGE,
+                        "a number between 0.01 and 5",
+                    )
+                    .map(|sensitivity| settings.mouse_sensitivity_x = sensitivity),
+                    "controls.mouse_sensitivity_y" => in_range(
+                        value,
+                        MOUSE_SENSITIVITY_RANGE,
+                        "a number between 0.01 and 5",
+                    )
+                    .map(|sensitivity| settings.mouse_sensitivity_y = sensitivity),
+                    "logging.level" => deserialize::<String>(value)
+                        .and_then(|level| level.parse::<log::Level>().ok())
+                        .ok_or("\"error\", \"warn\", \"info\", \"debug\" or \"trace\"")
+                        .map(|level| settings.log_level = level),
+                    _ => {
+                        errors.push(SettingsError::UnknownKey(key));
+                        continue;
+                    }
+                };
+
+                if let Err(expected) = result {
+                    errors.push(SettingsError::InvalidValue {
+                        key,
+                        value: value.to_string(),
+                        expected,
+                    });
+                }
+            }
+        }
+
+        (settings, errors)
+    }
+
+    pub fn to_file_contents(&self) -> String {
+        format!(
+            "# Client settings, they are read again when the file changes\n\
+             # The window size, the render distance, the shadows and the log level need a restart\n\
+             \n\
+             [window]\n\
+             width = {}\n\
+             height = {}\n\
+             \n\
+             [video]\n\
+             render_distance = {}\n\
+             fov = {:?}\n\
+             fog = {}\n\
+             chunk_uploads_per_frame = {}\n\
+             gui_scale = {:?}\n\
+             clouds = \"{}\"\n\
+             shadows = \"{}\"\n\
+             \n\
+             [post_processing]\n\
+             ssao = {}\n\
+             underwater_tint = {}\n\
+             tonemapping = {}\n\
+             fxaa = {}\n\
+             vignette = {}\n\
+             \n\
+             [controls]\n\
+             mouse_sensitivity_x = {:?}\n\
+             mouse_sensitivity_y = {:?}\n\
+             \n\
+             [logging]\n\
+             level = \"{}\"\n",
+            self.window_width,
+            self.window_height,
+            self.render_distance,
+            self.fov,
+            self.fog,
+            self.chunk_uploads_per_frame,
+            self.gui_scale,
+            self.clouds.name(),
+            self.shadows.name(),
+            self.ssao,
+            self.underwater_tint,
+            self.tonemapping,
+            self.fxaa,
+            self.vignette,
+            self.mouse_sensitivity_x,
+            self.mouse_sensitivity_y,
+            self.log_level.as_str().to_lowercase(),
+        )
+    }
+
+    // Takes the values of `other` that can change while playing
+    pub fn apply(&mut self, other: &Settings) {
+        self.fov = other.fov;
+        self.fog = other.fog;
+        self.chunk_uploads_per_frame = other.chunk_uploads_per_frame;
+        self.gui_scale = other.gui_scale;
+        self.clouds = other.clouds;
+        self.ssao = other.ssao;
+        self.underwater_tint = other.underwater_tint;
+        self.tonemapping = other.tonemapping;
+        self.fxaa = other.fxaa;
+        self.vignette = other.vignette;
+        self.mouse_sensitivity_x = other.mouse_sensitivity_x;
+        self.mouse_sensitivity_y = other.mouse_sensitivity_y;
+    }
+
+    // Names of the values that differ from `other` and are only read at startup
+    pub fn restart_required(&self, other: &Settings) -> Vec<&'static str> {
+        let mut names = Vec::new();
+
+        if (self.window_width, self.window_height) != (other.window_width, other.window_height) {
+            names.push("window size");
+        }
+        if self.render_distance != other.render_distance {
+            names.push("render distance");
+        }
+        if self.shadows != other.shadows {
+            names.push("shadows");
+        }
+        if self.log_level != other.log_level {
+            names.push("log level");
+        }
+
+        names
+    }
+
+    pub fn fov_radians(&self) -> f32 {
+        self.fov.to_radians()
+    }
+
+    pub fn aspect_ratio(&self) -> f32 {
+        self.window_width as f32 / self.window_height as f32
+    }
+}
+
+// The values that can be changed from the settings menu, in the order they are listed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SettingsEntry {
+    RenderDistance,
+    Fov,
+    MouseSensitivityX,
+    MouseSensitivityY,
+    Fog,
+    ChunkUploadsPerFrame,
+    GuiScale,
+    Clouds,
+    Shadows,
+    Ssao,
+    UnderwaterTint,
+    Tonemapping,
+    Fxaa,
+    Vignette,
+}
+
+impl SettingsEntry {
+    pub const ALL: [SettingsEntry; 14] = [
+        SettingsEntry::RenderDistance,
+        SettingsEntry::Fov,
+        SettingsEntry::MouseSensitivityX,
+        SettingsEntry::MouseSensitivityY,
+        SettingsEntry::Fog,
+        SettingsEntry::ChunkUploadsPerFrame,
+        SettingsEntry::GuiScale,
+        SettingsEntry::Clouds,
+        SettingsEntry::Shadows,
+        SettingsEntry::Ssao,
+        SettingsEntry::UnderwaterTint,
+        SettingsEntry::Tonemapping,
+        SettingsEntry::Fxaa,
+        SettingsEntry::Vignette,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SettingsEntry::RenderDistance => "Render distance",
+            SettingsEntry::Fov => "Field of view",
+            SettingsEntry::MouseSensitivityX => "Mouse sensitivity X",
+            SettingsEntry::MouseSensitivityY => "Mouse sensitivity Y",
+            SettingsEntry::Fog => "Fog",
+            SettingsEntry::ChunkUploadsPerFrame => "Chunk uploads per frame",
+            SettingsEntry::GuiScale => "GUI scale",
+            SettingsEntry::Clouds => "Clouds",
+            SettingsEntry::Shadows => "Shadows",
+            SettingsEntry::Ssao => "SSAO",
+            SettingsEntry::UnderwaterTint => "Underwater tint",
+            SettingsEntry::Tonemapping => "Tonemapping",
+            SettingsEntry::Fxaa => "FXAA",
+            SettingsEntry::Vignette => "Vignette",
+        }
+    }
+
+    // Whether a change only takes effect after a restart, see `Settings::restart_required`
+    pub fn needs_restart(&self) -> bool {
+        matches!(self, SettingsEntry::RenderDistance | SettingsEntry::Shadows)
+    }
+
+    pub fn value(&self, settings: &Settings) -> String {
+        let on_off = |value: bool| String::from(if value { "on" } else { "off" });
+
+        match self {
+            SettingsEntry::RenderDistance => format!("{} chunks", settings.render_distance),
+            SettingsEntry::Fov => format!("{} degrees", settings.fov),
+            SettingsEntry::MouseSensitivityX => format!("{:.2}", settings.mouse_sensitivity_x),
+            SettingsEntry::MouseSensitivityY => format!("{:.2}", settings.mouse_sensitivity_y),
+            SettingsEntry::Fog => on_off(settings.fog),
+            SettingsEntry::ChunkUploadsPerFrame => settings.chunk_uploads_per_frame.to_string(),
+            SettingsEntry::GuiScale => format!("{:.1}", settings.gui_scale),
+            SettingsEntry::Clouds => settings.clouds.name().to_string(),
+            SettingsEntry::Shadows => settings.shadows.name().to_string(),
+            SettingsEntry::Ssao => on_off(settings.ssao),
+            SettingsEntry::UnderwaterTint => on_off(settings.underwater_tint),
+            SettingsEntry::Tonemapping => on_off(settings.tonemapping),
+            SettingsEntry::Fxaa => on_off(settings.fxaa),
+            SettingsEntry::Vignette => on_off(settings.vignette),
+        }
+    }
+
+    // Moves the value one step up or down (`direction` is 1 or -1), the lists and the booleans
+    // wrap around
+    pub fn adjust(&self, settings: &mut Settings, direction: i32) {
+        match self {
+            SettingsEntry::RenderDistance => {
+                settings.render_distance = (settings.render_distance + direction)
+                    .clamp(*RENDER_DISTANCE_RANGE.start(), *RENDER_DISTANCE_RANGE.end())
+            }
+            SettingsEntry::Fov => settings.fov = step(settings.fov, 5.0, direction, FOV_RANGE),
+            SettingsEntry::MouseSensitivityX => {
+                settings.mouse_sensitivity_x = step(
+                    settings.mouse_sensitivity_x,
+                    0.05,
+                    direction,
+                    MOUSE_SENSITIVITY_RANGE,
+                )
+            }
+            SettingsEntry::MouseSensitivityY => {
+                settings.mouse_sensitivity_y = step(
+                    settings.mouse_sensitivity_y,
+                    0.05,
+                    direction,
+                    MOUSE_SENSITIVITY_RANGE,
+                )
+            }
+            SettingsEntry::Fog => settings.fog = !settings.fog,
+            SettingsEntry::ChunkUploadsPerFrame => {
+                settings.chunk_uploads_per_frame =
+                    (settings.chunk_uploads_per_frame as i32 + direction).clamp(
+                        *CHUNK_UPLOADS_RANGE.start() as i32,
+                        *CHUNK_UPLOADS_RANGE.end() as i32,
+                    ) as usize
+            }
+            SettingsEntry::GuiScale => {
+                settings.gui_scale = step(settings.gui_scale, 0.5, direction, GUI_SCALE_RANGE)
+            }
+            SettingsEntry::Clouds => {
+                settings.clouds = cycle(&CloudMode::ALL, settings.clouds, direction)
+            }
+            SettingsEntry::Shadows => {
+                settings.shadows = cycle(&ShadowQuality::ALL, settings.shadows, direction)
+            }
+            SettingsEntry::Ssao => settings.ssao = !settings.ssao,
+            SettingsEntry::UnderwaterTint => settings.underwater_tint = !settings.underwater_tint,
+            SettingsEntry::Tonemapping => settings.tonemapping = !settings.tonemapping,
+            SettingsEntry::Fxaa => settings.fxaa = !settings.fxaa,
+            SettingsEntry::Vignette => settings.vignette = !settings.vignette,
+        }
+    }
+}
+
+// State of the settings menu, opened with O
+// The changes are made to a copy of the settings file, the values that can change while playing
+// are applied at once and the file is saved when the menu is closed
+#[derive(Default)]
+pub struct SettingsMenu {
+    pub is_open: bool,
+    pub selected: usize,
+    pub draft: Settings,
+}
+
+impl SettingsMenu {
+    pub fn open(&mut self, draft: Settings) {
+        self.is_open = true;
+        self.draft = draft;
+    }
+
+    pub fn close(&mut self) {
+        self.is_open = false;
+    }
+
+    pub fn selected_entry(&self) -> SettingsEntry {
+        SettingsEntry::ALL[self.selected]
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = (self.selected + SettingsEntry::ALL.len() - 1) % SettingsEntry::ALL.len();
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % SettingsEntry::ALL.len();
+    }
+
+    pub fn adjust(&mut self, direction: i32) {
+        self.selected_entry().adjust(&mut self.draft, direction);
+    }
+}
+
+// Rounds to a multiple of `step` first, so that the values typed in the file fall back in line
+fn step(value: f32, step: f32, direction: i32, range: RangeInclusive<f32>) -> f32 {
+    (((value / step).round() + direction as f32) * step).clamp(*range.start(), *range.end())
+}
+
+fn cycle<T: Copy + PartialEq>(values: &[T], value: T, direction: i32) -> T {
+    let index = values.iter().position(|v| *v == value).unwrap_or(0) as i32;
+    values[(index + direction).rem_euclid(values.len() as i32) as usize]
+}
+
+// None when the value of the file has another type
+fn deserialize<T: DeserializeOwned>(value: &Value) -> Option<T> {
+    value.clone().try_into().ok()
+}
+
+fn in_range<T: DeserializeOwned + PartialOrd>(
+    value: &Value,
+    range: RangeInclusive<T>,
+    expected: &'static str,
+) -> Result<T, &'static str> {
+    deserialize(value)
+        .filter(|value| range.contains(value))
+        .ok_or(expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn written_settings_are_read_back() {
+        let settings = Settings {
+            window_width: 1280,
+            fov: 90.5,
+            clouds: CloudMode::Fast,
+            shadows: ShadowQuality::High,
+            fxaa: false,
+            log_level: log::Level::Debug,
+            ..Settings::default()
+        };
+
+        let (read, errors) = Settings::parse(&settings.to_file_contents(), &Settings::default());
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(read, settings);
+    }
+
+    #[test]
+    fn invalid_values_are_taken_from_the_fallback() {
+        let fallback = Settings {
+            fov: 80.0,
+            ..Settings::default()
+        };
+        let contents = "# Comment\n\
+                        [video]\n\
+                        fov = 200 # Out of the range\n\
+                        render_distance = 6\n\
+                        gui_scale = 3 # An integer for a float\n\
+                        clouds = \"thick\"\n\
+                        fog = \"yes\"\n\
+                        [controls]\n\
+                        invert_mouse = true\n";
+
+        let (settings, errors) = Settings::parse(contents, &fallback);
+        assert_eq!(
+            settings,
+            Settings {
+                render_distance: 6,
+                gui_scale: 3.0,
+                ..fallback
+            }
+        );
+
+        let messages: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Unknown setting controls.invert_mouse",
+                "Invalid video.clouds \"thick\", expected \"off\", \"fast\" or \"fancy\"",
+                "Invalid video.fog \"yes\", expected true or false",
+                "Invalid video.fov 200, expected a number between 30 and 110",
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_file_is_not_read() {
+        let contents = "[video]\nfov = 90\nshadows = \"low\n";
+
+        let (settings, errors) = Settings::parse(contents, &Settings::default());
+        assert_eq!(settings, Settings::default());
+        assert!(
+            matches!(errors[..], [SettingsError::Syntax { line: 3, .. }]),
+            "{errors:?}"
+        );
+    }
+}
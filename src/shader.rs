@@ -1,4 +1,3 @@
-use crate::gl_call;
 use gl;
 use std::fs::read_to_string;
 use std::{
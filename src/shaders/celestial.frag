@@ -0,0 +1,21 @@
+#version 460 core
+
+out vec4 Color;
+
+uniform vec3 color;
+// Opacity of the halo around the body
+uniform float halo;
+uniform float visibility;
+
+in vec2 quad_position;
+
+void main() {
+    // Square like in the vanilla game, in the middle of its halo
+    float distance = max(abs(quad_position.x), abs(quad_position.y));
+    float alpha = 1.0;
+    if (distance > 0.4) {
+        alpha = halo * pow(1.0 - (distance - 0.4) / 0.6, 2.0);
+    }
+
+    Color = vec4(color, alpha * visibility);
+}
@@ -0,0 +1,22 @@
+#version 460 core
+
+// Without the translation of the camera
+uniform mat4 view;
+uniform mat4 projection;
+uniform vec3 direction;
+uniform float size;
+
+layout (location = 0) in vec2 pos;
+
+out vec2 quad_position;
+
+void main() {
+    quad_position = pos;
+
+    // The sun and the moon turn around the north-south axis
+    vec3 right = vec3(0.0, 0.0, 1.0);
+    vec3 up = cross(right, direction);
+    vec3 position = direction * 100.0 + (right * pos.x + up * pos.y) * size;
+
+    gl_Position = projection * view * vec4(position, 1.0);
+}
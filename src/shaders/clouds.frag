@@ -0,0 +1,30 @@
+#version 460 core
+
+out vec4 Color;
+
+uniform vec3 color;
+uniform bool enable_fog;
+uniform vec3 sky_color;
+
+in VertexAttributes {
+    vec3 normal;
+    float visibility;
+} attrs;
+
+void main() {
+    Color = vec4(color, 0.8);
+
+    if (attrs.normal.y < 0.0) {
+        Color.rgb *= 0.7;
+    } else if (attrs.normal.x != 0.0) {
+        Color.rgb *= 0.9;
+    } else if (attrs.normal.z != 0.0) {
+        Color.rgb *= 0.8;
+    }
+
+    // Blending with the sky behind them hides the end of the clouds even without fog
+    if (enable_fog) {
+        Color.rgb = mix(sky_color, Color.rgb, attrs.visibility);
+    }
+    Color.a *= attrs.visibility;
+}
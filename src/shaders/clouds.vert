@@ -0,0 +1,28 @@
+#version 460 core
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+uniform vec3 camera_position;
+uniform float tile_size;
+
+layout (location = 0) in vec3 pos;
+layout (location = 1) in vec3 normal;
+
+out VertexAttributes {
+    vec3 normal;
+    float visibility;
+} attrs;
+
+void main() {
+    // The 9 instances are the tiles of the map around the camera
+    vec2 tile = vec2(gl_InstanceID % 3 - 1, gl_InstanceID / 3 - 1) * tile_size;
+    vec4 world_pos = model * vec4(pos + vec3(tile.x, 0.0, tile.y), 1.0);
+    gl_Position = projection * view * world_pos;
+
+    attrs.normal = normal;
+
+    // The clouds fade out before the edges of the tiles
+    float distance = length(world_pos.xz - camera_position.xz);
+    attrs.visibility = 1.0 - smoothstep(tile_size * 0.5, tile_size, distance);
+}
@@ -0,0 +1,17 @@
+#version 460 core
+
+out vec4 Color;
+
+uniform sampler2D tex;
+
+in vec2 uv;
+
+void main() {
+    vec4 diffuse_frag = texture(tex, uv);
+
+    if (diffuse_frag.a == 0) {
+        discard;
+    }
+
+    Color = diffuse_frag;
+}
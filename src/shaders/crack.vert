@@ -0,0 +1,22 @@
+#version 460 core
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+uniform int stage;
+
+layout (location = 0) in vec3 pos;
+layout (location = 1) in vec2 texture_coords;
+
+out vec2 uv;
+
+float z_offset = -0.001;
+
+void main() {
+    vec4 position = projection * view * model * vec4(pos, 1.0);
+    position.z += z_offset;
+    gl_Position = position;
+
+    // The 10 stages are laid out horizontally in the texture
+    uv = vec2((texture_coords.x + stage) / 10.0, texture_coords.y);
+}
@@ -0,0 +1,59 @@
+#version 460 core
+
+out vec4 Color;
+
+uniform sampler2D scene;
+
+in vec2 screen_position;
+
+const float edge_threshold = 1.0 / 8.0;
+const float edge_threshold_min = 1.0 / 16.0;
+const float span_max = 8.0;
+const float reduce_min = 1.0 / 128.0;
+const float reduce_mul = 1.0 / 8.0;
+
+float luma(vec3 color) {
+    return dot(color, vec3(0.299, 0.587, 0.114));
+}
+
+// A simplified FXAA: blurs the edges found from the contrast of the luma along them
+void main() {
+    vec2 uv = screen_position * 0.5 + 0.5;
+    vec2 texel = 1.0 / vec2(textureSize(scene, 0));
+
+    vec3 center = texture(scene, uv).rgb;
+    float luma_center = luma(center);
+    float luma_nw = luma(texture(scene, uv + vec2(-1.0, -1.0) * texel).rgb);
+    float luma_ne = luma(texture(scene, uv + vec2(1.0, -1.0) * texel).rgb);
+    float luma_sw = luma(texture(scene, uv + vec2(-1.0, 1.0) * texel).rgb);
+    float luma_se = luma(texture(scene, uv + vec2(1.0, 1.0) * texel).rgb);
+
+    float luma_min = min(luma_center, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    float luma_max = max(luma_center, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+    if (luma_max - luma_min < max(edge_threshold_min, luma_max * edge_threshold)) {
+        Color = vec4(center, 1.0);
+        return;
+    }
+
+    // Along the edge, perpendicular to the gradient of the luma
+    vec2 direction = vec2(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se)
+    );
+    float reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * reduce_mul, reduce_min);
+    float scale = 1.0 / (min(abs(direction.x), abs(direction.y)) + reduce);
+    direction = clamp(direction * scale, vec2(-span_max), vec2(span_max)) * texel;
+
+    vec3 near = 0.5 * (
+        texture(scene, uv + direction * (1.0 / 3.0 - 0.5)).rgb +
+        texture(scene, uv + direction * (2.0 / 3.0 - 0.5)).rgb
+    );
+    vec3 far = near * 0.5 + 0.25 * (
+        texture(scene, uv - direction * 0.5).rgb +
+        texture(scene, uv + direction * 0.5).rgb
+    );
+
+    // The far samples went past the edge if they are out of the range of the neighbours
+    float luma_far = luma(far);
+    Color = vec4(luma_far < luma_min || luma_far > luma_max ? near : far, 1.0);
+}
@@ -3,6 +3,7 @@
 out vec4 Color;
 
 uniform sampler2DArray array_texture;
+uniform float daylight;
 
 in VertexAttributes {
     vec3 texture_coords;
@@ -20,4 +21,5 @@ void main() {
 
     Color.rgb *= (1.0 - abs(attrs.normal.z) * 0.2);
     Color.rgb *= (1.0 - abs(attrs.normal.x) * 0.4);
+    Color.rgb *= daylight;
 }
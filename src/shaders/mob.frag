@@ -0,0 +1,29 @@
+#version 460 core
+
+out vec4 Color;
+
+uniform vec3 color;
+uniform bool enable_fog;
+uniform vec3 sky_color;
+uniform float daylight;
+
+in VertexAttributes {
+    vec3 normal;
+    float visibility;
+} attrs;
+
+void main() {
+    Color = vec4(color, 1.0);
+
+    // The parts rotate with the mob, so the shading depends on the world space normal
+    Color.rgb *= (1.0 - abs(attrs.normal.x) * 0.35);
+    Color.rgb *= (1.0 - abs(attrs.normal.z) * 0.15);
+    if (attrs.normal.y < 0.0) {
+        Color.rgb *= 0.6;
+    }
+    Color.rgb *= daylight;
+
+    if (enable_fog) {
+        Color = mix(vec4(sky_color, 1.0), Color, attrs.visibility);
+    }
+}
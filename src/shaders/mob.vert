@@ -0,0 +1,28 @@
+#version 460 core
+
+const float fog_gradient = 10.0;
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+uniform float render_distance;
+
+layout (location = 0) in vec3 pos;
+layout (location = 1) in vec3 normal;
+
+out VertexAttributes {
+    vec3 normal;
+    float visibility;
+} attrs;
+
+void main() {
+    vec4 frag_pos = view * model * vec4(pos, 1.0f);
+    gl_Position = projection * frag_pos;
+
+    attrs.normal = normalize(vec3(model * vec4(normal, 0.0)));
+
+    // Fog, same as the terrain
+    float fog_density = 0.080 / render_distance;
+    float distance = length(frag_pos.xyz);
+    attrs.visibility = exp(-pow(distance * fog_density, fog_gradient));
+}
@@ -3,6 +3,7 @@
 out vec4 Color;
 
 uniform sampler2DArray array_texture;
+uniform float daylight;
 
 in VertexAttributes {
     vec3 texture_coords;
@@ -10,5 +11,10 @@ in VertexAttributes {
 
 void main() {
     vec4 diffuse_frag = texture(array_texture, attrs.texture_coords);
-    Color = diffuse_frag;
+    // The rain and the snow textures are mostly transparent
+    if (diffuse_frag.a == 0) {
+        discard;
+    }
+
+    Color = vec4(diffuse_frag.rgb * daylight, diffuse_frag.a);
 }
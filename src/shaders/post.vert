@@ -0,0 +1,10 @@
+#version 460 core
+
+layout (location = 0) in vec2 pos;
+
+out vec2 screen_position;
+
+void main() {
+    screen_position = pos;
+    gl_Position = vec4(pos, 0.0, 1.0);
+}
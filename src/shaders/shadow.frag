@@ -0,0 +1,12 @@
+#version 460 core
+
+uniform sampler2DArray array_texture;
+
+in vec3 shadow_texture_coords;
+
+void main() {
+    // The holes of the leaves let the light through
+    if (texture(array_texture, shadow_texture_coords).a == 0) {
+        discard;
+    }
+}
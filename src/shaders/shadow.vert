@@ -0,0 +1,14 @@
+#version 460 core
+
+uniform mat4 model;
+uniform mat4 light_matrix;
+
+layout (location = 0) in vec3 pos;
+layout (location = 1) in vec3 texture_coords;
+
+out vec3 shadow_texture_coords;
+
+void main() {
+    shadow_texture_coords = texture_coords;
+    gl_Position = light_matrix * model * vec4(pos, 1.0);
+}
@@ -0,0 +1,28 @@
+#version 460 core
+
+out vec4 Color;
+
+// Without the translation of the camera
+uniform mat4 inverse_view_projection;
+uniform vec3 horizon_color;
+uniform vec3 zenith_color;
+uniform vec3 sunset_color;
+uniform vec3 sun_direction;
+uniform float twilight;
+
+in vec2 screen_position;
+
+void main() {
+    vec4 far_point = inverse_view_projection * vec4(screen_position, 1.0, 1.0);
+    vec3 direction = normalize(far_point.xyz / far_point.w);
+
+    // Most of the gradient is near the horizon
+    float height = clamp(direction.y, 0.0, 1.0);
+    vec3 color = mix(horizon_color, zenith_color, sqrt(height));
+
+    // Glow on the side of the sun at sunrise and sunset
+    float glow = pow(max(dot(direction, sun_direction), 0.0), 8.0) * twilight;
+    color = mix(color, sunset_color, glow * (1.0 - height));
+
+    Color = vec4(color, 1.0);
+}
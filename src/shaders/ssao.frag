@@ -0,0 +1,68 @@
+#version 460 core
+
+const int sample_count = 16;
+// In blocks, around the fragment
+const float radius = 0.75;
+const float strength = 0.6;
+
+out vec4 Color;
+
+uniform sampler2D scene;
+uniform sampler2D depth;
+uniform mat4 projection;
+uniform mat4 inverse_projection;
+
+in vec2 screen_position;
+
+vec3 view_position(vec2 uv) {
+    vec3 ndc = vec3(uv, texture(depth, uv).r) * 2.0 - 1.0;
+    vec4 position = inverse_projection * vec4(ndc, 1.0);
+    return position.xyz / position.w;
+}
+
+float random(vec2 seed) {
+    return fract(sin(dot(seed, vec2(12.9898, 78.233))) * 43758.5453);
+}
+
+// Occludes the fragments by the geometry around them in the hemisphere of their normal
+void main() {
+    vec2 uv = screen_position * 0.5 + 0.5;
+    vec3 color = texture(scene, uv).rgb;
+
+    // Nothing but the sky
+    if (texture(depth, uv).r == 1.0) {
+        Color = vec4(color, 1.0);
+        return;
+    }
+
+    vec3 position = view_position(uv);
+    vec3 normal = normalize(cross(dFdx(position), dFdy(position)));
+
+    // The kernel is turned randomly for every pixel, so the banding becomes noise
+    float angle = random(uv) * 6.2831853;
+    vec3 rotation = vec3(cos(angle), sin(angle), 0.0);
+    vec3 tangent = normalize(rotation - normal * dot(rotation, normal));
+    vec3 bitangent = cross(normal, tangent);
+    mat3 tbn = mat3(tangent, bitangent, normal);
+
+    float occlusion = 0.0;
+    for (int i = 0; i < sample_count; i++) {
+        // Points spread over the hemisphere, closer to the fragment than further away
+        float t = float(i) / float(sample_count);
+        float phi = float(i) * 2.3999632;
+        float z = 1.0 - t;
+        float r = sqrt(1.0 - z * z);
+        vec3 direction = vec3(cos(phi) * r, sin(phi) * r, z) * mix(0.1, 1.0, t * t);
+        vec3 sample_position = position + tbn * direction * radius;
+
+        vec4 offset = projection * vec4(sample_position, 1.0);
+        vec2 sample_uv = offset.xy / offset.w * 0.5 + 0.5;
+        float sample_depth = view_position(sample_uv).z;
+
+        // Geometry far in front of the fragment does not occlude it
+        float range = smoothstep(0.0, 1.0, radius / abs(position.z - sample_depth));
+        occlusion += (sample_depth >= sample_position.z + 0.025 ? 1.0 : 0.0) * range;
+    }
+
+    Color = vec4(color * (1.0 - occlusion / float(sample_count) * strength), 1.0);
+}
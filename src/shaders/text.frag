@@ -0,0 +1,20 @@
+#version 460 core
+
+out vec4 Color;
+
+uniform sampler2D tex;
+
+in VertexAttributes {
+    vec2 texture_coords;
+    vec4 color;
+} attrs;
+
+void main() {
+    float coverage = texture(tex, attrs.texture_coords).a;
+
+    if (coverage == 0) {
+        discard;
+    }
+
+    Color = vec4(attrs.color.rgb, attrs.color.a * coverage);
+}
@@ -0,0 +1,21 @@
+#version 460 core
+
+out vec4 Color;
+
+uniform sampler2D scene;
+uniform float exposure;
+uniform float gamma;
+
+in vec2 screen_position;
+
+// Fit of the ACES filmic curve by Krzysztof Narkowicz
+vec3 aces(vec3 x) {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+}
+
+void main() {
+    // The textures and the colours of the sky are already in gamma space
+    vec3 linear = pow(texture(scene, screen_position * 0.5 + 0.5).rgb, vec3(gamma));
+
+    Color = vec4(pow(aces(linear * exposure), vec3(1.0 / gamma)), 1.0);
+}
@@ -0,0 +1,15 @@
+#version 460 core
+
+out vec4 Color;
+
+uniform sampler2D scene;
+// The opacity is 0 when the camera is not in a fluid
+uniform vec4 tint;
+
+in vec2 screen_position;
+
+void main() {
+    vec3 color = texture(scene, screen_position * 0.5 + 0.5).rgb;
+
+    Color = vec4(mix(color, color * tint.rgb + tint.rgb * 0.2, tint.a), 1.0);
+}
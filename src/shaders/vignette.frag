@@ -0,0 +1,15 @@
+#version 460 core
+
+out vec4 Color;
+
+uniform sampler2D scene;
+
+in vec2 screen_position;
+
+void main() {
+    vec3 color = texture(scene, screen_position * 0.5 + 0.5).rgb;
+    // Darker towards the corners of the screen
+    float vignette = smoothstep(1.6, 0.6, length(screen_position));
+
+    Color = vec4(color * mix(0.6, 1.0, vignette), 1.0);
+}
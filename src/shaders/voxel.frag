@@ -1,18 +1,70 @@
 #version 460 core
 
+const int max_shadow_cascades = 4;
+
 out vec4 Color;
 
 uniform sampler2DArray array_texture;
 uniform bool enable_fog;
 uniform vec3 sky_color;
+// Lower at night, the blocks are only lit by the sky
+uniform float daylight;
+// The ambient occlusion is computed on the screen instead when SSAO is enabled
+uniform bool enable_baked_ao;
+
+// There are no cascades when the shadows are disabled
+uniform int shadow_cascades;
+uniform sampler2DArrayShadow shadow_map;
+uniform mat4 light_matrices[max_shadow_cascades];
+uniform float cascade_splits[max_shadow_cascades];
+uniform float texel_sizes[max_shadow_cascades];
+uniform vec3 light_direction;
+uniform float shadow_strength;
+uniform int pcf_radius;
 
 in VertexAttributes {
     vec3 texture_coords;
     vec3 normal;
     float ao;
     float visibility;
+    vec3 world_position;
+    float view_depth;
 } attrs;
 
+// From 0 in the light to 1 in the shadow
+float shadow() {
+    // The faces turned away from the light are always in the shadow
+    if (dot(attrs.normal, light_direction) <= 0.0) {
+        return 1.0;
+    }
+
+    int cascade = 0;
+    while (cascade < shadow_cascades && attrs.view_depth > cascade_splits[cascade]) {
+        cascade++;
+    }
+    if (cascade == shadow_cascades) {
+        return 0.0;
+    }
+
+    // Moving the position along the normal keeps the faces from shadowing themselves
+    vec3 position = attrs.world_position + attrs.normal * texel_sizes[cascade] * 1.5;
+    vec4 light_position = light_matrices[cascade] * vec4(position, 1.0);
+    vec3 coords = light_position.xyz / light_position.w * 0.5 + 0.5;
+
+    // Percentage-closer filtering, every sample blends the 4 closest texels
+    vec2 texel = 1.0 / vec2(textureSize(shadow_map, 0).xy);
+    float lit = 0.0;
+    for (int x = -pcf_radius; x <= pcf_radius; x++) {
+        for (int y = -pcf_radius; y <= pcf_radius; y++) {
+            vec2 offset = vec2(x, y) * texel;
+            lit += texture(shadow_map, vec4(coords.xy + offset, cascade, coords.z));
+        }
+    }
+    float samples = float((2 * pcf_radius + 1) * (2 * pcf_radius + 1));
+
+    return 1.0 - lit / samples;
+}
+
 void main() {
     vec4 diffuse_frag = texture(array_texture, attrs.texture_coords);
 
@@ -30,7 +82,14 @@ void main() {
         Color.rgb *= 1.0;
     }
 
-    Color.rgb *= (1.0 - attrs.ao * 0.15);
+    if (shadow_cascades > 0) {
+        Color.rgb *= 1.0 - shadow() * shadow_strength;
+    }
+
+    if (enable_baked_ao) {
+        Color.rgb *= (1.0 - attrs.ao * 0.15);
+    }
+    Color.rgb *= daylight;
     if (enable_fog) {
         Color = mix(vec4(sky_color, 1.0), Color, attrs.visibility);
     }
@@ -17,6 +17,8 @@ out VertexAttributes {
     vec3 normal;
     float ao;
     float visibility;
+    vec3 world_position;
+    float view_depth;
 } attrs;
 
 void main() {
@@ -26,9 +28,13 @@ void main() {
 
     attrs.visibility = 1.0;
 
-    vec4 frag_pos = view * model * vec4(pos, 1.0f);
+    vec4 world_position = model * vec4(pos, 1.0f);
+    vec4 frag_pos = view * world_position;
     gl_Position = projection * frag_pos;
 
+    attrs.world_position = world_position.xyz;
+    attrs.view_depth = -frag_pos.z;
+
     // Fog
     float fog_density = 0.080 / render_distance;
     float distance = length(frag_pos.xyz);
@@ -0,0 +1,301 @@
+use nalgebra_glm::{look_at, ortho, vec3, vec4, Mat4, Vec3};
+use serde::Deserialize;
+
+// The textures of the blocks and of the GUI are on the units 0 to 4
+pub const SHADOW_MAP_TEXTURE_UNIT: u32 = 5;
+pub const MAX_SHADOW_CASCADES: usize = 4;
+// Blocks in front of the cascades, towards the sun, whose shadows still fall in them
+const CASTER_MARGIN: f32 = 256.0;
+// Weight of the logarithmic splits of the view distance against the uniform ones
+const SPLIT_LAMBDA: f32 = 0.75;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShadowQuality {
+    // The faces are only shaded by their direction
+    Off,
+    Low,
+    Medium,
+    High,
+}
+
+impl ShadowQuality {
+    pub const ALL: [ShadowQuality; 4] = [
+        ShadowQuality::Off,
+        ShadowQuality::Low,
+        ShadowQuality::Medium,
+        ShadowQuality::High,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShadowQuality::Off => "off",
+            ShadowQuality::Low => "low",
+            ShadowQuality::Medium => "medium",
+            ShadowQuality::High => "high",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|quality| quality.name() == name)
+    }
+
+    // Width and height of the shadow map of every cascade
+    pub fn map_size(&self) -> i32 {
+        match self {
+            ShadowQuality::Off => 1,
+            ShadowQuality::Low => 1024,
+            ShadowQuality::Medium | ShadowQuality::High => 2048,
+        }
+    }
+
+    pub fn cascade_count(&self) -> usize {
+        match self {
+            ShadowQuality::Off => 0,
+            ShadowQuality::Low => 2,
+            ShadowQuality::Medium => 3,
+            ShadowQuality::High => MAX_SHADOW_CASCADES,
+        }
+    }
+
+    // Texels on each side of the center of the PCF kernel, 0 still blends the 4 closest texels
+    pub fn pcf_radius(&self) -> i32 {
+        match self {
+            ShadowQuality::Off | ShadowQuality::Low => 0,
+            ShadowQuality::Medium => 1,
+            ShadowQuality::High => 2,
+        }
+    }
+}
+
+pub struct ShadowCascade {
+    pub light_matrix: Mat4,
+    // Distance from the camera where the next cascade starts
+    pub split: f32,
+    // Width of a texel of the shadow map, in blocks
+    pub texel_size: f32,
+}
+
+// Updated every frame by `RenderShadows`, there are no cascades when the shadows are disabled or
+// when there is no light to cast them
+pub struct Shadows {
+    pub cascades: Vec<ShadowCascade>,
+    // Towards the sun during the day and towards the moon at night
+    pub light_direction: Vec3,
+    // How much darker the blocks in the shadows are
+    pub strength: f32,
+    pub pcf_radius: i32,
+}
+
+impl Default for Shadows {
+    fn default() -> Self {
+        Self {
+            cascades: Vec::new(),
+            light_direction: vec3(0.0, 1.0, 0.0),
+            strength: 0.0,
+            pcf_radius: 0,
+        }
+    }
+}
+
+// Splits the view of the camera up to `distance` in `quality.cascade_count()` parts, each of them
+// covered by an orthographic projection from the light
+pub fn compute_cascades(
+    quality: ShadowQuality,
+    view_matrix: &Mat4,
+    projection_matrix: &Mat4,
+    light_direction: &Vec3,
+    near: f32,
+    distance: f32,
+) -> Vec<ShadowCascade> {
+    let count = quality.cascade_count();
+    let map_size = quality.map_size() as f32;
+    let inverse_view = view_matrix.try_inverse().unwrap_or_else(Mat4::identity);
+    // Half the width and height of the view at a distance of 1
+    let tan_x = 1.0 / projection_matrix[(0, 0)];
+    let tan_y = 1.0 / projection_matrix[(1, 1)];
+
+    let mut cascades = Vec::with_capacity(count);
+    let mut start = near;
+
+    for i in 1..=count {
+        let fraction = i as f32 / count as f32;
+        let logarithmic = near * (distance / near).powf(fraction);
+        let uniform = near + (distance - near) * fraction;
+        let end = SPLIT_LAMBDA * logarithmic + (1.0 - SPLIT_LAMBDA) * uniform;
+
+        let corners: Vec<Vec3> = [start, end]
+            .iter()
+            .flat_map(|&depth| {
+                [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(|(x, y)| {
+                    (inverse_view * vec4(x * tan_x * depth, y * tan_y * depth, -depth, 1.0)).xyz()
+                })
+            })
+            .collect();
+
+        // A sphere keeps the same size when the camera turns, so the shadows do not flicker
+        let center = corners.iter().sum::<Vec3>() / corners.len() as f32;
+        let radius = corners
+            .iter()
+            .map(|corner| (corner - center).norm())
+            .fold(0.0, f32::max)
+            .ceil();
+
+        let view = look_at(
+            &(center + light_direction * (radius + CASTER_MARGIN)),
+            &center,
+            &vec3(0.0, 0.0, 1.0),
+        );
+        let mut projection = ortho(
+            -radius,
+            radius,
+            -radius,
+            radius,
+            0.0,
+            2.0 * radius + CASTER_MARGIN,
+        );
+
+        // Moves the projection by less than a texel, so that the texels stay at the same place in
+        // the world while the camera moves
+        let origin = (projection * view) * vec4(0.0, 0.0, 0.0, 1.0);
+        let texel_x = origin.x * map_size / 2.0;
+        let texel_y = origin.y * map_size / 2.0;
+        projection[(0, 3)] += (texel_x.round() - texel_x) * 2.0 / map_size;
+        projection[(1, 3)] += (texel_y.round() - texel_y) * 2.0 / map_size;
+
+        cascades.push(ShadowCascade {
+            light_matrix: projection * view,
+            split: end,
+            texel_size: 2.0 * radius / map_size,
+        });
+        start = end;
+    }
+
+    cascades
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::{normalize, perspective};
+
+    const NEAR: f32 = 0.1;
+    const DISTANCE: f32 = 192.0;
+
+    fn cascades_from(quality: ShadowQuality, eye: Vec3) -> (Mat4, Mat4, Vec<ShadowCascade>) {
+        let view = look_at(&eye, &(eye + vec3(1.0, -0.5, 0.3)), &vec3(0.0, 1.0, 0.0));
+        let projection = perspective(16.0 / 9.0, 70f32.to_radians(), NEAR, 1000.0);
+        let light_direction = normalize(&vec3(0.3, 0.8, -0.2));
+        let cascades = compute_cascades(
+            quality,
+            &view,
+            &projection,
+            &light_direction,
+            NEAR,
+            DISTANCE,
+        );
+
+        (view, projection, cascades)
+    }
+
+    // Corners of the view between the distances `start` and `end` from the camera
+    fn slice_corners(view: &Mat4, projection: &Mat4, start: f32, end: f32) -> Vec<Vec3> {
+        let inverse_view = view.try_inverse().unwrap();
+        let (tan_x, tan_y) = (1.0 / projection[(0, 0)], 1.0 / projection[(1, 1)]);
+
+        [start, end]
+            .iter()
+            .flat_map(|&depth| {
+                [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(|(x, y)| {
+                    (inverse_view * vec4(x * tan_x * depth, y * tan_y * depth, -depth, 1.0)).xyz()
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn splits_grow_up_to_the_distance() {
+        let eye = vec3(0.0, 80.0, 0.0);
+        assert!(cascades_from(ShadowQuality::Off, eye).2.is_empty());
+
+        for quality in [
+            ShadowQuality::Low,
+            ShadowQuality::Medium,
+            ShadowQuality::High,
+        ] {
+            let (_, _, cascades) = cascades_from(quality, eye);
+            assert_eq!(cascades.len(), quality.cascade_count());
+
+            let splits: Vec<f32> = cascades.iter().map(|cascade| cascade.split).collect();
+            assert!(
+                splits.windows(2).all(|pair| pair[0] < pair[1]),
+                "{splits:?}"
+            );
+            assert!(splits[0] > NEAR);
+            assert!((splits[splits.len() - 1] - DISTANCE).abs() < 1e-3);
+
+            // The closest cascades have the sharpest shadows
+            assert!(cascades
+                .windows(2)
+                .all(|pair| pair[0].texel_size < pair[1].texel_size));
+        }
+    }
+
+    #[test]
+    fn cascades_contain_their_part_of_the_view() {
+        let (view, projection, cascades) =
+            cascades_from(ShadowQuality::High, vec3(5.5, 80.0, -3.2));
+
+        let mut start = NEAR;
+        for cascade in cascades.iter() {
+            for corner in slice_corners(&view, &projection, start, cascade.split) {
+                let clip = cascade.light_matrix * vec4(corner.x, corner.y, corner.z, 1.0);
+                assert!(
+                    clip.x.abs() <= 1.0 && clip.y.abs() <= 1.0 && clip.z.abs() <= 1.0,
+                    "{corner:?} is outside of the cascade ending at {}",
+                    cascade.split
+                );
+            }
+            start = cascade.split;
+        }
+    }
+
+    #[test]
+    fn blocks_towards_the_light_still_cast_shadows() {
+        let (view, projection, cascades) = cascades_from(ShadowQuality::Low, vec3(0.0, 80.0, 0.0));
+        let light_direction = normalize(&vec3(0.3, 0.8, -0.2));
+        let corners = slice_corners(&view, &projection, NEAR, cascades[0].split);
+        let center = corners.iter().sum::<Vec3>() / corners.len() as f32;
+
+        // In front of the cascade, between the light and the blocks of the view
+        let caster = center + light_direction * (CASTER_MARGIN / 2.0);
+        let clip = cascades[0].light_matrix * vec4(caster.x, caster.y, caster.z, 1.0);
+        assert!(clip.z.abs() <= 1.0);
+    }
+
+    #[test]
+    fn texels_stay_in_place_when_the_camera_moves() {
+        let quality = ShadowQuality::Medium;
+        let half_size = quality.map_size() as f32 / 2.0;
+        let (_, _, before) = cascades_from(quality, vec3(0.0, 80.0, 0.0));
+        let (_, _, after) = cascades_from(quality, vec3(0.37, 80.2, 1.61));
+
+        for (before, after) in before.iter().zip(after.iter()) {
+            assert_eq!(before.texel_size, after.texel_size);
+
+            // The same point of the world falls at the same place inside of a texel
+            for point in [vec3(0.0, 0.0, 0.0), vec3(12.0, 64.0, -7.0)] {
+                let position = |cascade: &ShadowCascade| {
+                    let clip = cascade.light_matrix * vec4(point.x, point.y, point.z, 1.0);
+                    (clip.x * half_size, clip.y * half_size)
+                };
+                let ((x1, y1), (x2, y2)) = (position(before), position(after));
+                assert!(((x1 - x2) - (x1 - x2).round()).abs() < 0.01, "{x1} {x2}");
+                assert!(((y1 - y2) - (y1 - y2).round()).abs() < 0.01, "{y1} {y2}");
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::types::UVFaces;
+use crate::types::{TextureLayer, UVFaces};
 
 #[rustfmt::skip]
 pub fn quad(uv: (f32, f32, f32, f32)) -> Vec<f32> {
@@ -203,3 +203,28 @@ pub fn centered_unit_cube(x: f32, y: f32, z: f32, (front_layer, back_layer, top_
         0.0 + x, 0.0 + y, 0.0 + z, uv.0, uv.1, bottom_layer as f32, 0.0, -1.0, 0.0,
     ].to_vec()
 }
+
+// A flat double-sided square in the XY plane, used to draw the items that are not blocks
+// The normals point upward so that the faces are not shaded like the sides of a block
+#[rustfmt::skip]
+pub fn centered_sprite(x: f32, y: f32, z: f32, layer: TextureLayer) -> Vec<f32> {
+    let uv = (0.0, 0.0, 1.0, 1.0);
+    let layer = layer as f32;
+    let z = z + 0.5;
+
+    [
+        0.0 + x, 0.0 + y, z, uv.0, uv.1, layer, 0.0, 1.0, 0.0,
+        1.0 + x, 0.0 + y, z, uv.2, uv.1, layer, 0.0, 1.0, 0.0,
+        1.0 + x, 1.0 + y, z, uv.2, uv.3, layer, 0.0, 1.0, 0.0,
+        1.0 + x, 1.0 + y, z, uv.2, uv.3, layer, 0.0, 1.0, 0.0,
+        0.0 + x, 1.0 + y, z, uv.0, uv.3, layer, 0.0, 1.0, 0.0,
+        0.0 + x, 0.0 + y, z, uv.0, uv.1, layer, 0.0, 1.0, 0.0,
+
+        1.0 + x, 0.0 + y, z, uv.2, uv.1, layer, 0.0, 1.0, 0.0,
+        0.0 + x, 0.0 + y, z, uv.0, uv.1, layer, 0.0, 1.0, 0.0,
+        0.0 + x, 1.0 + y, z, uv.0, uv.3, layer, 0.0, 1.0, 0.0,
+        0.0 + x, 1.0 + y, z, uv.0, uv.3, layer, 0.0, 1.0, 0.0,
+        1.0 + x, 1.0 + y, z, uv.2, uv.3, layer, 0.0, 1.0, 0.0,
+        1.0 + x, 0.0 + y, z, uv.2, uv.1, layer, 0.0, 1.0, 0.0,
+    ].to_vec()
+}
@@ -0,0 +1,400 @@
+use crate::settings::Settings;
+use crate::shader::ShaderProgram;
+use std::os::raw::c_void;
+use std::ptr::null;
+
+pub const GLYPH_SIZE: u32 = 8;
+const FONT_TEXTURE_COLUMNS: u32 = 16;
+const FONT_TEXTURE_ROWS: u32 = 6;
+const FIRST_GLYPH: u8 = b' ';
+const SOLID_GLYPH: u8 = 0x7F;
+const FLOATS_PER_VERTEX: usize = 8;
+const MAX_QUADS: usize = 8192;
+
+// 8x8 bitmap font covering printable ASCII (0x20..=0x7E), one byte per row, least significant
+// bit on the left. The last glyph (0x7F) is a solid block used to draw plain rectangles.
+#[rustfmt::skip]
+const FONT_GLYPHS: [[u8; 8]; 96] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x18, 0x3C, 0x3C, 0x18, 0x18, 0x00, 0x18, 0x00], // '!'
+    [0x36, 0x36, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '"'
+    [0x36, 0x36, 0x7F, 0x36, 0x7F, 0x36, 0x36, 0x00], // '#'
+    [0x0C, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x0C, 0x00], // '$'
+    [0x00, 0x63, 0x33, 0x18, 0x0C, 0x66, 0x63, 0x00], // '%'
+    [0x1C, 0x36, 0x1C, 0x6E, 0x3B, 0x33, 0x6E, 0x00], // '&'
+    [0x06, 0x06, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00], // "'"
+    [0x18, 0x0C, 0x06, 0x06, 0x06, 0x0C, 0x18, 0x00], // '('
+    [0x06, 0x0C, 0x18, 0x18, 0x18, 0x0C, 0x06, 0x00], // ')'
+    [0x00, 0x66, 0x3C, 0xFF, 0x3C, 0x66, 0x00, 0x00], // '*'
+    [0x00, 0x0C, 0x0C, 0x3F, 0x0C, 0x0C, 0x00, 0x00], // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ','
+    [0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x00], // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x00], // '.'
+    [0x60, 0x30, 0x18, 0x0C, 0x06, 0x03, 0x01, 0x00], // '/'
+    [0x3E, 0x63, 0x73, 0x7B, 0x6F, 0x67, 0x3E, 0x00], // '0'
+    [0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x3F, 0x00], // '1'
+    [0x1E, 0x33, 0x30, 0x1C, 0x06, 0x33, 0x3F, 0x00], // '2'
+    [0x1E, 0x33, 0x30, 0x1C, 0x30, 0x33, 0x1E, 0x00], // '3'
+    [0x38, 0x3C, 0x36, 0x33, 0x7F, 0x30, 0x78, 0x00], // '4'
+    [0x3F, 0x03, 0x1F, 0x30, 0x30, 0x33, 0x1E, 0x00], // '5'
+    [0x1C, 0x06, 0x03, 0x1F, 0x33, 0x33, 0x1E, 0x00], // '6'
+    [0x3F, 0x33, 0x30, 0x18, 0x0C, 0x0C, 0x0C, 0x00], // '7'
+    [0x1E, 0x33, 0x33, 0x1E, 0x33, 0x33, 0x1E, 0x00], // '8'
+    [0x1E, 0x33, 0x33, 0x3E, 0x30, 0x18, 0x0E, 0x00], // '9'
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x00], // ':'
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ';'
+    [0x18, 0x0C, 0x06, 0x03, 0x06, 0x0C, 0x18, 0x00], // '<'
+    [0x00, 0x00, 0x3F, 0x00, 0x00, 0x3F, 0x00, 0x00], // '='
+    [0x06, 0x0C, 0x18, 0x30, 0x18, 0x0C, 0x06, 0x00], // '>'
+    [0x1E, 0x33, 0x30, 0x18, 0x0C, 0x00, 0x0C, 0x00], // '?'
+    [0x3E, 0x63, 0x7B, 0x7B, 0x7B, 0x03, 0x1E, 0x00], // '@'
+    [0x0C, 0x1E, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x00], // 'A'
+    [0x3F, 0x66, 0x66, 0x3E, 0x66, 0x66, 0x3F, 0x00], // 'B'
+    [0x3C, 0x66, 0x03, 0x03, 0x03, 0x66, 0x3C, 0x00], // 'C'
+    [0x1F, 0x36, 0x66, 0x66, 0x66, 0x36, 0x1F, 0x00], // 'D'
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x46, 0x7F, 0x00], // 'E'
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x06, 0x0F, 0x00], // 'F'
+    [0x3C, 0x66, 0x03, 0x03, 0x73, 0x66, 0x7C, 0x00], // 'G'
+    [0x33, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x33, 0x00], // 'H'
+    [0x1E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'I'
+    [0x78, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E, 0x00], // 'J'
+    [0x67, 0x66, 0x36, 0x1E, 0x36, 0x66, 0x67, 0x00], // 'K'
+    [0x0F, 0x06, 0x06, 0x06, 0x46, 0x66, 0x7F, 0x00], // 'L'
+    [0x63, 0x77, 0x7F, 0x7F, 0x6B, 0x63, 0x63, 0x00], // 'M'
+    [0x63, 0x67, 0x6F, 0x7B, 0x73, 0x63, 0x63, 0x00], // 'N'
+    [0x1C, 0x36, 0x63, 0x63, 0x63, 0x36, 0x1C, 0x00], // 'O'
+    [0x3F, 0x66, 0x66, 0x3E, 0x06, 0x06, 0x0F, 0x00], // 'P'
+    [0x1E, 0x33, 0x33, 0x33, 0x3B, 0x1E, 0x38, 0x00], // 'Q'
+    [0x3F, 0x66, 0x66, 0x3E, 0x36, 0x66, 0x67, 0x00], // 'R'
+    [0x1E, 0x33, 0x07, 0x0E, 0x38, 0x33, 0x1E, 0x00], // 'S'
+    [0x3F, 0x2D, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'T'
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x3F, 0x00], // 'U'
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // 'V'
+    [0x63, 0x63, 0x63, 0x6B, 0x7F, 0x77, 0x63, 0x00], // 'W'
+    [0x63, 0x63, 0x36, 0x1C, 0x1C, 0x36, 0x63, 0x00], // 'X'
+    [0x33, 0x33, 0x33, 0x1E, 0x0C, 0x0C, 0x1E, 0x00], // 'Y'
+    [0x7F, 0x63, 0x31, 0x18, 0x4C, 0x66, 0x7F, 0x00], // 'Z'
+    [0x1E, 0x06, 0x06, 0x06, 0x06, 0x06, 0x1E, 0x00], // '['
+    [0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x40, 0x00], // '\\'
+    [0x1E, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1E, 0x00], // ']'
+    [0x08, 0x1C, 0x36, 0x63, 0x00, 0x00, 0x00, 0x00], // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF], // '_'
+    [0x0C, 0x0C, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00], // '`'
+    [0x00, 0x00, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00], // 'a'
+    [0x07, 0x06, 0x06, 0x3E, 0x66, 0x66, 0x3B, 0x00], // 'b'
+    [0x00, 0x00, 0x1E, 0x33, 0x03, 0x33, 0x1E, 0x00], // 'c'
+    [0x38, 0x30, 0x30, 0x3E, 0x33, 0x33, 0x6E, 0x00], // 'd'
+    [0x00, 0x00, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00], // 'e'
+    [0x1C, 0x36, 0x06, 0x0F, 0x06, 0x06, 0x0F, 0x00], // 'f'
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x1F], // 'g'
+    [0x07, 0x06, 0x36, 0x6E, 0x66, 0x66, 0x67, 0x00], // 'h'
+    [0x0C, 0x00, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'i'
+    [0x30, 0x00, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E], // 'j'
+    [0x07, 0x06, 0x66, 0x36, 0x1E, 0x36, 0x67, 0x00], // 'k'
+    [0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'l'
+    [0x00, 0x00, 0x33, 0x7F, 0x7F, 0x6B, 0x63, 0x00], // 'm'
+    [0x00, 0x00, 0x1F, 0x33, 0x33, 0x33, 0x33, 0x00], // 'n'
+    [0x00, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00], // 'o'
+    [0x00, 0x00, 0x3B, 0x66, 0x66, 0x3E, 0x06, 0x0F], // 'p'
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x78], // 'q'
+    [0x00, 0x00, 0x3B, 0x6E, 0x66, 0x06, 0x0F, 0x00], // 'r'
+    [0x00, 0x00, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x00], // 's'
+    [0x08, 0x0C, 0x3E, 0x0C, 0x0C, 0x2C, 0x18, 0x00], // 't'
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00], // 'u'
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // 'v'
+    [0x00, 0x00, 0x63, 0x6B, 0x7F, 0x7F, 0x36, 0x00], // 'w'
+    [0x00, 0x00, 0x63, 0x36, 0x1C, 0x36, 0x63, 0x00], // 'x'
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x3E, 0x30, 0x1F], // 'y'
+    [0x00, 0x00, 0x3F, 0x19, 0x0C, 0x26, 0x3F, 0x00], // 'z'
+    [0x38, 0x0C, 0x0C, 0x07, 0x0C, 0x0C, 0x38, 0x00], // '{'
+    [0x18, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x00], // '|'
+    [0x07, 0x0C, 0x0C, 0x38, 0x0C, 0x0C, 0x07, 0x00], // '}'
+    [0x6E, 0x3B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '~'
+    [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF], // solid block (0x7F)
+];
+
+pub type TextColor = [f32; 4];
+
+pub const WHITE: TextColor = [1.0, 1.0, 1.0, 1.0];
+pub const GRAY: TextColor = [0.75, 0.75, 0.75, 1.0];
+pub const YELLOW: TextColor = [1.0, 1.0, 0.33, 1.0];
+pub const RED: TextColor = [1.0, 0.33, 0.33, 1.0];
+pub const GREEN: TextColor = [0.33, 1.0, 0.33, 1.0];
+pub const TRANSLUCENT_BLACK: TextColor = [0.0, 0.0, 0.0, 0.45];
+
+pub fn create_font_texture() -> u32 {
+    let width = FONT_TEXTURE_COLUMNS * GLYPH_SIZE;
+    let height = FONT_TEXTURE_ROWS * GLYPH_SIZE;
+    let mut pixels = vec![0u8; (4 * width * height) as usize];
+
+    for (index, glyph) in FONT_GLYPHS.iter().enumerate() {
+        let cell_x = (index as u32 % FONT_TEXTURE_COLUMNS) * GLYPH_SIZE;
+        let cell_y = (index as u32 / FONT_TEXTURE_COLUMNS) * GLYPH_SIZE;
+
+        for (row, bits) in glyph.iter().enumerate() {
+            for column in 0..GLYPH_SIZE {
+                let x = cell_x + column;
+                let y = cell_y + row as u32;
+                let offset = (4 * (y * width + x)) as usize;
+                let alpha = if (bits >> column) & 1 == 1 { 255 } else { 0 };
+
+                pixels[offset..offset + 4].copy_from_slice(&[255, 255, 255, alpha]);
+            }
+        }
+    }
+
+    // Upload the image to the GPU
+    let mut id = 0;
+    gl_call!(gl::CreateTextures(gl::TEXTURE_2D, 1, &mut id));
+    gl_call!(gl::TextureParameteri(
+        id,
+        gl::TEXTURE_MIN_FILTER,
+        gl::NEAREST as i32
+    ));
+    gl_call!(gl::TextureParameteri(
+        id,
+        gl::TEXTURE_MAG_FILTER,
+        gl::NEAREST as i32
+    ));
+    gl_call!(gl::TextureStorage2D(
+        id,
+        1,
+        gl::RGBA8,
+        width as i32,
+        height as i32
+    ));
+    gl_call!(gl::TextureSubImage2D(
+        id,
+        0,
+        0,
+        0,
+        width as i32,
+        height as i32,
+        gl::RGBA,
+        gl::UNSIGNED_BYTE,
+        pixels.as_ptr() as *mut c_void
+    ));
+
+    id
+}
+
+// Returns the UV rectangle (min_u, min_v, max_u, max_v) of a character in the font texture
+fn glyph_uv(character: u8) -> (f32, f32, f32, f32) {
+    let index = match character {
+        FIRST_GLYPH..=SOLID_GLYPH => character - FIRST_GLYPH,
+        _ => b'?' - FIRST_GLYPH,
+    } as u32;
+
+    let width = (FONT_TEXTURE_COLUMNS * GLYPH_SIZE) as f32;
+    let height = (FONT_TEXTURE_ROWS * GLYPH_SIZE) as f32;
+    let x = ((index % FONT_TEXTURE_COLUMNS) * GLYPH_SIZE) as f32;
+    let y = ((index / FONT_TEXTURE_COLUMNS) * GLYPH_SIZE) as f32;
+
+    (
+        x / width,
+        y / height,
+        (x + GLYPH_SIZE as f32) / width,
+        (y + GLYPH_SIZE as f32) / height,
+    )
+}
+
+// Batches text and solid rectangles into a single draw call
+// Coordinates are in window pixels, (0, 0) being the top-left corner of the window
+pub struct TextRenderer {
+    vao: u32,
+    vbo: u32,
+    vertices: Vec<f32>,
+}
+
+impl Default for TextRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextRenderer {
+    pub fn new() -> Self {
+        // Setup VAO
+        let mut vao = 0;
+        gl_call!(gl::CreateVertexArrays(1, &mut vao));
+
+        // Position
+        gl_call!(gl::EnableVertexArrayAttrib(vao, 0));
+        gl_call!(gl::VertexArrayAttribFormat(
+            vao,
+            0,
+            2,
+            gl::FLOAT,
+            gl::FALSE,
+            0
+        ));
+        gl_call!(gl::VertexArrayAttribBinding(vao, 0, 0));
+
+        // Texture coords
+        gl_call!(gl::EnableVertexArrayAttrib(vao, 1));
+        gl_call!(gl::VertexArrayAttribFormat(
+            vao,
+            1,
+            2,
+            gl::FLOAT,
+            gl::FALSE,
+            2 * std::mem::size_of::<f32>() as u32
+        ));
+        gl_call!(gl::VertexArrayAttribBinding(vao, 1, 0));
+
+        // Color
+        gl_call!(gl::EnableVertexArrayAttrib(vao, 2));
+        gl_call!(gl::VertexArrayAttribFormat(
+            vao,
+            2,
+            4,
+            gl::FLOAT,
+            gl::FALSE,
+            4 * std::mem::size_of::<f32>() as u32
+        ));
+        gl_call!(gl::VertexArrayAttribBinding(vao, 2, 0));
+
+        // Setup VBO
+        let mut vbo = 0;
+        gl_call!(gl::CreateBuffers(1, &mut vbo));
+        gl_call!(gl::NamedBufferData(
+            vbo,
+            (MAX_QUADS * 6 * FLOATS_PER_VERTEX * std::mem::size_of::<f32>()) as isize,
+            null(),
+            gl::DYNAMIC_DRAW
+        ));
+        gl_call!(gl::VertexArrayVertexBuffer(
+            vao,
+            0,
+            vbo,
+            0,
+            (FLOATS_PER_VERTEX * std::mem::size_of::<f32>()) as i32
+        ));
+
+        Self {
+            vao,
+            vbo,
+            vertices: Vec::new(),
+        }
+    }
+
+    fn push_quad(
+        &mut self,
+        (x, y, w, h): (f32, f32, f32, f32),
+        (u_min, v_min, u_max, v_max): (f32, f32, f32, f32),
+        color: TextColor,
+    ) {
+        if self.vertices.len() >= MAX_QUADS * 6 * FLOATS_PER_VERTEX {
+            return;
+        }
+
+        let [r, g, b, a] = color;
+
+        #[rustfmt::skip]
+        let quad = [
+            x,     y,     u_min, v_min, r, g, b, a,
+            x,     y + h, u_min, v_max, r, g, b, a,
+            x + w, y + h, u_max, v_max, r, g, b, a,
+            x + w, y + h, u_max, v_max, r, g, b, a,
+            x + w, y,     u_max, v_min, r, g, b, a,
+            x,     y,     u_min, v_min, r, g, b, a,
+        ];
+
+        self.vertices.extend_from_slice(&quad);
+    }
+
+    pub fn queue_rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: TextColor) {
+        self.push_quad((x, y, w, h), glyph_uv(SOLID_GLYPH), color);
+    }
+
+    // Queues a line of text and returns its width in pixels
+    pub fn queue_text(&mut self, x: f32, y: f32, scale: f32, color: TextColor, text: &str) -> f32 {
+        let glyph_size = GLYPH_SIZE as f32 * scale;
+        let mut cursor = x;
+
+        for character in text.bytes() {
+            if character != b' ' {
+                self.push_quad(
+                    (cursor, y, glyph_size, glyph_size),
+                    glyph_uv(character),
+                    color,
+                );
+            }
+
+            cursor += glyph_size;
+        }
+
+        cursor - x
+    }
+
+    // Like `queue_text` but draws a translucent background behind the text, Minecraft style
+    pub fn queue_text_with_background(
+        &mut self,
+        x: f32,
+        y: f32,
+        scale: f32,
+        color: TextColor,
+        text: &str,
+    ) {
+        let width = Self::text_width(text, scale);
+        let height = GLYPH_SIZE as f32 * scale;
+
+        self.queue_rect(
+            x - scale,
+            y - scale,
+            width + 2.0 * scale,
+            height + 2.0 * scale,
+            TRANSLUCENT_BLACK,
+        );
+        self.queue_text(x, y, scale, color, text);
+    }
+
+    pub fn text_width(text: &str, scale: f32) -> f32 {
+        text.len() as f32 * GLYPH_SIZE as f32 * scale
+    }
+
+    pub fn line_height(scale: f32) -> f32 {
+        (GLYPH_SIZE + 2) as f32 * scale
+    }
+
+    // Draws everything that was queued since the last call
+    pub fn flush(&mut self, shader: &mut ShaderProgram, settings: &Settings) {
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        let projection_matrix = nalgebra_glm::ortho(
+            0.0,
+            settings.window_width as f32,
+            settings.window_height as f32,
+            0.0,
+            -5.0,
+            5.0,
+        );
+
+        shader.use_program();
+        unsafe {
+            shader.set_uniform_matrix4fv("projection", projection_matrix.as_ptr());
+        }
+        shader.set_uniform1i("tex", 3);
+
+        gl_call!(gl::NamedBufferSubData(
+            self.vbo,
+            0,
+            (self.vertices.len() * std::mem::size_of::<f32>()) as isize,
+            self.vertices.as_ptr() as *const c_void
+        ));
+
+        gl_call!(gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA));
+        gl_call!(gl::Disable(gl::DEPTH_TEST));
+        gl_call!(gl::BindVertexArray(self.vao));
+        gl_call!(gl::DrawArrays(
+            gl::TRIANGLES,
+            0,
+            (self.vertices.len() / FLOATS_PER_VERTEX) as i32
+        ));
+        gl_call!(gl::Enable(gl::DEPTH_TEST));
+
+        self.vertices.clear();
+    }
+}
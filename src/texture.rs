@@ -1,5 +1,3 @@
-use crate::gl_call;
-
 use gl;
 use image::ColorType;
 use std::os::raw::c_void;
@@ -1,17 +1,24 @@
 use crate::block_texture_faces::BlockFaces;
 use crate::chunk::BlockID;
 use crate::constants::ITEM_ARRAY_TEXTURE_LAYERS;
-use crate::types::{TextureLayer, TexturePack};
+use crate::inventory::item::Item;
+use crate::types::{EnvironmentTextures, ItemTextures, TextureLayer, TexturePack};
 use image::{ColorType, DynamicImage};
 use std::collections::HashMap;
 use std::os::raw::c_void;
 
-pub fn generate_array_texture() -> (u32, TexturePack) {
+// Names of the textures in textures/environment
+const ENVIRONMENT_TEXTURES: [&str; 2] = ["rain", "snow"];
+
+pub fn generate_array_texture() -> (u32, TexturePack, ItemTextures, EnvironmentTextures) {
     let face_images = create_face_images_map();
     let array_texture = create_array_texture(ITEM_ARRAY_TEXTURE_LAYERS as i32);
-    let face_uvs = create_face_uvs_map(array_texture, face_images);
+    let mut layer = 0;
+    let face_uvs = create_face_uvs_map(array_texture, face_images, &mut layer);
+    let item_textures = create_item_textures_map(array_texture, &mut layer);
+    let environment_textures = create_environment_textures_map(array_texture, &mut layer);
 
-    (array_texture, face_uvs)
+    (array_texture, face_uvs, item_textures, environment_textures)
 }
 
 fn create_face_images_map() -> HashMap<BlockID, BlockFaces<&'static str>> {
@@ -56,6 +63,7 @@ fn create_face_images_map() -> HashMap<BlockID, BlockFaces<&'static str>> {
         BlockFaces::All("textures/blocks/oak_planks.png"),
     );
     face_images.insert(BlockID::Glass, BlockFaces::All("textures/blocks/glass.png"));
+    face_images.insert(BlockID::Lava, BlockFaces::All("textures/blocks/lava.png"));
     face_images.insert(BlockID::Debug, BlockFaces::All("textures/blocks/debug.png"));
     face_images.insert(
         BlockID::Debug2,
@@ -98,17 +106,17 @@ fn create_array_texture(layers: i32) -> u32 {
 fn create_face_uvs_map(
     array_texture: u32,
     face_images: HashMap<BlockID, BlockFaces<&str>>,
+    layer: &mut TextureLayer,
 ) -> TexturePack {
     // Load all the images and fill the UV map for all the blocks
-    let mut layer = 0;
 
     // Put an image into the array texture at layer "layer"
     let mut put_image_into_array_texture = |image: &DynamicImage| {
-        let layer_blit = layer;
+        let layer_blit = *layer;
         blit_image_to_texture(image, array_texture, layer_blit as i32);
 
         // Advance to the next available layer in the texture
-        layer += 1;
+        *layer += 1;
 
         // Return layer where we put the texture
         layer_blit
@@ -160,6 +168,43 @@ fn create_face_uvs_map(
     face_uvs
 }
 
+// Puts the sprites of the items that are not blocks after the block textures
+fn create_item_textures_map(array_texture: u32, layer: &mut TextureLayer) -> ItemTextures {
+    let mut item_textures = ItemTextures::new();
+
+    for item in Item::all().filter(|item| item.is_sprite()) {
+        let image = read_image(&format!("textures/items/{}.png", item.name()));
+        blit_image_to_texture(&image, array_texture, *layer as i32);
+        item_textures.insert(item, *layer);
+
+        *layer += 1;
+    }
+
+    item_textures
+}
+
+fn create_environment_textures_map(
+    array_texture: u32,
+    layer: &mut TextureLayer,
+) -> EnvironmentTextures {
+    let mut environment_textures = EnvironmentTextures::new();
+
+    for name in ENVIRONMENT_TEXTURES {
+        let image = read_image(&format!("textures/environment/{name}.png"));
+        blit_image_to_texture(&image, array_texture, *layer as i32);
+        environment_textures.insert(name, *layer);
+
+        *layer += 1;
+    }
+
+    assert!(
+        *layer <= ITEM_ARRAY_TEXTURE_LAYERS,
+        "Not enough layers in the array texture"
+    );
+
+    environment_textures
+}
+
 fn read_image(image_path: &str) -> DynamicImage {
     let img = image::open(image_path);
     let img = match img {
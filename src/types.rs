@@ -1,6 +1,9 @@
 use crate::block_texture_faces::BlockFaces;
 use crate::chunk::BlockID;
+use crate::inventory::item::Item;
+#[cfg(feature = "client")]
 use crate::particle_system::ParticleSystem;
+#[cfg(feature = "client")]
 use crate::shader::ShaderProgram;
 use std::collections::HashMap;
 
@@ -14,5 +17,11 @@ pub type UVFaces = (
     TextureLayer,
 );
 pub type TexturePack = HashMap<BlockID, BlockFaces<TextureLayer>>;
+// Layers of the sprites of the items that are not blocks
+pub type ItemTextures = HashMap<Item, TextureLayer>;
+// Layers of the textures that are neither blocks nor items, like the rain
+pub type EnvironmentTextures = HashMap<&'static str, TextureLayer>;
+#[cfg(feature = "client")]
 pub type ParticleSystems = HashMap<&'static str, ParticleSystem>;
+#[cfg(feature = "client")]
 pub type Shaders = HashMap<&'static str, ShaderProgram>;
@@ -0,0 +1,172 @@
+use nalgebra_glm::{mix, vec3, Vec3};
+use rand::{random, thread_rng, Rng};
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+// Particles of rain or snow falling around the camera at once
+pub const MAX_PRECIPITATION: usize = 2000;
+// There are no biomes, so like in the vanilla mountains it snows instead of raining above this
+// height
+pub const SNOW_LINE: i32 = 140;
+// Seconds for the rain and the darkness of the storms to fade in or out
+const FADE_DURATION: f32 = 8.0;
+// Average number of lightnings per second at the height of a thunderstorm
+const LIGHTNINGS_PER_SECOND: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WeatherKind {
+    Clear,
+    Rain,
+    Thunder,
+}
+
+impl WeatherKind {
+    pub const ALL: [WeatherKind; 3] = [WeatherKind::Clear, WeatherKind::Rain, WeatherKind::Thunder];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WeatherKind::Clear => "clear",
+            WeatherKind::Rain => "rain",
+            WeatherKind::Thunder => "thunder",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|kind| kind.name() == name)
+    }
+}
+
+// How long each kind of weather lasts, in seconds, picked at random in the range
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeatherDurations {
+    pub clear: RangeInclusive<u32>,
+    pub rain: RangeInclusive<u32>,
+    pub thunder: RangeInclusive<u32>,
+}
+
+// The same durations as in the vanilla game
+impl Default for WeatherDurations {
+    fn default() -> Self {
+        Self {
+            clear: 600..=9000,
+            rain: 600..=1200,
+            thunder: 180..=780,
+        }
+    }
+}
+
+impl WeatherDurations {
+    fn random(&self, kind: WeatherKind) -> Duration {
+        let range = match kind {
+            WeatherKind::Clear => &self.clear,
+            WeatherKind::Rain => &self.rain,
+            WeatherKind::Thunder => &self.thunder,
+        };
+
+        Duration::from_secs(thread_rng().gen_range(range.clone()) as u64)
+    }
+}
+
+// Clear weather turns into rain, and the rain either stops or turns into a thunderstorm
+// The weather only changes by itself on the server or offline, the clients follow the server
+pub struct Weather {
+    kind: WeatherKind,
+    // Time left before the next weather
+    remaining: Duration,
+    pub durations: WeatherDurations,
+    // From 0 to 1, they move towards the current weather so that the changes are progressive
+    precipitation: f32,
+    storm: f32,
+    // Brightness of the last lightning, fading out
+    lightning: f32,
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Self::new(WeatherDurations::default())
+    }
+}
+
+impl Weather {
+    pub fn new(durations: WeatherDurations) -> Self {
+        Self {
+            kind: WeatherKind::Clear,
+            remaining: durations.random(WeatherKind::Clear),
+            durations,
+            precipitation: 0.0,
+            storm: 0.0,
+            lightning: 0.0,
+        }
+    }
+
+    pub fn kind(&self) -> WeatherKind {
+        self.kind
+    }
+
+    // The duration is picked at random when there is none
+    pub fn set(&mut self, kind: WeatherKind, duration: Option<Duration>) {
+        self.kind = kind;
+        self.remaining = duration.unwrap_or_else(|| self.durations.random(kind));
+    }
+
+    pub fn advance_cycle(&mut self, elapsed: Duration) {
+        match self.remaining.checked_sub(elapsed) {
+            Some(remaining) => self.remaining = remaining,
+            None => {
+                let next = match self.kind {
+                    WeatherKind::Clear => WeatherKind::Rain,
+                    WeatherKind::Rain if random::<f32>() < 0.3 => WeatherKind::Thunder,
+                    WeatherKind::Rain => WeatherKind::Clear,
+                    WeatherKind::Thunder => WeatherKind::Rain,
+                };
+                self.set(next, None);
+            }
+        }
+    }
+
+    // Fades the rain and the darkness of the sky towards the current weather and strikes the
+    // lightnings of the storms
+    pub fn update(&mut self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f32();
+        let approach = |value: f32, target: f32| {
+            let step = seconds / FADE_DURATION;
+            if value < target {
+                (value + step).min(target)
+            } else {
+                (value - step).max(target)
+            }
+        };
+
+        let (precipitation, storm) = match self.kind {
+            WeatherKind::Clear => (0.0, 0.0),
+            WeatherKind::Rain => (1.0, 0.0),
+            WeatherKind::Thunder => (1.0, 1.0),
+        };
+        self.precipitation = approach(self.precipitation, precipitation);
+        self.storm = approach(self.storm, storm);
+
+        self.lightning = (self.lightning - seconds * 4.0).max(0.0);
+        if random::<f32>() < seconds * LIGHTNINGS_PER_SECOND * self.storm {
+            self.lightning = 1.0;
+        }
+    }
+
+    pub fn precipitation(&self) -> f32 {
+        self.precipitation
+    }
+
+    // Greyer and darker sky during the rain and the storms, brighter for a moment with lightnings
+    pub fn sky_color(&self, color: Vec3) -> Vec3 {
+        let gray = color.dot(&vec3(0.3, 0.59, 0.11)) * 0.6;
+        let color = mix(&color, &vec3(gray, gray, gray), self.precipitation * 0.75);
+        let color = color * (1.0 - self.storm * 0.5);
+
+        mix(&color, &vec3(0.9, 0.9, 1.0), self.lightning * 0.5)
+    }
+
+    pub fn daylight(&self, daylight: f32) -> f32 {
+        let daylight = daylight * (1.0 - self.precipitation * 0.2 - self.storm * 0.2);
+
+        daylight + (1.0 - daylight) * self.lightning * 0.5
+    }
+}
@@ -4,16 +4,19 @@ use glfw::ffi::glfwSwapInterval;
 use glfw::{Context, CursorMode, Glfw, OpenGlProfileHint, Window, WindowEvent, WindowHint};
 use std::sync::mpsc::Receiver;
 
+// The window is hidden when rendering headless, OpenGL still draws to its buffers
 pub fn create_window(
     width: u32,
     height: u32,
     title: &str,
+    is_visible: bool,
 ) -> (Glfw, Window, Receiver<(f64, WindowEvent)>) {
     let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
     glfw.window_hint(WindowHint::ContextVersionMajor(OPENGL_MAJOR_VERSION));
     glfw.window_hint(WindowHint::ContextVersionMinor(OPENGL_MINOR_VERSION));
     glfw.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
     glfw.window_hint(WindowHint::OpenGlDebugContext(true));
+    glfw.window_hint(WindowHint::Visible(is_visible));
 
     // Create a windowed mode window and its OpenGL context
     let (mut window, events) = glfw
@@ -27,6 +30,7 @@ pub fn create_window(
     // Make the window's context current
     window.make_current();
     window.set_key_polling(true);
+    window.set_char_polling(true);
     window.set_cursor_pos_polling(true);
     window.set_raw_mouse_motion(true);
     window.set_mouse_button_polling(true);
@@ -0,0 +1,57 @@
+use crate::inventory::tool::{Tool, ToolKind, ToolMaterial};
+use crate::schematic::Schematic;
+
+// Left clicking a block with the wand selects the first corner, right clicking the second one
+pub const WAND: Tool = Tool::new(ToolKind::Axe, ToolMaterial::Wooden);
+// Maximum number of blocks in the selection of an edit
+pub const MAX_EDIT_VOLUME: i64 = 128 * 128 * 128;
+
+pub type Corner = (i32, i32, i32);
+// Minimum and maximum corners of a cuboid
+pub type Selection = (Corner, Corner);
+
+// Selection and clipboard of the bulk edits of a player, the edits are undone with the journal
+#[derive(Default)]
+pub struct WorldEditSession {
+    pub first_corner: Option<Corner>,
+    pub second_corner: Option<Corner>,
+    // Copied blocks, their offset is relative to the player
+    pub clipboard: Option<Schematic>,
+}
+
+impl WorldEditSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Minimum and maximum corners of the selection, once both corners are selected
+    pub fn selection(&self) -> Option<Selection> {
+        let ((x1, y1, z1), (x2, y2, z2)) = (self.first_corner?, self.second_corner?);
+
+        Some((
+            (x1.min(x2), y1.min(y2), z1.min(z2)),
+            (x1.max(x2), y1.max(y2), z1.max(z2)),
+        ))
+    }
+
+    pub fn selection_volume(&self) -> Option<i64> {
+        let ((min_x, min_y, min_z), (max_x, max_y, max_z)) = self.selection()?;
+
+        Some((max_x - min_x + 1) as i64 * (max_y - min_y + 1) as i64 * (max_z - min_z + 1) as i64)
+    }
+}
+
+// Blocks of a cuboid, given by its minimum and maximum corners
+pub fn cuboid(
+    (min_x, min_y, min_z): (i32, i32, i32),
+    (max_x, max_y, max_z): (i32, i32, i32),
+) -> impl Iterator<Item = (i32, i32, i32)> {
+    (min_y..=max_y).flat_map(move |y| {
+        (min_z..=max_z).flat_map(move |z| (min_x..=max_x).map(move |x| (x, y, z)))
+    })
+}
+
+// The four vertical sides of the cuboid
+pub fn walls(min: (i32, i32, i32), max: (i32, i32, i32)) -> impl Iterator<Item = (i32, i32, i32)> {
+    cuboid(min, max).filter(move |&(x, _, z)| x == min.0 || x == max.0 || z == min.2 || z == max.2)
+}
@@ -0,0 +1,744 @@
+use crate::anvil::AnvilWorld;
+use crate::chunk::{BlockID, BlockIterator, Chunk, ChunkColumn};
+use crate::chunk_manager::ChunkManager;
+use crate::constants::{WORLD_GENERATION_THREAD_POOL_SIZE, WORLD_SEED};
+use crate::metrics::Metrics;
+use bit_vec::BitVec;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use noise::{NoiseFn, SuperSimplex};
+use num_traits::abs;
+use parking_lot::RwLock;
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Eq)]
+pub struct PrioritizedItem<T> {
+    pub item: T,
+    pub priority: i32,
+}
+
+impl<T: Eq> PartialEq<Self> for PrioritizedItem<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority
+    }
+}
+
+impl<T: Eq> PartialOrd<Self> for PrioritizedItem<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Eq> Ord for PrioritizedItem<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority.cmp(&other.priority)
+    }
+}
+
+impl<T> Deref for PrioritizedItem<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.item
+    }
+}
+
+// Generates the terrain around some centers (the players, the spawn of a server) on a thread pool
+// Does not depend on OpenGL, the client asks for the generated chunks to be meshed and sent back
+// so that it can upload them to the GPU
+pub struct ChunkGenerator {
+    noise_fn: SuperSimplex,
+    view_distance: i32,
+    chunk_column_pool: Arc<RwLock<Vec<Arc<ChunkColumn>>>>,
+
+    request_chunk_columns_tx: Sender<()>,
+    request_chunk_columns_rx: Receiver<()>,
+
+    requested_chunk_column_tx: Sender<Arc<ChunkColumn>>,
+    requested_chunk_column_rx: Receiver<Arc<ChunkColumn>>,
+
+    // Meshed chunks are sent here, nothing is meshed without it
+    upload_chunks_tx: Option<Sender<PrioritizedItem<(i32, i32, i32)>>>,
+    // Without terrain, only the columns added by someone else (e.g. a server) are meshed
+    generates_terrain: bool,
+    // The terrain is read from this world instead of being generated
+    imported_world: Option<Arc<AnvilWorld>>,
+
+    expand_chunks: Arc<RwLock<bool>>,
+    // Centers are expanded around one after the other
+    next_center: usize,
+    world_generation_thread_pool: rayon::ThreadPool,
+}
+
+impl ChunkGenerator {
+    // Columns are kept loaded up to `view_distance + 2` chunks away from a center
+    pub fn new(view_distance: i32) -> Self {
+        let (request_chunk_columns_tx, request_chunk_columns_rx) = unbounded();
+        let (requested_chunk_column_tx, requested_chunk_column_rx) = unbounded();
+
+        Self {
+            noise_fn: SuperSimplex::new(*WORLD_SEED),
+            view_distance,
+            chunk_column_pool: Arc::new(RwLock::new({
+                let mut vec = Vec::new();
+                let matrix_width = (2 * (view_distance + 2) + 1) as usize;
+
+                let reserved_columns = matrix_width * matrix_width;
+                vec.reserve(reserved_columns);
+                for _ in 0..reserved_columns {
+                    vec.push(Arc::new(ChunkColumn::new()));
+                }
+
+                vec
+            })),
+            request_chunk_columns_tx,
+            request_chunk_columns_rx,
+            requested_chunk_column_tx,
+            requested_chunk_column_rx,
+            upload_chunks_tx: None,
+            generates_terrain: true,
+            imported_world: None,
+            expand_chunks: Arc::new(RwLock::new(true)),
+            next_center: 0,
+            world_generation_thread_pool: rayon::ThreadPoolBuilder::new()
+                .stack_size(4 * 1024 * 1024)
+                .num_threads(*WORLD_GENERATION_THREAD_POOL_SIZE)
+                .build()
+                .unwrap(),
+        }
+    }
+
+    // Computes the faces and the ambient occlusion of the generated chunks and sends them
+    pub fn with_meshing(
+        mut self,
+        upload_chunks_tx: Sender<PrioritizedItem<(i32, i32, i32)>>,
+    ) -> Self {
+        self.upload_chunks_tx = Some(upload_chunks_tx);
+        self
+    }
+
+    // The columns come from elsewhere and are loaded and unloaded by their provider
+    pub fn without_terrain(mut self) -> Self {
+        self.generates_terrain = false;
+        self
+    }
+
+    // The columns are read from a vanilla world, the columns it does not have are left empty
+    pub fn with_imported_world(mut self, world: AnvilWorld) -> Self {
+        self.imported_world = Some(Arc::new(world));
+        self
+    }
+
+    pub fn view_distance(&self) -> i32 {
+        self.view_distance
+    }
+
+    pub fn thread_pool(&self) -> &rayon::ThreadPool {
+        &self.world_generation_thread_pool
+    }
+
+    // No generation is running, the loaded columns can be changed
+    pub fn is_idle(&self) -> bool {
+        *self.expand_chunks.read()
+    }
+
+    // Unloads the distant columns, feeds the running generation and starts a new one when idle
+    // The centers are chunk coordinates
+    pub fn update(&mut self, chunk_manager: &Arc<ChunkManager>, centers: &[(i32, i32, i32)]) {
+        if centers.is_empty() {
+            return;
+        }
+
+        if self.generates_terrain {
+            if self.is_idle() {
+                self.unload_distant_columns(chunk_manager, centers);
+            }
+
+            self.provide_chunk_columns();
+        }
+
+        if self.is_idle() {
+            self.next_center = (self.next_center + 1) % centers.len();
+            self.expand(chunk_manager, centers[self.next_center]);
+        }
+    }
+
+    pub fn update_metrics(&self, chunk_manager: &ChunkManager, metrics: &mut Metrics) {
+        metrics.generation_queue_depth = self.request_chunk_columns_rx.len();
+        metrics.is_generating = !self.is_idle();
+        metrics.loaded_columns = chunk_manager.loaded_chunk_columns.read().len();
+    }
+
+    // Removes the columns too far from every center, they are reused for the next columns
+    fn unload_distant_columns(&self, chunk_manager: &ChunkManager, centers: &[(i32, i32, i32)]) {
+        let distance = self.view_distance + 2;
+        let mut columns_to_remove = Vec::new();
+
+        for &(x, z) in chunk_manager.loaded_chunk_columns.read().keys() {
+            let is_distant = centers.iter().all(|&(chunk_x, _, chunk_z)| {
+                abs(x - chunk_x) > distance || abs(z - chunk_z) > distance
+            });
+
+            if is_distant {
+                columns_to_remove.push((x, z));
+            }
+        }
+
+        for xz in columns_to_remove {
+            if let Some(column) = chunk_manager.remove_chunk_column(&xz) {
+                self.chunk_column_pool.write().push(column);
+            }
+        }
+    }
+
+    // Reset chunk columns and send them to the caller (world generation)
+    fn provide_chunk_columns(&self) {
+        let time_cap = Duration::from_micros(500);
+        let before = Instant::now();
+
+        for _ in self.request_chunk_columns_rx.try_iter() {
+            let column = match self.chunk_column_pool.write().pop() {
+                Some(column) => {
+                    for chunk in column.chunks.iter() {
+                        chunk.reset();
+                    }
+
+                    column.highest_blocks.write().fill(0);
+                    *column.has_foliage.write() = false;
+
+                    column
+                }
+                None => Arc::new(ChunkColumn::new()),
+            };
+
+            if let Err(err) = self.requested_chunk_column_tx.send(column) {
+                eprintln!("{err}");
+            }
+
+            if Instant::now().duration_since(before) >= time_cap {
+                break;
+            }
+        }
+    }
+
+    fn expand(&self, chunk_manager: &Arc<ChunkManager>, center: (i32, i32, i32)) {
+        *self.expand_chunks.write() = false;
+
+        let (chunk_x, chunk_y, chunk_z) = center;
+        let view_distance = self.view_distance;
+        let generates_terrain = self.generates_terrain;
+        let imported_world = self.imported_world.clone();
+        let noise_fn = self.noise_fn;
+        let upload_chunks_tx = self.upload_chunks_tx.clone();
+        let chunk_manager = Arc::clone(chunk_manager);
+        let expand_chunks = Arc::clone(&self.expand_chunks);
+        let request_chunk_columns_tx = self.request_chunk_columns_tx.clone();
+        let requested_chunk_column_rx = self.requested_chunk_column_rx.clone();
+
+        self.world_generation_thread_pool.spawn(move || {
+            if generates_terrain {
+                let new_columns = flood_fill_unloaded_columns(
+                    &chunk_manager,
+                    chunk_x,
+                    chunk_z,
+                    view_distance + 2,
+                );
+
+                for _ in 0..new_columns.len() {
+                    request_chunk_columns_tx.send(()).unwrap();
+                }
+
+                let mut unloaded_columns = Vec::new();
+
+                for (x, z) in new_columns {
+                    let column = match requested_chunk_column_rx.recv() {
+                        Ok(column) => column,
+                        Err(err) => {
+                            eprintln!("{err}");
+                            return;
+                        }
+                    };
+
+                    unloaded_columns.push((x, z, column))
+                }
+
+                // Terrain generation
+                rayon::scope(|s| {
+                    for (x, z, column) in unloaded_columns {
+                        let chunk_manager = Arc::clone(&chunk_manager);
+                        let imported_world = imported_world.clone();
+
+                        s.spawn(move |_| {
+                            match imported_world {
+                                Some(world) => import_terrain(&world, x, z, &column),
+                                None => generate_terrain(&noise_fn, x, z, &column),
+                            }
+                            chunk_manager.add_chunk_column((x, z), column);
+                        });
+                    }
+                });
+
+                for (chunk_x, chunk_z) in
+                    flood_fill_unfoliated_columns(&chunk_manager, chunk_x, chunk_z, view_distance)
+                {
+                    place_trees(&noise_fn, &chunk_manager, chunk_x, chunk_z);
+                }
+            }
+
+            // Chunk face culling & AO, only needed to render the chunks
+            if let Some(upload_chunks_tx) = upload_chunks_tx {
+                rayon::scope(|s| {
+                    let new_chunks =
+                        flood_fill_chunks(&chunk_manager, chunk_x, chunk_y, chunk_z, view_distance);
+
+                    for (chunk_x, chunk_y, chunk_z) in new_chunks {
+                        let chunk_manager = Arc::clone(&chunk_manager);
+                        let send_chunks = upload_chunks_tx.clone();
+
+                        s.spawn(move |_| {
+                            if let Some(chunk) = chunk_manager.get_chunk(chunk_x, chunk_y, chunk_z)
+                            {
+                                if chunk.is_empty() {
+                                    *chunk.is_generated.write() = true;
+                                    *chunk.is_uploaded_to_gpu.write() = true;
+                                    return;
+                                }
+
+                                chunk_manager.update_blocks(
+                                    chunk_x,
+                                    chunk_y,
+                                    chunk_z,
+                                    BlockIterator::new(),
+                                );
+                                *chunk.is_generated.write() = true;
+
+                                if let Err(err) = send_chunks.send(PrioritizedItem {
+                                    item: (chunk_x, chunk_y, chunk_z),
+                                    priority: 0,
+                                }) {
+                                    error!("{err}");
+                                }
+                            }
+                        });
+                    }
+                });
+            }
+
+            *expand_chunks.write() = true;
+        });
+    }
+}
+
+fn compute_tree_placement_in_chunk(noise: &SuperSimplex, x: f64, z: f64) -> Vec<(u32, u32)> {
+    let mut maximums = Vec::new();
+
+    #[inline]
+    fn index(i: i32, j: i32) -> usize {
+        (18 * i + j) as usize
+    }
+
+    let mut samples: [f64; 18 * 18] = [0.0; 18 * 18];
+
+    for i in -1..=16 {
+        for j in -1..16 {
+            let x = x + j as f64 * 0.075;
+            let z = z + i as f64 * 0.075;
+            samples[index(i + 1, j + 1)] = noise.get([x, z]);
+        }
+    }
+
+    for i in 1..17 {
+        for j in 1..17 {
+            let center = samples[index(i, j)];
+            let is_max = (|| {
+                for i_new in i - 1..=i + 1 {
+                    for j_new in j - 1..=j + 1 {
+                        if i_new == i && j_new == j {
+                            continue;
+                        }
+
+                        if samples[index(i_new, j_new)] >= center {
+                            return false;
+                        }
+                    }
+                }
+
+                true
+            })();
+
+            if is_max {
+                maximums.push(((j - 1) as u32, (i - 1) as u32));
+            }
+        }
+    }
+
+    maximums
+}
+
+// Fills a column with stone, grass, dirt and bedrock
+fn generate_terrain(noise_fn: &SuperSimplex, x: i32, z: i32, column: &ChunkColumn) {
+    // Stone
+    for y in (0..16).rev() {
+        let y = 16 * y;
+        for block_y in 0..16 {
+            for block_x in 0..16 {
+                for block_z in 0..16 {
+                    let x = 16 * x;
+                    let z = 16 * z;
+                    let scale = 90.0;
+
+                    // Scale the input for the noise function
+                    let (xf, yf, zf) = (
+                        (x + block_x as i32) as f64 / scale,
+                        (y + block_y as i32) as f64 / (scale / 1.0),
+                        (z + block_z as i32) as f64 / scale,
+                    );
+
+                    let height = (y + block_y as i32) as f64;
+                    let noise = noise_fn.get([xf, yf, zf]) * 64.0 + 64.0 + height * 1.7;
+
+                    if noise < 256.0 {
+                        column.set_block(block_x, y as u32 + block_y, block_z, BlockID::Stone);
+                    }
+                }
+            }
+        }
+    }
+
+    // Grass and dirt
+    for block_x in 0..16 {
+        for block_z in 0..16 {
+            let y = column.highest_blocks.read()[16 * block_z + block_x] as i32;
+            let chunk_y = y / 16;
+            let block_y = (y % 16) as usize;
+
+            column.get_chunk(chunk_y).set_block(
+                block_x as u32,
+                block_y as u32,
+                block_z as u32,
+                BlockID::GrassBlock,
+            );
+
+            for y in (y - 3)..y {
+                let chunk_y = y / 16;
+                let block_y = (y % 16) as usize;
+                let chunk = column.get_chunk(chunk_y);
+
+                if chunk
+                    .get_block(block_x as u32, block_y as u32, block_z as u32)
+                    .is_air()
+                {
+                    continue;
+                }
+
+                chunk.set_block(
+                    block_x as u32,
+                    block_y as u32,
+                    block_z as u32,
+                    BlockID::Dirt,
+                );
+            }
+        }
+    }
+
+    // Bedrock
+    let chunk = column.get_chunk(0);
+
+    for block_x in 0..16 {
+        for block_z in 0..16 {
+            for block_y in 0..3 {
+                chunk.set_block(block_x, block_y, block_z, BlockID::Bedrock);
+            }
+        }
+    }
+}
+
+// The imported columns already have their trees
+fn import_terrain(world: &AnvilWorld, x: i32, z: i32, column: &ChunkColumn) {
+    if let Err(err) = world.load_column(x, z, column) {
+        warn!("Could not import the column ({x}, {z}): {err}");
+    }
+
+    *column.has_foliage.write() = true;
+}
+
+// Trees can overlap the neighbouring columns, so they are placed once the neighbours are loaded
+fn place_trees(noise_fn: &SuperSimplex, chunk_manager: &ChunkManager, chunk_x: i32, chunk_z: i32) {
+    let column = chunk_manager.get_column(chunk_x, chunk_z).unwrap();
+    *column.has_foliage.write() = true;
+
+    for (x, z) in
+        compute_tree_placement_in_chunk(noise_fn, (chunk_x * 16) as f64, (chunk_z * 16) as f64)
+    {
+        let (x, z) = (x as usize, z as usize);
+        let y = column.highest_blocks.read()[16 * z + x] as i32;
+
+        let x = chunk_x * 16 + x as i32;
+        let z = chunk_z * 16 + z as i32;
+
+        let h = 5;
+
+        for i in y + 1..y + 1 + h {
+            chunk_manager.set_block(x, i, z, BlockID::OakLog);
+        }
+
+        for yy in y + h - 2..=y + h - 1 {
+            for xx in x - 2..=x + 2 {
+                for zz in z - 2..=z + 2 {
+                    if xx != x || zz != z {
+                        chunk_manager.set_block(xx, yy, zz, BlockID::OakLeaves);
+                    }
+                }
+            }
+        }
+
+        for xx in x - 1..=x + 1 {
+            for zz in z - 1..=z + 1 {
+                if xx != x || zz != z {
+                    chunk_manager.set_block(xx, y + h, zz, BlockID::OakLeaves);
+                }
+            }
+        }
+
+        for (xx, zz) in [(x, z), (x + 1, z), (x - 1, z), (x, z + 1), (x, z - 1)] {
+            chunk_manager.set_block(xx, y + h + 1, zz, BlockID::OakLeaves);
+        }
+    }
+}
+
+fn flood_fill_unloaded_columns(
+    chunk_manager: &ChunkManager,
+    x: i32,
+    z: i32,
+    distance: i32,
+) -> Vec<(i32, i32)> {
+    assert!(distance >= 2);
+
+    let matrix_width = 2 * distance + 1;
+    let mut is_visited = BitVec::from_elem((matrix_width * matrix_width) as usize, false);
+
+    let center = (x, z);
+    let matrix_index = move |x: i32, z: i32| {
+        (matrix_width * (x - center.0 + distance) + (z - center.1 + distance)) as usize
+    };
+    let is_position_valid =
+        |coord_x: i32, coord_z: i32| abs(x - coord_x) <= distance && abs(z - coord_z) <= distance;
+
+    let mut queue = VecDeque::new();
+    let mut ring = Vec::new();
+    let mut ring_number = 0;
+
+    queue.push_back((x, z));
+    ring.push((x, z));
+    is_visited.set(matrix_index(x, z), true);
+
+    while !queue.is_empty() {
+        // Expand the ring
+        for (coord_x, coord_z) in queue.drain(..) {
+            for &(coord_x, coord_z) in &[
+                (coord_x + 1, coord_z),
+                (coord_x - 1, coord_z),
+                (coord_x, coord_z + 1),
+                (coord_x, coord_z - 1),
+            ] {
+                if is_position_valid(coord_x, coord_z)
+                    && !is_visited[matrix_index(coord_x, coord_z)]
+                {
+                    ring.push((coord_x, coord_z));
+                    is_visited.set(matrix_index(coord_x, coord_z), true);
+                }
+            }
+        }
+
+        // We must expand at least 2 rings before returning something
+        ring_number += 1;
+
+        if ring_number < 2 {
+            queue.extend(ring.iter());
+            continue;
+        }
+
+        let mut unloaded_columns = Vec::new();
+
+        for column in ring.iter() {
+            if !chunk_manager
+                .loaded_chunk_columns
+                .read()
+                .contains_key(column)
+            {
+                unloaded_columns.push(*column);
+            }
+        }
+
+        if !unloaded_columns.is_empty() {
+            return unloaded_columns;
+        } else {
+            queue.extend(ring.iter());
+            ring.clear();
+        }
+    }
+
+    Vec::new()
+}
+
+fn flood_fill_unfoliated_columns(
+    chunk_manager: &ChunkManager,
+    x: i32,
+    z: i32,
+    distance: i32,
+) -> Vec<(i32, i32)> {
+    assert!(distance >= 0);
+
+    let matrix_width = 2 * distance + 1;
+    let mut is_visited = BitVec::from_elem((matrix_width * matrix_width) as usize, false);
+
+    let center = (x, z);
+    let matrix_index = move |x: i32, z: i32| {
+        (matrix_width * (x - center.0 + distance) + (z - center.1 + distance)) as usize
+    };
+
+    let is_position_valid =
+        |chunk_x: i32, chunk_z: i32| abs(x - chunk_x) <= distance && abs(z - chunk_z) <= distance;
+
+    let mut queue = VecDeque::new();
+    let mut ring = Vec::new();
+
+    queue.push_back((x, z));
+    ring.push((x, z));
+    is_visited.set(matrix_index(x, z), true);
+
+    // First column
+    if let Some(column) = chunk_manager.get_column(x, z) {
+        if !*column.has_foliage.read() {
+            return ring;
+        }
+    }
+
+    while !queue.is_empty() {
+        // Expand the ring
+        for (chunk_x, chunk_z) in queue.drain(..) {
+            for &(chunk_x, chunk_z) in &[
+                (chunk_x + 1, chunk_z),
+                (chunk_x - 1, chunk_z),
+                (chunk_x, chunk_z + 1),
+                (chunk_x, chunk_z - 1),
+            ] {
+                if is_position_valid(chunk_x, chunk_z)
+                    && !is_visited[matrix_index(chunk_x, chunk_z)]
+                {
+                    ring.push((chunk_x, chunk_z));
+                    is_visited.set(matrix_index(chunk_x, chunk_z), true);
+                }
+            }
+        }
+
+        let mut unfoliated_columns = Vec::new();
+
+        for &(x, z) in ring.iter() {
+            let has_foliage = match chunk_manager.get_column(x, z) {
+                Some(column) => *column.has_foliage.read(),
+                None => true,
+            };
+
+            if !has_foliage {
+                unfoliated_columns.push((x, z));
+            }
+        }
+
+        if !unfoliated_columns.is_empty() {
+            return unfoliated_columns;
+        } else {
+            queue.extend(ring.iter());
+            ring.clear();
+        }
+    }
+
+    Vec::new()
+}
+
+fn flood_fill_chunks(
+    chunk_manager: &ChunkManager,
+    x: i32,
+    y: i32,
+    z: i32,
+    distance: i32,
+) -> Vec<(i32, i32, i32)> {
+    assert!(distance >= 0);
+
+    let matrix_width = 2 * distance + 1;
+    let mut is_visited =
+        BitVec::from_elem((matrix_width * matrix_width * matrix_width) as usize, false);
+
+    let center = (x, y, z);
+    let coords_to_index = move |x: i32, y: i32, z: i32| {
+        (matrix_width * matrix_width * (x - center.0 + distance)
+            + matrix_width * (y - center.1 + distance)
+            + (z - center.2 + distance)) as usize
+    };
+    let is_position_valid = |coord_x: i32, coord_y: i32, coord_z: i32| {
+        abs(x - coord_x) <= distance && abs(y - coord_y) <= distance && abs(z - coord_z) <= distance
+    };
+
+    let mut queue = VecDeque::new();
+    let mut ring = Vec::new();
+
+    queue.push_back((x, y, z));
+    ring.push((x, y, z));
+    is_visited.set(coords_to_index(x, y, z), true);
+
+    let criteria = |chunk: &Chunk| !*chunk.is_generated.read() || !*chunk.is_uploaded_to_gpu.read();
+
+    // The faces on the borders of a chunk depend on the neighbouring columns
+    let has_neighbours = |x: i32, z: i32| {
+        [(x + 1, z), (x - 1, z), (x, z + 1), (x, z - 1)]
+            .iter()
+            .all(|&(x, z)| chunk_manager.get_column(x, z).is_some())
+    };
+
+    if let Some(chunk) = chunk_manager.get_chunk(x, y, z) {
+        if criteria(chunk.as_ref()) && has_neighbours(x, z) {
+            return ring;
+        }
+    }
+
+    while !queue.is_empty() {
+        for (x, y, z) in queue.drain(..) {
+            for &(x, y, z) in &[
+                (x + 1, y, z),
+                (x - 1, y, z),
+                (x, y, z + 1),
+                (x, y, z - 1),
+                (x, y + 1, z),
+                (x, y - 1, z),
+            ] {
+                if is_position_valid(x, y, z) && !is_visited[coords_to_index(x, y, z)] {
+                    ring.push((x, y, z));
+                    is_visited.set(coords_to_index(x, y, z), true);
+                }
+            }
+        }
+
+        let mut unloaded_chunks = Vec::new();
+
+        for &(x, y, z) in ring.iter() {
+            // Missing when the columns come from a server
+            if let Some(chunk) = chunk_manager.get_chunk(x, y, z) {
+                if !*chunk.is_generated.read() && criteria(chunk.as_ref()) && has_neighbours(x, z) {
+                    unloaded_chunks.push((x, y, z));
+                }
+            }
+        }
+
+        if !unloaded_chunks.is_empty() {
+            return unloaded_chunks;
+        } else {
+            queue.extend(ring.iter());
+            ring.clear();
+        }
+    }
+
+    Vec::new()
+}
@@ -0,0 +1,118 @@
+use crate::constants::{
+    DAY_SKY_COLOR, DAY_ZENITH_COLOR, NIGHT_SKY_COLOR, NIGHT_ZENITH_COLOR, SUNSET_COLOR,
+};
+use nalgebra_glm::{mix, vec3, Vec3};
+use std::f32::consts::PI;
+use std::time::Duration;
+
+// Like in the vanilla game, a day lasts 20 minutes
+pub const TICKS_PER_SECOND: f64 = 20.0;
+pub const TICKS_PER_DAY: u64 = 24000;
+// Brightness of the blocks lit by the sky in the middle of the night
+const MIN_DAYLIGHT: f32 = 0.2;
+
+// Times of day of `/time set`, the day starts at sunrise
+pub fn named_time(name: &str) -> Option<u64> {
+    match name {
+        "day" => Some(1000),
+        "noon" => Some(6000),
+        "night" => Some(13000),
+        "midnight" => Some(18000),
+        _ => None,
+    }
+}
+
+// Time of the world in ticks, advanced with the timer so that it stops when the timer is paused
+#[derive(Clone, Copy, Debug)]
+pub struct WorldTime {
+    ticks: f64,
+}
+
+// The worlds start in the morning rather than in the dark of sunrise
+impl Default for WorldTime {
+    fn default() -> Self {
+        Self::from_ticks(named_time("day").unwrap())
+    }
+}
+
+impl WorldTime {
+    pub fn from_ticks(ticks: u64) -> Self {
+        Self {
+            ticks: ticks as f64,
+        }
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks as u64
+    }
+
+    pub fn day(&self) -> u64 {
+        self.ticks() / TICKS_PER_DAY
+    }
+
+    pub fn time_of_day(&self) -> u64 {
+        self.ticks() % TICKS_PER_DAY
+    }
+
+    // Stays in the current day, the time can go backwards
+    pub fn set_time_of_day(&mut self, time: u64) {
+        self.ticks = (self.day() * TICKS_PER_DAY + time % TICKS_PER_DAY) as f64;
+    }
+
+    pub fn add_ticks(&mut self, ticks: u64) {
+        self.ticks += ticks as f64;
+    }
+
+    pub fn advance(&mut self, elapsed: Duration) {
+        self.ticks += elapsed.as_secs_f64() * TICKS_PER_SECOND;
+    }
+
+    // The sun rises in the east (+x) at the start of the day and sets in the west at the middle
+    // The moon is on the opposite side
+    pub fn sun_direction(&self) -> Vec3 {
+        let fraction = (self.ticks % TICKS_PER_DAY as f64) / TICKS_PER_DAY as f64;
+        let angle = fraction as f32 * 2.0 * PI;
+
+        vec3(angle.cos(), angle.sin(), 0.0)
+    }
+
+    // From 0 at night to 1 during the day, the transitions are around sunrise and sunset
+    fn day_factor(&self) -> f32 {
+        let t = (self.sun_direction().y * 4.0 + 0.5).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    // Multiplies the colour of the blocks, they are lit by the sky only
+    pub fn daylight(&self) -> f32 {
+        MIN_DAYLIGHT + (1.0 - MIN_DAYLIGHT) * self.day_factor()
+    }
+
+    // Strength of the colours of sunrise and sunset, the highest when the sun is at the horizon
+    pub fn twilight(&self) -> f32 {
+        (1.0 - self.sun_direction().y.abs() * 4.0).max(0.0)
+    }
+
+    // Colour of the sky at the horizon, also the colour of the fog
+    pub fn sky_color(&self) -> Vec3 {
+        let sky = mix(
+            &color(NIGHT_SKY_COLOR),
+            &color(DAY_SKY_COLOR),
+            self.day_factor(),
+        );
+
+        mix(&sky, &color(SUNSET_COLOR), self.twilight() * 0.4)
+    }
+
+    // Colour of the top of the sky
+    pub fn zenith_color(&self) -> Vec3 {
+        mix(
+            &color(NIGHT_ZENITH_COLOR),
+            &color(DAY_ZENITH_COLOR),
+            self.day_factor(),
+        )
+    }
+}
+
+fn color((r, g, b): (f32, f32, f32)) -> Vec3 {
+    vec3(r, g, b)
+}
@@ -0,0 +1,166 @@
+// Renders frames with the client in a hidden window, like `--headless`
+// They need OpenGL 4.5 and a display, with Mesa they run on llvmpipe under Xvfb:
+// `xvfb-run cargo test --test headless -- --ignored`
+// The reference frames are written again with `BLESS_HEADLESS=1`, the test then fails so that
+// they are checked and committed before it passes
+#![cfg(unix)]
+
+use image::RgbImage;
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const REFERENCES_DIRECTORY: &str = "tests/fixtures/headless";
+// Differences of the rasterization between the versions of Mesa
+const CHANNEL_TOLERANCE: u8 = 8;
+const MAX_DIFFERENT_PIXELS: f64 = 0.005;
+
+// A directory with the shaders and the textures of the client, and its own settings
+struct RenderDirectory(PathBuf);
+
+impl RenderDirectory {
+    fn new(name: &str, settings: &str) -> Self {
+        let directory =
+            std::env::temp_dir().join(format!("minecraft-headless-{name}-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        for assets in ["src", "textures"] {
+            let _ = symlink(root.join(assets), directory.join(assets));
+        }
+        fs::write(
+            directory.join("settings.toml"),
+            format!(
+                "[window]\n\
+                 width = 320\n\
+                 height = 180\n\
+                 \n\
+                 [video]\n\
+                 render_distance = 4\n\
+                 clouds = \"off\"\n\
+                 {settings}\n"
+            ),
+        )
+        .unwrap();
+
+        Self(directory)
+    }
+
+    // The world of the seed 1 seen from above the spawn point
+    fn render(&self, time: &str) -> RgbImage {
+        let output = self.0.join("frame.png");
+        let status = Command::new(env!("CARGO_BIN_EXE_minecraft"))
+            .current_dir(&self.0)
+            .args(["--seed", "1", "--time", time, "--headless"])
+            .arg(&output)
+            .env("LIBGL_ALWAYS_SOFTWARE", "1")
+            .env("GALLIUM_DRIVER", "llvmpipe")
+            .status()
+            .unwrap();
+        assert!(status.success(), "the client failed to render");
+
+        image::open(&output).unwrap().to_rgb8()
+    }
+}
+
+impl Drop for RenderDirectory {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn mean_luminance(image: &RgbImage) -> f64 {
+    let total: f64 = image
+        .pixels()
+        .map(|pixel| {
+            let [r, g, b] = pixel.0.map(f64::from);
+            0.2126 * r + 0.7152 * g + 0.0722 * b
+        })
+        .sum();
+
+    total / (image.width() * image.height()) as f64
+}
+
+fn reference_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join(REFERENCES_DIRECTORY)
+        .join(format!("{name}.png"))
+}
+
+// The frame is kept in the target directory when it differs from the reference
+fn assert_matches_reference(name: &str, image: &RgbImage) {
+    let reference_path = reference_path(name);
+
+    // A missing reference is a failure, never a frame to compare the next runs with
+    let reference = match image::open(&reference_path) {
+        Ok(reference) => reference.to_rgb8(),
+        Err(err) => panic!(
+            "Could not read {}: {err}, render it with BLESS_HEADLESS=1",
+            reference_path.display()
+        ),
+    };
+    assert_eq!(image.dimensions(), reference.dimensions());
+
+    let different_pixels = image
+        .pixels()
+        .zip(reference.pixels())
+        .filter(|(pixel, reference)| {
+            (0..3).any(|i| pixel.0[i].abs_diff(reference.0[i]) > CHANNEL_TOLERANCE)
+        })
+        .count();
+    let fraction = different_pixels as f64 / (image.width() * image.height()) as f64;
+
+    if fraction > MAX_DIFFERENT_PIXELS {
+        let actual_path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.png"));
+        image.save(&actual_path).unwrap();
+        panic!(
+            "{:.2}% of the pixels differ from {}, the frame is {}",
+            100.0 * fraction,
+            reference_path.display(),
+            actual_path.display()
+        );
+    }
+}
+
+#[test]
+#[ignore = "needs an OpenGL 4.5 display, run under Xvfb with Mesa"]
+fn frames_match_the_references() {
+    let directory = RenderDirectory::new("references", "shadows = \"medium\"");
+    let is_blessing = std::env::var_os("BLESS_HEADLESS").is_some();
+
+    for time in ["noon", "midnight"] {
+        let frame = directory.render(time);
+
+        if is_blessing {
+            let reference_path = reference_path(time);
+            fs::create_dir_all(reference_path.parent().unwrap()).unwrap();
+            frame.save(&reference_path).unwrap();
+        } else {
+            assert_matches_reference(time, &frame);
+        }
+    }
+
+    assert!(
+        !is_blessing,
+        "The references were written in {REFERENCES_DIRECTORY}, check them and run the test again \
+         without BLESS_HEADLESS"
+    );
+}
+
+#[test]
+#[ignore = "needs an OpenGL 4.5 display, run under Xvfb with Mesa"]
+fn shadows_darken_the_terrain() {
+    let lit = RenderDirectory::new("lit", "shadows = \"off\"").render("day");
+    let shadowed = RenderDirectory::new("shadowed", "shadows = \"high\"").render("day");
+
+    assert_eq!(lit.dimensions(), (320, 180));
+    assert_eq!(shadowed.dimensions(), lit.dimensions());
+    // The sun is low in the morning, the trees and the hills cast long shadows
+    assert!(
+        mean_luminance(&shadowed) < 0.99 * mean_luminance(&lit),
+        "{} is not darker than {}",
+        mean_luminance(&shadowed),
+        mean_luminance(&lit)
+    );
+}
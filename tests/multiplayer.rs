@@ -0,0 +1,235 @@
+// Runs the dedicated server and scripted clients over localhost
+use minecraft::chunk::{BlockID, ChunkColumn};
+use minecraft::network::chunk_data::decompress_column;
+use minecraft::network::client::ServerConnection;
+use minecraft::network::packet::{ClientboundPacket, ServerboundPacket};
+use minecraft::network::PROTOCOL_VERSION;
+use minecraft::player::PlayerInput;
+use nalgebra_glm::vec3;
+use std::fs;
+use std::io::Write;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// The terrain around the spawn point is generated before the first column is sent
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+// The server binary running in its own directory, killed when the test fails
+struct TestServer {
+    process: Child,
+    directory: PathBuf,
+    port: u16,
+}
+
+impl TestServer {
+    fn start() -> Self {
+        // Port picked by the system, free again once the listener is dropped
+        let port = TcpListener::bind(("127.0.0.1", 0))
+            .and_then(|listener| listener.local_addr())
+            .unwrap()
+            .port();
+
+        let directory = std::env::temp_dir().join(format!("minecraft-multiplayer-{port}"));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(
+            directory.join("server.properties"),
+            format!(
+                "view-distance=2\n\
+                 spawn-x=8\n\
+                 spawn-y=195\n\
+                 spawn-z=8\n\
+                 level-seed=1\n\
+                 server-port={port}\n\
+                 max-players=2\n"
+            ),
+        )
+        .unwrap();
+
+        let process = Command::new(env!("CARGO_BIN_EXE_server"))
+            .current_dir(&directory)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        Self {
+            process,
+            directory,
+            port,
+        }
+    }
+
+    // Retries until the server listens
+    fn connect(&self, name: &str) -> ServerConnection {
+        let start = Instant::now();
+
+        let connection = loop {
+            match ServerConnection::connect(("127.0.0.1", self.port)) {
+                Ok(connection) => break connection,
+                Err(_) if start.elapsed() < TIMEOUT => {
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Err(err) => panic!("Could not connect to the server: {err}"),
+            }
+        };
+
+        connection.send(ServerboundPacket::Login {
+            protocol_version: PROTOCOL_VERSION,
+            name: name.to_string(),
+        });
+        connection
+    }
+
+    fn command(&mut self, command: &str) {
+        let stdin = self.process.stdin.as_mut().unwrap();
+        writeln!(stdin, "{command}").unwrap();
+    }
+
+    fn wait_for_exit(&mut self) -> bool {
+        let start = Instant::now();
+
+        while start.elapsed() < TIMEOUT {
+            if let Some(status) = self.process.try_wait().unwrap() {
+                return status.success();
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        false
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+        let _ = fs::remove_dir_all(&self.directory);
+    }
+}
+
+// Waits for the first packet accepted by `filter`, the other packets are skipped
+fn expect<T>(
+    connection: &ServerConnection,
+    mut filter: impl FnMut(ClientboundPacket) -> Option<T>,
+) -> T {
+    let start = Instant::now();
+
+    while start.elapsed() < TIMEOUT {
+        for packet in connection.received_packets() {
+            let packet = packet.unwrap_or_else(|err| panic!("Connection closed: {err}"));
+
+            if let ClientboundPacket::KeepAlive { id } = packet {
+                connection.send(ServerboundPacket::KeepAlive { id });
+                continue;
+            }
+
+            if let Some(value) = filter(packet) {
+                return value;
+            }
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    panic!("Timed out waiting for a packet");
+}
+
+fn expect_chat(connection: &ServerConnection, expected: &str) {
+    expect(connection, |packet| match packet {
+        ClientboundPacket::Chat { message } if message == expected => Some(()),
+        _ => None,
+    });
+}
+
+#[test]
+fn clients_play_together_on_a_server() {
+    let mut server = TestServer::start();
+
+    let alice = server.connect("alice");
+    let (alice_id, spawn_point) = expect(&alice, |packet| match packet {
+        ClientboundPacket::LoginSuccess {
+            entity_id,
+            position,
+        } => Some((entity_id, position)),
+        ClientboundPacket::Disconnect { reason } => panic!("Disconnected: {reason}"),
+        _ => None,
+    });
+    assert_eq!(spawn_point, vec3(8.0, 195.0, 8.0));
+
+    // The second player is told about the first one, and the first one about the second
+    let bob = server.connect("bob");
+    expect_chat(&alice, "bob joined the game");
+    expect(&bob, |packet| match packet {
+        ClientboundPacket::SpawnEntity { entity_id, .. } if entity_id == alice_id => Some(()),
+        _ => None,
+    });
+
+    // The column of the spawn point, once its terrain is final
+    let data = expect(&alice, |packet| match packet {
+        ClientboundPacket::ChunkColumn { x: 0, z: 0, data } => Some(data),
+        _ => None,
+    });
+    let column = ChunkColumn::new();
+    decompress_column(&data, &column).unwrap();
+    assert_eq!(column.get_chunk(0).get_block(0, 0, 0), BlockID::Bedrock);
+
+    // The edits are broadcast to the clients that have the column
+    alice.send(ServerboundPacket::BlockEdit {
+        x: 9,
+        y: 196,
+        z: 8,
+        block: BlockID::Glass,
+    });
+    for connection in [&alice, &bob] {
+        expect(connection, |packet| match packet {
+            ClientboundPacket::BlockChange {
+                x: 9,
+                y: 196,
+                z: 8,
+                block: BlockID::Glass,
+            } => Some(()),
+            _ => None,
+        });
+    }
+
+    // The server simulates the inputs again and acknowledges the last one
+    for sequence in 0..5 {
+        alice.send(ServerboundPacket::PlayerInput {
+            sequence,
+            input: PlayerInput::default(),
+        });
+    }
+    let position = expect(&alice, |packet| match packet {
+        ClientboundPacket::PlayerMovement {
+            sequence: 4,
+            position,
+            ..
+        } => Some(position),
+        _ => None,
+    });
+    assert!(position.y < spawn_point.y, "the player falls");
+
+    // The server decides whether the player is dead, a living player stays where it is
+    alice.send(ServerboundPacket::Respawn);
+    let respawn_position = expect(&alice, |packet| match packet {
+        ClientboundPacket::Respawn { position } => Some(position),
+        _ => None,
+    });
+    assert_eq!(respawn_position, position);
+
+    bob.send(ServerboundPacket::Chat {
+        message: "hello".to_string(),
+    });
+    expect_chat(&alice, "<bob> hello");
+
+    drop(bob);
+    expect_chat(&alice, "bob left the game");
+
+    server.command("/stop");
+    assert!(server.wait_for_exit(), "the server stops cleanly");
+    assert!(server.directory.join("journal.dat").exists());
+}